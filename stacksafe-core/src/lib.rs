@@ -0,0 +1,73 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Version-stable runtime state for the `stacksafe` crate.
+//!
+//! Every release of `stacksafe` stores its per-thread protection flag and its stack size
+//! configuration here rather than in its own statics. Since this crate never makes a breaking
+//! release, Cargo links exactly one copy of it into a binary, so protection established by a
+//! function compiled against one `stacksafe` version is observed by [`StackSafe<T>`] values
+//! created by another, and configuration applied through any version affects all of them.
+//!
+//! The API is deliberately minimal and frozen: items may be added, but never changed or removed.
+//!
+//! [`StackSafe<T>`]: https://docs.rs/stacksafe/latest/stacksafe/struct.StackSafe.html
+
+#![deny(missing_docs)]
+
+use std::cell::Cell;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+thread_local! {
+    static PROTECTED: Cell<bool> = const { Cell::new(false) };
+}
+
+static MINIMUM_STACK_SIZE: AtomicUsize = AtomicUsize::new(128 * 1024);
+static STACK_ALLOC_SIZE: AtomicUsize = AtomicUsize::new(2 * 1024 * 1024);
+
+/// Returns whether the current thread is executing inside a stack-safe context.
+#[inline]
+pub fn is_protected() -> bool {
+    PROTECTED.with(|p| p.get())
+}
+
+/// Sets whether the current thread is executing inside a stack-safe context, returning the
+/// previous value so that callers can restore it afterwards.
+#[inline]
+pub fn replace_protected(protected: bool) -> bool {
+    PROTECTED.with(|p| p.replace(protected))
+}
+
+/// Sets the minimum stack space threshold for triggering stack allocation in bytes.
+pub fn set_minimum_stack_size(bytes: usize) {
+    MINIMUM_STACK_SIZE.store(bytes, Ordering::Relaxed);
+}
+
+/// Returns the minimum stack space threshold for triggering stack allocation in bytes.
+#[inline]
+pub fn get_minimum_stack_size() -> usize {
+    MINIMUM_STACK_SIZE.load(Ordering::Relaxed)
+}
+
+/// Sets the size of newly allocated stack segments in bytes.
+pub fn set_stack_allocation_size(bytes: usize) {
+    STACK_ALLOC_SIZE.store(bytes, Ordering::Relaxed);
+}
+
+/// Returns the size of newly allocated stack segments in bytes.
+#[inline]
+pub fn get_stack_allocation_size() -> usize {
+    STACK_ALLOC_SIZE.load(Ordering::Relaxed)
+}
@@ -0,0 +1,146 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of `#[derive(DeepDrop)]`.
+
+use proc_macro_error2::abort;
+use quote::format_ident;
+use quote::quote;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Fields;
+use syn::GenericParam;
+use syn::ItemConst;
+use syn::Path;
+use syn::parse_quote;
+
+pub(crate) fn derive(input: DeriveInput) -> ItemConst {
+    let mut crate_path: Option<Path> = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("deep_drop"))
+    {
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                crate_path = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `crate`"))
+            }
+        });
+        if let Err(err) = result {
+            abort!(err.span(), "{}", err);
+        }
+    }
+    let stacksafe_crate = crate_path.unwrap_or_else(|| parse_quote!(::stacksafe));
+
+    let mut arms: Vec<syn::Arm> = Vec::new();
+    let mut arm = |path: Path, fields: &Fields| {
+        let mut names: Vec<syn::Member> = Vec::new();
+        let mut bindings = Vec::new();
+        let mut drops: Vec<syn::Stmt> = Vec::new();
+        for (index, field) in fields.iter().enumerate() {
+            let binding = format_ident!("__field{}", index);
+            names.push(match &field.ident {
+                Some(ident) => syn::Member::Named(ident.clone()),
+                None => syn::Member::Unnamed(index.into()),
+            });
+            if skip(field) {
+                drops.push(parse_quote!(::core::mem::drop(#binding);));
+            } else {
+                drops.push(parse_quote!(#stacksafe_crate::DeepDrop::deep_drop(#binding);));
+            }
+            bindings.push(binding);
+        }
+        let pattern = match fields {
+            Fields::Named(_) => quote!({ #(#names: #bindings),* }),
+            Fields::Unnamed(_) => quote!(( #(#bindings),* )),
+            Fields::Unit => Default::default(),
+        };
+        arms.push(parse_quote!(#path #pattern => { #(#drops)* }));
+    };
+    match &input.data {
+        Data::Struct(data) => arm(parse_quote!(Self), &data.fields),
+        Data::Enum(data) => {
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                arm(parse_quote!(Self::#ident), &variant.fields);
+            }
+        }
+        Data::Union(data) => abort!(
+            data.union_token,
+            "#[derive(DeepDrop)] does not support unions"
+        ),
+    }
+
+    // Like the standard derives, require every type parameter to implement the trait.
+    let mut generics = input.generics.clone();
+    let bounds: Vec<syn::WherePredicate> = (input.generics.params.iter())
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => {
+                let ident = &param.ident;
+                Some(parse_quote!(#ident: #stacksafe_crate::DeepDrop))
+            }
+            _ => None,
+        })
+        .collect();
+    generics.make_where_clause().predicates.extend(bounds);
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (_, _, bounded_where_clause) = generics.split_for_impl();
+    // The fields are moved out, which types implementing `Drop` do not allow. The second impl
+    // conflicts with the first one for such types, which reports this at the derive itself.
+    parse_quote! {
+        const _: () = {
+            trait MustNotImplDrop {}
+            #[allow(clippy::drop_bounds, drop_bounds)]
+            impl<T: ::core::ops::Drop> MustNotImplDrop for T {}
+            impl #impl_generics MustNotImplDrop for #ident #ty_generics #where_clause {}
+
+            impl #impl_generics #stacksafe_crate::DeepDrop for #ident #ty_generics #bounded_where_clause {
+                #[#stacksafe_crate::stacksafe(crate = #stacksafe_crate)]
+                fn deep_drop(self) {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        };
+    }
+}
+
+/// Returns `true` if the field is marked with `#[deep_drop(skip)]`.
+fn skip(field: &syn::Field) -> bool {
+    let mut skip = false;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("deep_drop"))
+    {
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `skip`"))
+            }
+        });
+        if let Err(err) = result {
+            abort!(err.span(), "{}", err);
+        }
+    }
+    skip
+}
@@ -15,29 +15,100 @@
 //! Procedural macro implementation for the `stacksafe` crate.
 //!
 //! This crate provides the `#[stacksafe]` attribute macro that transforms functions
-//! to use automatic stack growth, preventing stack overflow in deeply recursive scenarios.
+//! to use automatic stack growth, preventing stack overflow in deeply recursive scenarios,
+//! `#[stacksafe_type]` for recursive types, `#[derive(Strip)]` for converting between
+//! wrapped and plain recursive types, and `#[derive(DeepDrop)]` for dropping deeply nested values.
+//!
+//! ## Runtime Contract
+//!
+//! The code generated by these macros only refers to the following items of the `stacksafe`
+//! crate, or of the crate given with `crate = ...`, so that any 1.x release of this crate works
+//! with any 1.x release of `stacksafe` that has them:
+//!
+//! - `internal::Site::new`, and the `const` builder methods `profile`, `red_zone`, `stack_size`,
+//!   `max_depth` and `check_every`, each only emitted when the corresponding argument is given.
+//! - `internal::guard`, or `internal::depth_guard` for `depth_only` functions.
+//! - `internal::drop_guard`, for the `Drop` implementations of `#[stacksafe_type]`.
+//! - The `stacksafe`, `Strip` and `DeepDrop` items, for the implementations of the derives.
+//!
+//! These items keep their signatures and semantics for the whole 1.x series. New arguments are
+//! expressed as new items, which are only emitted when the argument is used, so that code which
+//! does not use them keeps compiling against older releases of `stacksafe`.
 
+mod deep_drop;
+mod recursive;
+mod strip;
+
+use proc_macro::Span;
 use proc_macro::TokenStream;
 use proc_macro_error2::abort;
 use proc_macro_error2::abort_call_site;
 use proc_macro_error2::proc_macro_error;
 use quote::ToTokens;
+use quote::format_ident;
 use quote::quote;
+use syn::Attribute;
+use syn::Block;
+use syn::DeriveInput;
+use syn::Expr;
+use syn::ImplItem;
+use syn::Item;
 use syn::ItemFn;
+use syn::LitStr;
 use syn::Path;
 use syn::ReturnType;
+use syn::Signature;
+use syn::TraitItem;
 use syn::Type;
+use syn::TypeParamBound;
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
 use syn::parse_macro_input;
 use syn::parse_quote;
+use syn::visit::Visit;
 
-#[proc_macro_attribute]
-#[proc_macro_error]
-pub fn stacksafe(args: TokenStream, item: TokenStream) -> TokenStream {
-    let mut crate_path: Option<Path> = None;
+/// The arguments of `#[stacksafe(...)]`.
+#[derive(Default)]
+struct Args {
+    crate_path: Option<Path>,
+    name: Option<LitStr>,
+    profile: Option<LitStr>,
+    red_zone: Option<Expr>,
+    stack_size: Option<Expr>,
+    depth_only: bool,
+    max_depth: Option<Expr>,
+    check_every: Option<Expr>,
+    skip: bool,
+}
 
-    let arg_parser = syn::meta::parser(|meta| {
+impl Args {
+    fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
         if meta.path.is_ident("crate") {
-            crate_path = Some(meta.value()?.parse()?);
+            self.crate_path = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("profile") {
+            self.profile = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("red_zone") {
+            self.red_zone = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("stack_size") {
+            self.stack_size = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("depth_only") {
+            self.depth_only = true;
+            Ok(())
+        } else if meta.path.is_ident("max_depth") {
+            self.max_depth = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("check_every") {
+            self.check_every = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("skip") {
+            self.skip = true;
             Ok(())
         } else {
             Err(meta.error(format!(
@@ -47,41 +118,422 @@ pub fn stacksafe(args: TokenStream, item: TokenStream) -> TokenStream {
                     .map_or("unknown".to_string(), |i| i.to_string())
             )))
         }
-    });
+    }
+}
+
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn stacksafe(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut arguments = Args::default();
+    let arg_parser = syn::meta::parser(|meta| arguments.parse(meta));
     parse_macro_input!(args with arg_parser);
+    let args = arguments;
 
-    let item_fn: ItemFn = match syn::parse(item.clone()) {
-        Ok(item) => item,
-        Err(_) => abort_call_site!("#[stacksafe] can only be applied to functions"),
-    };
+    if let Some(max_depth) = &args.max_depth {
+        if !args.depth_only {
+            abort!(max_depth, "`max_depth` requires `depth_only`");
+        }
+    }
+    if args.depth_only {
+        // These only affect growing the stack, which depth-only functions never do.
+        let sizes = [
+            args.profile.as_ref().map(ToTokens::to_token_stream),
+            args.red_zone.as_ref().map(ToTokens::to_token_stream),
+            args.stack_size.as_ref().map(ToTokens::to_token_stream),
+            args.check_every.as_ref().map(ToTokens::to_token_stream),
+        ];
+        if let Some(tokens) = sizes.into_iter().flatten().next() {
+            abort!(
+                tokens,
+                "`depth_only` cannot be combined with `profile`, `red_zone`, `stack_size` or `check_every`"
+            );
+        }
+    }
+    if args.skip {
+        abort_call_site!(
+            "#[stacksafe(skip)] can only be applied to methods in an impl block or a trait annotated with #[stacksafe]"
+        );
+    }
+
+    match syn::parse(item) {
+        Ok(Item::Fn(mut item_fn)) => {
+            let label = match &args.name {
+                Some(name) => name.value(),
+                None => item_fn.sig.ident.unraw().to_string(),
+            };
+            *item_fn.block = wrap(&args, &label, &item_fn.sig, &item_fn.block);
+            item_fn.into_token_stream().into()
+        }
+        Ok(Item::Impl(mut item_impl)) => {
+            reject_name(&args, "impl blocks");
+            // Label methods as `Type::method`, like `#[stacksafe(name = "...")]` would.
+            let prefix = match &*item_impl.self_ty {
+                Type::Path(ty) => ty
+                    .path
+                    .segments
+                    .last()
+                    .map(|segment| format!("{}::", segment.ident.unraw())),
+                _ => None,
+            };
+            for item in &mut item_impl.items {
+                if let ImplItem::Fn(method) = item {
+                    if wraps_method(&mut method.attrs) {
+                        let label = format!(
+                            "{}{}",
+                            prefix.as_deref().unwrap_or_default(),
+                            method.sig.ident.unraw()
+                        );
+                        method.block = wrap(&args, &label, &method.sig, &method.block);
+                    }
+                }
+            }
+            item_impl.into_token_stream().into()
+        }
+        Ok(Item::Trait(mut item_trait)) => {
+            reject_name(&args, "traits");
+            let prefix = item_trait.ident.unraw();
+            for item in &mut item_trait.items {
+                if let TraitItem::Fn(method) = item {
+                    // Required methods have no body to wrap; implementations can be annotated
+                    // themselves.
+                    let Some(block) = &mut method.default else {
+                        continue;
+                    };
+                    if wraps_method(&mut method.attrs) {
+                        let label = format!("{prefix}::{}", method.sig.ident.unraw());
+                        *block = wrap(&args, &label, &method.sig, block);
+                    }
+                }
+            }
+            item_trait.into_token_stream().into()
+        }
+        _ => abort_call_site!(
+            "#[stacksafe] can only be applied to functions, impl blocks and traits"
+        ),
+    }
+}
 
-    if item_fn.sig.asyncness.is_some() {
+fn reject_name(args: &Args, items: &str) {
+    if let Some(name) = &args.name {
         abort!(
-            item_fn.sig.asyncness,
+            name,
+            "`name` cannot be applied to {}, as it would label every method the same",
+            items
+        );
+    }
+}
+
+/// Returns whether a method in an annotated impl block or trait is to be wrapped, removing a
+/// `#[stacksafe(skip)]` attribute from it.
+///
+/// Methods with their own `#[stacksafe(...)]` attribute are left to it, so that they can use
+/// different arguments.
+fn wraps_method(attrs: &mut Vec<Attribute>) -> bool {
+    let mut wraps = true;
+    attrs.retain(|attr| {
+        let is_stacksafe = attr
+            .path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "stacksafe");
+        if !is_stacksafe {
+            return true;
+        }
+        wraps = false;
+        let mut skip = false;
+        if let syn::Meta::List(list) = &attr.meta {
+            // Any other arguments are reported by the attribute's own expansion.
+            let _ = list.parse_nested_meta(|meta| {
+                skip |= meta.path.is_ident("skip");
+                Ok(())
+            });
+        }
+        !skip
+    });
+    wraps
+}
+
+/// Returns `block` wrapped in a guarded call labeled with `label`.
+fn wrap(args: &Args, label: &str, sig: &Signature, block: &Block) -> Block {
+    if sig.asyncness.is_some() {
+        abort!(
+            sig.asyncness,
             "#[stacksafe] does not support async functions"
         );
     }
 
-    let mut item_fn = item_fn;
-    let ret = match &item_fn.sig.output {
+    let ret = match &sig.output {
         // impl trait is not supported in closure return type, override with
         // default, which is inferring.
         ReturnType::Type(_, ty) if matches!(**ty, Type::ImplTrait(_)) => ReturnType::Default,
-        _ => item_fn.sig.output.clone(),
+        _ => sig.output.clone(),
+    };
+
+    let stacksafe_crate = args
+        .crate_path
+        .clone()
+        .unwrap_or_else(|| parse_quote!(::stacksafe));
+    // Locals introduced by the expansion resolve at the macro definition site, so that they can
+    // neither shadow nor be shadowed by identifiers in the body, wherever it comes from.
+    let ret_local = format_ident!("__stacksafe_ret", span = Span::mixed_site().into());
+    let block = match return_hint(sig) {
+        Some((hint, args)) => quote! {
+            {
+                #hint
+                #[allow(unused_braces)]
+                let #ret_local = __stacksafe_hint::<#(#args,)* _>(#block);
+                #ret_local
+            }
+        },
+        None => block.to_token_stream(),
+    };
+    let block = match captures(sig) {
+        Some(captures) => quote! {
+            {
+                #captures
+                #block
+            }
+        },
+        None => block,
+    };
+    let profile = args
+        .profile
+        .as_ref()
+        .map(|profile| quote!(.profile(#profile)));
+    let red_zone = args
+        .red_zone
+        .as_ref()
+        .map(|red_zone| quote!(.red_zone(#red_zone)));
+    let stack_size = args
+        .stack_size
+        .as_ref()
+        .map(|stack_size| quote!(.stack_size(#stack_size)));
+    let max_depth = args
+        .max_depth
+        .as_ref()
+        .map(|max_depth| quote!(.max_depth(#max_depth)));
+    let check_every = args
+        .check_every
+        .as_ref()
+        .map(|check_every| quote!(.check_every(#check_every)));
+    let guard = if args.depth_only {
+        quote!(depth_guard)
+    } else {
+        quote!(guard)
+    };
+    parse_quote! {
+        {
+            static __STACKSAFE_SITE: #stacksafe_crate::internal::Site =
+                #stacksafe_crate::internal::Site::new(::core::concat!(::core::module_path!(), "::", #label))
+                    #profile
+                    #red_zone
+                    #stack_size
+                    #max_depth
+                    #check_every;
+            #stacksafe_crate::internal::#guard(&__STACKSAFE_SITE, move || #ret #block)
+        }
+    }
+}
+
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn stacksafe_type(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut options = recursive::Options::default();
+    let arg_parser = syn::meta::parser(|meta| options.parse(meta));
+    parse_macro_input!(args with arg_parser);
+
+    let input: DeriveInput = match syn::parse(item) {
+        Ok(input) => input,
+        Err(_) => abort_call_site!("#[stacksafe_type] can only be applied to structs and enums"),
+    };
+    recursive::expand(options, input).into_token_stream().into()
+}
+
+#[proc_macro_derive(Strip, attributes(strip))]
+#[proc_macro_error]
+pub fn derive_strip(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    strip::derive(input).into_token_stream().into()
+}
+
+#[proc_macro_derive(DeepDrop, attributes(deep_drop))]
+#[proc_macro_error]
+pub fn derive_deep_drop(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    deep_drop::derive(input).into_token_stream().into()
+}
+
+/// Returns a statement that makes the wrapped body capture every argument as a whole.
+///
+/// A `move` closure only captures the places it uses, so a body that uses a field of an argument
+/// would otherwise leave the rest of the argument behind, to be dropped outside of the guarded
+/// call. Mentioning each binding makes the arguments behave exactly as they do in the original
+/// function, including for closures in the body that capture them.
+fn captures(sig: &Signature) -> Option<syn::Stmt> {
+    let mut bindings = Bindings(Vec::new());
+    for input in &sig.inputs {
+        match input {
+            syn::FnArg::Receiver(receiver) => {
+                bindings.0.push(receiver.self_token.into());
+            }
+            syn::FnArg::Typed(arg) => bindings.visit_pat(&arg.pat),
+        }
+    }
+    let bindings = bindings.0;
+    if bindings.is_empty() {
+        return None;
+    }
+    Some(parse_quote! {
+        let _ = (#(&#bindings,)*);
+    })
+}
+
+/// Collects the identifiers bound by argument patterns.
+struct Bindings(Vec<syn::Ident>);
+
+impl<'ast> Visit<'ast> for Bindings {
+    fn visit_pat_ident(&mut self, pat: &'ast syn::PatIdent) {
+        self.0.push(pat.ident.clone());
+        syn::visit::visit_pat_ident(self, pat);
+    }
+}
+
+/// Returns a function that passes the body's value through a generic parameter bounded like an
+/// `impl Trait` return type.
+///
+/// Closures cannot be declared to return `impl Trait`, so the wrapped body loses the expected
+/// type it would otherwise be checked against. Without it, closures returned by the body cannot
+/// infer higher-ranked signatures such as `impl Fn(&u32) -> u32`. The bounds are restated on a
+/// nested function, which also redeclares the generic parameters of the signature. This is only
+/// possible if neither the bounds nor those parameters refer to elided lifetimes or `Self`.
+fn return_hint(sig: &Signature) -> Option<(ItemFn, Vec<syn::Ident>)> {
+    let ReturnType::Type(_, ty) = &sig.output else {
+        return None;
+    };
+    let Type::ImplTrait(impl_trait) = &**ty else {
+        return None;
+    };
+
+    let mut visitor = LocalBounds {
+        // Lifetime parameters are redeclared as well, and are always inferred.
+        lifetimes: sig
+            .generics
+            .lifetimes()
+            .map(|param| param.lifetime.ident.to_string())
+            .collect(),
+        fn_sugar: 0,
+        local: true,
     };
+    let bounds: Vec<_> = impl_trait
+        .bounds
+        .iter()
+        .filter(|bound| matches!(bound, TypeParamBound::Trait(_)))
+        .inspect(|bound| visitor.visit_type_param_bound(bound))
+        .collect();
 
-    let stacksafe_crate = crate_path.unwrap_or_else(|| parse_quote!(::stacksafe));
-    let block = &item_fn.block;
-    let wrapped_block = quote! {
+    let mut params = Vec::new();
+    let mut args = Vec::new();
+    for param in &sig.generics.params {
+        match param {
+            syn::GenericParam::Lifetime(param) => {
+                visitor.visit_lifetime_param(param);
+                params.push(param.to_token_stream());
+            }
+            syn::GenericParam::Type(param) => {
+                visitor.visit_type_param(param);
+                args.push(param.ident.clone());
+                params.push(param.to_token_stream());
+            }
+            syn::GenericParam::Const(param) => {
+                visitor.visit_const_param(param);
+                args.push(param.ident.clone());
+                params.push(param.to_token_stream());
+            }
+        }
+    }
+    if let Some(where_clause) = &sig.generics.where_clause {
+        visitor.visit_where_clause(where_clause);
+    }
+    if bounds.is_empty() || !visitor.local {
+        return None;
+    }
+
+    let where_clause = &sig.generics.where_clause;
+    let hint = parse_quote! {
+        #[inline(always)]
+        fn __stacksafe_hint<#(#params,)* __StackSafeRet: #(#bounds)+*>(
+            ret: __StackSafeRet,
+        ) -> __StackSafeRet
+        #where_clause
         {
-            #stacksafe_crate::internal::stacker::maybe_grow(
-                #stacksafe_crate::get_minimum_stack_size(),
-                #stacksafe_crate::get_stack_allocation_size(),
-                #stacksafe_crate::internal::with_protected(move || #ret { #block })
-            )
+            ret
         }
     };
+    Some((hint, args))
+}
 
-    *item_fn.block = syn::parse(wrapped_block.into()).unwrap();
-    item_fn.into_token_stream().into()
+/// Checks whether bounds can be restated outside of the signature they appear in.
+struct LocalBounds {
+    // The lifetimes that are in scope.
+    lifetimes: Vec<String>,
+    // How many `Fn(..)` or `fn(..)` argument lists are being visited, where elided lifetimes
+    // are higher-ranked.
+    fn_sugar: usize,
+    local: bool,
+}
+
+impl<'ast> Visit<'ast> for LocalBounds {
+    fn visit_trait_bound(&mut self, bound: &'ast syn::TraitBound) {
+        let scope = self.lifetimes.len();
+        syn::visit::visit_trait_bound(self, bound);
+        self.lifetimes.truncate(scope);
+    }
+
+    fn visit_type_bare_fn(&mut self, bare_fn: &'ast syn::TypeBareFn) {
+        let scope = self.lifetimes.len();
+        self.fn_sugar += 1;
+        syn::visit::visit_type_bare_fn(self, bare_fn);
+        self.fn_sugar -= 1;
+        self.lifetimes.truncate(scope);
+    }
+
+    fn visit_parenthesized_generic_arguments(
+        &mut self,
+        arguments: &'ast syn::ParenthesizedGenericArguments,
+    ) {
+        self.fn_sugar += 1;
+        syn::visit::visit_parenthesized_generic_arguments(self, arguments);
+        self.fn_sugar -= 1;
+    }
+
+    fn visit_bound_lifetimes(&mut self, lifetimes: &'ast syn::BoundLifetimes) {
+        for param in &lifetimes.lifetimes {
+            if let syn::GenericParam::Lifetime(param) = param {
+                self.lifetimes.push(param.lifetime.ident.to_string());
+            }
+        }
+        syn::visit::visit_bound_lifetimes(self, lifetimes);
+    }
+
+    fn visit_lifetime(&mut self, lifetime: &'ast syn::Lifetime) {
+        let name = lifetime.ident.to_string();
+        if name != "static" && !self.lifetimes.contains(&name) {
+            self.local = false;
+        }
+    }
+
+    fn visit_type_reference(&mut self, reference: &'ast syn::TypeReference) {
+        if reference.lifetime.is_none() && self.fn_sugar == 0 {
+            self.local = false;
+        }
+        syn::visit::visit_type_reference(self, reference);
+    }
+
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        if path.leading_colon.is_none() && path.segments.first().is_some_and(|s| s.ident == "Self")
+        {
+            self.local = false;
+        }
+        syn::visit::visit_path(self, path);
+    }
 }
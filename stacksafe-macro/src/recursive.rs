@@ -0,0 +1,390 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of `#[stacksafe_type]`.
+
+use proc_macro_error2::abort;
+use quote::format_ident;
+use quote::quote;
+use syn::Attribute;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Expr;
+use syn::Fields;
+use syn::Generics;
+use syn::Ident;
+use syn::Item;
+use syn::LitStr;
+use syn::Pat;
+use syn::Path;
+use syn::meta::ParseNestedMeta;
+use syn::parse_quote;
+
+/// The traits that are implemented unless they are skipped.
+const TRAITS: [&str; 4] = ["Clone", "Debug", "PartialEq", "Hash"];
+
+/// The arguments of `#[stacksafe_type(...)]`.
+pub(crate) struct Options {
+    crate_path: Path,
+    placeholder: Option<Expr>,
+    skip: Vec<Ident>,
+    serde: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            crate_path: parse_quote!(::stacksafe),
+            placeholder: None,
+            skip: Vec::new(),
+            serde: false,
+        }
+    }
+}
+
+impl Options {
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("crate") {
+            self.crate_path = meta.value()?.parse()?;
+        } else if meta.path.is_ident("placeholder") {
+            self.placeholder = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("skip") {
+            meta.parse_nested_meta(|meta| match meta.path.get_ident() {
+                Some(ident) if TRAITS.contains(&&*ident.to_string()) => {
+                    self.skip.push(ident.clone());
+                    Ok(())
+                }
+                _ => Err(meta.error(format!(
+                    "expected one of {}",
+                    TRAITS.map(|t| format!("`{t}`")).join(", ")
+                ))),
+            })?;
+        } else if meta.path.is_ident("serde") {
+            self.serde = true;
+        } else {
+            return Err(meta.error(format!(
+                "unknown attribute parameter `{}`",
+                meta.path
+                    .get_ident()
+                    .map_or("unknown".to_string(), |i| i.to_string())
+            )));
+        }
+        Ok(())
+    }
+
+    fn implements(&self, name: &str) -> bool {
+        !self.skip.iter().any(|ident| ident == name)
+    }
+}
+
+/// A struct, or a variant of an enum.
+struct Variant<'a> {
+    path: Path,
+    name: &'a Ident,
+    fields: &'a Fields,
+}
+
+impl Variant<'_> {
+    /// Returns a pattern that binds every field to an identifier starting with `prefix`, along
+    /// with those identifiers.
+    fn pattern(&self, prefix: &str) -> (Pat, Vec<Ident>) {
+        let path = &self.path;
+        let bindings: Vec<Ident> = (0..self.fields.len())
+            .map(|index| format_ident!("__{}{}", prefix, index))
+            .collect();
+        let pat = match self.fields {
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|field| &field.ident);
+                parse_quote!(#path { #(#names: #bindings),* })
+            }
+            Fields::Unnamed(_) => parse_quote!(#path(#(#bindings),*)),
+            Fields::Unit => parse_quote!(#path),
+        };
+        (pat, bindings)
+    }
+
+    /// Returns an expression that constructs the variant from the given field values.
+    fn construct(&self, values: impl Iterator<Item = Expr>) -> Expr {
+        let path = &self.path;
+        match self.fields {
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|field| &field.ident);
+                parse_quote!(#path { #(#names: #values),* })
+            }
+            Fields::Unnamed(_) => parse_quote!(#path(#(#values),*)),
+            Fields::Unit => parse_quote!(#path),
+        }
+    }
+}
+
+pub(crate) fn expand(options: Options, input: DeriveInput) -> syn::File {
+    let stacksafe_crate = &options.crate_path;
+    let ident = &input.ident;
+
+    let variants: Vec<Variant> = match &input.data {
+        Data::Struct(data) => vec![Variant {
+            path: parse_quote!(Self),
+            name: ident,
+            fields: &data.fields,
+        }],
+        Data::Enum(data) => {
+            if data.variants.is_empty() {
+                abort!(
+                    ident,
+                    "#[stacksafe_type] does not support enums without variants"
+                );
+            }
+            (data.variants.iter())
+                .map(|variant| {
+                    let name = &variant.ident;
+                    Variant {
+                        path: parse_quote!(Self::#name),
+                        name,
+                        fields: &variant.fields,
+                    }
+                })
+                .collect()
+        }
+        Data::Union(data) => abort!(
+            data.union_token,
+            "#[stacksafe_type] does not support unions"
+        ),
+    };
+
+    let placeholder = match &options.placeholder {
+        Some(placeholder) => placeholder.clone(),
+        None => parse_quote!(<Self as ::core::default::Default>::default()),
+    };
+    let label = LitStr::new(&format!("{ident}::drop"), ident.span());
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut items: Vec<Item> = vec![parse_quote! {
+        impl #impl_generics ::core::ops::Drop for #ident #ty_generics #where_clause {
+            fn drop(&mut self) {
+                static __STACKSAFE_SITE: #stacksafe_crate::internal::Site =
+                    #stacksafe_crate::internal::Site::new(
+                        ::core::concat!(::core::module_path!(), "::", #label)
+                    );
+                #stacksafe_crate::internal::drop_guard(&__STACKSAFE_SITE, self, || #placeholder);
+            }
+        }
+    }];
+
+    let attr: Attribute = parse_quote!(#[#stacksafe_crate::stacksafe(crate = #stacksafe_crate)]);
+    if options.implements("Clone") {
+        let generics = bounded_generics(&input.generics, parse_quote!(::core::clone::Clone), None);
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let arms = variants.iter().map(|variant| {
+            let (pat, bindings) = variant.pattern("a");
+            let values = (bindings.iter())
+                .map(|binding| parse_quote!(::core::clone::Clone::clone(#binding)));
+            let value = variant.construct(values);
+            quote!(#pat => #value)
+        });
+        items.push(parse_quote! {
+            impl #impl_generics ::core::clone::Clone for #ident #ty_generics #where_clause {
+                #attr
+                fn clone(&self) -> Self {
+                    match self {
+                        #(#arms,)*
+                    }
+                }
+            }
+        });
+    }
+
+    if options.implements("Debug") {
+        let generics = bounded_generics(&input.generics, parse_quote!(::core::fmt::Debug), None);
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let arms = variants.iter().map(|variant| {
+            let (pat, bindings) = variant.pattern("a");
+            let name = variant.name.to_string();
+            let value: Expr = match variant.fields {
+                Fields::Named(fields) => {
+                    let names = (fields.named.iter())
+                        .map(|field| field.ident.as_ref().unwrap().to_string());
+                    parse_quote! {
+                        f.debug_struct(#name)#(.field(#names, #bindings))*.finish()
+                    }
+                }
+                Fields::Unnamed(_) => parse_quote! {
+                    f.debug_tuple(#name)#(.field(#bindings))*.finish()
+                },
+                Fields::Unit => parse_quote!(f.write_str(#name)),
+            };
+            quote!(#pat => #value)
+        });
+        items.push(parse_quote! {
+            impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+                #attr
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match self {
+                        #(#arms,)*
+                    }
+                }
+            }
+        });
+    }
+
+    if options.implements("PartialEq") {
+        let generics =
+            bounded_generics(&input.generics, parse_quote!(::core::cmp::PartialEq), None);
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let arms = variants.iter().map(|variant| {
+            let (a, a_bindings) = variant.pattern("a");
+            let (b, b_bindings) = variant.pattern("b");
+            quote! {
+                (#a, #b) => true #(&& ::core::cmp::PartialEq::eq(#a_bindings, #b_bindings))*
+            }
+        });
+        let mismatch = (variants.len() > 1).then(|| quote!(_ => false,));
+        items.push(parse_quote! {
+            impl #impl_generics ::core::cmp::PartialEq for #ident #ty_generics #where_clause {
+                #attr
+                fn eq(&self, other: &Self) -> bool {
+                    match (self, other) {
+                        #(#arms,)*
+                        #mismatch
+                    }
+                }
+            }
+        });
+    }
+
+    if options.implements("Hash") {
+        let generics = bounded_generics(&input.generics, parse_quote!(::core::hash::Hash), None);
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let discriminant = matches!(input.data, Data::Enum(_)).then(|| {
+            quote! {
+                ::core::hash::Hash::hash(&::core::mem::discriminant(self), state);
+            }
+        });
+        let arms = variants.iter().map(|variant| {
+            let (pat, bindings) = variant.pattern("a");
+            quote! {
+                #pat => {
+                    #(::core::hash::Hash::hash(#bindings, state);)*
+                }
+            }
+        });
+        items.push(parse_quote! {
+            impl #impl_generics ::core::hash::Hash for #ident #ty_generics #where_clause {
+                #attr
+                fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                    #discriminant
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        });
+    }
+
+    let mut item = input.clone();
+    if options.serde {
+        items.push(serde(&input, &attr));
+        // The attributes are only meant for the `serde` derive macros.
+        retain(&mut item, |attr| !attr.path().is_ident("serde"));
+    }
+
+    parse_quote! {
+        #item
+        #(#items)*
+    }
+}
+
+/// Returns the `serde` implementations, which delegate to a copy of the type definition that
+/// derives them for the original type with `#[serde(remote = "...")]`.
+fn serde(input: &DeriveInput, attr: &Attribute) -> Item {
+    let ident = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let remote = LitStr::new(&quote!(#ident #ty_generics).to_string(), ident.span());
+
+    let mut shadow = input.clone();
+    shadow.ident = format_ident!("__StackSafeRemote");
+    shadow.vis = syn::Visibility::Inherited;
+    retain(&mut shadow, |attr| attr.path().is_ident("serde"));
+    shadow.attrs.push(parse_quote!(#[serde(remote = #remote)]));
+    let shadow_ident = &shadow.ident;
+
+    let serialize = bounded_generics(&input.generics, parse_quote!(::serde::Serialize), None);
+    let (serialize_generics, _, serialize_where) = serialize.split_for_impl();
+    let deserialize = bounded_generics(
+        &input.generics,
+        parse_quote!(::serde::Deserialize<'de>),
+        Some(parse_quote!('de)),
+    );
+    let (deserialize_generics, _, deserialize_where) = deserialize.split_for_impl();
+
+    parse_quote! {
+        const _: () = {
+            #[derive(::serde::Serialize, ::serde::Deserialize)]
+            #shadow
+
+            impl #serialize_generics ::serde::Serialize for #ident #ty_generics #serialize_where {
+                #attr
+                fn serialize<__S: ::serde::Serializer>(
+                    &self,
+                    serializer: __S,
+                ) -> ::core::result::Result<__S::Ok, __S::Error> {
+                    #shadow_ident::serialize(self, serializer)
+                }
+            }
+
+            impl #deserialize_generics ::serde::Deserialize<'de> for #ident #ty_generics
+            #deserialize_where
+            {
+                #attr
+                fn deserialize<__D: ::serde::Deserializer<'de>>(
+                    deserializer: __D,
+                ) -> ::core::result::Result<Self, __D::Error> {
+                    #shadow_ident::deserialize(deserializer)
+                }
+            }
+        };
+    }
+}
+
+/// Returns `generics` with `bound` added to every type parameter, and `lifetime` prepended.
+fn bounded_generics(
+    generics: &Generics,
+    bound: syn::TypeParamBound,
+    lifetime: Option<syn::LifetimeParam>,
+) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(bound.clone());
+    }
+    if let Some(lifetime) = lifetime {
+        generics.params.insert(0, lifetime.into());
+    }
+    generics
+}
+
+/// Keeps only the attributes of the type, its variants and their fields that satisfy `keep`.
+fn retain(input: &mut DeriveInput, keep: impl Fn(&Attribute) -> bool) {
+    input.attrs.retain(&keep);
+    match &mut input.data {
+        Data::Struct(data) => data.fields.iter_mut().for_each(|f| f.attrs.retain(&keep)),
+        Data::Enum(data) => {
+            for variant in &mut data.variants {
+                variant.attrs.retain(&keep);
+                variant
+                    .fields
+                    .iter_mut()
+                    .for_each(|f| f.attrs.retain(&keep));
+            }
+        }
+        Data::Union(_) => {}
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of `#[derive(Strip)]`.
+
+use proc_macro_error2::abort;
+use proc_macro_error2::abort_call_site;
+use quote::format_ident;
+use quote::quote;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Fields;
+use syn::Ident;
+use syn::ItemImpl;
+use syn::Path;
+use syn::PathArguments;
+use syn::parse_quote;
+
+pub(crate) fn derive(input: DeriveInput) -> ItemImpl {
+    let mut crate_path: Option<Path> = None;
+    let mut plain: Option<Path> = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("strip"))
+    {
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                crate_path = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("plain") {
+                plain = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `plain` or `crate`"))
+            }
+        });
+        if let Err(err) = result {
+            abort!(err.span(), "{}", err);
+        }
+    }
+    let Some(plain) = plain else {
+        abort_call_site!("#[derive(Strip)] requires the plain type as #[strip(plain = ...)]");
+    };
+    let stacksafe_crate = crate_path.unwrap_or_else(|| parse_quote!(::stacksafe));
+    let plain_expr = expr_path(&plain);
+
+    let mut strip_arms: Vec<syn::Arm> = Vec::new();
+    let mut wrap_arms: Vec<syn::Arm> = Vec::new();
+    let mut arms = |wrapped: Path, plain: Path, fields: &Fields| {
+        let converted = Converted::new(fields, &stacksafe_crate);
+        let (bindings, stripped, wrapped_fields) =
+            (&converted.bindings, &converted.stripped, &converted.wrapped);
+        let (pattern, strip, wrap) = match fields {
+            Fields::Named(_) => {
+                let names = &converted.names;
+                (
+                    quote!({ #(#names: #bindings),* }),
+                    quote!({ #(#names: #stripped),* }),
+                    quote!({ #(#names: #wrapped_fields),* }),
+                )
+            }
+            Fields::Unnamed(_) => (
+                quote!(( #(#bindings),* )),
+                quote!(( #(#stripped),* )),
+                quote!(( #(#wrapped_fields),* )),
+            ),
+            Fields::Unit => Default::default(),
+        };
+        strip_arms.push(parse_quote!(#wrapped #pattern => #plain #strip));
+        wrap_arms.push(parse_quote!(#plain #pattern => #wrapped #wrap));
+    };
+    match &input.data {
+        Data::Struct(data) => arms(parse_quote!(Self), plain_expr.clone(), &data.fields),
+        Data::Enum(data) => {
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                arms(
+                    parse_quote!(Self::#ident),
+                    parse_quote!(#plain_expr::#ident),
+                    &variant.fields,
+                );
+            }
+        }
+        Data::Union(data) => abort!(data.union_token, "#[derive(Strip)] does not support unions"),
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    parse_quote! {
+        impl #impl_generics #stacksafe_crate::Strip for #ident #ty_generics #where_clause {
+            type Plain = #plain;
+
+            #[#stacksafe_crate::stacksafe(crate = #stacksafe_crate)]
+            fn strip(self) -> #plain {
+                match self {
+                    #(#strip_arms,)*
+                }
+            }
+
+            #[#stacksafe_crate::stacksafe(crate = #stacksafe_crate)]
+            fn wrap(plain: #plain) -> Self {
+                match plain {
+                    #(#wrap_arms,)*
+                }
+            }
+        }
+    }
+}
+
+/// Returns `path` with turbofish generic arguments, for use in expressions and patterns.
+fn expr_path(path: &Path) -> Path {
+    let mut path = path.clone();
+    for segment in &mut path.segments {
+        if let PathArguments::AngleBracketed(args) = &mut segment.arguments {
+            args.colon2_token = Some(Default::default());
+        }
+    }
+    path
+}
+
+/// The bindings of the fields of a struct or variant, and their conversions.
+struct Converted {
+    names: Vec<syn::Member>,
+    bindings: Vec<Ident>,
+    stripped: Vec<syn::Expr>,
+    wrapped: Vec<syn::Expr>,
+}
+
+impl Converted {
+    fn new(fields: &Fields, stacksafe_crate: &Path) -> Converted {
+        let mut converted = Converted {
+            names: Vec::new(),
+            bindings: Vec::new(),
+            stripped: Vec::new(),
+            wrapped: Vec::new(),
+        };
+        for (index, field) in fields.iter().enumerate() {
+            let binding = format_ident!("__field{}", index);
+            let keep = keep(field);
+            converted.names.push(match &field.ident {
+                Some(ident) => syn::Member::Named(ident.clone()),
+                None => syn::Member::Unnamed(index.into()),
+            });
+            if keep {
+                converted.stripped.push(parse_quote!(#binding));
+                converted.wrapped.push(parse_quote!(#binding));
+            } else {
+                converted
+                    .stripped
+                    .push(parse_quote!(#stacksafe_crate::Strip::strip(#binding)));
+                converted
+                    .wrapped
+                    .push(parse_quote!(#stacksafe_crate::Strip::wrap(#binding)));
+            }
+            converted.bindings.push(binding);
+        }
+        converted
+    }
+}
+
+/// Returns `true` if the field is marked with `#[strip(keep)]`.
+fn keep(field: &syn::Field) -> bool {
+    let mut keep = false;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("strip"))
+    {
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("keep") {
+                keep = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `keep`"))
+            }
+        });
+        if let Err(err) = result {
+            abort!(err.span(), "{}", err);
+        }
+    }
+    keep
+}
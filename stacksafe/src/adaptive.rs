@@ -0,0 +1,96 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-callsite red zones learned from observed frame sizes.
+//!
+//! Every guarded call records the stack pointer it was entered at. When a nested guarded call is
+//! entered on the same segment, the distance between the two is the stack consumed by one
+//! activation of the outer function, and the largest distance seen so far is kept in its
+//! [`Site`]. The red zone checked for a site is then derived from its largest frame rather than
+//! from the global minimum.
+
+use std::cell::Cell;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use crate::internal::Site;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// The red zone is never lowered below this, so that unguarded leaf calls, which are not
+// measured, keep some headroom.
+pub(crate) const FLOOR: usize = 32 * 1024;
+
+// How many of its largest observed frames a site must still fit on the current segment.
+const FRAMES: usize = 4;
+
+pub(crate) fn set_enabled(enabled: bool) {
+    if enabled {
+        crate::hook::install();
+    }
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[inline(always)]
+pub(crate) fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    sp: usize,
+    depth: usize,
+    site: &'static Site,
+}
+
+thread_local! {
+    // The innermost guarded call of the current thread.
+    static LAST: Cell<Option<Entry>> = const { Cell::new(None) };
+}
+
+/// Records a guarded call for `site`, and restores the enclosing one when the call returns.
+pub(crate) struct Enter(Option<Entry>);
+
+impl Enter {
+    pub(crate) fn new(site: &'static Site) -> Enter {
+        let entry = Entry {
+            sp: psm::stack_pointer() as usize,
+            depth: crate::segment::depth(),
+            site,
+        };
+        let previous = LAST.with(|l| l.replace(Some(entry)));
+        if let Some(previous) = previous {
+            // Calls on different segments are not comparable.
+            if previous.depth == entry.depth && previous.sp > entry.sp {
+                previous.site.observe_frame(previous.sp - entry.sp);
+            }
+        }
+        Enter(previous)
+    }
+}
+
+impl Drop for Enter {
+    fn drop(&mut self) {
+        LAST.with(|l| l.set(self.0));
+    }
+}
+
+/// Returns the red zone to check for `site` given the configured `minimum`.
+pub(crate) fn red_zone(site: &Site, minimum: usize) -> usize {
+    match site.largest_frame() {
+        // Nothing has been learned yet.
+        0 => minimum,
+        frame => frame.saturating_mul(FRAMES).max(minimum.min(FLOOR)),
+    }
+}
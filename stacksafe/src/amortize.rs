@@ -0,0 +1,112 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Stack checks amortized over several guarded calls.
+//!
+//! Consecutive calls to a function with a check interval count down a thread-local counter, and
+//! only the call that finds it exhausted checks the remaining stack space. That call checks for a
+//! red zone enlarged by the stack the calls skipping the check until the next one may consume,
+//! which is bounded by the largest distance the stack pointer ever moved between two consecutive
+//! calls to the function. A call to another function with a check interval, a call on another
+//! segment, or a call that moves the stack pointer further than that bound checks again.
+
+use std::cell::Cell;
+
+use crate::internal::Site;
+
+// The frame size assumed for calls to a function before any has been observed.
+const UNKNOWN_FRAME: usize = 1024;
+
+#[derive(Clone, Copy)]
+struct State {
+    // The function whose calls may skip the check, and how many of them still may.
+    site: *const Site,
+    left: usize,
+    // The stack pointer and the segment depth at the last call.
+    sp: usize,
+    depth: usize,
+    // The distance between consecutive calls the red zone of the last check accounted for.
+    frame: usize,
+}
+
+thread_local! {
+    static STATE: Cell<State> = const {
+        Cell::new(State {
+            site: std::ptr::null(),
+            left: 0,
+            sp: 0,
+            depth: 0,
+            frame: 0,
+        })
+    };
+}
+
+/// Returns `true` if the current call to `site` may skip checking the remaining stack space.
+#[inline(always)]
+pub(crate) fn skip(site: &Site) -> bool {
+    STATE.with(|s| {
+        let state = s.get();
+        if state.left == 0 || !std::ptr::eq(state.site, site) {
+            return false;
+        }
+        let sp = psm::stack_pointer() as usize;
+        if state.sp.saturating_sub(sp) > state.frame || crate::segment::depth() != state.depth {
+            return false;
+        }
+        s.set(State {
+            left: state.left - 1,
+            sp,
+            ..state
+        });
+        true
+    })
+}
+
+/// Starts a new interval of `interval` calls to `site`, and returns the red zone its check must
+/// use so that the calls skipping the check until the next one fit on the stack as well.
+///
+/// If they need more than half of what a new segment has left above the red zone, the calls do
+/// not skip the check at all, and `red_zone` is returned unchanged.
+pub(crate) fn red_zone(site: &Site, interval: usize, red_zone: usize, stack_size: usize) -> usize {
+    let sp = psm::stack_pointer() as usize;
+    let depth = crate::segment::depth();
+    let last = STATE.get();
+    // Calls to other functions and calls on different segments are not comparable.
+    if std::ptr::eq(last.site, site) && last.depth == depth && last.sp > sp {
+        site.observe_frame(last.sp - sp);
+    }
+    let frame = match site.largest_frame() {
+        0 => UNKNOWN_FRAME,
+        frame => frame,
+    };
+    let extra = interval
+        .checked_mul(frame)
+        .filter(|&extra| extra <= stack_size.saturating_sub(red_zone) / 2);
+    STATE.set(State {
+        site,
+        left: if extra.is_some() { interval - 1 } else { 0 },
+        sp,
+        depth,
+        frame,
+    });
+    red_zone + extra.unwrap_or(0)
+}
+
+/// Makes the next call check the remaining stack space again, once the stack it grew into is left.
+pub(crate) struct Restart;
+
+impl Drop for Restart {
+    fn drop(&mut self) {
+        STATE.with(|s| s.set(State { left: 0, ..s.get() }));
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::internal::Site;
+
+/// Recursive data structures whose nodes can take back the children they were dismantled into.
+///
+/// This is the inverse of [`Dismantle`](crate::Dismantle): implementations move every node in
+/// `children` back into `self`, in the order in which
+/// [`Dismantle::dismantle`](crate::Dismantle::dismantle) handed them over. Together, the two traits
+/// let a recursive structure be converted to and from index-based representations, such as those of
+/// the `indextree` and `petgraph` modules, and rewritten with [`map_tree`], without recursion.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::Assemble;
+/// use stacksafe::Dismantle;
+/// use stacksafe::StackSafe;
+///
+/// struct Tree {
+///     value: u32,
+///     children: Vec<StackSafe<Tree>>,
+/// }
+///
+/// impl Dismantle for Tree {
+///     fn dismantle(&mut self, children: &mut Vec<Self>) {
+///         children.extend(self.children.drain(..).map(StackSafe::into_inner));
+///     }
+/// }
+///
+/// impl Assemble for Tree {
+///     fn assemble(&mut self, children: Vec<Self>) {
+///         self.children
+///             .extend(children.into_iter().map(StackSafe::new));
+///     }
+/// }
+/// ```
+pub trait Assemble: Sized {
+    /// Moves `children` back into `self`.
+    ///
+    /// This is always called in a stack-safe context.
+    fn assemble(&mut self, children: Vec<Self>);
+}
+
+/// Rebuilds `root` by applying `f` to every node, bottom-up.
+///
+/// Every node is [dismantled](crate::Dismantle), its children are mapped first, and then `f` is
+/// applied to the node after its mapped children have been [assembled](Assemble) back into it. The
+/// tree is traversed with an explicit worklist in a stack-safe context, so `f` may inspect the
+/// node's children through [`StackSafe<T>`](crate::StackSafe) and the tree may be of any depth.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::Assemble;
+/// use stacksafe::Dismantle;
+/// use stacksafe::StackSafe;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Expr {
+///     Num(i64),
+///     Add(Vec<StackSafe<Expr>>),
+/// }
+///
+/// impl Dismantle for Expr {
+///     fn dismantle(&mut self, children: &mut Vec<Self>) {
+///         if let Expr::Add(operands) = self {
+///             children.extend(operands.drain(..).map(StackSafe::into_inner));
+///         }
+///     }
+/// }
+///
+/// impl Assemble for Expr {
+///     fn assemble(&mut self, children: Vec<Self>) {
+///         if let Expr::Add(operands) = self {
+///             operands.extend(children.into_iter().map(StackSafe::new));
+///         }
+///     }
+/// }
+///
+/// // Constant folding: every `Add` whose operands are all numbers becomes a number.
+/// let fold = |expr: Expr| match &expr {
+///     Expr::Add(operands) => {
+///         let values: Option<Vec<i64>> = (operands.iter())
+///             .map(|operand| match **operand {
+///                 Expr::Num(value) => Some(value),
+///                 _ => None,
+///             })
+///             .collect();
+///         values.map_or(expr, |values| Expr::Num(values.into_iter().sum()))
+///     }
+///     Expr::Num(_) => expr,
+/// };
+///
+/// let expr = (0..100_000).fold(Expr::Num(0), |expr, _| {
+///     Expr::Add(vec![StackSafe::new(expr), StackSafe::new(Expr::Num(1))])
+/// });
+/// assert_eq!(stacksafe::map_tree(expr, fold), Expr::Num(100_000));
+/// ```
+pub fn map_tree<T: crate::Dismantle + Assemble>(root: T, mut f: impl FnMut(T) -> T) -> T {
+    static SITE: Site = Site::new("stacksafe::map_tree");
+    crate::internal::guard(&SITE, || map_nodes(root, &mut f))
+}
+
+/// Applies `f` to every node of `root` in place, bottom-up.
+///
+/// This is the in-place counterpart of [`map_tree`]: the children of `root` are mapped first, then
+/// `f` is applied to `root` itself once its children have been assembled back into it.
+pub fn map_tree_in_place<T: crate::Dismantle + Assemble>(root: &mut T, mut f: impl FnMut(&mut T)) {
+    static SITE: Site = Site::new("stacksafe::map_tree_in_place");
+    crate::internal::guard(&SITE, || {
+        let mut children = Vec::new();
+        root.dismantle(&mut children);
+        let children = (children.into_iter())
+            .map(|child| {
+                map_nodes(child, &mut |mut node| {
+                    f(&mut node);
+                    node
+                })
+            })
+            .collect();
+        root.assemble(children);
+        f(root);
+    })
+}
+
+/// Builds a tree incrementally from its nodes in pre-order.
+///
+/// Every node is pushed without its children, along with the number of children that follow it;
+/// once the last of them is complete, they are [assembled](Assemble) into it. The nodes that are
+/// still missing children are kept on an explicit stack, so trees of any depth are built without
+/// recursion, and without growing the stack. This makes it possible to construct deep structures
+/// from flat, streamed input, such as with `serde::deserialize_preorder`.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::Assemble;
+/// use stacksafe::PreorderBuilder;
+/// use stacksafe::StackSafe;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Tree {
+///     value: u32,
+///     children: Vec<StackSafe<Tree>>,
+/// }
+///
+/// impl Assemble for Tree {
+///     fn assemble(&mut self, children: Vec<Self>) {
+///         self.children
+///             .extend(children.into_iter().map(StackSafe::new));
+///     }
+/// }
+///
+/// let node = |value| Tree {
+///     value,
+///     children: vec![],
+/// };
+///
+/// let mut builder = PreorderBuilder::new();
+/// builder.extend([(node(1), 2), (node(2), 1), (node(3), 0)]);
+/// assert!(!builder.is_complete());
+/// builder.push(node(4), 0);
+/// assert!(builder.is_complete());
+///
+/// let tree = builder.finish().unwrap();
+/// let expected = Tree {
+///     value: 1,
+///     children: vec![
+///         StackSafe::new(Tree {
+///             value: 2,
+///             children: vec![StackSafe::new(node(3))],
+///         }),
+///         StackSafe::new(node(4)),
+///     ],
+/// };
+/// assert_eq!(tree, expected);
+/// ```
+pub struct PreorderBuilder<T> {
+    // The nodes still missing children, each with the number it still needs and those built so
+    // far.
+    open: Vec<(T, usize, Vec<T>)>,
+    root: Option<T>,
+}
+
+impl<T: Assemble> PreorderBuilder<T> {
+    /// Creates a builder for a tree without nodes.
+    pub fn new() -> Self {
+        PreorderBuilder {
+            open: Vec::new(),
+            root: None,
+        }
+    }
+
+    /// Adds the next node in pre-order, which is followed by `children` children.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree is already complete.
+    pub fn push(&mut self, node: T, children: usize) {
+        static SITE: Site = Site::new("stacksafe::PreorderBuilder::push");
+        assert!(!self.is_complete(), "the tree is already complete");
+        if children > 0 {
+            self.open.push((node, children, Vec::new()));
+            return;
+        }
+        crate::internal::guard(&SITE, || {
+            let mut node = node;
+            // Hand the complete node to its parent, completing the parent in turn if this was its
+            // last child.
+            while let Some((_, missing, siblings)) = self.open.last_mut() {
+                siblings.push(node);
+                *missing -= 1;
+                if *missing > 0 {
+                    return;
+                }
+                let (mut parent, _, children) = self.open.pop().unwrap();
+                parent.assemble(children);
+                node = parent;
+            }
+            self.root = Some(node);
+        })
+    }
+
+    /// Returns `true` if the root node and all of its descendants have been pushed.
+    pub fn is_complete(&self) -> bool {
+        self.root.is_some()
+    }
+
+    /// Returns the tree, or `None` if it is not complete.
+    pub fn finish(self) -> Option<T> {
+        self.root
+    }
+}
+
+impl<T: Assemble> Default for PreorderBuilder<T> {
+    fn default() -> Self {
+        PreorderBuilder::new()
+    }
+}
+
+impl<T: Assemble> Extend<(T, usize)> for PreorderBuilder<T> {
+    fn extend<I: IntoIterator<Item = (T, usize)>>(&mut self, iter: I) {
+        for (node, children) in iter {
+            self.push(node, children);
+        }
+    }
+}
+
+fn map_nodes<T: crate::Dismantle + Assemble>(root: T, f: &mut dyn FnMut(T) -> T) -> T {
+    struct Frame<T> {
+        node: T,
+        // The children that have not been mapped yet, in reverse order.
+        pending: Vec<T>,
+        mapped: Vec<T>,
+    }
+
+    let dismantle = |mut node: T| {
+        let mut pending = Vec::new();
+        node.dismantle(&mut pending);
+        pending.reverse();
+        Frame {
+            node,
+            mapped: Vec::with_capacity(pending.len()),
+            pending,
+        }
+    };
+
+    let mut stack = vec![dismantle(root)];
+    loop {
+        let frame = stack.last_mut().expect("the root frame is popped last");
+        if let Some(child) = frame.pending.pop() {
+            stack.push(dismantle(child));
+            continue;
+        }
+        let Frame {
+            mut node, mapped, ..
+        } = stack.pop().expect("the root frame is popped last");
+        node.assemble(mapped);
+        let node = f(node);
+        match stack.last_mut() {
+            Some(parent) => parent.mapped.push(node),
+            None => return node,
+        }
+    }
+}
+
+/// Dismantles `root` and all of its descendants, passing each node to `insert` along with its
+/// parent's id and its position among its siblings. Parents are always inserted before their
+/// children, and siblings in order. Returns the id of the root.
+#[cfg(any(feature = "indextree", feature = "petgraph"))]
+pub(crate) fn flatten<T: crate::Dismantle, I: Copy>(
+    root: T,
+    mut insert: impl FnMut(T, Option<(I, usize)>) -> I,
+) -> I {
+    let mut pending = vec![(root, None)];
+    let mut children = Vec::new();
+    let mut root_id = None;
+    while let Some((mut node, parent)) = pending.pop() {
+        node.dismantle(&mut children);
+        let id = insert(node, parent);
+        root_id.get_or_insert(id);
+        // Push the children in reverse so that they are popped, and inserted, in order.
+        let children = children.drain(..).enumerate().rev();
+        pending.extend(children.map(|(position, child)| (child, Some((id, position)))));
+    }
+    root_id.expect("the root is always inserted")
+}
+
+/// Assembles the node at index `root` of `nodes` from its descendants, where `children[i]` lists
+/// the indices of the children of node `i`, in order.
+///
+/// # Panics
+///
+/// Panics if the nodes reachable from `root` do not form a tree.
+#[cfg(any(feature = "indextree", feature = "petgraph"))]
+pub(crate) fn unflatten<T: Assemble>(
+    mut nodes: Vec<Option<T>>,
+    children: &[Vec<usize>],
+    root: usize,
+) -> T {
+    // Order the nodes so that every node comes before its children.
+    let mut seen = vec![false; nodes.len()];
+    let mut order = Vec::new();
+    let mut pending = vec![root];
+    while let Some(index) = pending.pop() {
+        assert!(!seen[index], "the nodes do not form a tree");
+        seen[index] = true;
+        order.push(index);
+        pending.extend(&children[index]);
+    }
+
+    // Assemble the nodes bottom-up, so that every child is complete when its parent takes it.
+    for &index in order.iter().rev() {
+        let taken = children[index]
+            .iter()
+            .map(|&child| nodes[child].take().expect("the node has been removed"))
+            .collect();
+        nodes[index]
+            .as_mut()
+            .expect("the node has been removed")
+            .assemble(taken);
+    }
+    nodes[root].take().expect("the node has been removed")
+}
@@ -0,0 +1,127 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::internal::Site;
+
+/// Runs a backtracking search with an explicit stack of choice points.
+///
+/// Pattern matchers, parsers with ordered choice, and constraint solvers are naturally written
+/// as recursion that tries one alternative after another, but their recursion is irregular and
+/// spread over many small functions. [`backtrack`] drives such a search in a loop instead: every
+/// state to be explored is handed to `step`, which either returns a result, ending the search, or
+/// returns `None` after pushing any number of follow-up states to the [`Choices`]. States pushed
+/// by one step are explored in the order they were pushed, depth first, before the remaining
+/// states pushed by earlier steps, which is exactly the order the recursive formulation would try
+/// them in. If no states are left, the search fails and `None` is returned.
+///
+/// The search runs in a stack-safe context, so `step` may access [`StackSafe<T>`](crate::StackSafe)
+/// values and call functions marked with [`#[stacksafe]`](crate::stacksafe), but the search
+/// itself does not grow the stack, no matter how deep it goes.
+///
+/// # Examples
+///
+/// A glob matcher supporting `*` and `?`:
+///
+/// ```rust
+/// fn glob(pattern: &[u8], text: &[u8]) -> bool {
+///     stacksafe::backtrack((0, 0), |(p, t), choices| {
+///         match pattern.get(p) {
+///             None => return (t == text.len()).then_some(()),
+///             Some(b'*') => {
+///                 // Try to match the empty string first, then one more character.
+///                 choices.push((p + 1, t));
+///                 if t < text.len() {
+///                     choices.push((p, t + 1));
+///                 }
+///             }
+///             Some(&c) => {
+///                 if t < text.len() && (c == b'?' || c == text[t]) {
+///                     choices.push((p + 1, t + 1));
+///                 }
+///             }
+///         }
+///         None
+///     })
+///     .is_some()
+/// }
+///
+/// let text = "a".repeat(100_000) + "b";
+/// assert!(glob(b"*a?", text.as_bytes()));
+/// assert!(!glob(b"*c*", text.as_bytes()));
+/// ```
+pub fn backtrack<S, R>(
+    initial: S,
+    mut step: impl FnMut(S, &mut Choices<S>) -> Option<R>,
+) -> Option<R> {
+    static SITE: Site = Site::new("stacksafe::backtrack");
+    crate::internal::guard(&SITE, || {
+        let mut choices = Choices {
+            stack: vec![initial],
+            base: 0,
+        };
+        while let Some(state) = choices.stack.pop() {
+            choices.base = choices.stack.len();
+            if let Some(ret) = step(state, &mut choices) {
+                return Some(ret);
+            }
+            // The most recently pushed state is explored first, so the new ones are reversed to
+            // explore them in the order they were pushed.
+            let base = choices.base;
+            choices.stack[base..].reverse();
+        }
+        None
+    })
+}
+
+/// The pending choice points of a search run by [`backtrack`].
+#[derive(Debug)]
+pub struct Choices<S> {
+    stack: Vec<S>,
+    // The number of states pushed before the current step.
+    base: usize,
+}
+
+impl<S> Choices<S> {
+    /// Adds a state to explore after the current one.
+    ///
+    /// States pushed by the same step are explored in the order they were pushed.
+    pub fn push(&mut self, state: S) {
+        self.stack.push(state);
+    }
+
+    /// Discards all pending states, including those pushed by the current step so far.
+    ///
+    /// This commits the search to the states pushed afterwards, like a cut in Prolog or an atomic
+    /// group in a regular expression.
+    pub fn cut(&mut self) {
+        self.stack.clear();
+        self.base = 0;
+    }
+
+    /// Returns the number of pending states.
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Returns `true` if there are no pending states.
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+}
+
+impl<S> Extend<S> for Choices<S> {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        self.stack.extend(iter);
+    }
+}
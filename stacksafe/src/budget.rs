@@ -0,0 +1,119 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+
+use crate::Error;
+
+/// A quota on the nesting depth of guarded calls, for interpreters that report running out of it
+/// to the program they run.
+///
+/// Functions marked with [`#[stacksafe]`](crate::stacksafe) never overflow the stack, but an
+/// interpreter for a language with a recursion limit still needs to stop runaway recursion in the
+/// interpreted program, and report it as an error of that program instead of growing the stack
+/// until memory runs out. A [`CallBudget`] counts the frames that are currently entered with
+/// [`enter`](Self::enter), which fails with [`Error::BudgetExceeded`] once `max_frames` frames
+/// are in use. Each [`Frame`] gives its slot back when it is dropped, so the budget recovers as the
+/// recursion unwinds, including after an error.
+///
+/// A [`CallBudget`] is meant to be passed down the recursion on a single thread, so it is [`Send`]
+/// but not [`Sync`].
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::CallBudget;
+/// use stacksafe::Error;
+/// use stacksafe::stacksafe;
+///
+/// enum Expr {
+///     Num(i64),
+///     Neg(Box<Expr>),
+/// }
+///
+/// #[stacksafe]
+/// fn eval(expr: &Expr, budget: &CallBudget) -> Result<i64, Error> {
+///     let _frame = budget.enter()?;
+///     match expr {
+///         Expr::Num(n) => Ok(*n),
+///         Expr::Neg(inner) => Ok(-eval(inner, budget)?),
+///     }
+/// }
+///
+/// let expr = (0..10_000).fold(Expr::Num(1), |expr, _| Expr::Neg(Box::new(expr)));
+///
+/// assert_eq!(eval(&expr, &CallBudget::new(20_000)).unwrap(), 1);
+///
+/// let budget = CallBudget::new(1000);
+/// let err = eval(&expr, &budget).unwrap_err();
+/// assert!(matches!(err, Error::BudgetExceeded { budget: 1000 }));
+/// assert_eq!(budget.depth(), 0);
+/// ```
+#[derive(Debug)]
+pub struct CallBudget {
+    max_frames: usize,
+    depth: Cell<usize>,
+}
+
+impl CallBudget {
+    /// Creates a budget that allows up to `max_frames` frames to be entered at the same time.
+    pub const fn new(max_frames: usize) -> Self {
+        CallBudget {
+            max_frames,
+            depth: Cell::new(0),
+        }
+    }
+
+    /// Enters a frame, or returns [`Error::BudgetExceeded`] if all frames are in use.
+    ///
+    /// The frame is left when the returned [`Frame`] is dropped.
+    pub fn enter(&self) -> Result<Frame<'_>, Error> {
+        let depth = self.depth.get();
+        if depth >= self.max_frames {
+            return Err(Error::BudgetExceeded {
+                budget: self.max_frames,
+            });
+        }
+        self.depth.set(depth + 1);
+        Ok(Frame { budget: self })
+    }
+
+    /// Returns the maximum number of frames that can be entered at the same time.
+    pub fn max_frames(&self) -> usize {
+        self.max_frames
+    }
+
+    /// Returns the number of frames that are currently entered.
+    pub fn depth(&self) -> usize {
+        self.depth.get()
+    }
+
+    /// Returns the number of frames that can still be entered.
+    pub fn remaining(&self) -> usize {
+        self.max_frames - self.depth.get()
+    }
+}
+
+/// A frame entered with [`CallBudget::enter`], which is left when it is dropped.
+#[derive(Debug)]
+#[must_use = "the frame is left immediately if it is not kept alive"]
+pub struct Frame<'a> {
+    budget: &'a CallBudget,
+}
+
+impl Drop for Frame<'_> {
+    fn drop(&mut self) {
+        self.budget.depth.set(self.budget.depth.get() - 1);
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Allocation of recursive structures in [`bumpalo`] arenas.
+//!
+//! Compilers and similar programs often allocate their syntax trees in an arena and free them all
+//! at once. A [`StackSafe<&'bump mut T>`](StackSafe) node created with
+//! [`StackSafe::new_in`] lives in a [`Bump`], so dropping a tree of such nodes does nothing at all:
+//! no destructor runs and no stack-safe context is entered. Traversals still go through
+//! [`#[stacksafe]`](crate::stacksafe) functions as usual, and [`CloneIn`] copies a structure into
+//! an arena with the same protection. [`clone_into_arena`] compacts a structure built from
+//! [`StackSafe<Box<T>>`](StackSafe) nodes, for example by a parser, into an arena-allocated
+//! counterpart.
+//!
+//! Like every value allocated in a [`Bump`], the wrapped values are never dropped. Arena nodes
+//! should therefore not own resources that need to be released, such as heap allocations.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use bumpalo::Bump;
+//! use stacksafe::StackSafe;
+//! use stacksafe::bumpalo::CloneIn;
+//! use stacksafe::stacksafe;
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum Expr<'bump> {
+//!     Num(i64),
+//!     Add(
+//!         StackSafe<&'bump mut Expr<'bump>>,
+//!         StackSafe<&'bump mut Expr<'bump>>,
+//!     ),
+//! }
+//!
+//! impl<'bump> CloneIn<'bump> for Expr<'bump> {
+//!     fn clone_in(&self, bump: &'bump Bump) -> Self {
+//!         match self {
+//!             Expr::Num(value) => Expr::Num(*value),
+//!             Expr::Add(lhs, rhs) => Expr::Add(lhs.clone_in(bump), rhs.clone_in(bump)),
+//!         }
+//!     }
+//! }
+//!
+//! #[stacksafe]
+//! fn eval(expr: &Expr) -> i64 {
+//!     match expr {
+//!         Expr::Num(value) => *value,
+//!         Expr::Add(lhs, rhs) => eval(lhs) + eval(rhs),
+//!     }
+//! }
+//!
+//! let bump = Bump::new();
+//! let expr = (0..100_000).fold(Expr::Num(0), |expr, _| {
+//!     Expr::Add(
+//!         StackSafe::new_in(expr, &bump),
+//!         StackSafe::new_in(Expr::Num(1), &bump),
+//!     )
+//! });
+//! assert_eq!(eval(&expr), 100_000);
+//!
+//! let copy = expr.clone_in(&bump);
+//! assert_eq!(copy, expr);
+//! ```
+
+use ::bumpalo::Bump;
+
+use crate::StackSafe;
+use crate::stacksafe;
+
+impl<'bump, T> StackSafe<&'bump mut T> {
+    /// Moves `value` into `bump` and wraps the resulting reference in a [`StackSafe`].
+    ///
+    /// The value is never dropped; its memory is released along with the arena.
+    pub fn new_in(value: T, bump: &'bump Bump) -> Self {
+        StackSafe::new(bump.alloc(value))
+    }
+}
+
+/// Values that can be deeply copied into a [`Bump`].
+///
+/// This is the arena counterpart of [`Clone`]: nodes referenced by the value are copied into the
+/// arena as well. The implementations for [`StackSafe`] references run in a stack-safe context,
+/// so implementations for recursive types can simply call [`clone_in`](CloneIn::clone_in) on
+/// their children.
+pub trait CloneIn<'bump>: Sized {
+    /// Returns a copy of `self` whose nodes are allocated in `bump`.
+    fn clone_in(&self, bump: &'bump Bump) -> Self;
+}
+
+impl<'bump, T: CloneIn<'bump>> CloneIn<'bump> for StackSafe<&'bump mut T> {
+    #[stacksafe(crate = crate)]
+    fn clone_in(&self, bump: &'bump Bump) -> Self {
+        StackSafe::new_in(self.0.clone_in(bump), bump)
+    }
+}
+
+impl<'bump, T: CloneIn<'bump>> CloneIn<'bump> for StackSafe<&'bump T> {
+    #[stacksafe(crate = crate)]
+    fn clone_in(&self, bump: &'bump Bump) -> Self {
+        StackSafe::new(bump.alloc(self.0.clone_in(bump)))
+    }
+}
+
+impl<'bump, T: CloneIn<'bump>> CloneIn<'bump> for Option<T> {
+    fn clone_in(&self, bump: &'bump Bump) -> Self {
+        self.as_ref().map(|value| value.clone_in(bump))
+    }
+}
+
+/// Values that can be deeply copied into a [`Bump`] as a different, arena-allocated type.
+///
+/// This converts a structure that owns its nodes, typically through [`StackSafe<Box<T>>`], into
+/// its arena counterpart, typically built from [`StackSafe<&'bump mut T>`](StackSafe). The
+/// implementation for [`StackSafe<Box<T>>`] runs in a stack-safe context, so implementations for
+/// recursive types can simply call [`clone_into_arena`](CloneIntoArena::clone_into_arena) on
+/// their children.
+pub trait CloneIntoArena<'bump> {
+    /// The arena-allocated counterpart of `Self`.
+    type Output: 'bump;
+
+    /// Returns a copy of `self` whose nodes are allocated in `bump`.
+    fn clone_into_arena(&self, bump: &'bump Bump) -> Self::Output;
+}
+
+/// Returns a copy of `value` whose nodes are allocated in `bump`.
+///
+/// See [`CloneIntoArena`].
+///
+/// # Examples
+///
+/// ```rust
+/// use bumpalo::Bump;
+/// use stacksafe::StackSafe;
+/// use stacksafe::bumpalo::CloneIntoArena;
+///
+/// enum Expr {
+///     Num(i64),
+///     Neg(StackSafe<Box<Expr>>),
+/// }
+///
+/// enum ArenaExpr<'bump> {
+///     Num(i64),
+///     Neg(StackSafe<&'bump mut ArenaExpr<'bump>>),
+/// }
+///
+/// impl<'bump> CloneIntoArena<'bump> for Expr {
+///     type Output = ArenaExpr<'bump>;
+///
+///     fn clone_into_arena(&self, bump: &'bump Bump) -> ArenaExpr<'bump> {
+///         match self {
+///             Expr::Num(value) => ArenaExpr::Num(*value),
+///             Expr::Neg(inner) => ArenaExpr::Neg(inner.clone_into_arena(bump)),
+///         }
+///     }
+/// }
+///
+/// let expr = (0..100_000).fold(Expr::Num(1), |expr, _| {
+///     Expr::Neg(StackSafe::new(Box::new(expr)))
+/// });
+///
+/// let bump = Bump::new();
+/// let compact = stacksafe::bumpalo::clone_into_arena(&expr, &bump);
+/// assert!(matches!(compact, ArenaExpr::Neg(_)));
+/// ```
+pub fn clone_into_arena<'bump, T>(value: &T, bump: &'bump Bump) -> T::Output
+where T: ?Sized + CloneIntoArena<'bump> {
+    static SITE: crate::internal::Site =
+        crate::internal::Site::new("stacksafe::bumpalo::clone_into_arena");
+
+    crate::internal::guard(&SITE, || value.clone_into_arena(bump))
+}
+
+impl<'bump, T: CloneIntoArena<'bump>> CloneIntoArena<'bump> for StackSafe<Box<T>> {
+    type Output = StackSafe<&'bump mut T::Output>;
+
+    #[stacksafe(crate = crate)]
+    fn clone_into_arena(&self, bump: &'bump Bump) -> Self::Output {
+        StackSafe::new_in(self.0.clone_into_arena(bump), bump)
+    }
+}
+
+impl<'bump, T: CloneIntoArena<'bump>> CloneIntoArena<'bump> for Option<T> {
+    type Output = Option<T::Output>;
+
+    fn clone_into_arena(&self, bump: &'bump Bump) -> Self::Output {
+        self.as_ref().map(|value| value.clone_into_arena(bump))
+    }
+}
+
+impl<'bump, T: CloneIntoArena<'bump>> CloneIntoArena<'bump> for [T] {
+    type Output = &'bump mut [T::Output];
+
+    fn clone_into_arena(&self, bump: &'bump Bump) -> Self::Output {
+        bump.alloc_slice_fill_iter(self.iter().map(|value| value.clone_into_arena(bump)))
+    }
+}
+
+impl<'bump, T: CloneIntoArena<'bump>> CloneIntoArena<'bump> for Vec<T> {
+    type Output = &'bump mut [T::Output];
+
+    fn clone_into_arena(&self, bump: &'bump Bump) -> Self::Output {
+        self.as_slice().clone_into_arena(bump)
+    }
+}
@@ -0,0 +1,332 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::fmt;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// A validated set of stack configuration values.
+///
+/// The individual setters such as [`set_minimum_stack_size`](crate::set_minimum_stack_size)
+/// accept any value, even when it is inconsistent with the rest of the configuration. For
+/// example, a minimum stack size of 8 MiB combined with 2 MiB segments means that every new
+/// segment is already below the threshold, so each guarded call grows the stack again.
+/// [`StackConfig`] checks such combinations and reports them as a [`ConfigError`] before anything
+/// is changed.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::StackConfig;
+///
+/// StackConfig::current()
+///     .minimum_stack_size(256 * 1024)
+///     .stack_allocation_size(4 * 1024 * 1024)
+///     .apply()
+///     .unwrap();
+///
+/// let err = StackConfig::current()
+///     .minimum_stack_size(8 * 1024 * 1024)
+///     .stack_allocation_size(2 * 1024 * 1024)
+///     .apply()
+///     .unwrap_err();
+/// println!("{err}");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackConfig {
+    minimum_stack_size: usize,
+    stack_allocation_size: usize,
+}
+
+impl Default for StackConfig {
+    /// Returns the default configuration: a minimum stack size of 128 KiB and 2 MiB segments.
+    fn default() -> Self {
+        StackConfig {
+            minimum_stack_size: 128 * 1024,
+            stack_allocation_size: 2 * 1024 * 1024,
+        }
+    }
+}
+
+impl StackConfig {
+    /// Returns the configuration currently in effect on the current thread, which is the one
+    /// of the innermost [`scope`](StackConfig::scope) running on it, if any, or the global one.
+    pub fn current() -> Self {
+        let (minimum_stack_size, stack_allocation_size) = effective_sizes();
+        StackConfig {
+            minimum_stack_size,
+            stack_allocation_size,
+        }
+    }
+
+    /// Sets the minimum stack space threshold in bytes.
+    ///
+    /// See [`set_minimum_stack_size`](crate::set_minimum_stack_size).
+    pub fn minimum_stack_size(mut self, bytes: usize) -> Self {
+        self.minimum_stack_size = bytes;
+        self
+    }
+
+    /// Sets the size of newly allocated stack segments in bytes.
+    ///
+    /// See [`set_stack_allocation_size`](crate::set_stack_allocation_size).
+    pub fn stack_allocation_size(mut self, bytes: usize) -> Self {
+        self.stack_allocation_size = bytes;
+        self
+    }
+
+    /// Returns the configured minimum stack space threshold in bytes.
+    pub fn get_minimum_stack_size(&self) -> usize {
+        self.minimum_stack_size
+    }
+
+    /// Returns the configured size of newly allocated stack segments in bytes.
+    pub fn get_stack_allocation_size(&self) -> usize {
+        self.stack_allocation_size
+    }
+
+    /// Checks that the values are consistent with each other.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.minimum_stack_size == 0 {
+            return Err(ConfigError::ZeroMinimumStackSize);
+        }
+        if self.stack_allocation_size <= self.minimum_stack_size {
+            return Err(ConfigError::SegmentTooSmall {
+                minimum_stack_size: self.minimum_stack_size,
+                stack_allocation_size: self.stack_allocation_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Validates the configuration and, if it is consistent, makes it the one in effect.
+    ///
+    /// Nothing is changed if validation fails.
+    pub fn apply(self) -> Result<(), ConfigError> {
+        self.validate()?;
+        crate::set_minimum_stack_size(self.minimum_stack_size);
+        crate::set_stack_allocation_size(self.stack_allocation_size);
+        Ok(())
+    }
+
+    /// Validates the configuration and, if it is consistent, runs `f` with it in effect on the
+    /// current thread instead of the global one.
+    ///
+    /// This lets a library tune stack growth for its own call tree without changing the global
+    /// configuration that other code relies on, and lets tests use different configurations
+    /// without racing each other. The configuration applies to guarded functions called by `f`
+    /// on the current thread that do not use a [profile](crate::register_profile) or sizes of
+    /// their own. It only carries over to other threads started with the functions of the
+    /// [`thread`](crate::thread) module, and to rayon tasks started with the helpers of the `rayon`
+    /// feature. Scopes nest, and the previous configuration is restored when `f` returns or
+    /// panics. Functions compiled against another major version of this crate do not see the
+    /// scope, see [Configuration](crate#configuration).
+    ///
+    /// Nothing is run if validation fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use stacksafe::StackConfig;
+    /// use stacksafe::stacksafe;
+    ///
+    /// #[stacksafe]
+    /// fn segment_size(n: u64) -> Option<usize> {
+    ///     if n == 0 {
+    ///         stacksafe::current_segment().map(|segment| segment.size())
+    ///     } else {
+    ///         segment_size(n - 1)
+    ///     }
+    /// }
+    ///
+    /// let config = StackConfig::default().stack_allocation_size(16 * 1024 * 1024);
+    /// let size = config.scope(|| segment_size(1_000_000)).unwrap();
+    /// if let Some(size) = size {
+    ///     assert!(size >= 16 * 1024 * 1024);
+    /// }
+    /// assert_eq!(stacksafe::get_stack_allocation_size(), 2 * 1024 * 1024);
+    /// ```
+    pub fn scope<R>(self, f: impl FnOnce() -> R) -> Result<R, ConfigError> {
+        self.validate()?;
+        observe_red_zone(self.minimum_stack_size);
+        let sizes = (self.minimum_stack_size, self.stack_allocation_size);
+        Ok(with_scoped_sizes(Some(sizes), f))
+    }
+}
+
+thread_local! {
+    // The minimum stack size and stack allocation size of the innermost scope on this thread.
+    static SCOPED: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+/// Returns the minimum stack size and the stack allocation size of the innermost scope on the
+/// current thread, if any.
+pub(crate) fn scoped_sizes() -> Option<(usize, usize)> {
+    SCOPED.with(|s| s.get())
+}
+
+/// Runs `f` with `sizes` as the innermost scope on the current thread, and restores the previous
+/// one when `f` returns or panics.
+pub(crate) fn with_scoped_sizes<R>(sizes: Option<(usize, usize)>, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<(usize, usize)>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED.with(|s| s.set(self.0));
+        }
+    }
+
+    let _restore = Restore(SCOPED.with(|s| s.replace(sizes)));
+    f()
+}
+
+/// Returns the minimum stack size and the stack allocation size in effect on the current thread
+/// for functions that do not use a profile.
+#[inline(always)]
+pub(crate) fn effective_sizes() -> (usize, usize) {
+    match SCOPED.with(|s| s.get()) {
+        Some(sizes) => sizes,
+        None => (
+            crate::get_minimum_stack_size(),
+            crate::get_stack_allocation_size(),
+        ),
+    }
+}
+
+/// A named configuration registered with [`register_profile`](crate::register_profile).
+///
+/// Profiles are never deallocated, so that sites can cache a reference to the profile they use.
+pub(crate) struct Profile {
+    name: Box<str>,
+    minimum_stack_size: AtomicUsize,
+    stack_allocation_size: AtomicUsize,
+}
+
+impl Profile {
+    pub(crate) fn minimum_stack_size(&self) -> usize {
+        self.minimum_stack_size.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn stack_allocation_size(&self) -> usize {
+        self.stack_allocation_size.load(Ordering::Relaxed)
+    }
+}
+
+static PROFILES: RwLock<Vec<&'static Profile>> = RwLock::new(Vec::new());
+
+// How many profiles have been registered, so that functions know when to look theirs up again.
+static PROFILE_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+// The smallest minimum stack size any profile has ever been registered with, any scope has ever
+// been entered with, or any function with its own red zone has ever been called with.
+static SMALLEST_PROFILE_MINIMUM: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Notes that a guarded function checks for a red zone of `bytes`, which may be smaller than any
+/// configured one.
+#[inline(always)]
+pub(crate) fn observe_red_zone(bytes: usize) {
+    if SMALLEST_PROFILE_MINIMUM.load(Ordering::Relaxed) > bytes {
+        SMALLEST_PROFILE_MINIMUM.fetch_min(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Returns the smallest red zone that a guarded function may currently check for.
+#[cfg_attr(not(feature = "verify-stack"), allow(dead_code))]
+pub(crate) fn smallest_red_zone() -> usize {
+    let mut red_zone =
+        crate::get_minimum_stack_size().min(SMALLEST_PROFILE_MINIMUM.load(Ordering::Relaxed));
+    if crate::adaptive::is_enabled() {
+        red_zone = red_zone.min(crate::adaptive::FLOOR);
+    }
+    red_zone
+}
+
+pub(crate) fn register_profile(name: &str, config: StackConfig) -> Result<(), ConfigError> {
+    config.validate()?;
+    SMALLEST_PROFILE_MINIMUM.fetch_min(config.minimum_stack_size, Ordering::Relaxed);
+    let mut profiles = PROFILES.write().unwrap_or_else(|e| e.into_inner());
+    match profiles.iter().find(|p| &*p.name == name) {
+        Some(profile) => {
+            profile
+                .minimum_stack_size
+                .store(config.minimum_stack_size, Ordering::Relaxed);
+            profile
+                .stack_allocation_size
+                .store(config.stack_allocation_size, Ordering::Relaxed);
+        }
+        None => profiles.push(Box::leak(Box::new(Profile {
+            name: name.into(),
+            minimum_stack_size: AtomicUsize::new(config.minimum_stack_size),
+            stack_allocation_size: AtomicUsize::new(config.stack_allocation_size),
+        }))),
+    }
+    PROFILE_GENERATION.store(profiles.len(), Ordering::Release);
+    Ok(())
+}
+
+pub(crate) fn profile_generation() -> usize {
+    PROFILE_GENERATION.load(Ordering::Acquire)
+}
+
+pub(crate) fn find_profile(name: &str) -> Option<&'static Profile> {
+    let profiles = PROFILES.read().unwrap_or_else(|e| e.into_inner());
+    profiles.iter().find(|p| &*p.name == name).copied()
+}
+
+pub(crate) fn profile_config(name: &str) -> Option<StackConfig> {
+    find_profile(name).map(|profile| StackConfig {
+        minimum_stack_size: profile.minimum_stack_size(),
+        stack_allocation_size: profile.stack_allocation_size(),
+    })
+}
+
+/// An inconsistent [`StackConfig`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// The minimum stack size is zero, so the stack would never grow.
+    ZeroMinimumStackSize,
+    /// New segments are not larger than the minimum stack size, so every guarded call running on
+    /// a new segment would immediately grow the stack again.
+    SegmentTooSmall {
+        /// The configured minimum stack size in bytes.
+        minimum_stack_size: usize,
+        /// The configured segment size in bytes.
+        stack_allocation_size: usize,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::ZeroMinimumStackSize => f.write_str(
+                "the minimum stack size is zero, so the stack would never grow and deep recursion \
+                 would overflow",
+            ),
+            ConfigError::SegmentTooSmall {
+                minimum_stack_size,
+                stack_allocation_size,
+            } => write!(
+                f,
+                "stack segments of {stack_allocation_size} bytes are not larger than the minimum \
+                 stack size of {minimum_stack_size} bytes, so every new segment would immediately \
+                 be grown again; use segments several times larger than the minimum stack size",
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,125 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::cell::RefCell;
+
+thread_local! {
+    // Guarded calls left until the yield hook is invoked, or zero if it is not invoked by count.
+    static COUNTDOWN: Cell<usize> = const { Cell::new(0) };
+    static HOOK: RefCell<Option<YieldHook>> = const { RefCell::new(None) };
+}
+
+struct YieldHook {
+    interval: usize,
+    callback: Box<dyn FnMut()>,
+}
+
+/// Runs `f`, periodically invoking `hook` from within the guarded functions it calls.
+///
+/// Long, deeply recursive computations never reach a point where they could hand control back to
+/// a cooperative scheduler. While `f` runs, `hook` is invoked on the current thread every
+/// `interval` calls to functions marked with [`#[stacksafe]`](crate::stacksafe), and whenever a
+/// new stack segment is allocated. An `interval` of zero only invokes the hook on growth.
+///
+/// The hook may, for example, call [`std::thread::yield_now`], check a deadline or a
+/// cancellation flag and panic to abort the computation, or use an executor-specific facility
+/// such as `tokio::task::block_in_place`. The hook is not invoked recursively: guarded functions
+/// called by the hook itself do not trigger it again, and do not grow the stack.
+///
+/// Hooks installed by nested calls replace the outer hook until the nested call returns.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use std::sync::atomic::AtomicUsize;
+/// use std::sync::atomic::Ordering;
+///
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn count(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + count(n - 1) }
+/// }
+///
+/// let yields = Arc::new(AtomicUsize::new(0));
+/// let hook = {
+///     let yields = yields.clone();
+///     move || {
+///         yields.fetch_add(1, Ordering::Relaxed);
+///         std::thread::yield_now();
+///     }
+/// };
+///
+/// assert_eq!(
+///     stacksafe::with_yield_hook(1000, hook, || count(10_000)),
+///     10_000
+/// );
+/// assert!(yields.load(Ordering::Relaxed) >= 10);
+/// ```
+pub fn with_yield_hook<R>(
+    interval: usize,
+    hook: impl FnMut() + 'static,
+    f: impl FnOnce() -> R,
+) -> R {
+    struct Restore(usize, Option<YieldHook>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            COUNTDOWN.with(|c| c.set(self.0));
+            HOOK.with(|h| *h.borrow_mut() = self.1.take());
+        }
+    }
+
+    crate::hook::install();
+    let hook = YieldHook {
+        interval,
+        callback: Box::new(hook),
+    };
+    let _restore = Restore(
+        COUNTDOWN.with(|c| c.replace(interval)),
+        HOOK.with(|h| h.borrow_mut().replace(hook)),
+    );
+    f()
+}
+
+/// Counts a guarded call, invoking the yield hook if its interval has elapsed.
+#[inline(always)]
+pub(crate) fn tick() {
+    COUNTDOWN.with(|c| match c.get() {
+        0 => {}
+        1 => invoke(),
+        n => c.set(n - 1),
+    })
+}
+
+/// Invokes the yield hook, if any, because a new stack segment is about to be allocated.
+pub(crate) fn on_growth() {
+    invoke();
+}
+
+#[cold]
+fn invoke() {
+    HOOK.with(|h| {
+        // The hook is already running if it is borrowed, in which case it must not be reentered.
+        let Ok(mut hook) = h.try_borrow_mut() else {
+            return;
+        };
+        if let Some(hook) = hook.as_mut() {
+            COUNTDOWN.with(|c| c.set(hook.interval));
+            crate::hook::run(&mut hook.callback);
+        }
+    })
+}
@@ -0,0 +1,191 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+use std::mem::ManuallyDrop;
+use std::ops::Deref;
+
+use crate::stacksafe;
+
+/// A clone-on-write wrapper for recursive data structures with stack-safe cloning and dropping.
+///
+/// [`StackSafeCow<'a, T>`] either borrows a `T` or owns one, like [`Cow<'a, T>`](Cow). It is
+/// meant for transformation passes that only rewrite a few subtrees of a large recursive
+/// structure: untouched subtrees stay borrowed, and the deep clone of a subtree only happens
+/// on the first mutable access through [`to_mut`](StackSafeCow::to_mut). Both that clone and the
+/// eventual drop of an owned value run with [`#[stacksafe]`](stacksafe) protection.
+///
+/// Like [`StackSafe<T>`](crate::StackSafe), shared access through [`Deref`] is only allowed
+/// within a stack-safe context.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::StackSafeCow;
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn normalize<'a>(name: &'a String) -> StackSafeCow<'a, String> {
+///     let mut name = StackSafeCow::borrowed(name);
+///     if name.contains(' ') {
+///         *name.to_mut() = name.replace(' ', "_");
+///     }
+///     name
+/// }
+///
+/// let name = "foo".to_string();
+/// assert!(normalize(&name).is_borrowed());
+///
+/// let name = "foo bar".to_string();
+/// assert_eq!(normalize(&name).into_owned(), "foo_bar");
+/// ```
+pub struct StackSafeCow<'a, T: Clone>(ManuallyDrop<Cow<'a, T>>);
+
+impl<'a, T: Clone> StackSafeCow<'a, T> {
+    /// Creates a [`StackSafeCow<'a, T>`] that borrows the given value.
+    pub const fn borrowed(value: &'a T) -> Self {
+        StackSafeCow(ManuallyDrop::new(Cow::Borrowed(value)))
+    }
+
+    /// Creates a [`StackSafeCow<'a, T>`] that owns the given value.
+    pub const fn owned(value: T) -> Self {
+        StackSafeCow(ManuallyDrop::new(Cow::Owned(value)))
+    }
+
+    /// Returns `true` if the wrapped value is borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(*self.0, Cow::Borrowed(_))
+    }
+
+    /// Returns `true` if the wrapped value is owned.
+    pub fn is_owned(&self) -> bool {
+        !self.is_borrowed()
+    }
+
+    /// Returns a mutable reference to the owned value, cloning the borrowed value first if
+    /// necessary.
+    ///
+    /// The clone is performed in a stack-safe context.
+    #[stacksafe(crate = crate)]
+    pub fn to_mut(&mut self) -> &mut T {
+        self.0.to_mut()
+    }
+
+    /// Consumes the [`StackSafeCow<'a, T>`] and returns the owned value, cloning the borrowed
+    /// value if necessary.
+    ///
+    /// The clone is performed in a stack-safe context.
+    #[stacksafe(crate = crate)]
+    pub fn into_owned(mut self) -> T {
+        let value = unsafe { ManuallyDrop::take(&mut self.0) };
+        std::mem::forget(self);
+        value.into_owned()
+    }
+}
+
+impl<'a, T: Clone> From<&'a T> for StackSafeCow<'a, T> {
+    fn from(value: &'a T) -> Self {
+        StackSafeCow::borrowed(value)
+    }
+}
+
+impl<T: Clone> From<T> for StackSafeCow<'_, T> {
+    fn from(value: T) -> Self {
+        StackSafeCow::owned(value)
+    }
+}
+
+impl<T: Clone> Deref for StackSafeCow<'_, T> {
+    type Target = T;
+
+    /// Provides transparent access to the borrowed or owned value.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        crate::internal::assert_protected::<T>();
+
+        &self.0
+    }
+}
+
+impl<T: Clone> Clone for StackSafeCow<'_, T> {
+    #[stacksafe(crate = crate)]
+    fn clone(&self) -> Self {
+        StackSafeCow(self.0.clone())
+    }
+}
+
+impl<T: Clone> Drop for StackSafeCow<'_, T> {
+    #[stacksafe(crate = crate)]
+    fn drop(&mut self) {
+        unsafe {
+            ManuallyDrop::drop(&mut self.0);
+        }
+    }
+}
+
+impl<T: Clone + std::fmt::Debug> std::fmt::Debug for StackSafeCow<'_, T> {
+    #[stacksafe(crate = crate)]
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#?}", &**self.0)
+        } else {
+            write!(f, "{:?}", &**self.0)
+        }
+    }
+}
+
+impl<T: Clone + std::fmt::Display> std::fmt::Display for StackSafeCow<'_, T> {
+    #[stacksafe(crate = crate)]
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#}", &**self.0)
+        } else {
+            write!(f, "{}", &**self.0)
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for StackSafeCow<'_, T> {
+    #[stacksafe(crate = crate)]
+    fn eq(&self, other: &Self) -> bool {
+        **self.0 == **other.0
+    }
+}
+
+impl<T: Clone + Eq> Eq for StackSafeCow<'_, T> {}
+
+impl<T: Clone + PartialOrd> PartialOrd for StackSafeCow<'_, T> {
+    #[stacksafe(crate = crate)]
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        (**self.0).partial_cmp(&**other.0)
+    }
+}
+
+impl<T: Clone + Ord> Ord for StackSafeCow<'_, T> {
+    #[stacksafe(crate = crate)]
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (**self.0).cmp(&**other.0)
+    }
+}
+
+impl<T: Clone + std::hash::Hash> std::hash::Hash for StackSafeCow<'_, T> {
+    #[stacksafe(crate = crate)]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (**self.0).hash(state);
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::StackSafe;
+use crate::internal::Site;
+use crate::stacksafe;
+
+/// Dropping of deeply nested values whose types do not use [`StackSafe<T>`].
+///
+/// Recursive types defined by other crates, such as a `Box`-linked list, cannot be changed to
+/// wrap their children, and their drop glue overflows the stack on deep values. [`DeepDrop`]
+/// tears such values down instead: every owned value is moved out and dropped with [`DeepDrop`]
+/// in turn, in a stack-safe context, so the stack grows as needed and the drop glue of each
+/// node only ever has shallow fields left to drop.
+///
+/// [`DeepDrop`] is implemented for [`StackSafe<T>`], [`Box<T>`], [`Option<T>`], [`Vec<T>`] and
+/// tuples, which drop their contents with [`DeepDrop`], and for primitive types and [`String`],
+/// which are dropped as they are. It is implemented with `#[derive(DeepDrop)]`, which drops
+/// every field with [`DeepDrop`] except for fields marked with `#[deep_drop(skip)]`, which are
+/// dropped as usual. Like the standard derives, the derived implementation requires every type
+/// parameter to implement [`DeepDrop`]. Since the fields are moved out, the derive rejects types
+/// that implement [`Drop`] themselves. For types that cannot be derived, such as types of other
+/// crates, implement the trait by hand, moving the nested values out and passing them to
+/// [`DeepDrop::deep_drop`].
+///
+/// ```compile_fail
+/// #[derive(stacksafe::DeepDrop)]
+/// struct Guard(Box<u64>);
+///
+/// impl Drop for Guard {
+///     fn drop(&mut self) {}
+/// }
+/// ```
+///
+/// Types implementing [`Dismantle`](crate::Dismantle) can be dropped without any recursion with
+/// [`drop_all_flattened`](crate::drop_all_flattened) instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::DeepDrop;
+///
+/// // A type from another crate.
+/// pub enum List {
+///     Nil,
+///     Cons(u64, Box<List>),
+/// }
+///
+/// impl DeepDrop for List {
+///     fn deep_drop(self) {
+///         if let List::Cons(_, tail) = self {
+///             tail.deep_drop();
+///         }
+///     }
+/// }
+///
+/// let list = (0..1_000_000).fold(List::Nil, |tail, i| List::Cons(i, Box::new(tail)));
+/// stacksafe::drop_deep(list);
+/// ```
+///
+/// With the derive:
+///
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use stacksafe::DeepDrop;
+///
+/// #[derive(DeepDrop)]
+/// enum Expr {
+///     Num(i64),
+///     Neg(Box<Expr>),
+///     Call {
+///         args: Vec<Expr>,
+///         #[deep_drop(skip)]
+///         attributes: HashMap<String, String>,
+///     },
+/// }
+///
+/// let expr = (0..1_000_000).fold(Expr::Num(1), |expr, _| Expr::Neg(Box::new(expr)));
+/// stacksafe::drop_deep(expr);
+/// ```
+pub trait DeepDrop: Sized {
+    /// Drops this value, dropping everything it owns with [`DeepDrop`] in turn.
+    ///
+    /// Use [`drop_deep`] to call this from outside of a stack-safe context.
+    fn deep_drop(self);
+}
+
+/// Drops a deeply nested value with [`DeepDrop`], growing the stack as needed.
+pub fn drop_deep<T: DeepDrop>(value: T) {
+    static SITE: Site = Site::new("stacksafe::drop_deep");
+    crate::internal::guard(&SITE, || value.deep_drop())
+}
+
+impl<T: DeepDrop> DeepDrop for StackSafe<T> {
+    #[stacksafe(crate = crate)]
+    fn deep_drop(self) {
+        self.into_inner().deep_drop();
+    }
+}
+
+impl<T: DeepDrop> DeepDrop for Box<T> {
+    #[stacksafe(crate = crate)]
+    fn deep_drop(self) {
+        (*self).deep_drop();
+    }
+}
+
+impl<T: DeepDrop> DeepDrop for Option<T> {
+    fn deep_drop(self) {
+        if let Some(value) = self {
+            value.deep_drop();
+        }
+    }
+}
+
+impl<T: DeepDrop> DeepDrop for Vec<T> {
+    #[stacksafe(crate = crate)]
+    fn deep_drop(self) {
+        self.into_iter().for_each(T::deep_drop);
+    }
+}
+
+macro_rules! deep_drop_tuple {
+    ($(($($name:ident),+))*) => {
+        $(
+            impl<$($name: DeepDrop),+> DeepDrop for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn deep_drop(self) {
+                    let ($($name,)+) = self;
+                    $($name.deep_drop();)+
+                }
+            }
+        )*
+    };
+}
+
+deep_drop_tuple! {
+    (A)
+    (A, B)
+    (A, B, C)
+    (A, B, C, D)
+    (A, B, C, D, E)
+    (A, B, C, D, E, F)
+}
+
+macro_rules! deep_drop_as_is {
+    ($($ty:ty),*) => {
+        $(
+            impl DeepDrop for $ty {
+                fn deep_drop(self) {}
+            }
+        )*
+    };
+}
+
+deep_drop_as_is!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    String
+);
@@ -0,0 +1,153 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Stack-safe traversal of [`StackSafe<T>`] fields with [`derive_visitor`].
+//!
+//! [`StackSafe<T>`] implements [`Drive`] and [`DriveMut`] whenever `T` does. Like `Box<T>`, the
+//! wrapper is transparent: the visitor is driven through the wrapped value, in a stack-safe
+//! context, without visiting the wrapper itself.
+//!
+//! Fields can choose how they are traversed with `#[drive(with = "...")]`, using the helpers of
+//! this module, which work for both [`Drive`] and [`DriveMut`]. [`drive_shallow`] makes the visitor
+//! enter and exit the wrapper, but not the wrapped value, so that a rewriter can inspect or replace
+//! a subtree as a whole without descending into it. [`drive_deep`] makes the visitor enter the
+//! wrapper as well, and then drives it through the wrapped value.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use derive_visitor::Drive;
+//! use derive_visitor::Visitor;
+//! use stacksafe::StackSafe;
+//!
+//! #[derive(Drive)]
+//! enum Expr {
+//!     Num(#[drive(skip)] i64),
+//!     Neg(#[drive(with = "stacksafe::derive_visitor::drive_deep")] StackSafe<Box<Expr>>),
+//!     Quote(#[drive(with = "stacksafe::derive_visitor::drive_shallow")] StackSafe<Box<Expr>>),
+//! }
+//!
+//! #[derive(Visitor, Default)]
+//! #[visitor(Expr(enter))]
+//! struct CountExprs(usize);
+//!
+//! impl CountExprs {
+//!     fn enter_expr(&mut self, _: &Expr) {
+//!         self.0 += 1;
+//!     }
+//! }
+//!
+//! let mut expr = Expr::Num(1);
+//! for _ in 0..100_000 {
+//!     expr = Expr::Neg(StackSafe::new(Box::new(expr)));
+//! }
+//! let mut count = CountExprs::default();
+//! expr.drive(&mut count);
+//! assert_eq!(count.0, 100_001);
+//!
+//! // The quoted expression is not traversed.
+//! let quoted = Expr::Quote(StackSafe::new(Box::new(expr)));
+//! let mut count = CountExprs::default();
+//! quoted.drive(&mut count);
+//! assert_eq!(count.0, 1);
+//! ```
+
+use ::derive_visitor::Drive;
+use ::derive_visitor::DriveMut;
+use ::derive_visitor::Event;
+use ::derive_visitor::Visitor;
+use ::derive_visitor::VisitorMut;
+
+use crate::StackSafe;
+use crate::stacksafe;
+
+impl<T: Drive> Drive for StackSafe<T> {
+    #[stacksafe(crate = crate)]
+    fn drive<V: Visitor>(&self, visitor: &mut V) {
+        self.0.drive(visitor);
+    }
+}
+
+impl<T: DriveMut> DriveMut for StackSafe<T> {
+    #[stacksafe(crate = crate)]
+    fn drive_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
+        self.0.drive_mut(visitor);
+    }
+}
+
+/// Makes `visitor` enter and exit `value`, without driving it through the wrapped value.
+///
+/// Use it as `#[drive(with = "stacksafe::derive_visitor::drive_shallow")]`. With a
+/// [`VisitorMut`], the visitor may replace the wrapped value.
+pub fn drive_shallow<W: Traverse<V>, V>(value: W, visitor: &mut V) {
+    value.shallow(visitor);
+}
+
+/// Makes `visitor` enter `value`, drives it through the wrapped value in a stack-safe context,
+/// and makes it exit `value`.
+///
+/// Use it as `#[drive(with = "stacksafe::derive_visitor::drive_deep")]`.
+pub fn drive_deep<W: Traverse<V>, V>(value: W, visitor: &mut V) {
+    value.deep(visitor);
+}
+
+/// A reference to a [`StackSafe<T>`] that [`drive_shallow`] and [`drive_deep`] can drive a
+/// visitor of type `V` through.
+///
+/// The derived implementations of [`Drive`] and [`DriveMut`] call the same function for a field,
+/// so this is implemented for shared references with a [`Visitor`], and for mutable references
+/// with a [`VisitorMut`]. It cannot be implemented outside of this crate.
+pub trait Traverse<V>: sealed::Sealed {
+    #[doc(hidden)]
+    fn shallow(self, visitor: &mut V);
+
+    #[doc(hidden)]
+    fn deep(self, visitor: &mut V);
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+impl<T> sealed::Sealed for &StackSafe<T> {}
+
+impl<T> sealed::Sealed for &mut StackSafe<T> {}
+
+impl<T: Drive, V: Visitor> Traverse<V> for &StackSafe<T> {
+    fn shallow(self, visitor: &mut V) {
+        visitor.visit(self, Event::Enter);
+        visitor.visit(self, Event::Exit);
+    }
+
+    #[stacksafe(crate = crate)]
+    fn deep(self, visitor: &mut V) {
+        visitor.visit(self, Event::Enter);
+        self.0.drive(visitor);
+        visitor.visit(self, Event::Exit);
+    }
+}
+
+impl<T: DriveMut, V: VisitorMut> Traverse<V> for &mut StackSafe<T> {
+    fn shallow(self, visitor: &mut V) {
+        visitor.visit(self, Event::Enter);
+        visitor.visit(self, Event::Exit);
+    }
+
+    #[stacksafe(crate = crate)]
+    fn deep(self, visitor: &mut V) {
+        visitor.visit(self, Event::Enter);
+        self.0.drive_mut(visitor);
+        visitor.visit(self, Event::Exit);
+    }
+}
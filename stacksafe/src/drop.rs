@@ -0,0 +1,217 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::StackSafe;
+use crate::internal::Site;
+
+/// Recursive data structures whose nodes can hand over their children before being dropped.
+///
+/// Implementations move every directly owned child node out of `self` and into `children`,
+/// leaving `self` cheap to drop. This lets [`IncrementalDrop`] tear a structure down with an
+/// explicit worklist instead of recursion.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::Dismantle;
+/// use stacksafe::StackSafe;
+///
+/// enum Tree {
+///     Leaf(u32),
+///     Node(Vec<StackSafe<Tree>>),
+/// }
+///
+/// impl Dismantle for Tree {
+///     fn dismantle(&mut self, children: &mut Vec<Self>) {
+///         if let Tree::Node(nodes) = self {
+///             children.extend(nodes.drain(..).map(StackSafe::into_inner));
+///         }
+///     }
+/// }
+/// ```
+pub trait Dismantle: Sized {
+    /// Moves the children owned by `self` into `children`.
+    ///
+    /// This is always called in a stack-safe context.
+    fn dismantle(&mut self, children: &mut Vec<Self>);
+}
+
+/// A recursive data structure that is being dropped a few nodes at a time.
+///
+/// Dropping a structure with millions of nodes takes a while even when it cannot overflow the
+/// stack. [`IncrementalDrop`] spreads that cost over multiple calls to
+/// [`step`](IncrementalDrop::step), each of which drops at most a given number of nodes, which
+/// suits frame loops and other latency-sensitive code.
+///
+/// Any nodes that are still pending when the [`IncrementalDrop`] itself is dropped are released
+/// at that point, still without recursion.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::Dismantle;
+/// use stacksafe::IncrementalDrop;
+/// use stacksafe::StackSafe;
+///
+/// enum List {
+///     Nil,
+///     Cons(u32, StackSafe<Box<List>>),
+/// }
+///
+/// impl Dismantle for List {
+///     fn dismantle(&mut self, children: &mut Vec<Self>) {
+///         if let List::Cons(_, tail) = self {
+///             children.push(std::mem::replace(&mut **tail, List::Nil));
+///         }
+///     }
+/// }
+///
+/// let list = (0..100_000).fold(List::Nil, |tail, i| List::Cons(i, StackSafe::boxed(tail)));
+///
+/// let mut garbage = IncrementalDrop::new(list);
+/// while !garbage.step(1000) {
+///     // Render a frame, process a buffer, ...
+/// }
+/// ```
+pub struct IncrementalDrop<T: Dismantle> {
+    pending: Vec<T>,
+}
+
+impl<T: Dismantle> IncrementalDrop<T> {
+    /// Creates an [`IncrementalDrop`] that will drop `value`.
+    pub fn new(value: T) -> Self {
+        IncrementalDrop {
+            pending: vec![value],
+        }
+    }
+
+    /// Schedules another value to be dropped along with the pending nodes.
+    pub fn defer(&mut self, value: T) {
+        self.pending.push(value);
+    }
+
+    /// Returns the number of nodes known to be pending.
+    ///
+    /// Children of pending nodes are only discovered as their parents are dropped, so the actual
+    /// amount of remaining work may be higher.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns `true` if every node has been dropped.
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Drops at most `budget` nodes, returning `true` if every node has been dropped.
+    pub fn step(&mut self, budget: usize) -> bool {
+        static SITE: Site = Site::new("stacksafe::IncrementalDrop::step");
+        crate::internal::guard(&SITE, || {
+            for _ in 0..budget {
+                let Some(mut node) = self.pending.pop() else {
+                    break;
+                };
+                node.dismantle(&mut self.pending);
+            }
+        });
+        self.is_finished()
+    }
+}
+
+impl<T: Dismantle> Drop for IncrementalDrop<T> {
+    fn drop(&mut self) {
+        self.step(usize::MAX);
+    }
+}
+
+impl<T: Dismantle> std::fmt::Debug for IncrementalDrop<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("IncrementalDrop")
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
+
+/// Drops every value yielded by `values` within a single stack-safe context.
+///
+/// Dropping a collection of [`StackSafe`] values one by one enters a separate stack-safe context
+/// for each of them. This enters one for the whole batch and drops the wrapped values directly,
+/// which adds up for collections with millions of entries. Values nested inside the wrapped ones
+/// are still dropped as usual.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::StackSafe;
+///
+/// struct Node {
+///     children: Vec<StackSafe<Box<Node>>>,
+/// }
+///
+/// let nodes: Vec<_> = (0..1_000_000)
+///     .map(|_| StackSafe::boxed(Node { children: vec![] }))
+///     .collect();
+/// stacksafe::drop_all(nodes);
+/// ```
+pub fn drop_all<T>(values: impl IntoIterator<Item = StackSafe<T>>) {
+    static SITE: Site = Site::new("stacksafe::drop_all");
+    crate::internal::guard(&SITE, || {
+        for value in values {
+            drop(value.into_inner());
+        }
+    });
+}
+
+/// Drops every value yielded by `values`, and everything they own, with an explicit worklist.
+///
+/// Like [`drop_all`], this enters a single stack-safe context for the whole batch, but it also
+/// [dismantles](Dismantle) every node instead of recursing into its children, so the stack usage
+/// does not depend on how deeply the values are nested.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::Dismantle;
+/// use stacksafe::StackSafe;
+///
+/// enum List {
+///     Nil,
+///     Cons(u32, StackSafe<Box<List>>),
+/// }
+///
+/// impl Dismantle for List {
+///     fn dismantle(&mut self, children: &mut Vec<Self>) {
+///         if let List::Cons(_, tail) = self {
+///             children.push(std::mem::replace(&mut **tail, List::Nil));
+///         }
+///     }
+/// }
+///
+/// let lists: Vec<List> = (0..100)
+///     .map(|_| (0..10_000).fold(List::Nil, |tail, i| List::Cons(i, StackSafe::boxed(tail))))
+///     .collect();
+/// stacksafe::drop_all_flattened(lists);
+/// ```
+pub fn drop_all_flattened<T: Dismantle>(values: impl IntoIterator<Item = T>) {
+    static SITE: Site = Site::new("stacksafe::drop_all_flattened");
+    crate::internal::guard(&SITE, || {
+        let mut pending = Vec::new();
+        for value in values {
+            pending.push(value);
+            while let Some(mut node) = pending.pop() {
+                node.dismantle(&mut pending);
+            }
+        }
+    });
+}
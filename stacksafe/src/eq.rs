@@ -0,0 +1,245 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+
+use crate::StackSafe;
+use crate::internal::Site;
+
+/// Recursive data structures that can be compared for equality one node at a time.
+///
+/// The [`PartialEq`] implementation of [`StackSafe<T>`](crate::StackSafe) is safe for any depth,
+/// but it still recurses once per node, so comparing two long chains allocates many stack
+/// segments along the way. Implementing [`IterativeEq`] lets [`deep_eq_iterative`] compare such
+/// structures in a loop instead, on the current stack segment.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::IterativeEq;
+/// use stacksafe::StackSafe;
+///
+/// enum List {
+///     Nil,
+///     Cons(u64, StackSafe<Box<List>>),
+/// }
+///
+/// impl IterativeEq for List {
+///     fn eq_shallow<'a>(
+///         &'a self,
+///         other: &'a Self,
+///         pending: &mut Vec<(&'a Self, &'a Self)>,
+///     ) -> bool {
+///         match (self, other) {
+///             (List::Nil, List::Nil) => true,
+///             (List::Cons(a, a_tail), List::Cons(b, b_tail)) if a == b => {
+///                 pending.push((a_tail, b_tail));
+///                 true
+///             }
+///             _ => false,
+///         }
+///     }
+/// }
+///
+/// let build = || (0..1_000_000).fold(List::Nil, |tail, i| List::Cons(i, StackSafe::boxed(tail)));
+/// assert!(stacksafe::deep_eq_iterative(&build(), &build()));
+/// ```
+pub trait IterativeEq {
+    /// Compares `self` and `other` without comparing their children, pushing every pair of
+    /// corresponding children that still needs to be compared onto `pending`.
+    ///
+    /// Returns `false` if the nodes themselves differ, for example because they are different
+    /// variants, hold different values, or have different numbers of children.
+    ///
+    /// This is always called in a stack-safe context.
+    fn eq_shallow<'a>(&'a self, other: &'a Self, pending: &mut Vec<(&'a Self, &'a Self)>) -> bool;
+}
+
+/// Compares two recursive data structures for equality without recursion.
+///
+/// Pairs of nodes are compared with [`IterativeEq::eq_shallow`] in a loop until a difference is
+/// found or no pairs are left, so the comparison runs on the current stack segment no matter how
+/// deep the structures are.
+pub fn deep_eq_iterative<T: IterativeEq>(a: &T, b: &T) -> bool {
+    static SITE: Site = Site::new("stacksafe::deep_eq_iterative");
+    crate::internal::guard(&SITE, || {
+        let mut pending = vec![(a, b)];
+        while let Some((a, b)) = pending.pop() {
+            if !a.eq_shallow(b, &mut pending) {
+                return false;
+            }
+        }
+        true
+    })
+}
+
+/// Compares two hash maps of [`StackSafe<V>`] values for equality in a single stack-safe context.
+///
+/// Comparing the maps with `==` establishes a stack-safe context for every value. This establishes
+/// one for the whole comparison and compares the wrapped values directly, which saves the
+/// overhead for large maps. Values still establish their own contexts for the values nested in
+/// them.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use stacksafe::StackSafe;
+///
+/// let a: HashMap<_, _> = [
+///     ("x", StackSafe::new(vec![1])),
+///     ("y", StackSafe::new(vec![2])),
+/// ]
+/// .into();
+/// let mut b = a.clone();
+/// assert!(stacksafe::map_eq_deep(&a, &b));
+///
+/// b.insert("y", StackSafe::new(vec![3]));
+/// assert!(!stacksafe::map_eq_deep(&a, &b));
+/// ```
+pub fn map_eq_deep<K: Eq + Hash, V: PartialEq, S: BuildHasher>(
+    a: &HashMap<K, StackSafe<V>, S>,
+    b: &HashMap<K, StackSafe<V>, S>,
+) -> bool {
+    static SITE: Site = Site::new("stacksafe::map_eq_deep");
+    a.len() == b.len()
+        && crate::internal::guard(&SITE, || {
+            a.iter()
+                .all(|(key, value)| b.get(key).is_some_and(|other| **value == **other))
+        })
+}
+
+/// Like [`map_eq_deep`], but for B-tree maps.
+pub fn btree_map_eq_deep<K: PartialEq, V: PartialEq>(
+    a: &BTreeMap<K, StackSafe<V>>,
+    b: &BTreeMap<K, StackSafe<V>>,
+) -> bool {
+    static SITE: Site = Site::new("stacksafe::btree_map_eq_deep");
+    a.len() == b.len()
+        && crate::internal::guard(&SITE, || {
+            a.iter()
+                .zip(b)
+                .all(|((k1, v1), (k2, v2))| k1 == k2 && **v1 == **v2)
+        })
+}
+
+/// Compares two slices of [`StackSafe<T>`] values for equality in a single stack-safe context.
+///
+/// See [`map_eq_deep`].
+pub fn slice_eq_deep<T: PartialEq>(a: &[StackSafe<T>], b: &[StackSafe<T>]) -> bool {
+    static SITE: Site = Site::new("stacksafe::slice_eq_deep");
+    a.len() == b.len() && crate::internal::guard(&SITE, || a.iter().zip(b).all(|(a, b)| **a == **b))
+}
+
+/// Compares two slices of [`StackSafe<T>`] values
+/// [lexicographically](Ord#lexicographical-comparison) in a single stack-safe context.
+///
+/// See [`map_eq_deep`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cmp::Ordering;
+///
+/// use stacksafe::StackSafe;
+///
+/// let a = [StackSafe::new("apple"), StackSafe::new("pear")];
+/// let b = [StackSafe::new("apple"), StackSafe::new("plum")];
+/// assert_eq!(stacksafe::slice_cmp_deep(&a, &b), Ordering::Less);
+/// assert_eq!(stacksafe::slice_cmp_deep(&a, &a[..1]), Ordering::Greater);
+/// ```
+pub fn slice_cmp_deep<T: Ord>(a: &[StackSafe<T>], b: &[StackSafe<T>]) -> Ordering {
+    static SITE: Site = Site::new("stacksafe::slice_cmp_deep");
+    crate::internal::guard(&SITE, || {
+        let a = a.iter().map(|value| &**value);
+        a.cmp(b.iter().map(|value| &**value))
+    })
+}
+
+/// Like [`slice_cmp_deep`], but for values that are only partially ordered.
+pub fn slice_partial_cmp_deep<T: PartialOrd>(
+    a: &[StackSafe<T>],
+    b: &[StackSafe<T>],
+) -> Option<Ordering> {
+    static SITE: Site = Site::new("stacksafe::slice_partial_cmp_deep");
+    crate::internal::guard(&SITE, || {
+        let a = a.iter().map(|value| &**value);
+        a.partial_cmp(b.iter().map(|value| &**value))
+    })
+}
+
+/// Sorts a slice of [`StackSafe<T>`] values in a single stack-safe context.
+///
+/// Sorting the slice with [`slice::sort`] establishes a stack-safe context for each of its
+/// O(*n* log *n*) comparisons. This establishes one for the whole sort and compares the wrapped
+/// values directly, which saves the overhead for large slices. The sort is stable, like
+/// [`slice::sort`].
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::StackSafe;
+///
+/// let mut values = vec![
+///     StackSafe::new(vec![3]),
+///     StackSafe::new(vec![1, 2]),
+///     StackSafe::new(vec![1]),
+/// ];
+/// stacksafe::sort_deep(&mut values);
+/// assert_eq!(values, [
+///     StackSafe::new(vec![1]),
+///     StackSafe::new(vec![1, 2]),
+///     StackSafe::new(vec![3])
+/// ]);
+/// ```
+pub fn sort_deep<T: Ord>(values: &mut [StackSafe<T>]) {
+    static SITE: Site = Site::new("stacksafe::sort_deep");
+    crate::internal::guard(&SITE, || values.sort_by(|a, b| T::cmp(a, b)));
+}
+
+/// Like [`sort_deep`], but unstable, like [`slice::sort_unstable`].
+pub fn sort_unstable_deep<T: Ord>(values: &mut [StackSafe<T>]) {
+    static SITE: Site = Site::new("stacksafe::sort_unstable_deep");
+    crate::internal::guard(&SITE, || values.sort_unstable_by(|a, b| T::cmp(a, b)));
+}
+
+/// Searches a sorted slice of [`StackSafe<T>`] values for `value` in a single stack-safe context.
+///
+/// Returns the same result as [`slice::binary_search`], without establishing a stack-safe
+/// context for every comparison. See [`sort_deep`].
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::StackSafe;
+///
+/// let values = [
+///     StackSafe::new("apple"),
+///     StackSafe::new("pear"),
+///     StackSafe::new("plum"),
+/// ];
+/// assert_eq!(stacksafe::binary_search_deep(&values, &"pear"), Ok(1));
+/// assert_eq!(stacksafe::binary_search_deep(&values, &"peach"), Err(1));
+/// ```
+pub fn binary_search_deep<T: Ord>(values: &[StackSafe<T>], value: &T) -> Result<usize, usize> {
+    static SITE: Site = Site::new("stacksafe::binary_search_deep");
+    crate::internal::guard(&SITE, || {
+        values.binary_search_by(|probe| T::cmp(probe, value))
+    })
+}
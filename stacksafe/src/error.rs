@@ -0,0 +1,87 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+/// A failure reported by the fallible functions of this crate.
+///
+/// Inconsistent configurations are reported separately, as a [`ConfigError`](crate::ConfigError).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A stack segment could not be allocated, or not be locked in memory.
+    AllocationFailed {
+        /// The size of the segment in bytes.
+        size: usize,
+        /// The underlying operating system error, if any.
+        source: Option<std::io::Error>,
+    },
+    /// A [`CallBudget`](crate::CallBudget) was used up.
+    BudgetExceeded {
+        /// The budget that was exceeded.
+        budget: usize,
+    },
+    /// A depth limit was exceeded.
+    DepthExceeded {
+        /// The limit that was exceeded.
+        limit: usize,
+    },
+    /// The stack needed to grow while growth is disabled with
+    /// [`set_growth_enabled`](crate::set_growth_enabled).
+    GrowthDisabled,
+    /// The operation is not supported on this platform, or not in the current state.
+    Unsupported {
+        /// A description of the operation.
+        operation: &'static str,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::AllocationFailed { size, .. } => {
+                write!(f, "failed to allocate a stack segment of {size} bytes")
+            }
+            Error::BudgetExceeded { budget } => {
+                write!(f, "the call budget of {budget} was exceeded")
+            }
+            Error::DepthExceeded { limit } => write!(f, "the depth limit of {limit} was exceeded"),
+            Error::GrowthDisabled => write!(f, "stack growth is disabled"),
+            Error::Unsupported { operation } => write!(f, "{operation} is not supported"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::AllocationFailed {
+                source: Some(source),
+                ..
+            } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match err {
+            Error::AllocationFailed { .. } => std::io::ErrorKind::OutOfMemory,
+            Error::Unsupported { .. } => std::io::ErrorKind::Unsupported,
+            _ => std::io::ErrorKind::Other,
+        };
+        std::io::Error::new(kind, err)
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Growth telemetry as ETW events, written with the self-describing TraceLogging encoding so that
+//! no manifest has to be registered.
+
+use std::sync::OnceLock;
+
+use windows_sys::Win32::System::Diagnostics::Etw::EVENT_DATA_DESCRIPTOR;
+use windows_sys::Win32::System::Diagnostics::Etw::EVENT_DATA_DESCRIPTOR_0;
+use windows_sys::Win32::System::Diagnostics::Etw::EVENT_DATA_DESCRIPTOR_0_0;
+use windows_sys::Win32::System::Diagnostics::Etw::EVENT_DATA_DESCRIPTOR_TYPE_EVENT_METADATA;
+use windows_sys::Win32::System::Diagnostics::Etw::EVENT_DATA_DESCRIPTOR_TYPE_PROVIDER_METADATA;
+use windows_sys::Win32::System::Diagnostics::Etw::EVENT_DESCRIPTOR;
+use windows_sys::Win32::System::Diagnostics::Etw::EventProviderEnabled;
+use windows_sys::Win32::System::Diagnostics::Etw::EventProviderSetTraits;
+use windows_sys::Win32::System::Diagnostics::Etw::EventRegister;
+use windows_sys::Win32::System::Diagnostics::Etw::EventSetInformation;
+use windows_sys::Win32::System::Diagnostics::Etw::EventWriteTransfer;
+use windows_sys::Win32::System::Diagnostics::Etw::REGHANDLE;
+use windows_sys::core::GUID;
+
+// The provider is named `StackSafe`; its GUID is derived from the name as is customary for
+// TraceLogging providers, so tools can also enable it by name, e.g. as `*StackSafe` in PerfView.
+const PROVIDER_ID: GUID = GUID::from_u128(0xdd2b30fd_bf66_5a2f_8ad7_e6af9913aad8);
+
+// The provider traits: their total size, followed by the provider name.
+const PROVIDER_TRAITS: &[u8] = b"\x0c\x00StackSafe\0";
+
+// The event metadata: its total size, the event tags, the event name, then the name and input
+// type of every field: a string and two 64-bit unsigned integers.
+const GROWTH_METADATA: &[u8] = b"\x1e\x00\x00Growth\0label\0\x02size\0\x0adepth\0\x0a";
+const RELEASE_METADATA: &[u8] = b"\x1f\x00\x00Release\0label\0\x02size\0\x0adepth\0\x0a";
+
+// The TraceLogging channel, the informational level, and the start and stop opcodes, which let
+// tools pair a growth with the matching release.
+const CHANNEL: u8 = 11;
+const LEVEL: u8 = 4;
+const OPCODE_START: u8 = 1;
+const OPCODE_STOP: u8 = 2;
+
+/// Emits an event for a new stack segment being entered.
+pub(crate) fn growth(label: &'static str, size: usize, depth: usize) {
+    write(GROWTH_METADATA, OPCODE_START, label, size, depth);
+}
+
+/// Emits an event for a stack segment being left.
+pub(crate) fn release(label: &'static str, size: usize, depth: usize) {
+    write(RELEASE_METADATA, OPCODE_STOP, label, size, depth);
+}
+
+fn provider() -> Option<REGHANDLE> {
+    static HANDLE: OnceLock<Option<REGHANDLE>> = OnceLock::new();
+
+    *HANDLE.get_or_init(|| {
+        let mut handle = 0;
+        // SAFETY: the GUID and the handle are valid for the duration of the call. The provider
+        // stays registered for the lifetime of the process.
+        if unsafe { EventRegister(&PROVIDER_ID, None, std::ptr::null(), &mut handle) } != 0 {
+            return None;
+        }
+        let handle = handle as REGHANDLE;
+        // SAFETY: the traits are valid for the duration of the call.
+        unsafe {
+            EventSetInformation(
+                handle,
+                EventProviderSetTraits,
+                PROVIDER_TRAITS.as_ptr().cast(),
+                PROVIDER_TRAITS.len() as u32,
+            )
+        };
+        Some(handle)
+    })
+}
+
+fn write(metadata: &'static [u8], opcode: u8, label: &'static str, size: usize, depth: usize) {
+    let Some(handle) = provider() else {
+        return;
+    };
+    // SAFETY: the handle is registered.
+    if unsafe { EventProviderEnabled(handle, LEVEL, 0) } == 0 {
+        return;
+    }
+    let descriptor = EVENT_DESCRIPTOR {
+        Id: 0,
+        Version: 0,
+        Channel: CHANNEL,
+        Level: LEVEL,
+        Opcode: opcode,
+        Task: 0,
+        Keyword: 0,
+    };
+    let size = (size as u64).to_ne_bytes();
+    let depth = (depth as u64).to_ne_bytes();
+    let data = [
+        data(
+            PROVIDER_TRAITS,
+            EVENT_DATA_DESCRIPTOR_TYPE_PROVIDER_METADATA,
+        ),
+        data(metadata, EVENT_DATA_DESCRIPTOR_TYPE_EVENT_METADATA),
+        data(label.as_bytes(), 0),
+        data(b"\0", 0),
+        data(&size, 0),
+        data(&depth, 0),
+    ];
+    // SAFETY: the descriptors point to data that outlives the call.
+    unsafe {
+        EventWriteTransfer(
+            handle,
+            &descriptor,
+            std::ptr::null(),
+            std::ptr::null(),
+            data.len() as u32,
+            data.as_ptr(),
+        )
+    };
+}
+
+fn data(bytes: &[u8], kind: u32) -> EVENT_DATA_DESCRIPTOR {
+    EVENT_DATA_DESCRIPTOR {
+        Ptr: bytes.as_ptr() as u64,
+        Size: bytes.len() as u32,
+        Anonymous: EVENT_DATA_DESCRIPTOR_0 {
+            Anonymous: EVENT_DATA_DESCRIPTOR_0_0 {
+                Type: kind as u8,
+                Reserved1: 0,
+                Reserved2: 0,
+            },
+        },
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::time::Duration;
+use std::time::Instant;
+
+thread_local! {
+    static RECORDER: RefCell<Option<Explanation>> = const { RefCell::new(None) };
+}
+
+/// A stack segment allocated while running under [`explain`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GrowthEvent {
+    label: &'static str,
+    size: usize,
+    depth: usize,
+    used: usize,
+}
+
+impl GrowthEvent {
+    /// Returns the label of the function whose call allocated the segment.
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    /// Returns the size of the segment that was requested, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the depth of the new segment; see [`SegmentInfo::depth`](crate::SegmentInfo::depth).
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns how many bytes of the previous segment were in use when it was left, or zero if
+    /// the previous stack was the thread's original one.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+}
+
+/// What happened to the stack during a call to [`explain`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Explanation {
+    growths: Vec<GrowthEvent>,
+    max_depth: usize,
+    peak_segment_usage: usize,
+    growth_time: Duration,
+    elapsed: Duration,
+}
+
+impl Explanation {
+    /// Returns every stack segment allocated, in order.
+    pub fn growths(&self) -> &[GrowthEvent] {
+        &self.growths
+    }
+
+    /// Returns the deepest segment reached; see [`SegmentInfo::depth`](crate::SegmentInfo::depth).
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Returns the largest number of bytes in use on an allocated segment when it was left for a
+    /// new one.
+    pub fn peak_segment_usage(&self) -> usize {
+        self.peak_segment_usage
+    }
+
+    /// Returns the time spent allocating, switching to and releasing segments.
+    pub fn growth_time(&self) -> Duration {
+        self.growth_time
+    }
+
+    /// Returns the time the whole call took.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// Runs `f`, recording how it used the stack, and returns its result along with the record.
+///
+/// This is meant for one-off investigations, in a test or while debugging, of why and where a
+/// computation grows the stack, without setting up hooks or global telemetry. Only growth on the
+/// current thread is recorded. Nested calls record separately: the inner call's growth is not
+/// part of the outer record.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn depth(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + depth(n - 1) }
+/// }
+///
+/// let (n, explanation) = stacksafe::explain(|| depth(1_000_000));
+/// assert_eq!(n, 1_000_000);
+/// assert!(!explanation.growths().is_empty());
+/// assert_eq!(explanation.growths().len(), explanation.max_depth());
+/// println!("{explanation:#?}");
+/// ```
+pub fn explain<R>(f: impl FnOnce() -> R) -> (R, Explanation) {
+    struct Restore(Option<Explanation>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            RECORDER.with(|r| *r.borrow_mut() = self.0.take());
+        }
+    }
+
+    let start = Instant::now();
+    let restore = Restore(RECORDER.with(|r| r.borrow_mut().replace(Explanation::default())));
+    let ret = f();
+    let mut explanation = RECORDER.with(|r| r.borrow_mut().take()).unwrap_or_default();
+    drop(restore);
+    explanation.elapsed = start.elapsed();
+    (ret, explanation)
+}
+
+/// Measures the time spent on a stack growth outside of its callback, if it is being recorded.
+pub(crate) struct GrowthTimer(Cell<Option<Instant>>);
+
+impl GrowthTimer {
+    /// Records a growth into a segment of `size` bytes labeled with `label`.
+    pub(crate) fn start(label: &'static str, size: usize) -> GrowthTimer {
+        let recording = RECORDER.with(|r| {
+            let mut recorder = r.borrow_mut();
+            let Some(explanation) = recorder.as_mut() else {
+                return false;
+            };
+            let current = crate::segment::current_segment();
+            let used = current.map_or(0, |segment| segment.used());
+            let depth = current.map_or(0, |segment| segment.depth()) + 1;
+            explanation.growths.push(GrowthEvent {
+                label,
+                size,
+                depth,
+                used,
+            });
+            explanation.max_depth = explanation.max_depth.max(depth);
+            explanation.peak_segment_usage = explanation.peak_segment_usage.max(used);
+            true
+        });
+        GrowthTimer(Cell::new(recording.then(Instant::now)))
+    }
+
+    /// Runs `callback` without counting it as growth time.
+    pub(crate) fn exclude(&self, callback: &mut dyn FnMut()) {
+        let recording = self.stop();
+        callback();
+        if recording {
+            self.0.set(Some(Instant::now()));
+        }
+    }
+
+    /// Adds the time since the timer was last started to the record, returning whether it was
+    /// running.
+    fn stop(&self) -> bool {
+        let Some(start) = self.0.take() else {
+            return false;
+        };
+        let elapsed = start.elapsed();
+        RECORDER.with(|r| {
+            if let Some(explanation) = r.borrow_mut().as_mut() {
+                explanation.growth_time += elapsed;
+            }
+        });
+        true
+    }
+}
+
+impl Drop for GrowthTimer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,337 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Stack-safe recursive formatting.
+//!
+//! Pretty-printers for deep structures, such as [`Display`](fmt::Display) implementations for an
+//! AST that format their children with `write!`, recurse once per level and overflow the stack
+//! just like any other recursive function. A [`StackSafe<T>`](crate::StackSafe) child is already
+//! formatted in a stack-safe context; this module covers the remaining cases: [`guarded`] formats
+//! any value in a stack-safe context, [`Indented`] tracks the indentation of nested output
+//! while guarding each level of nesting, and [`debug_limited`] formats only the first bit of a
+//! large structure.
+
+use std::fmt;
+use std::fmt::Write;
+
+use crate::stacksafe;
+
+/// Wraps `value` so that formatting it, with any of the formatting traits it implements, happens
+/// in a stack-safe context.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::fmt;
+///
+/// enum Expr {
+///     Num(i64),
+///     Neg(Box<Expr>),
+/// }
+///
+/// impl fmt::Display for Expr {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         match self {
+///             Expr::Num(n) => write!(f, "{n}"),
+///             Expr::Neg(inner) => write!(f, "-{}", stacksafe::fmt::guarded(inner)),
+///         }
+///     }
+/// }
+///
+/// let expr = (0..100_000).fold(Expr::Num(1), |expr, _| Expr::Neg(Box::new(expr)));
+/// assert_eq!(expr.to_string().len(), 100_001);
+/// # std::mem::forget(expr);
+/// ```
+pub fn guarded<T: ?Sized>(value: &T) -> Guarded<'_, T> {
+    Guarded(value)
+}
+
+/// A value that is formatted in a stack-safe context.
+///
+/// See [`guarded`].
+pub struct Guarded<'a, T: ?Sized>(&'a T);
+
+macro_rules! impl_guarded {
+    ($($trait:ident),*) => {$(
+        impl<T: ?Sized + fmt::$trait> fmt::$trait for Guarded<'_, T> {
+            #[stacksafe(crate = crate)]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::$trait::fmt(self.0, f)
+            }
+        }
+    )*};
+}
+
+impl_guarded!(
+    Display, Debug, LowerHex, UpperHex, Octal, Binary, LowerExp, UpperExp, Pointer
+);
+
+/// Wraps `value` so that its [`Debug`](fmt::Debug) output is limited to `max_depth` levels of
+/// nesting.
+///
+/// Nesting is tracked through the brackets, braces and parentheses in the output, outside of
+/// string literals, so this works with any `Debug` implementation, including derived ones for
+/// foreign types. Nested output beyond `max_depth` levels is replaced by `..`. An additional
+/// budget of bytes can be set with [`max_bytes`](DebugLimited::max_bytes); formatting stops as
+/// soon as the output would exceed it, and the output ends with `...`. The value is formatted in
+/// a stack-safe context, and the alternate flag (`{:#}`) selects pretty-printed output.
+///
+/// # Examples
+///
+/// ```rust
+/// #[derive(Debug)]
+/// struct Node {
+///     value: u32,
+///     next: Option<Box<Node>>,
+/// }
+///
+/// let list = (0..100).fold(None, |next, value| Some(Box::new(Node { value, next })));
+///
+/// let limited = stacksafe::fmt::debug_limited(&list, 2);
+/// assert_eq!(
+///     limited.to_string(),
+///     "Some(Node { value: 99, next: Some(..) })"
+/// );
+///
+/// let limited = stacksafe::fmt::debug_limited(&list, usize::MAX).max_bytes(20);
+/// assert_eq!(limited.to_string(), "Some(Node { value: 9...");
+/// ```
+pub fn debug_limited<T: ?Sized + fmt::Debug>(value: &T, max_depth: usize) -> DebugLimited<'_, T> {
+    DebugLimited {
+        value,
+        max_depth,
+        max_bytes: usize::MAX,
+    }
+}
+
+/// A value whose [`Debug`](fmt::Debug) output is limited in depth and length.
+///
+/// This is created by [`debug_limited`], and implements both [`Display`](fmt::Display) and
+/// [`Debug`](fmt::Debug) with the limited output.
+pub struct DebugLimited<'a, T: ?Sized> {
+    value: &'a T,
+    max_depth: usize,
+    max_bytes: usize,
+}
+
+impl<T: ?Sized> DebugLimited<'_, T> {
+    /// Limits the output to `max_bytes` bytes, not counting the markers for elided output.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Display for DebugLimited<'_, T> {
+    #[stacksafe(crate = crate)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut limiter = Limiter {
+            inner: f,
+            max_depth: self.max_depth,
+            remaining: self.max_bytes,
+            depth: 0,
+            string: false,
+            escaped: false,
+            truncated: false,
+        };
+        let result = if limiter.inner.alternate() {
+            write!(limiter, "{:#?}", self.value)
+        } else {
+            write!(limiter, "{:?}", self.value)
+        };
+        match result {
+            Err(_) if limiter.truncated => limiter.inner.write_str("..."),
+            result => result,
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for DebugLimited<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A writer that elides nested output beyond a depth, and fails once its budget is exhausted.
+struct Limiter<'a, 'f> {
+    inner: &'a mut fmt::Formatter<'f>,
+    max_depth: usize,
+    remaining: usize,
+    depth: usize,
+    // Whether the output is within a string literal, and after a backslash in it.
+    string: bool,
+    escaped: bool,
+    truncated: bool,
+}
+
+impl Limiter<'_, '_> {
+    fn emit(&mut self, c: char) -> fmt::Result {
+        if self.remaining < c.len_utf8() {
+            self.truncated = true;
+            return Err(fmt::Error);
+        }
+        self.remaining -= c.len_utf8();
+        self.inner.write_char(c)
+    }
+}
+
+impl fmt::Write for Limiter<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            let visible = self.depth <= self.max_depth;
+            if self.string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if c == '\\' {
+                    self.escaped = true;
+                } else if c == '"' {
+                    self.string = false;
+                }
+            } else {
+                match c {
+                    '"' => self.string = true,
+                    '(' | '[' | '{' => {
+                        self.depth += 1;
+                        if visible {
+                            self.emit(c)?;
+                            if self.depth > self.max_depth {
+                                self.inner.write_str("..")?;
+                            }
+                        }
+                        continue;
+                    }
+                    ')' | ']' | '}' => {
+                        self.depth = self.depth.saturating_sub(1);
+                        if self.depth <= self.max_depth {
+                            self.emit(c)?;
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            if visible {
+                self.emit(c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A writer that indents every line by its current nesting level.
+///
+/// Each call to [`nested`](Indented::nested) adds a level of indentation for the output written
+/// by its closure, and runs that closure in a stack-safe context, so recursive printers built on
+/// it can handle structures of any depth.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::fmt;
+/// use std::fmt::Write;
+///
+/// use stacksafe::fmt::Indented;
+///
+/// struct Tree {
+///     name: &'static str,
+///     children: Vec<Tree>,
+/// }
+///
+/// fn print(tree: &Tree, out: &mut Indented<'_>) -> fmt::Result {
+///     writeln!(out, "{}", tree.name)?;
+///     out.nested(|out| {
+///         for child in &tree.children {
+///             print(child, out)?;
+///         }
+///         Ok(())
+///     })
+/// }
+///
+/// impl fmt::Display for Tree {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         print(self, &mut Indented::new(f, "  "))
+///     }
+/// }
+///
+/// let tree = Tree {
+///     name: "root",
+///     children: vec![Tree {
+///         name: "child",
+///         children: vec![Tree {
+///             name: "grandchild",
+///             children: vec![],
+///         }],
+///     }],
+/// };
+/// assert_eq!(tree.to_string(), "root\n  child\n    grandchild\n");
+/// ```
+pub struct Indented<'a> {
+    inner: &'a mut dyn fmt::Write,
+    unit: &'a str,
+    level: usize,
+    line_start: bool,
+}
+
+impl<'a> Indented<'a> {
+    /// Creates a writer that writes to `inner`, indenting each level with `unit`.
+    ///
+    /// A [`Formatter`](fmt::Formatter) can be passed as `inner`.
+    pub fn new(inner: &'a mut dyn fmt::Write, unit: &'a str) -> Self {
+        Indented {
+            inner,
+            unit,
+            level: 0,
+            line_start: true,
+        }
+    }
+
+    /// Returns the current nesting level.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Runs `f` with one more level of indentation, in a stack-safe context.
+    #[stacksafe(crate = crate)]
+    pub fn nested<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.level += 1;
+        let ret = f(self);
+        self.level -= 1;
+        ret
+    }
+}
+
+impl fmt::Write for Indented<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for line in s.split_inclusive('\n') {
+            // Blank lines are not indented.
+            if self.line_start && line != "\n" && !self.unit.is_empty() {
+                for _ in 0..self.level {
+                    self.inner.write_str(self.unit)?;
+                }
+            }
+            self.inner.write_str(line)?;
+            self.line_start = line.ends_with('\n');
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Indented<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Indented")
+            .field("unit", &self.unit)
+            .field("level", &self.level)
+            .finish_non_exhaustive()
+    }
+}
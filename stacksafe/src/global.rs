@@ -0,0 +1,215 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use crate::ConfigError;
+use crate::SegmentAllocator;
+use crate::SegmentFallback;
+use crate::StackConfig;
+use crate::Violation;
+use crate::ViolationAction;
+
+type ThreadStart = Arc<dyn Fn() + Send + Sync>;
+
+static THREAD_START: RwLock<Option<ThreadStart>> = RwLock::new(None);
+
+// Incremented whenever a thread-start callback is installed, so that threads that already ran an
+// older callback run the new one as well.
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // The generation of the last thread-start callback run on this thread.
+    static STARTED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Process-wide settings and hooks for [`install_global`].
+///
+/// Only the settings that are explicitly set are changed; everything else keeps its current
+/// value.
+#[derive(Default)]
+pub struct GlobalSetup {
+    config: Option<StackConfig>,
+    segment_allocator: Option<SegmentAllocator>,
+    prefault_segments: Option<bool>,
+    adaptive_red_zone: Option<bool>,
+    panic_hook: bool,
+    violation_handler: Option<crate::violation::Handler>,
+    fallback_hook: Option<crate::segment::FallbackHook>,
+    thread_start: Option<ThreadStart>,
+}
+
+impl GlobalSetup {
+    /// Creates a setup that changes nothing.
+    pub fn new() -> Self {
+        GlobalSetup::default()
+    }
+
+    /// Sets the stack configuration, which is [validated](StackConfig::validate) when installed.
+    pub fn config(mut self, config: StackConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Sets where stack segments come from.
+    ///
+    /// See [`set_segment_allocator`](crate::set_segment_allocator).
+    pub fn segment_allocator(mut self, allocator: SegmentAllocator) -> Self {
+        self.segment_allocator = Some(allocator);
+        self
+    }
+
+    /// Sets whether new stack segments are pre-faulted.
+    ///
+    /// See [`set_prefault_segments`](crate::set_prefault_segments).
+    pub fn prefault_segments(mut self, prefault: bool) -> Self {
+        self.prefault_segments = Some(prefault);
+        self
+    }
+
+    /// Enables or disables adaptive red zones.
+    ///
+    /// See [`set_adaptive_red_zone`](crate::set_adaptive_red_zone).
+    pub fn adaptive_red_zone(mut self, enabled: bool) -> Self {
+        self.adaptive_red_zone = Some(enabled);
+        self
+    }
+
+    /// Installs the panic hook that annotates panics raised on grown stack segments.
+    ///
+    /// See [`install_panic_hook`](crate::install_panic_hook).
+    pub fn panic_hook(mut self) -> Self {
+        self.panic_hook = true;
+        self
+    }
+
+    /// Sets the violation handler.
+    ///
+    /// See [`set_violation_handler`](crate::set_violation_handler).
+    pub fn violation_handler(
+        mut self,
+        handler: impl Fn(&Violation) -> ViolationAction + Send + Sync + 'static,
+    ) -> Self {
+        self.violation_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Sets the segment fallback hook.
+    ///
+    /// See [`set_fallback_hook`](crate::set_fallback_hook).
+    pub fn fallback_hook(
+        mut self,
+        hook: impl Fn(&SegmentFallback) + Send + Sync + 'static,
+    ) -> Self {
+        self.fallback_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Sets a callback that runs once on every thread, before that thread first grows its stack.
+    ///
+    /// The standard library offers no way to run code whenever a thread is spawned, so the
+    /// callback runs lazily instead: the first time guarded code on a thread needs a new stack
+    /// segment, the callback runs before the segment is allocated. This covers threads spawned
+    /// by libraries as well, and is where per-thread setup such as
+    /// [`realtime::init`](crate::realtime::init) belongs. Threads from a rayon thread pool can
+    /// run the callback eagerly with `rayon::start_handler`.
+    ///
+    /// The callback runs as a hook: the stack is not grown while it runs. Installing a new
+    /// callback makes every thread run it again, including threads that ran a previous one.
+    pub fn on_thread_start(mut self, callback: impl Fn() + Send + Sync + 'static) -> Self {
+        self.thread_start = Some(Arc::new(callback));
+        self
+    }
+}
+
+/// Applies `setup` to the whole process.
+///
+/// Instead of configuring the crate piecemeal and opting in at every spawn site, a program can
+/// describe its stack configuration, hooks and per-thread setup once at startup. The stack
+/// configuration is validated first; nothing is changed if it is inconsistent.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::GlobalSetup;
+/// use stacksafe::StackConfig;
+///
+/// stacksafe::install_global(
+///     GlobalSetup::new()
+///         .config(StackConfig::default().stack_allocation_size(4 * 1024 * 1024))
+///         .panic_hook()
+///         .fallback_hook(|fallback| {
+///             eprintln!(
+///                 "warning: `{}` got a smaller stack segment",
+///                 fallback.label()
+///             );
+///         })
+///         .on_thread_start(|| {
+///             let _ = stacksafe::realtime::init(&stacksafe::realtime::RealtimeConfig::new(4));
+///         }),
+/// )
+/// .unwrap();
+/// ```
+pub fn install_global(setup: GlobalSetup) -> Result<(), ConfigError> {
+    if let Some(config) = setup.config {
+        config.apply()?;
+    }
+    if let Some(allocator) = setup.segment_allocator {
+        crate::set_segment_allocator(allocator);
+    }
+    if let Some(prefault) = setup.prefault_segments {
+        crate::set_prefault_segments(prefault);
+    }
+    if let Some(enabled) = setup.adaptive_red_zone {
+        crate::set_adaptive_red_zone(enabled);
+    }
+    if setup.panic_hook {
+        crate::install_panic_hook();
+    }
+    if let Some(handler) = setup.violation_handler {
+        crate::violation::set_violation_handler(Some(handler));
+    }
+    if let Some(hook) = setup.fallback_hook {
+        crate::segment::set_fallback_hook(Some(hook));
+    }
+    if let Some(callback) = setup.thread_start {
+        *THREAD_START.write().unwrap_or_else(|e| e.into_inner()) = Some(callback);
+        GENERATION.fetch_add(1, Ordering::Release);
+    }
+    Ok(())
+}
+
+/// Runs the thread-start callback on the current thread, unless it already ran.
+#[inline]
+pub(crate) fn start_thread() {
+    let generation = GENERATION.load(Ordering::Acquire);
+    if STARTED.with(|s| s.replace(generation)) != generation {
+        run_thread_start();
+    }
+}
+
+#[cold]
+fn run_thread_start() {
+    let callback = THREAD_START
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some(callback) = callback {
+        crate::hook::run(|| callback());
+    }
+}
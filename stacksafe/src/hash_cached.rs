@@ -0,0 +1,169 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::hash::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::ops::Deref;
+use std::ops::DerefMut;
+use std::sync::OnceLock;
+
+use crate::stacksafe;
+
+/// A wrapper that computes the hash of a large value once and caches it.
+///
+/// Hashing a deep recursive structure visits every node, which is wasteful when the same value is
+/// hashed over and over, e.g. when it is used as a key in several maps. [`HashCached<T>`] hashes
+/// the wrapped value the first time its hash is needed, in a stack-safe context, and from then on
+/// only feeds the cached result to the [`Hasher`]. Any mutable access through [`DerefMut`]
+/// discards the cached hash, so it is computed again the next time it is needed.
+///
+/// The cached hash is also used to tell unequal values apart without comparing them.
+///
+/// Note that the [`Hash`] implementation of [`HashCached<T>`] is not compatible with that of `T`:
+/// hashing a [`HashCached<T>`] and the `T` it wraps yields different results.
+///
+/// Since the cache is filled in through shared references, Clippy's `mutable_key_type` lint
+/// flags maps keyed by [`HashCached<T>`]. This is a false positive, as the cache never changes
+/// the outcome of hashing or comparison; `stacksafe::HashCached` can be added to the
+/// `ignore-interior-mutability` setting in `clippy.toml` to silence it.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use stacksafe::HashCached;
+/// use stacksafe::StackSafe;
+///
+/// #[derive(PartialEq, Eq, Hash)]
+/// enum List {
+///     Nil,
+///     Cons(u64, StackSafe<Box<List>>),
+/// }
+///
+/// let list = (0..100_000).fold(List::Nil, |tail, i| List::Cons(i, StackSafe::boxed(tail)));
+/// let key = HashCached::new(list);
+///
+/// let mut map = HashMap::new();
+/// map.insert(&key, "first");
+/// // The list is not traversed again.
+/// assert_eq!(map.get(&&key), Some(&"first"));
+/// ```
+pub struct HashCached<T> {
+    value: T,
+    hash: OnceLock<u64>,
+}
+
+impl<T> HashCached<T> {
+    /// Creates a new [`HashCached<T>`] wrapper around the given value.
+    ///
+    /// The hash is computed lazily, when it is first needed.
+    pub const fn new(value: T) -> Self {
+        HashCached {
+            value,
+            hash: OnceLock::new(),
+        }
+    }
+
+    /// Consumes the [`HashCached<T>`] wrapper and returns the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Returns the hash of the wrapped value, computing it in a stack-safe context if it is not
+    /// cached yet.
+    pub fn hash_value(&self) -> u64
+    where T: Hash {
+        *self.hash.get_or_init(|| compute_hash(&self.value))
+    }
+
+    /// Returns `true` if the hash of the wrapped value is currently cached.
+    pub fn is_cached(&self) -> bool {
+        self.hash.get().is_some()
+    }
+}
+
+#[stacksafe(crate = crate)]
+fn compute_hash<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<T> From<T> for HashCached<T> {
+    fn from(value: T) -> Self {
+        HashCached::new(value)
+    }
+}
+
+impl<T: Default> Default for HashCached<T> {
+    fn default() -> Self {
+        HashCached::new(T::default())
+    }
+}
+
+impl<T> Deref for HashCached<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for HashCached<T> {
+    /// Provides mutable access to the wrapped value, discarding the cached hash.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.hash.take();
+        &mut self.value
+    }
+}
+
+impl<T: Clone> Clone for HashCached<T> {
+    fn clone(&self) -> Self {
+        HashCached {
+            value: self.value.clone(),
+            hash: self.hash.clone(),
+        }
+    }
+}
+
+impl<T: Hash> Hash for HashCached<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash_value());
+    }
+}
+
+impl<T: PartialEq> PartialEq for HashCached<T> {
+    #[stacksafe(crate = crate)]
+    fn eq(&self, other: &Self) -> bool {
+        if let (Some(a), Some(b)) = (self.hash.get(), other.hash.get()) {
+            if a != b {
+                return false;
+            }
+        }
+        self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for HashCached<T> {}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for HashCached<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("HashCached")
+            .field("value", &self.value)
+            .field("hash", &self.hash.get())
+            .finish()
+    }
+}
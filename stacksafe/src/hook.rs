@@ -0,0 +1,70 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reentrancy protection for user-provided hooks.
+//!
+//! Hooks may call guarded code themselves, for example when they format a [`StackSafe`] value
+//! for logging. While a hook runs on a thread, no other hook is invoked on that thread and the
+//! stack is not grown, so that a hook can neither recurse into itself nor into the growth
+//! machinery that invoked it.
+//!
+//! [`StackSafe`]: crate::StackSafe
+
+use std::cell::Cell;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+thread_local! {
+    static RUNNING: Cell<bool> = const { Cell::new(false) };
+}
+
+// Set once a yield hook has been installed or adaptive red zones have been enabled, so that
+// guarded calls skip both with a single load for as long as neither has ever been used.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Makes guarded calls check for per-call hooks from now on.
+pub(crate) fn install() {
+    if !INSTALLED.load(Ordering::Relaxed) {
+        INSTALLED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Returns `true` if guarded calls may have per-call hooks to run.
+#[inline(always)]
+pub(crate) fn any_installed() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Returns `true` if a hook is running on the current thread.
+pub(crate) fn is_running() -> bool {
+    RUNNING.with(|r| r.get())
+}
+
+/// Runs `hook`, unless another hook is already running on the current thread, in which case
+/// `None` is returned.
+pub(crate) fn run<R>(hook: impl FnOnce() -> R) -> Option<R> {
+    struct Reset;
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            RUNNING.with(|r| r.set(false));
+        }
+    }
+
+    if RUNNING.with(|r| r.replace(true)) {
+        return None;
+    }
+    let _reset = Reset;
+    Some(hook())
+}
@@ -0,0 +1,87 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-function stack growth counters.
+
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::Ordering;
+
+use crate::internal::Site;
+
+// Every site that has grown the stack at least once, linked through `Site::next`. Sites are only
+// ever pushed, so that registering one from the growth path never takes a lock or allocates.
+static SITES: AtomicPtr<Site> = AtomicPtr::new(std::ptr::null_mut());
+
+/// The number of times a function marked with [`#[stacksafe]`](crate::stacksafe) had to grow
+/// the stack.
+///
+/// See [`growth_hotspots`](crate::growth_hotspots).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hotspot {
+    label: &'static str,
+    growths: usize,
+}
+
+impl Hotspot {
+    /// Returns the label of the function, such as `my_crate::parser::parse_expr`.
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    /// Returns how many new stack segments were allocated on entry to the function.
+    pub fn growths(&self) -> usize {
+        self.growths
+    }
+}
+
+#[cold]
+pub(crate) fn record(site: &'static Site) {
+    if site.count_growth() {
+        let ptr = site as *const Site as *mut Site;
+        let mut head = SITES.load(Ordering::Relaxed);
+        loop {
+            site.next().store(head, Ordering::Relaxed);
+            match SITES.compare_exchange_weak(head, ptr, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+    }
+}
+
+fn sites() -> impl Iterator<Item = &'static Site> {
+    let head = SITES.load(Ordering::Acquire);
+    // SAFETY: only `&'static Site`s are ever linked.
+    std::iter::successors(unsafe { head.as_ref() }, |site| unsafe {
+        site.next().load(Ordering::Relaxed).as_ref()
+    })
+}
+
+pub(crate) fn hotspots() -> Vec<Hotspot> {
+    let mut hotspots: Vec<Hotspot> = sites()
+        .map(|site| Hotspot {
+            label: site.label(),
+            growths: site.growths(),
+        })
+        .filter(|hotspot| hotspot.growths > 0)
+        .collect();
+    hotspots.sort_by(|a, b| b.growths.cmp(&a.growths).then(a.label.cmp(b.label)));
+    hotspots
+}
+
+pub(crate) fn reset() {
+    for site in sites() {
+        site.reset_growths();
+    }
+}
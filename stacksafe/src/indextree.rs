@@ -0,0 +1,130 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion of recursive structures to and from [`indextree`] arenas.
+//!
+//! Index-based trees are often faster to process in bulk, while recursive types are more
+//! convenient to build and pattern match on. The functions in this module move the nodes of a
+//! recursive structure into an [`Arena`] and back without recursion, so hot algorithms can work
+//! on the arena while the rest of a program keeps using the recursive form.
+//!
+//! Nodes are split apart with [`Dismantle`] and put back together with [`Assemble`]. Every node
+//! in the arena holds a dismantled node, that is, one whose children have been moved out.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use stacksafe::Assemble;
+//! use stacksafe::Dismantle;
+//! use stacksafe::StackSafe;
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Tree {
+//!     value: u32,
+//!     children: Vec<StackSafe<Tree>>,
+//! }
+//!
+//! impl Dismantle for Tree {
+//!     fn dismantle(&mut self, children: &mut Vec<Self>) {
+//!         children.extend(self.children.drain(..).map(StackSafe::into_inner));
+//!     }
+//! }
+//!
+//! impl Assemble for Tree {
+//!     fn assemble(&mut self, children: Vec<Self>) {
+//!         self.children
+//!             .extend(children.into_iter().map(StackSafe::new));
+//!     }
+//! }
+//!
+//! let leaf = |value| Tree {
+//!     value,
+//!     children: vec![],
+//! };
+//! let tree = Tree {
+//!     value: 1,
+//!     children: vec![StackSafe::new(leaf(2)), StackSafe::new(leaf(3))],
+//! };
+//!
+//! let (mut arena, root) = stacksafe::indextree::to_arena(tree);
+//! for node in arena.iter_mut() {
+//!     node.get_mut().value *= 10;
+//! }
+//! let values: Vec<u32> = root
+//!     .descendants(&arena)
+//!     .map(|id| arena[id].get().value)
+//!     .collect();
+//! assert_eq!(values, vec![10, 20, 30]);
+//!
+//! let tree = stacksafe::indextree::from_arena(arena, root);
+//! let expected = Tree {
+//!     value: 10,
+//!     children: vec![StackSafe::new(leaf(20)), StackSafe::new(leaf(30))],
+//! };
+//! assert_eq!(tree, expected);
+//! ```
+
+use ::indextree::Arena;
+use ::indextree::NodeId;
+
+use crate::Assemble;
+use crate::Dismantle;
+use crate::internal::Site;
+
+/// Moves `root` and all of its descendants into a new [`Arena`], returning the arena and the id
+/// of the root node.
+pub fn to_arena<T: Dismantle>(root: T) -> (Arena<T>, NodeId) {
+    let mut arena = Arena::new();
+    let id = insert(&mut arena, root);
+    (arena, id)
+}
+
+/// Moves `root` and all of its descendants into `arena`, returning the id of the root node.
+///
+/// The root is added as a new, detached node; it can be attached to existing nodes with
+/// [`NodeId::append`] and similar methods.
+pub fn insert<T: Dismantle>(arena: &mut Arena<T>, root: T) -> NodeId {
+    static SITE: Site = Site::new("stacksafe::indextree::insert");
+    crate::internal::guard(&SITE, || {
+        crate::assemble::flatten(root, |node, parent: Option<(NodeId, usize)>| {
+            let id = arena.new_node(node);
+            if let Some((parent, _)) = parent {
+                parent.append(id, arena);
+            }
+            id
+        })
+    })
+}
+
+/// Rebuilds the recursive structure rooted at `root` from `arena`.
+///
+/// The arena is consumed; nodes that are not descendants of `root` are dropped.
+///
+/// # Panics
+///
+/// Panics if `root` has been removed from the arena.
+pub fn from_arena<T: Assemble>(arena: Arena<T>, root: NodeId) -> T {
+    static SITE: Site = Site::new("stacksafe::indextree::from_arena");
+
+    assert!(!root.is_removed(&arena), "the root has been removed");
+    let index = |id: NodeId| usize::from(id) - 1;
+    let mut children = vec![Vec::new(); arena.len()];
+    for id in root.descendants(&arena) {
+        children[index(id)] = id.children(&arena).map(index).collect();
+    }
+    let nodes = arena.into_iter().map(|node| node.into_data()).collect();
+    crate::internal::guard(&SITE, || {
+        crate::assemble::unflatten(nodes, &children, index(root))
+    })
+}
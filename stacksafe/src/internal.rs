@@ -12,23 +12,412 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! Runtime support for code generated by [`#[stacksafe]`](crate::stacksafe).
+//!
+//! This module is not meant to be used directly, but it is a stable contract between the
+//! procedural macro and the runtime: every item listed below keeps its signature and semantics
+//! for the whole 1.x series, so code expanded by any 1.x release of `stacksafe-macro` keeps
+//! compiling and behaving correctly against any newer 1.x release of this crate.
+//!
+//! - [`guard`] and [`Site`] are emitted by the current macro: every annotated function declares a
+//!   `static` [`Site`] describing itself and passes it to [`guard`] along with its body.
+//! - [`depth_guard`] is emitted instead of [`guard`] for `#[stacksafe(depth_only)]`.
+//! - [`stacker`], [`with_protected`], and the crate-level `get_minimum_stack_size` and
+//!   `get_stack_allocation_size` functions are emitted by the 1.0 macro and are kept for
+//!   compatibility with code expanded by it. Other code should use [`raw`](crate::raw) instead.
+//! - [`is_protected`] is queried by [`StackSafe<T>`](crate::StackSafe) accessors.
+//! - [`drop_guard`] is emitted by [`#[stacksafe_type]`](crate::stacksafe_type) in the generated
+//!   [`Drop`] implementations.
+//!
+//! New macro features must be expressed as new items here (such as new `const` builder methods
+//! on [`Site`]) rather than as changes to existing ones. The contract is documented for users in
+//! the `stacksafe-macro` crate, and the expansions are checked by the `test_*_expansion` tests.
+
 #![doc(hidden)]
 
+use std::cell::Cell;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
 pub use stacker;
 
-#[cfg(debug_assertions)]
+use crate::config::Profile;
+
+/// Static information about a function marked with `#[stacksafe]`.
+///
+/// The macro emits one `static` instance per annotated function.
+#[derive(Debug)]
+pub struct Site {
+    label: &'static str,
+    profile: Option<&'static str>,
+    // The sizes given in the attribute, which take precedence over the profile and the globals.
+    red_zone: Option<usize>,
+    stack_size: Option<usize>,
+    // The limit on the logical depth of `depth_only` functions when calling the function.
+    max_depth: Option<usize>,
+    // How many calls share a single check of the remaining stack space, if more than one.
+    check_every: Option<usize>,
+    // The profile named by `profile`, once it has been registered, and the generation of the
+    // registered profiles it was last missing from, if any.
+    resolved: AtomicPtr<Profile>,
+    missed: AtomicUsize,
+    // The largest stack frame observed for the function, in bytes.
+    frame: AtomicUsize,
+    // How many times the function had to grow the stack, whether it is known to `hotspot`, and
+    // the site registered before it there.
+    growths: AtomicUsize,
+    registered: AtomicBool,
+    next: AtomicPtr<Site>,
+}
+
+impl Site {
+    /// Creates the site of the function identified by `label`.
+    pub const fn new(label: &'static str) -> Site {
+        Site {
+            label,
+            profile: None,
+            red_zone: None,
+            stack_size: None,
+            max_depth: None,
+            check_every: None,
+            resolved: AtomicPtr::new(std::ptr::null_mut()),
+            missed: AtomicUsize::new(usize::MAX),
+            frame: AtomicUsize::new(0),
+            growths: AtomicUsize::new(0),
+            registered: AtomicBool::new(false),
+            next: AtomicPtr::new(std::ptr::null_mut()),
+        }
+    }
+
+    /// Makes the function use the configuration profile registered under `name`.
+    pub const fn profile(mut self, name: &'static str) -> Site {
+        self.profile = Some(name);
+        self
+    }
+
+    /// Makes the function grow the stack when less than `bytes` are left.
+    pub const fn red_zone(mut self, bytes: usize) -> Site {
+        assert!(bytes > 0, "the red zone must not be empty");
+        self.red_zone = Some(bytes);
+        self.check_sizes()
+    }
+
+    /// Makes the function allocate segments of `bytes` when it grows the stack.
+    pub const fn stack_size(mut self, bytes: usize) -> Site {
+        self.stack_size = Some(bytes);
+        self.check_sizes()
+    }
+
+    /// Makes [`depth_guard`] panic when a call would nest more than `depth` calls to
+    /// `depth_only` functions.
+    pub const fn max_depth(mut self, depth: usize) -> Site {
+        assert!(depth > 0, "the depth limit must allow at least one call");
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Makes [`guard`] check the remaining stack space only on every `interval`th consecutive
+    /// call to the function, with a red zone large enough for the calls in between.
+    pub const fn check_every(mut self, interval: usize) -> Site {
+        assert!(interval > 0, "the check interval must be at least one call");
+        if interval > 1 {
+            self.check_every = Some(interval);
+        }
+        self
+    }
+
+    const fn check_sizes(self) -> Site {
+        if let (Some(red_zone), Some(stack_size)) = (self.red_zone, self.stack_size) {
+            assert!(
+                stack_size > red_zone,
+                "the stack size must be larger than the red zone"
+            );
+        }
+        self
+    }
+
+    /// Returns the label identifying the function.
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    /// Returns the minimum stack size and the stack allocation size that apply to the function.
+    #[inline(always)]
+    pub(crate) fn sizes(&self) -> (usize, usize) {
+        let (minimum_stack_size, stack_allocation_size) =
+            match self.profile.and_then(|name| self.resolve(name)) {
+                Some(profile) => (
+                    profile.minimum_stack_size(),
+                    profile.stack_allocation_size(),
+                ),
+                None => crate::config::effective_sizes(),
+            };
+        let minimum_stack_size = match self.red_zone {
+            Some(red_zone) => {
+                crate::config::observe_red_zone(red_zone);
+                red_zone
+            }
+            None => minimum_stack_size,
+        };
+        (
+            minimum_stack_size,
+            self.stack_size.unwrap_or(stack_allocation_size),
+        )
+    }
+
+    #[cold]
+    fn resolve_slow(&self, name: &str) -> Option<&'static Profile> {
+        let generation = crate::config::profile_generation();
+        if self.missed.load(Ordering::Relaxed) == generation {
+            return None;
+        }
+        let Some(profile) = crate::config::find_profile(name) else {
+            if self.missed.swap(generation, Ordering::Relaxed) == usize::MAX {
+                #[cfg(debug_assertions)]
+                eprintln!(
+                    "warning: `{}` uses the profile `{name}`, which is not registered, so the \
+                    global configuration applies",
+                    self.label
+                );
+            }
+            return None;
+        };
+        self.resolved
+            .store(profile as *const Profile as *mut Profile, Ordering::Release);
+        Some(profile)
+    }
+
+    #[inline(always)]
+    fn resolve(&self, name: &str) -> Option<&'static Profile> {
+        let resolved = self.resolved.load(Ordering::Acquire);
+        if resolved.is_null() {
+            self.resolve_slow(name)
+        } else {
+            // SAFETY: profiles are leaked when registered, so they are never deallocated.
+            Some(unsafe { &*resolved })
+        }
+    }
+
+    pub(crate) fn largest_frame(&self) -> usize {
+        self.frame.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn observe_frame(&self, size: usize) {
+        self.frame.fetch_max(size, Ordering::Relaxed);
+    }
+
+    /// Counts a stack growth, returning `true` if it is the first one ever counted for the site.
+    pub(crate) fn count_growth(&self) -> bool {
+        self.growths.fetch_add(1, Ordering::Relaxed);
+        !self.registered.swap(true, Ordering::Relaxed)
+    }
+
+    pub(crate) fn growths(&self) -> usize {
+        self.growths.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn reset_growths(&self) {
+        self.growths.store(0, Ordering::Relaxed);
+    }
+
+    /// Returns the link to the site registered before this one with `hotspot`.
+    pub(crate) fn next(&self) -> &AtomicPtr<Site> {
+        &self.next
+    }
+}
+
+/// Runs `callback` in a stack-safe context, growing the stack first if the remaining space is
+/// below the configured minimum.
+///
+/// This is what `#[stacksafe]` wraps every function body in.
+#[inline(always)]
+pub fn guard<R>(site: &'static Site, callback: impl FnOnce() -> R) -> R {
+    if crate::hook::any_installed() && crate::adaptive::is_enabled() {
+        return adaptive_guard(site, callback);
+    }
+    if let Some(interval) = site.check_every {
+        if crate::amortize::skip(site) {
+            tick();
+            return with_protected(callback)();
+        }
+        return amortized_guard(site, interval, callback);
+    }
+    match growth(site) {
+        Ok(_window) => with_protected(callback)(),
+        Err(stack_size) => crate::segment::grow(stack_size, site.label, with_protected(callback)),
+    }
+}
+
+/// Counts a guarded call for the yield hook, unless no hook has ever been installed.
+#[inline(always)]
+fn tick() {
+    if crate::hook::any_installed() {
+        crate::cooperate::tick();
+    }
+}
+
+/// Like [`guard`], but returns `None` without running `callback` if the stack needed to grow and
+/// no segment could be allocated.
+pub(crate) fn try_guard<R>(site: &'static Site, callback: impl FnOnce() -> R) -> Option<R> {
+    match growth(site) {
+        Ok(_window) => Some(with_protected(callback)()),
+        Err(stack_size) => {
+            crate::segment::try_grow(stack_size, site.label, with_protected(callback))
+        }
+    }
+}
+
 thread_local! {
-    static PROTECTED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+    // The number of calls to `depth_only` functions running on the current thread.
+    static LOGICAL_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Runs `callback` as a call at one more level of logical depth, without growing the stack.
+///
+/// Panics if this exceeds the depth limit of `site`.
+#[inline(always)]
+#[track_caller]
+pub fn depth_guard<R>(site: &'static Site, callback: impl FnOnce() -> R) -> R {
+    struct Leave;
+
+    impl Drop for Leave {
+        fn drop(&mut self) {
+            LOGICAL_DEPTH.with(|d| d.set(d.get() - 1));
+        }
+    }
+
+    tick();
+    let depth = LOGICAL_DEPTH.with(|d| d.get()) + 1;
+    if let Some(limit) = site.max_depth {
+        if depth > limit {
+            depth_exceeded(site, limit);
+        }
+    }
+    LOGICAL_DEPTH.with(|d| d.set(depth));
+    let _leave = Leave;
+    callback()
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn depth_exceeded(site: &Site, limit: usize) -> ! {
+    panic!(
+        "{} in `{}`",
+        crate::Error::DepthExceeded { limit },
+        site.label
+    )
+}
+
+/// Like [`guard`], but with the given sizes instead of those of a site, for
+/// [`raw::maybe_grow`](crate::raw::maybe_grow).
+pub(crate) fn guard_sized<R>(
+    label: &'static str,
+    red_zone: usize,
+    stack_size: usize,
+    callback: impl FnOnce() -> R,
+) -> R {
+    tick();
+    crate::config::observe_red_zone(red_zone);
+    if let Some(_window) = crate::segment::check_room(red_zone) {
+        with_protected(callback)()
+    } else if !may_grow() {
+        with_protected(callback)()
+    } else {
+        crate::segment::grow(stack_size, label, with_protected(callback))
+    }
+}
+
+/// Lets the fields of `value` be dropped on a new stack segment if the remaining stack space is
+/// below the red zone of `site`.
+///
+/// Fields are dropped after [`Drop::drop`] returns, so the stack cannot be grown around that. If
+/// it needs to grow, the value is replaced with `placeholder` instead, whose fields are dropped on
+/// the current stack, and the original value is dropped on a new segment. While unwinding, the
+/// original value is leaked if no segment can be allocated.
+pub fn drop_guard<T>(site: &'static Site, value: &mut T, placeholder: impl FnOnce() -> T) {
+    if let Err(stack_size) = growth(site) {
+        let value = std::mem::ManuallyDrop::new(std::mem::replace(value, placeholder()));
+        let callback = with_protected(move || drop(std::mem::ManuallyDrop::into_inner(value)));
+        if std::thread::panicking() {
+            let _ = crate::segment::try_grow(stack_size, site.label, callback);
+        } else {
+            crate::segment::grow(stack_size, site.label, callback);
+        }
+    }
+}
+
+/// Counts a guarded call for `site`, and returns the size of the segment to grow into if the
+/// remaining stack space is below its red zone, or the window to keep alive otherwise. The stack is
+/// never grown while growth is disabled, on an alternate signal stack, where allocating a segment
+/// is not safe, nor from within a hook.
+///
+/// This is kept out of [`guard`] so that its locals do not enlarge the frame of every guarded
+/// function in unoptimized builds.
+#[inline]
+fn growth(site: &'static Site) -> Result<Option<crate::segment::Window>, usize> {
+    tick();
+    let (minimum_stack_size, stack_allocation_size) = site.sizes();
+    if let Some(window) = crate::segment::check_room(minimum_stack_size) {
+        Ok(Some(window))
+    } else if !may_grow() {
+        Ok(None)
+    } else {
+        crate::hotspot::record(site);
+        Err(stack_allocation_size)
+    }
+}
+
+#[cold]
+fn may_grow() -> bool {
+    crate::segment::get_growth_enabled()
+        && !crate::hook::is_running()
+        && !crate::segment::on_signal_stack()
+}
+
+#[inline(never)]
+fn adaptive_guard<R>(site: &'static Site, callback: impl FnOnce() -> R) -> R {
+    tick();
+    let _enter = crate::adaptive::Enter::new(site);
+    let (minimum_stack_size, stack_allocation_size) = site.sizes();
+    let red_zone = crate::adaptive::red_zone(site, minimum_stack_size);
+    if let Some(_window) = crate::segment::check_room(red_zone) {
+        with_protected(callback)()
+    } else if !may_grow() {
+        with_protected(callback)()
+    } else {
+        crate::hotspot::record(site);
+        crate::segment::grow(stack_allocation_size, site.label, with_protected(callback))
+    }
+}
+
+#[inline(never)]
+fn amortized_guard<R>(site: &'static Site, interval: usize, callback: impl FnOnce() -> R) -> R {
+    tick();
+    let (minimum_stack_size, stack_allocation_size) = site.sizes();
+    let red_zone =
+        crate::amortize::red_zone(site, interval, minimum_stack_size, stack_allocation_size);
+    if let Some(_window) = crate::segment::check_room(red_zone) {
+        with_protected(callback)()
+    } else if !may_grow() {
+        with_protected(callback)()
+    } else {
+        crate::hotspot::record(site);
+        let _restart = crate::amortize::Restart;
+        crate::segment::grow(stack_allocation_size, site.label, with_protected(callback))
+    }
 }
 
 #[inline(always)]
 pub fn is_protected() -> bool {
-    #[cfg(debug_assertions)]
+    #[cfg(any(debug_assertions, feature = "strict"))]
     {
-        PROTECTED.with(|p| p.get())
+        stacksafe_core::is_protected()
     }
 
-    #[cfg(not(debug_assertions))]
+    #[cfg(not(any(debug_assertions, feature = "strict")))]
     {
         true
     }
@@ -37,17 +426,49 @@ pub fn is_protected() -> bool {
 #[inline(always)]
 pub fn with_protected<R>(callback: impl FnOnce() -> R) -> impl FnOnce() -> R {
     move || {
-        #[cfg(debug_assertions)]
+        #[cfg(feature = "tracing")]
+        let _nesting = crate::tracing::Nesting::enter();
+        #[cfg(feature = "metrics")]
+        let _depth = crate::metrics::Nesting::enter();
+
+        #[cfg(any(debug_assertions, feature = "strict"))]
         {
-            let old = PROTECTED.with(|p| p.replace(true));
+            let old = stacksafe_core::replace_protected(true);
             let ret = callback();
-            PROTECTED.with(|p| p.set(old));
+            stacksafe_core::replace_protected(old);
             ret
         }
 
-        #[cfg(not(debug_assertions))]
+        #[cfg(not(any(debug_assertions, feature = "strict")))]
         {
             callback()
         }
     }
 }
+
+/// Reports a violation if a `T` wrapped in a [`StackSafe`](crate::StackSafe) is accessed
+/// outside of a stack-safe context.
+///
+/// With the `verify-stack` feature, the remaining stack space is checked as well.
+#[track_caller]
+#[inline(always)]
+pub(crate) fn assert_protected<T: ?Sized>() {
+    let protected = is_protected();
+
+    #[cfg(feature = "verify-stack")]
+    if let Some(remaining) = crate::segment::remaining_stack() {
+        // A guarded function may legitimately run below its red zone, for example in unguarded
+        // helpers it calls, but not by much. A caller that is known to be unguarded gets no
+        // such leeway, as nothing grows the stack for it.
+        let red_zone = crate::config::smallest_red_zone();
+        let limit = if protected { red_zone / 4 } else { red_zone };
+        if remaining < limit {
+            crate::violation::report(std::any::type_name::<T>(), Some(remaining));
+            return;
+        }
+    }
+
+    if !protected {
+        crate::violation::report(std::any::type_name::<T>(), None);
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lazy iterators driven by recursive producer functions.
+//!
+//! Writing an [`Iterator`] over a recursive structure usually means maintaining an explicit stack
+//! by hand. [`from_recursive`] instead runs a plain recursive function on its own, growable
+//! stack and hands the items it emits to the consumer one at a time:
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use stacksafe::StackSafe;
+//! use stacksafe::iter::Yielder;
+//! use stacksafe::stacksafe;
+//!
+//! struct Tree {
+//!     value: u32,
+//!     children: Vec<StackSafe<Tree>>,
+//! }
+//!
+//! #[stacksafe]
+//! fn preorder(tree: &Tree, out: &mut Yielder<u32>) {
+//!     out.emit(tree.value);
+//!     for child in &tree.children {
+//!         preorder(child, out);
+//!     }
+//! }
+//!
+//! let tree = Arc::new(Tree {
+//!     value: 1,
+//!     children: vec![
+//!         StackSafe::new(Tree {
+//!             value: 2,
+//!             children: vec![],
+//!         }),
+//!         StackSafe::new(Tree {
+//!             value: 3,
+//!             children: vec![],
+//!         }),
+//!     ],
+//! });
+//!
+//! let iter = stacksafe::iter::from_recursive(move |out| preorder(&tree, out));
+//! assert_eq!(iter.collect::<Vec<_>>(), vec![1, 2, 3]);
+//! ```
+
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::SyncSender;
+use std::thread::JoinHandle;
+
+use crate::internal::Site;
+
+/// Creates a lazy iterator over the items emitted by a recursive `producer`.
+///
+/// The producer runs on a dedicated thread, in a stack-safe context, so it may recurse as deeply
+/// as needed. Each call to [`Yielder::emit`] blocks until the consumer asks for the item, so the
+/// producer never runs more than one item ahead of the iterator.
+///
+/// If the iterator is dropped before the producer finishes, the producer is unwound the next
+/// time it calls [`Yielder::emit`]. If the producer panics, the panic is propagated to the
+/// consumer by [`Iterator::next`].
+pub fn from_recursive<T, F>(producer: F) -> RecursiveIter<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut Yielder<T>) + Send + 'static,
+{
+    let (sender, receiver) = std::sync::mpsc::sync_channel(0);
+    let handle = std::thread::Builder::new()
+        .name("stacksafe-iter".to_string())
+        .stack_size(crate::get_stack_allocation_size())
+        .spawn(move || {
+            let mut yielder = Yielder { sender };
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                static SITE: Site = Site::new("stacksafe::iter::from_recursive");
+                crate::internal::guard(&SITE, || producer(&mut yielder))
+            }));
+            if let Err(payload) = result {
+                if !payload.is::<Cancelled>() {
+                    std::panic::resume_unwind(payload);
+                }
+            }
+        })
+        .expect("failed to spawn the producer thread");
+
+    RecursiveIter {
+        receiver: Some(receiver),
+        handle: Some(handle),
+    }
+}
+
+/// The handle through which a recursive producer emits items to a [`RecursiveIter`].
+pub struct Yielder<T> {
+    sender: SyncSender<T>,
+}
+
+impl<T> Yielder<T> {
+    /// Emits an item to the consumer, blocking until it is requested.
+    ///
+    /// If the consuming iterator has been dropped, this unwinds the producer.
+    pub fn emit(&mut self, item: T) {
+        if self.sender.send(item).is_err() {
+            std::panic::resume_unwind(Box::new(Cancelled));
+        }
+    }
+}
+
+/// An iterator over the items emitted by a recursive producer.
+///
+/// This struct is created by [`from_recursive`].
+pub struct RecursiveIter<T> {
+    receiver: Option<Receiver<T>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<T> Iterator for RecursiveIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let item = self.receiver.as_ref()?.recv().ok();
+        if item.is_none() {
+            self.receiver = None;
+            if let Some(Err(payload)) = self.handle.take().map(JoinHandle::join) {
+                std::panic::resume_unwind(payload);
+            }
+        }
+        item
+    }
+}
+
+impl<T> std::fmt::Debug for RecursiveIter<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("RecursiveIter")
+            .field("finished", &self.receiver.is_none())
+            .finish()
+    }
+}
+
+/// Panic payload used to unwind a producer whose consumer has gone away.
+struct Cancelled;
@@ -72,6 +72,32 @@
 //! }
 //! ```
 //!
+//! ## Recursive Types Without Wrappers
+//!
+//! Wrapping every recursive field in [`StackSafe<T>`] shows up in every pattern match on the
+//! type. Alternatively, [`#[stacksafe_type]`](stacksafe_type) implements [`Drop`], [`Clone`],
+//! [`Debug`], [`PartialEq`] and [`Hash`](std::hash::Hash) in a stack-safe context for a type
+//! whose fields contain the type itself without any wrapper, such as `Box<Self>` or `Vec<Self>`:
+//!
+//! ```rust
+//! use stacksafe::stacksafe_type;
+//!
+//! #[stacksafe_type(placeholder = Expr::Num(0))]
+//! enum Expr {
+//!     Num(i64),
+//!     Neg(Box<Expr>),
+//!     Add(Box<Expr>, Box<Expr>),
+//! }
+//!
+//! let expr = (0..1_000_000).fold(Expr::Num(1), |expr, _| Expr::Neg(Box::new(expr)));
+//! let copy = expr.clone();
+//! assert!(copy == expr);
+//! ```
+//!
+//! See [`#[stacksafe_type]`](stacksafe_type) for details. Deep values of recursive types defined
+//! by other crates, which can use neither, are dropped with [`drop_deep`] instead; see
+//! [`DeepDrop`].
+//!
 //! ## How It Works
 //!
 //! - [`#[stacksafe]`](stacksafe) attribute monitors remaining stack space at function entry points.
@@ -82,6 +108,15 @@
 //!   [`Debug`], and [`PartialEq`] with `#[stacksafe]` support, ensuring stack-safe operations on
 //!   recursive data structures without risking overflow.
 //!
+//! - [`StackSafeCow<'a, T>`] is a clone-on-write counterpart of [`StackSafe<T>`] for transformation
+//!   passes that only modify a few subtrees of a large structure.
+//!
+//! - [`StackSafeSmallBox<T, N>`] is a box that stores values of up to `N` bytes inline, so that
+//!   small nodes such as AST leaves do not need a heap allocation each.
+//!
+//! - [`Trampoline<'a, T>`] keeps the pending work of recursion on the heap, for recursion that
+//!   passes through callbacks of other crates, whose frames `#[stacksafe]` cannot check.
+//!
 //! - In `debug` builds, accessing [`StackSafe<T>`] performs additional checks to ensure the current
 //!   function is properly annotated with `#[stacksafe]`, helping catch potential issues during
 //!   development.
@@ -104,11 +139,124 @@
 //! set_stack_allocation_size(4 * 1024 * 1024);
 //! ```
 //!
+//! These settings apply to the whole process. Libraries can instead tune stack growth for their
+//! own call tree on the current thread with [`StackConfig::scope`], without affecting anyone else:
+//!
+//! ```rust
+//! use stacksafe::StackConfig;
+//!
+//! let config = StackConfig::default().stack_allocation_size(8 * 1024 * 1024);
+//! let sum = config.scope(|| (0..100u64).sum::<u64>()).unwrap();
+//! assert_eq!(sum, 4950);
+//! ```
+//!
+//! Threads started with the [`thread`] module inherit both the scoped configuration and the
+//! stack-safe context of the thread that starts them.
+//!
+//! If a binary links several semver-incompatible versions of this crate, they share the
+//! stack-safe context and the sizes set with [`set_minimum_stack_size`] and
+//! [`set_stack_allocation_size`], which live in the `stacksafe-core` crate. Everything else is
+//! kept by each version on its own and only affects functions compiled against the version it
+//! was applied through: scoped configurations, [profiles](register_profile), the growth switch of
+//! [`set_growth_enabled`], the [violation handler](set_violation_handler), and the settings of
+//! [`set_adaptive_red_zone`], [`set_growth_sampling`], and the other `set_*` functions.
+//!
 //! ## Feature Flags
 //!
 //! StackSafe supports several optional features:
 //!
-//! - `serde`: Provides stack-safe serialization and deserialization for [`StackSafe<T>`].
+//! - `arbitrary`: Implements `Arbitrary` from the `arbitrary` crate for [`StackSafe<T>`], so that
+//!   deeply recursive fuzzing inputs are generated without overflowing the stack.
+//! - `bumpalo`: Provides allocation of recursive data structures in `bumpalo` arenas in the
+//!   `bumpalo` module.
+//! - `derive-visitor`: Implements `Drive` and `DriveMut` from the `derive-visitor` crate for
+//!   [`StackSafe<T>`], and provides helpers for `#[drive(with = ...)]` that choose between shallow
+//!   and deep traversal of a field, in the `derive_visitor` module.
+//! - `gc`: Implements `Trace` and `Finalize` from the `gc` crate for [`StackSafe<T>`], so that
+//!   recursive object graphs managed by the garbage collector are traced without overflowing the
+//!   stack.
+//! - `indextree`: Provides conversion of recursive data structures to and from `indextree` arenas
+//!   in the `indextree` module.
+//! - `metrics`: Counts grown stack segments and their sizes, tracks the maximum recursion depth of
+//!   every thread, and calls a hook whenever the stack grows, in the `metrics` module.
+//! - `petgraph`: Provides conversion of recursive data structures to and from `petgraph` graphs in
+//!   the `petgraph` module.
+//! - `proptest`: Implements `Arbitrary` from the `proptest` crate for [`StackSafe<T>`], with
+//!   stack-safe generation and shrinking, in the `proptest` module.
+//! - `profiling`: Runs the code on every grown stack segment below a dedicated frame, so that
+//!   profiles show a continuous stack across segments. See [Profiling](#profiling).
+//! - `rayon`: Provides parallel drop and traversal of recursive data structures, guarded parallel
+//!   iterators, and a `join` whose tasks inherit the stack-safe context of the caller, in the
+//!   `rayon` module.
+//! - `rkyv`: Implements archiving with `rkyv` for [`StackSafe<T>`]. The archived form of a
+//!   `StackSafe<T>` is a `StackSafe` of the archived `T`, so that validating and deserializing deep
+//!   archives does not overflow the stack either.
+//! - `schemars`: Implements `JsonSchema` from the `schemars` crate for [`StackSafe<T>`],
+//!   transparently.
+//! - `serde`: Provides stack-safe serialization and deserialization for [`StackSafe<T>`], limits on
+//!   the nesting depth of deserialized input, deserialization of deep structures from flat input,
+//!   and stack-safe adapters for any serializer and deserializer, in the `serde` module.
+//! - `serde_json`: Provides stack-safe cloning, dropping, comparison, formatting, serialization and
+//!   deserialization of `serde_json::Value` trees in the `serde_json` module. Implies `serde`.
+//! - `strict`: Keeps checking that every access to a [`StackSafe<T>`] happens in a stack-safe
+//!   context in release builds, reporting violations to the [handler](set_violation_handler) like
+//!   debug builds do, instead of letting them run unchecked until the stack overflows.
+//! - `tracing`: Tracks the nesting depth of stack-safe contexts, and records it along with the
+//!   number of grown segments in `tracing` spans, in the `tracing` module. Also emits a `tracing`
+//!   event whenever a stack segment is entered.
+//! - `verify-stack`: Makes every access to a [`StackSafe<T>`] verify that enough stack space is
+//!   actually left, in release builds too, in addition to checking for a stack-safe context in
+//!   debug builds. See [`set_violation_handler`].
+//! - `windows-telemetry`: Emits an ETW event whenever a stack segment is entered or left on
+//!   Windows, so that stack growth can be observed with tools such as WPA or PerfView. See [Windows
+//!   Telemetry](#windows-telemetry).
+//!
+//! ## Profiling
+//!
+//! Sampling profilers such as `perf` or Instruments walk the stack of the sampled thread. Stack
+//! segments are switched to with frame pointers and unwind information intact, so a walk that
+//! follows frame pointers crosses from a segment into the previous one, and flamegraphs show the
+//! whole logical stack. This requires building with frame pointers, e.g. with
+//! `RUSTFLAGS="-C force-frame-pointers=yes"`, and recording with `perf record --call-graph fp`:
+//! DWARF-based recording only copies a fixed amount of stack from the sampled thread, which ends
+//! at the boundary of the current segment.
+//!
+//! With the `profiling` feature, the code on every grown segment runs below a
+//! `stacksafe::segment::segment_entry` frame, which marks each switch in a profile and can be
+//! used to fold or filter them.
+//!
+//! ## Windows Telemetry
+//!
+//! With the `windows-telemetry` feature, the events are written by a TraceLogging provider named
+//! `StackSafe`, with the GUID `dd2b30fd-bf66-5a2f-8ad7-e6af9913aad8` derived from that name, so
+//! no manifest needs to be installed. A `Growth` event with the start opcode is written when a
+//! segment is entered, and a `Release` event with the stop opcode when it is left; both carry the
+//! `label` of the function that grew the stack, the `size` of the segment, and the `depth` of the
+//! segment. For example, `wpr` or `tracelog` can record them, and PerfView enables the provider
+//! with `/OnlyProviders=*StackSafe`. The feature has no effect on other platforms.
+//!
+//! ## Signal Handlers
+//!
+//! Functions marked with [`#[stacksafe]`](stacksafe) may be called from a signal handler that runs
+//! on an alternate signal stack installed with `sigaltstack`, as reported by
+//! [`sigaltstack_compatible`]. On such a stack, a guarded call only reads thread-local state, such
+//! as the cached limit of the thread's stack, and calls `sigaltstack` to detect the alternate
+//! stack, both of which are async-signal-safe. It never grows the stack there, so it neither
+//! allocates a segment nor takes a lock, and it runs no growth hooks or telemetry.
+//!
+//! The rest is not async-signal-safe, and must be avoided in signal handlers:
+//!
+//! - The limit of the thread's stack is looked up on the first guarded call of every thread, which
+//!   may allocate. Make a guarded call on the thread before the handler can run, or reserve
+//!   segments with [`realtime::init`], which looks it up as well.
+//! - A [yield hook](with_yield_hook) may run from any guarded call, including one in the handler.
+//! - The `tracing` and `metrics` features record every guarded call, and violations of the
+//!   stack-safe context are reported to the [handler](set_violation_handler), which logs them by
+//!   default.
+//! - The stack space left on the alternate stack is not checked, so recursion in the handler must
+//!   fit into it.
+//! - A handler installed without `SA_ONSTACK` runs on the stack of the interrupted code, where
+//!   guarded calls grow the stack as anywhere else, which is not safe in a signal handler.
 //!
 //! ## Platform Support
 //!
@@ -119,17 +267,133 @@
 //! - Windows (MSVC, GNU)
 //! - FreeBSD, NetBSD, OpenBSD
 //! - And more...
+//!
+//! On targets where `stacker` cannot determine or grow the stack, such as
+//! `wasm32-unknown-unknown`, install the [`HeapTrampoline`] strategy with [`set_growth_strategy`],
+//! or a [`GrowthStrategy`] of your own.
 
 #![deny(missing_docs)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(feature = "bumpalo")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bumpalo")))]
+pub mod bumpalo;
+#[cfg(feature = "derive-visitor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "derive-visitor")))]
+pub mod derive_visitor;
+pub mod fmt;
+#[cfg(feature = "indextree")]
+#[cfg_attr(docsrs, doc(cfg(feature = "indextree")))]
+pub mod indextree;
 pub mod internal;
+pub mod iter;
+pub mod memo;
+#[cfg(feature = "metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+pub mod metrics;
+#[cfg(feature = "petgraph")]
+#[cfg_attr(docsrs, doc(cfg(feature = "petgraph")))]
+pub mod petgraph;
+#[cfg(feature = "proptest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "proptest")))]
+pub mod proptest;
+pub mod raw;
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub mod rayon;
+pub mod realtime;
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod serde;
+#[cfg(feature = "serde_json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde_json")))]
+pub mod serde_json;
+pub mod testing;
+pub mod thread;
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+pub mod tracing;
+
+mod adaptive;
+mod amortize;
+mod assemble;
+mod backtrack;
+mod budget;
+mod config;
+mod cooperate;
+mod cow;
+mod deep_drop;
+mod drop;
+mod eq;
+mod error;
+#[cfg(all(windows, feature = "windows-telemetry"))]
+mod etw;
+mod explain;
+mod global;
+mod hash_cached;
+mod hook;
+mod hotspot;
+mod sampling;
+mod segment;
+mod small_box;
+mod strategy;
+mod strip;
+mod trampoline;
+mod violation;
 
 use std::ops::Deref;
 use std::ops::DerefMut;
-use std::sync::atomic::AtomicUsize;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 
+pub use assemble::Assemble;
+pub use assemble::PreorderBuilder;
+pub use assemble::map_tree;
+pub use assemble::map_tree_in_place;
+pub use backtrack::Choices;
+pub use backtrack::backtrack;
+pub use budget::CallBudget;
+pub use budget::Frame;
+pub use config::ConfigError;
+pub use config::StackConfig;
+pub use cooperate::with_yield_hook;
+pub use cow::StackSafeCow;
+pub use deep_drop::DeepDrop;
+pub use deep_drop::drop_deep;
+pub use drop::Dismantle;
+pub use drop::IncrementalDrop;
+pub use drop::drop_all;
+pub use drop::drop_all_flattened;
+pub use eq::IterativeEq;
+pub use eq::binary_search_deep;
+pub use eq::btree_map_eq_deep;
+pub use eq::deep_eq_iterative;
+pub use eq::map_eq_deep;
+pub use eq::slice_cmp_deep;
+pub use eq::slice_eq_deep;
+pub use eq::slice_partial_cmp_deep;
+pub use eq::sort_deep;
+pub use eq::sort_unstable_deep;
+pub use error::Error;
+pub use explain::Explanation;
+pub use explain::GrowthEvent;
+pub use explain::explain;
+pub use global::GlobalSetup;
+pub use global::install_global;
+pub use hash_cached::HashCached;
+pub use hotspot::Hotspot;
+pub use sampling::GrowthSampling;
+pub use segment::SegmentAllocator;
+pub use segment::SegmentFallback;
+pub use segment::SegmentInfo;
+pub use small_box::StackSafeSmallBox;
+/// Derives [`DeepDrop`](trait@DeepDrop) for a recursive data structure; see the trait for
+/// details.
+pub use stacksafe_macro::DeepDrop;
+/// Derives [`Strip`](trait@Strip) for a recursive data structure; see the trait for details.
+pub use stacksafe_macro::Strip;
 /// Attribute macro for automatic stack overflow prevention in recursive functions.
 ///
 /// This macro transforms functions to automatically check available stack space
@@ -168,15 +432,316 @@ use std::sync::atomic::Ordering;
 /// }
 /// ```
 ///
+/// Functions that never return, such as event-loop-style recursive drivers that only exit by
+/// panicking or through [`std::process::exit`], can be annotated as well:
+///
+/// ```rust,no_run
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn drive(state: u64) -> ! {
+///     if state == 0 {
+///         std::process::exit(0);
+///     }
+///     drive(state - 1)
+/// }
+///
+/// drive(1_000_000);
+/// ```
+///
+/// # Impl Blocks and Traits
+///
+/// The attribute can also be applied to an `impl` block or a trait, which applies it to every
+/// method in the block, or to every trait method with a default implementation. Arguments
+/// apply to every method. This keeps large sets of mutually recursive methods, such as those
+/// of a visitor, from missing an annotation. Use `#[stacksafe(skip)]` to leave a method as it
+/// is, for example a `const` or `async` method, or `#[stacksafe(...)]` with arguments of its
+/// own:
+///
+/// ```rust
+/// use stacksafe::stacksafe;
+///
+/// enum Expr {
+///     Num(i64),
+///     Neg(Box<Expr>),
+///     Add(Box<Expr>, Box<Expr>),
+/// }
+///
+/// struct Evaluator;
+///
+/// #[stacksafe]
+/// impl Evaluator {
+///     fn eval(&self, expr: &Expr) -> i64 {
+///         match expr {
+///             Expr::Num(n) => *n,
+///             Expr::Neg(expr) => self.eval_neg(expr),
+///             Expr::Add(lhs, rhs) => self.eval(lhs) + self.eval(rhs),
+///         }
+///     }
+///
+///     fn eval_neg(&self, expr: &Expr) -> i64 {
+///         -self.eval(expr)
+///     }
+///
+///     #[stacksafe(skip)]
+///     const fn new() -> Self {
+///         Evaluator
+///     }
+/// }
+///
+/// let expr = (0..100_000).fold(Expr::Num(1), |expr, _| Expr::Neg(Box::new(expr)));
+/// assert_eq!(Evaluator::new().eval(&expr), 1);
+/// # std::mem::forget(expr);
+/// ```
+///
+/// Methods are labeled with the name of the type or trait and their own, such as
+/// `my_crate::Evaluator::eval`, so `name` is not accepted on blocks. Required trait methods
+/// have no body, and are only protected where they are implemented.
+///
+/// # Profiles
+///
+/// Use `#[stacksafe(profile = "name")]` to make a function use the thresholds of a profile
+/// registered with [`register_profile`] instead of the global configuration, so that different
+/// subsystems get appropriate tuning. Until a profile with that name is registered, the global
+/// configuration applies, and debug builds print a warning the first time such a function is
+/// called, as the name may be misspelled.
+///
+/// ```rust
+/// use stacksafe::StackConfig;
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe(profile = "parser")]
+/// fn parse_nested(depth: u64) -> u64 {
+///     if depth == 0 {
+///         0
+///     } else {
+///         1 + parse_nested(depth - 1)
+///     }
+/// }
+///
+/// stacksafe::register_profile(
+///     "parser",
+///     StackConfig::default()
+///         .minimum_stack_size(64 * 1024)
+///         .stack_allocation_size(8 * 1024 * 1024),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(parse_nested(100_000), 100_000);
+/// ```
+///
+/// Individual functions can also set the minimum stack size they check for with `red_zone`,
+/// and the size of the segments they allocate with `stack_size`, both in bytes. These take
+/// precedence over the profile and the global configuration, which still provide any value
+/// that is left out. For example, a small recursive helper with tiny frames can get by with a
+/// small red zone, while a parser with large frames allocates bigger segments:
+///
+/// ```rust
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe(red_zone = 16 * 1024)]
+/// fn count(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + count(n - 1) }
+/// }
+///
+/// #[stacksafe(red_zone = 256 * 1024, stack_size = 16 * 1024 * 1024)]
+/// fn parse(depth: u64) -> u64 {
+///     let buffer = [0u8; 4096];
+///     if depth == 0 {
+///         0
+///     } else {
+///         1 + parse(depth - 1) + u64::from(std::hint::black_box(buffer)[0])
+///     }
+/// }
+///
+/// assert_eq!(count(100_000), 100_000);
+/// assert_eq!(parse(10_000), 10_000);
+/// ```
+///
+/// The values must be constant expressions, and a `stack_size` that is not larger than the
+/// `red_zone` is rejected at compile time.
+///
+/// # Depth Only
+///
+/// Functions with small frames may still need to bound how deeply they recurse, for example to
+/// enforce a nesting limit of an interpreted language. `#[stacksafe(depth_only)]` skips
+/// checking the stack and never grows it. Instead, calls to such functions count towards a
+/// logical depth of the current thread, and `max_depth = ...` makes a function panic when
+/// called with that many calls to `depth_only` functions already running:
+///
+/// ```rust
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe(depth_only, max_depth = 100)]
+/// fn nest(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + nest(n - 1) }
+/// }
+///
+/// assert_eq!(nest(99), 99);
+/// assert!(std::panic::catch_unwind(|| nest(100)).is_err());
+/// ```
+///
+/// The logical depth spans all `depth_only` functions, so mutually recursive functions share
+/// it, and each checks it against its own limit. Calls still count towards the interval of a
+/// [yield hook](with_yield_hook). As the stack is not checked, the body does not run in a
+/// stack-safe context, and `depth_only` cannot be combined with `profile`, `red_zone`,
+/// `stack_size` or `check_every`.
+///
+/// # Check Interval
+///
+/// Querying the remaining stack space on every call can dominate the cost of small, hot
+/// functions. `#[stacksafe(check_every = N)]` makes only every `N`th consecutive call to such
+/// a function on the current thread query it, while the others run directly in a stack-safe
+/// context:
+///
+/// ```rust
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe(check_every = 64)]
+/// fn count(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + count(n - 1) }
+/// }
+///
+/// assert_eq!(count(1_000_000), 1_000_000);
+/// ```
+///
+/// The calls in between must fit into the red zone as well, so the checking call grows the red
+/// zone by `N` times the largest distance the stack pointer ever moved between two consecutive
+/// calls to the function. A call that moves it further than that, that follows a call to
+/// another function with a check interval, or that runs on another segment checks again. If
+/// the enlarged red zone would take more than half of the stack size, every call checks. Keep
+/// `N` small for functions with large or irregular frames, such as ones that recurse through
+/// other functions with large locals, as those may not be covered by the bound.
+///
+/// # Labels
+///
+/// Every function is labeled with its path, such as `my_crate::parser::parse_expr`, in
+/// [`SegmentInfo::label`], [`Hotspot`]s and other telemetry. Use `#[stacksafe(name = "...")]`
+/// to replace the function's name in the label, for example to tell apart methods of different
+/// types that share a name:
+///
+/// ```rust
+/// use stacksafe::stacksafe;
+///
+/// struct Parser;
+///
+/// impl Parser {
+///     #[stacksafe(name = "Parser::parse")]
+///     fn parse(&self, depth: u64) -> Option<&'static str> {
+///         if depth == 0 {
+///             stacksafe::current_segment().map(|segment| segment.label())
+///         } else {
+///             self.parse(depth - 1)
+///         }
+///     }
+/// }
+///
+/// if let Some(label) = Parser.parse(1_000_000) {
+///     assert!(label.ends_with("::Parser::parse"));
+/// }
+/// ```
+///
+/// Other attributes of the function, such as `#[doc]`, `#[deprecated]` or `#[must_use]`, are
+/// left in place and apply to it as usual.
+///
+/// # Use in Macros
+///
+/// The attribute can be emitted by declarative macros. By default, the expansion refers to the
+/// runtime as `::stacksafe`; a macro exported by a crate that re-exports `stacksafe` should
+/// pass the re-export's path with `crate = ...`, so that crates using the macro do not need to
+/// depend on `stacksafe` themselves:
+///
+/// ```rust
+/// # pub extern crate stacksafe;
+/// #[doc(hidden)]
+/// pub use ::stacksafe as __stacksafe;
+///
+/// #[macro_export]
+/// macro_rules! recursive_fn {
+///     ($name:ident) => {
+///         #[$crate::__stacksafe::stacksafe(crate = $crate::__stacksafe)]
+///         fn $name(n: u64) -> u64 {
+///             if n == 0 { 0 } else { 1 + $name(n - 1) }
+///         }
+///     };
+/// }
+///
+/// recursive_fn!(depth);
+///
+/// fn main() {
+///     assert_eq!(depth(100_000), 100_000);
+/// }
+/// ```
+///
 /// # Limitations
 ///
-/// - Cannot be applied to `async` functions
+/// - Cannot be applied to `async` functions, which need `#[stacksafe(skip)]` in annotated impl
+///   blocks and traits
 /// - Functions with `impl Trait` return types may need type annotations
-/// - Adds small runtime overhead for stack size checking
+/// - Adds small runtime overhead for stack size checking; guarded calls nested within another
+///   guarded call on the same stack reuse its lookup of the stack limit
 pub use stacksafe_macro::stacksafe;
-
-static MINIMUM_STACK_SIZE: AtomicUsize = AtomicUsize::new(128 * 1024);
-static STACK_ALLOC_SIZE: AtomicUsize = AtomicUsize::new(2 * 1024 * 1024);
+/// Implements [`Drop`], [`Clone`], [`Debug`], [`PartialEq`] and [`Hash`](std::hash::Hash) in a
+/// stack-safe context for a recursive struct or enum without [`StackSafe<T>`] wrappers.
+///
+/// The implementations are equivalent to the derived ones, but each call runs in a stack-safe
+/// context, so they work for fields that contain the type itself at any depth, such as
+/// `Box<Self>`, `Option<Box<Self>>` or `Vec<Self>`. The [`Drop`] implementation takes care of
+/// dropping deep values, which the compiler does recursively otherwise.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::stacksafe_type;
+///
+/// #[stacksafe_type(placeholder = Tree::Leaf)]
+/// enum Tree {
+///     Leaf,
+///     Node {
+///         value: i32,
+///         left: Box<Tree>,
+///         right: Box<Tree>,
+///     },
+/// }
+///
+/// let tree = (0..1_000_000).fold(Tree::Leaf, |tree, value| Tree::Node {
+///     value,
+///     left: Box::new(Tree::Leaf),
+///     right: Box::new(tree),
+/// });
+/// assert!(tree.clone() == tree);
+/// ```
+///
+/// # Arguments
+///
+/// - `placeholder = ...`: A cheap value of the type without any nested values. When the stack
+///   needs to grow while a value is dropped, the value is replaced with the placeholder, and
+///   dropped on a new stack segment. Defaults to [`Default::default()`]. For generic types,
+///   the placeholder must be valid for all type parameters.
+/// - `skip(...)`: The traits not to implement, such as `skip(Hash, Debug)`, for example to
+///   derive or implement them differently.
+/// - `serde`: Also implements `Serialize` and `Deserialize` in a stack-safe context, with the
+///   `serde` derive macros, which requires a dependency on `serde` with the `derive` feature.
+///   `#[serde(...)]` attributes apply as usual.
+/// - `crate = ...`: The path of this crate, as for [`#[stacksafe]`](stacksafe#use-in-macros).
+///
+/// Implementations for generic types require all type parameters to implement the trait, as
+/// the derived ones do.
+///
+/// # Limitations
+///
+/// Since the type implements [`Drop`], its values cannot be taken apart by moving their fields
+/// out in patterns. Match on references, or use [`std::mem::take`] or [`std::mem::replace`]
+/// instead.
+pub use stacksafe_macro::stacksafe_type;
+pub use strategy::GrowthStrategy;
+pub use strategy::HeapTrampoline;
+pub use strip::Strip;
+pub use strip::strip;
+pub use strip::wrap;
+pub use trampoline::Trampoline;
+pub use violation::Violation;
+pub use violation::ViolationAction;
 
 /// Configures the minimum stack space threshold for triggering stack allocation in bytes.
 ///
@@ -184,8 +749,11 @@ static STACK_ALLOC_SIZE: AtomicUsize = AtomicUsize::new(2 * 1024 * 1024);
 /// space is less than this threshold, a new stack segment will be allocated.
 ///
 /// Defaults to 128 KiB.
+///
+/// This value is not checked against the rest of the configuration; use [`StackConfig`] to
+/// validate it.
 pub fn set_minimum_stack_size(bytes: usize) {
-    MINIMUM_STACK_SIZE.store(bytes, Ordering::Relaxed);
+    stacksafe_core::set_minimum_stack_size(bytes);
 }
 
 /// Returns the current minimum stack space threshold in bytes.
@@ -193,59 +761,1090 @@ pub fn set_minimum_stack_size(bytes: usize) {
 /// This value determines when new stack segments are allocated for functions
 /// marked with [`#[stacksafe]`](stacksafe).
 pub fn get_minimum_stack_size() -> usize {
-    MINIMUM_STACK_SIZE.load(Ordering::Relaxed)
+    stacksafe_core::get_minimum_stack_size()
+}
+
+/// Configures the size of newly allocated stack segments in bytes.
+///
+/// When a function marked with [`#[stacksafe]`](stacksafe) needs more stack space,
+/// it allocates a new stack segment of this size.
+///
+/// Defaults to 2 MiB.
+///
+/// This value is not checked against the rest of the configuration; use [`StackConfig`] to
+/// validate it.
+pub fn set_stack_allocation_size(bytes: usize) {
+    stacksafe_core::set_stack_allocation_size(bytes);
+}
+
+/// Returns the current stack allocation size in bytes.
+///
+/// This is the size of new stack segments allocated when functions marked
+/// with [`#[stacksafe]`](stacksafe) require additional stack space.
+pub fn get_stack_allocation_size() -> usize {
+    stacksafe_core::get_stack_allocation_size()
+}
+
+/// Enables or disables adaptive red zones.
+///
+/// By default, every function marked with [`#[stacksafe]`](stacksafe) grows the stack when less
+/// than [`get_minimum_stack_size`] bytes are left. With adaptive red zones, the runtime measures
+/// how much stack each annotated function consumes per call and derives a red zone for that
+/// function from the largest frame it has seen: functions with large frames grow the stack
+/// earlier than the configured minimum, reducing the risk of overflowing, while functions with
+/// small frames make better use of each segment. Until a function has been observed, the
+/// configured minimum applies.
+///
+/// Measuring frames adds a small cost to every guarded call. Frames are only observed between
+/// nested annotated calls, so stack used by unannotated callees at the deepest level is not
+/// accounted for; the red zone is therefore never lowered below 32 KiB, or below the configured
+/// minimum if that is smaller.
+///
+/// Defaults to `false`.
+pub fn set_adaptive_red_zone(enabled: bool) {
+    adaptive::set_enabled(enabled);
+}
+
+/// Returns whether adaptive red zones are enabled.
+///
+/// See [`set_adaptive_red_zone`].
+pub fn get_adaptive_red_zone() -> bool {
+    adaptive::is_enabled()
+}
+
+/// Registers a named configuration profile for functions marked with
+/// `#[stacksafe(profile = "name")]`.
+///
+/// The configuration is [validated](StackConfig::validate) first. Registering a profile under a
+/// name that is already in use replaces its configuration. Profiles are meant to be registered
+/// once at startup; they are never deallocated.
+///
+/// See the [`#[stacksafe]`](stacksafe#profiles) documentation for an example.
+pub fn register_profile(name: &str, config: StackConfig) -> Result<(), ConfigError> {
+    config::register_profile(name, config)
+}
+
+/// Returns the configuration of the profile registered under `name`, if any.
+pub fn get_profile(name: &str) -> Option<StackConfig> {
+    config::profile_config(name)
+}
+
+/// Runs `callback` in a stack-safe context, growing the stack first if needed, just like the body
+/// of a function marked with [`#[stacksafe]`](stacksafe).
+///
+/// This makes closures and blocks stack-safe without extracting them into functions, such as the
+/// closures passed to combinators like [`Iterator::fold`] or to visitor callbacks, through which
+/// code recurses. Stacks grown by `protect` are labeled `stacksafe::protect`.
+///
+/// # Examples
+///
+/// ```rust
+/// struct Tree {
+///     children: Vec<Tree>,
+/// }
+///
+/// fn size(tree: &Tree) -> usize {
+///     tree.children
+///         .iter()
+///         .fold(1, |total, child| total + stacksafe::protect(|| size(child)))
+/// }
+///
+/// let mut tree = Tree {
+///     children: Vec::new(),
+/// };
+/// for _ in 0..100_000 {
+///     tree = Tree {
+///         children: vec![tree],
+///     };
+/// }
+/// assert_eq!(size(&tree), 100_001);
+/// # std::mem::forget(tree);
+/// ```
+#[inline]
+pub fn protect<R>(callback: impl FnOnce() -> R) -> R {
+    static SITE: internal::Site = internal::Site::new("stacksafe::protect");
+    internal::guard(&SITE, callback)
+}
+
+/// Runs `callback` on a freshly allocated stack segment of `size` bytes and returns its result.
+///
+/// Unlike [`#[stacksafe]`](stacksafe), which only allocates a new segment when the remaining
+/// stack space falls below [`get_minimum_stack_size`], this function always switches stacks, and
+/// the new segment has exactly the requested size (rounded up to the page size) regardless of
+/// [`get_stack_allocation_size`]. This is useful for running a known stack-hungry call, such as
+/// a third-party recursive parser, with a precise stack budget: exceeding the budget hits a
+/// guard page instead of silently eating into the caller's stack.
+///
+/// The segment is always freshly allocated: segments reserved with [`realtime::init`] or
+/// [`reserve_address_space`] and pooled segments are never used, since they may be larger than
+/// requested, and a failed allocation is never retried with a smaller segment. It aborts the
+/// process instead. Only an installed [`GrowthStrategy`] decides on the size by itself.
+///
+/// The callback runs in a stack-safe context, so functions marked with
+/// [`#[stacksafe]`](stacksafe) that it calls may still grow the stack further when needed.
+///
+/// # Examples
+///
+/// ```rust
+/// let sum = stacksafe::on_new_stack(8 * 1024 * 1024, || (0..100u64).sum::<u64>());
+/// assert_eq!(sum, 4950);
+/// ```
+pub fn on_new_stack<R>(size: usize, callback: impl FnOnce() -> R) -> R {
+    segment::grow_exact(
+        size,
+        "stacksafe::on_new_stack",
+        internal::with_protected(callback),
+    )
+}
+
+/// Like [`on_new_stack`], but returns [`Error::AllocationFailed`] instead of panicking or aborting
+/// the process if the segment cannot be allocated.
+///
+/// # Examples
+///
+/// ```rust
+/// let sum = stacksafe::try_on_new_stack(8 * 1024 * 1024, || (0..100u64).sum::<u64>()).unwrap();
+/// assert_eq!(sum, 4950);
+/// ```
+pub fn try_on_new_stack<R>(size: usize, callback: impl FnOnce() -> R) -> Result<R, Error> {
+    segment::try_grow_exact(
+        size,
+        "stacksafe::try_on_new_stack",
+        internal::with_protected(callback),
+    )
+}
+
+/// Runs `callback` to completion on a new thread with a stack of `size` bytes, and returns its
+/// result.
+///
+/// This is a coarse alternative to growing the stack in segments, for one-shot jobs such as batch
+/// conversions where simplicity matters more than memory usage: the whole computation, including
+/// plain recursive code that is not marked with [`#[stacksafe]`](stacksafe), runs on one large
+/// contiguous stack. Most platforms only commit the pages of a thread's stack as they are used, so
+/// a large `size` mostly costs address space. The callback runs in a stack-safe context, and
+/// functions marked with [`#[stacksafe]`](stacksafe) that it calls still grow the stack if it is
+/// exhausted nonetheless.
+///
+/// The callback may borrow from the caller, which is blocked until it returns. A panic in the
+/// callback is propagated to the caller. Thread-local state, such as a hook installed with
+/// [`with_yield_hook`], does not carry over to the new thread.
+///
+/// # Panics
+///
+/// Panics if the thread cannot be spawned, like [`std::thread::spawn`].
+///
+/// # Examples
+///
+/// ```rust
+/// fn depth(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + depth(n - 1) }
+/// }
+///
+/// let n = 100_000;
+/// assert_eq!(stacksafe::run_with_stack(256 * 1024 * 1024, || depth(n)), n);
+/// ```
+pub fn run_with_stack<R: Send>(size: usize, callback: impl FnOnce() -> R + Send) -> R {
+    std::thread::scope(|scope| {
+        let handle = std::thread::Builder::new()
+            .stack_size(size)
+            .spawn_scoped(scope, internal::with_protected(callback))
+            .expect("failed to spawn thread");
+        match handle.join() {
+            Ok(ret) => ret,
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+    })
+}
+
+/// Returns information about the stack segment the current thread is running on, or `None` if it
+/// is running on its original stack.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn deepest(n: u64) -> usize {
+///     if n == 0 {
+///         stacksafe::current_segment().map_or(0, |segment| segment.depth())
+///     } else {
+///         deepest(n - 1)
+///     }
+/// }
+///
+/// assert!(deepest(100_000) > 0);
+/// assert!(stacksafe::current_segment().is_none());
+/// ```
+pub fn current_segment() -> Option<SegmentInfo> {
+    segment::current_segment()
+}
+
+/// Returns the functions marked with [`#[stacksafe]`](stacksafe) that had to grow the stack,
+/// most frequent first.
+///
+/// Every allocation of a new stack segment on entry to an annotated function is counted, on all
+/// threads, at negligible cost. The functions at the top of the list are the best candidates for
+/// restructuring, or for a [profile](register_profile) with larger segments.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn depth(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + depth(n - 1) }
+/// }
+///
+/// depth(1_000_000);
+///
+/// for hotspot in stacksafe::growth_hotspots().iter().take(10) {
+///     println!("{}: {} segments", hotspot.label(), hotspot.growths());
+/// }
+/// assert!(stacksafe::growth_hotspots()[0].label().ends_with("::depth"));
+/// ```
+pub fn growth_hotspots() -> Vec<Hotspot> {
+    hotspot::hotspots()
+}
+
+/// Resets the counters reported by [`growth_hotspots`] to zero.
+pub fn reset_growth_hotspots() {
+    hotspot::reset();
+}
+
+/// Installs a panic hook that annotates panics raised on grown stack segments.
+///
+/// The hook first delegates to the previously installed hook, then, if the panicking thread is
+/// running on a stack segment allocated by this crate, prints a note with the segment depth, the
+/// function that allocated the segment, and how much of it was in use, e.g.:
+///
+/// ```text
+/// note: panicked on stack segment 3 allocated by `my_crate::parser::parse_expr` (1204224 of 2097152 bytes used)
+/// ```
+///
+/// Calling this function more than once has no further effect.
+pub fn install_panic_hook() {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            if let Some(segment) = current_segment() {
+                eprintln!(
+                    "note: panicked on stack segment {} allocated by `{}` ({} of {} bytes used)",
+                    segment.depth(),
+                    segment.label(),
+                    segment.used(),
+                    segment.size()
+                );
+            }
+        }));
+    });
 }
 
-/// Configures the size of newly allocated stack segments in bytes.
-///
-/// When a function marked with [`#[stacksafe]`](stacksafe) needs more stack space,
-/// it allocates a new stack segment of this size.
-///
-/// Defaults to 2 MiB.
-pub fn set_stack_allocation_size(bytes: usize) {
-    STACK_ALLOC_SIZE.store(bytes, Ordering::Relaxed);
-}
+/// Enables or disables stack growth for the whole process.
+///
+/// This is a kill switch for emergencies, such as a pathological input pattern causing runaway
+/// segment allocation in production. While growth is disabled, functions marked with
+/// [`#[stacksafe]`](stacksafe) run on the current stack as if they were not marked, and may
+/// overflow it. Functions that always switch stacks fail fast instead: [`try_on_new_stack`]
+/// returns [`Error::GrowthDisabled`], and [`on_new_stack`] panics. Calls that are already running
+/// on a grown segment are not affected.
+///
+/// A service that would rather reject requests than grow should pair this with its own check of
+/// [`get_growth_enabled`] or of the nesting depth of its input.
+///
+/// Defaults to `true`.
+///
+/// # Examples
+///
+/// ```rust,standalone_crate
+/// stacksafe::set_growth_enabled(false);
+/// assert!(matches!(
+///     stacksafe::try_on_new_stack(1024 * 1024, || ()),
+///     Err(stacksafe::Error::GrowthDisabled)
+/// ));
+/// stacksafe::set_growth_enabled(true);
+/// ```
+pub fn set_growth_enabled(enabled: bool) {
+    segment::set_growth_enabled(enabled);
+}
+
+/// Returns whether stack growth is enabled; see [`set_growth_enabled`].
+pub fn get_growth_enabled() -> bool {
+    segment::get_growth_enabled()
+}
+
+/// Configures which stack growth events are emitted as telemetry.
+///
+/// Emitting an event for every new stack segment can be too chatty for services that handle many
+/// requests. The sampling applies to all events emitted for a growth alike: the events of the
+/// `tracing` feature, the hook installed with `metrics::on_grow` of the `metrics` feature, and
+/// the ETW events of the `windows-telemetry` feature, where a segment that is left emits its
+/// release event only if its growth event was emitted. Counters such as the ones reported by
+/// [`growth_hotspots`] and [`explain`], or by the `metrics` feature, keep counting every growth.
+///
+/// Defaults to [`GrowthSampling::All`].
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::GrowthSampling;
+///
+/// stacksafe::set_growth_sampling(GrowthSampling::EveryNth(100));
+/// assert_eq!(
+///     stacksafe::get_growth_sampling(),
+///     GrowthSampling::EveryNth(100)
+/// );
+///
+/// stacksafe::set_growth_sampling(GrowthSampling::Probability(0.25));
+/// assert_eq!(
+///     stacksafe::get_growth_sampling(),
+///     GrowthSampling::Probability(0.25)
+/// );
+/// # stacksafe::set_growth_sampling(GrowthSampling::All);
+/// ```
+pub fn set_growth_sampling(sampling: GrowthSampling) {
+    sampling::set_growth_sampling(sampling);
+}
+
+/// Returns which stack growth events are emitted as telemetry; see [`set_growth_sampling`].
+pub fn get_growth_sampling() -> GrowthSampling {
+    sampling::get_growth_sampling()
+}
+
+/// Configures whether newly allocated stack segments are pre-faulted.
+///
+/// The operating system only backs the pages of a fresh segment with memory when they are first
+/// touched, so a deep recursion takes a page fault every few frames on a new segment. When
+/// enabled, every page of a new segment is touched right after it is allocated, before the guarded
+/// function continues on it. This makes each growth more expensive, but keeps page faults out of
+/// the recursion itself, for more predictable latency.
+///
+/// Pre-faulting is not supported on platforms where this crate cannot switch stacks by itself
+/// (such as Windows), where this setting has no effect. Segments reserved with
+/// [`realtime::init`] are configured separately.
+///
+/// Defaults to `false`.
+///
+/// # Examples
+///
+/// ```rust
+/// stacksafe::set_prefault_segments(true);
+/// assert!(stacksafe::get_prefault_segments());
+/// ```
+pub fn set_prefault_segments(prefault: bool) {
+    segment::set_prefault(prefault);
+}
+
+/// Returns whether newly allocated stack segments are pre-faulted.
+pub fn get_prefault_segments() -> bool {
+    segment::get_prefault()
+}
+
+/// Configures how many stack segments each thread keeps for reuse after leaving them.
+///
+/// A loop that calls a deeply recursive function many times grows the stack and releases the new
+/// segments again on every iteration, which makes allocating and freeing segments the dominant
+/// cost. With a non-zero capacity, every thread keeps up to `segments` of the segments it left in
+/// a pool, and grows into one of the same size from the pool instead of allocating a new one.
+/// Pooled segments stay committed, so each one costs its full size in memory until it is freed
+/// with [`shrink_pool`] or the thread exits.
+///
+/// Pooled segments are allocated with the configured [`SegmentAllocator`], but are always managed
+/// by this crate rather than by `stacker`, with the same caveat as [`SegmentAllocator::Global`].
+/// Segments reserved with [`reserve_address_space`] or [`realtime::init`] take precedence over the
+/// pool.
+///
+/// Pooling is not supported on platforms where this crate cannot switch stacks by itself (such
+/// as Windows), where this setting has no effect.
+///
+/// Defaults to 0, which disables pooling.
+///
+/// # Examples
+///
+/// ```rust,standalone_crate
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn depth(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + depth(n - 1) }
+/// }
+///
+/// stacksafe::set_pool_capacity(4);
+/// for _ in 0..100 {
+///     // Grows into the same pooled segments every time.
+///     assert_eq!(depth(100_000), 100_000);
+/// }
+/// stacksafe::shrink_pool();
+/// ```
+pub fn set_pool_capacity(segments: usize) {
+    segment::set_pool_capacity(segments);
+}
+
+/// Returns how many stack segments each thread keeps for reuse; see [`set_pool_capacity`].
+pub fn get_pool_capacity() -> usize {
+    segment::get_pool_capacity()
+}
+
+/// Frees the stack segments pooled by the current thread; see [`set_pool_capacity`].
+///
+/// Segments in use are not affected, and are pooled again when they are left if the capacity
+/// allows.
+pub fn shrink_pool() {
+    segment::shrink_pool();
+}
+
+/// Configures where newly allocated stack segments come from.
+///
+/// By default, segments are mapped directly from the operating system, which makes them invisible
+/// to heap profilers. Use [`SegmentAllocator::Global`] to allocate them through the global
+/// allocator instead, so that memory dashboards and heap-profiling tools account for them.
+///
+/// Defaults to [`SegmentAllocator::System`].
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::SegmentAllocator;
+///
+/// stacksafe::set_segment_allocator(SegmentAllocator::Global);
+/// ```
+pub fn set_segment_allocator(allocator: SegmentAllocator) {
+    segment::set_segment_allocator(allocator);
+}
+
+/// Reserves a contiguous range of address space for `segments` stack segments of `segment_size`
+/// bytes, shared by all threads.
+///
+/// From then on, whenever the stack grows by at most `segment_size` bytes, the new segment is
+/// carved out of the reserved range rather than mapped on its own, and its slot is handed back
+/// when the segment is left. Each slot is preceded by a guard page. Since the layout of the range
+/// is set up once, repeated growth neither fragments the address space nor changes the number of
+/// memory mappings of the process, and segments keep reusing the same addresses, which makes them
+/// easier to recognize while debugging. The memory of a slot is returned to the operating system
+/// when it is released, but the range itself is reserved for the lifetime of the process.
+///
+/// Segments reserved with [`realtime::init`] are still used first. Once every slot is in use, or
+/// when a larger segment is requested, segments come from the configured
+/// [`SegmentAllocator`] as usual.
+///
+/// # Errors
+///
+/// Fails with [`Error::Unsupported`] if address space has already been reserved, or if this crate
+/// cannot switch stacks by itself on this platform (such as Windows), and with
+/// [`Error::AllocationFailed`] if the range cannot be mapped.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn depth(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + depth(n - 1) }
+/// }
+///
+/// if let Err(error) = stacksafe::reserve_address_space(64, stacksafe::get_stack_allocation_size())
+/// {
+///     eprintln!("segments are mapped one by one: {error}");
+/// }
+/// assert_eq!(depth(1_000_000), 1_000_000);
+/// ```
+pub fn reserve_address_space(segments: usize, segment_size: usize) -> Result<(), Error> {
+    segment::reserve_region(segments, segment_size)
+}
+
+/// Installs a handler deciding what happens when a [`StackSafe`] value is accessed outside of a
+/// stack-safe context.
+///
+/// Such accesses are only detected in debug builds, unless the `strict` feature is enabled, and
+/// panic by default. The handler receives the accessed type and the location of the access, and
+/// can for example log the violation and let the access proceed, or only tolerate it for some
+/// types. It replaces any previously installed handler. Whatever the handler decides, every
+/// violation is counted, see [`violation_count`].
+///
+/// The handler may access [`StackSafe`] values itself, for example to log them: violations
+/// raised while it runs, or while any other hook runs on the same thread, are ignored instead of
+/// invoking the handler again.
+///
+/// With the `verify-stack` feature, accesses are also checked against the stack space that is
+/// actually left, in every build profile: an access is a violation once less than a quarter of
+/// the smallest red zone in use remains, or less than all of it if the access is known to happen
+/// outside of a stack-safe context. A missing annotation on a recursive path then results in a
+/// violation, and by default a panic, instead of a stack overflow, even in release builds, where
+/// the stack-safe context is not tracked. An access cannot grow the stack itself, since its
+/// caller continues on the same stack, but the recursive operations of the wrapper, such as
+/// dropping, cloning, comparing or formatting, are guarded and grow it wherever they run.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::ViolationAction;
+///
+/// stacksafe::set_violation_handler(|violation| {
+///     eprintln!(
+///         "warning: `StackSafe<{}>` accessed outside of a stack-safe context at {}",
+///         violation.type_name(),
+///         violation.location()
+///     );
+///     ViolationAction::Ignore
+/// });
+/// ```
+pub fn set_violation_handler(
+    handler: impl Fn(&Violation) -> ViolationAction + Send + Sync + 'static,
+) {
+    violation::set_violation_handler(Some(Box::new(handler)));
+}
+
+/// Removes the handler installed with [`set_violation_handler`], so that violations panic again.
+pub fn clear_violation_handler() {
+    violation::set_violation_handler(None);
+}
+
+/// Returns the number of violations detected since the process started, or since the count was
+/// last reset with [`reset_violation_count`].
+///
+/// Violations are counted whether they panic or not, so a service built with the `strict` feature
+/// and a handler that lets accesses proceed can still report missing annotations, for example as
+/// a metric.
+///
+/// # Examples
+///
+/// ```rust,standalone_crate
+/// use stacksafe::ViolationAction;
+///
+/// stacksafe::set_violation_handler(|_| ViolationAction::Ignore);
+/// // ...
+/// if stacksafe::violation_count() > 0 {
+///     eprintln!("warning: `StackSafe` values were accessed outside of a stack-safe context");
+/// }
+/// ```
+pub fn violation_count() -> usize {
+    violation::count()
+}
+
+/// Resets the count returned by [`violation_count`] to zero.
+pub fn reset_violation_count() {
+    violation::reset_count();
+}
+
+/// Installs a hook that is called whenever a stack segment cannot be allocated and a smaller one
+/// is tried instead.
+///
+/// When a new segment cannot be allocated, for example because a container's memory limit has
+/// been reached, the allocation is retried with half the size, down to 64 KiB, before giving up.
+/// Execution continues on the smaller segment, growing the stack more often. The hook is called
+/// before each retry and replaces any previously installed hook. Segments of an exact size, as
+/// allocated by [`on_new_stack`] and [`try_on_new_stack`], are never retried.
+///
+/// The hook may call guarded code, but the stack is not grown while it runs, and it is not
+/// invoked again for allocations failing in the meantime.
+///
+/// # Examples
+///
+/// ```rust
+/// stacksafe::set_fallback_hook(|fallback| {
+///     eprintln!(
+///         "warning: `{}` got a {} byte stack segment instead of {} bytes",
+///         fallback.label(),
+///         fallback.size(),
+///         fallback.requested()
+///     );
+/// });
+/// ```
+pub fn set_fallback_hook(hook: impl Fn(&SegmentFallback) + Send + Sync + 'static) {
+    segment::set_fallback_hook(Some(Box::new(hook)));
+}
+
+/// Removes the hook installed with [`set_fallback_hook`].
+pub fn clear_fallback_hook() {
+    segment::set_fallback_hook(None);
+}
+
+/// Returns `true` if functions marked with [`#[stacksafe]`](stacksafe) that are called from a
+/// signal handler running on an alternate signal stack are detected on this platform, in which
+/// case they never try to grow the stack.
+///
+/// This is the case on all Unix platforms. See the [crate documentation](crate#signal-handlers)
+/// for details.
+pub fn sigaltstack_compatible() -> bool {
+    cfg!(unix)
+}
+
+/// Returns where newly allocated stack segments currently come from.
+pub fn get_segment_allocator() -> SegmentAllocator {
+    segment::get_segment_allocator()
+}
+
+/// Installs the [`GrowthStrategy`] that all guarded code measures and grows the stack with.
+///
+/// The strategy is meant to be installed once at startup, before any guarded code runs, and
+/// cannot be replaced afterwards. Without one, the stack is grown in segments allocated by
+/// `stacker`, or by this crate as configured with [`set_segment_allocator`].
+///
+/// # Errors
+///
+/// Fails with [`Error::Unsupported`] if a strategy has already been installed.
+///
+/// # Examples
+///
+/// ```rust,standalone_crate
+/// use stacksafe::HeapTrampoline;
+///
+/// stacksafe::set_growth_strategy(HeapTrampoline).unwrap();
+/// assert!(stacksafe::set_growth_strategy(HeapTrampoline).is_err());
+/// ```
+pub fn set_growth_strategy(strategy: impl GrowthStrategy) -> Result<(), Error> {
+    strategy::set_growth_strategy(Box::new(strategy))
+}
+
+/// A wrapper type for recursive data structures with automatic stack-safe operations.
+///
+/// [`StackSafe<T>`] wraps values that are part of recursive data structures, ensuring
+/// that operations like cloning, dropping, comparison, and serialization are performed
+/// safely without risking stack overflow.
+///
+/// The wrapper provides transparent access to the underlying value through [`Deref`]
+/// and [`DerefMut`], but enforces that such access occurs within a stack-safe context
+/// (i.e., within a function marked with [`#[stacksafe]`](stacksafe)).
+///
+/// # Dropping
+///
+/// The wrapped value is held in a [`ManuallyDrop`](std::mem::ManuallyDrop) and is dropped exactly
+/// once, by the [`Drop`] implementation of the wrapper, in a stack-safe context. Ownership moves
+/// out of the wrapper only through [`into_inner`](Self::into_inner), after which the wrapper is
+/// forgotten, and through [`forget`](Self::forget), which drops nothing. Assigning a new value
+/// through [`DerefMut`] drops the old value immediately, while [`std::mem::take`] and
+/// [`std::mem::replace`] hand it back to the caller. Either way, the new value is dropped with
+/// the wrapper.
+///
+/// Each wrapper is dropped independently, so a partially moved structure drops exactly the
+/// [`StackSafe<T>`] fields it still owns, in declaration order, like any other field.
+///
+/// If the destructor of the wrapped value panics, the panic propagates out of the wrapper's
+/// destructor as usual: the remaining fields of the wrapped value are still dropped while
+/// unwinding, and the wrapped value is never dropped again.
+///
+/// # Unwinding
+///
+/// When a panic unwinds through a deep structure, for example because the destructor of one of
+/// its nodes panicked, the remaining nodes are still dropped in a stack-safe context, using
+/// segments reserved with [`realtime::init`] first if there are any. If the stack needs to grow
+/// during unwinding but no segment can be allocated, the affected values are leaked instead of
+/// aborting the process with a panic inside a destructor. Once any wrapped value has been
+/// [pinned](#pinning), leaking is no longer an option, since the memory of a pinned value must not
+/// be reused before it is dropped, and the process is aborted instead.
+///
+/// # Layout
+///
+/// [`StackSafe<T>`] is `#[repr(transparent)]`, so it is guaranteed to have the same size,
+/// alignment, and ABI as `T`. In particular, niches of `T` are preserved: just like
+/// `Option<Box<T>>`, `Option<StackSafe<Box<T>>>` is guaranteed to be pointer-sized.
+///
+/// ```rust
+/// use stacksafe::StackSafe;
+///
+/// struct Node {
+///     value: i32,
+///     next: Option<StackSafe<Box<Node>>>,
+/// }
+///
+/// assert_eq!(
+///     size_of::<Option<StackSafe<Box<Node>>>>(),
+///     size_of::<usize>()
+/// );
+/// ```
+///
+/// # Thread Safety
+///
+/// [`StackSafe<T>`] is [`Send`], [`Sync`], [`Unpin`], and [`UnwindSafe`](std::panic::UnwindSafe)
+/// exactly when `T` is. The wrapper holds no state of its own, so it can be shared and moved
+/// between threads like the wrapped value.
+///
+/// # Pinning
+///
+/// The wrapped value is structurally pinned: it is never moved out of a pinned wrapper, and it is
+/// never leaked once pinned. [`as_pin_ref`](Self::as_pin_ref) and [`as_pin_mut`](Self::as_pin_mut)
+/// project a pinned wrapper to the pinned value, and a wrapped [`Future`] is a [`Future`] itself,
+/// polled in a stack-safe context, so that deeply nested futures can be awaited.
+///
+/// Pinned wrappers must only be projected through these methods, which record that values may be
+/// pinned, so that a value that cannot be dropped while [unwinding](#unwinding) aborts the process
+/// rather than being leaked. Projecting them by other means, such as with
+/// [`Pin::map_unchecked_mut`], is unsound.
+///
+/// ```rust
+/// use std::pin::Pin;
+///
+/// use stacksafe::StackSafe;
+///
+/// fn depth(n: u64) -> Pin<Box<dyn Future<Output = u64>>> {
+///     Box::pin(StackSafe::new(async move {
+///         if n == 0 { 0 } else { 1 + depth(n - 1).await }
+///     }))
+/// }
+///
+/// let mut future = depth(100_000);
+/// let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+/// assert_eq!(
+///     future.as_mut().poll(&mut cx),
+///     std::task::Poll::Ready(100_000)
+/// );
+/// ```
+#[repr(transparent)]
+pub struct StackSafe<T>(std::mem::ManuallyDrop<T>);
+
+// `Option<StackSafe<Box<T>>>` is documented to be pointer-sized, so it must never regress.
+const _: () = assert!(size_of::<Option<StackSafe<Box<u8>>>>() == size_of::<Box<u8>>());
+const _: () = assert!(size_of::<Option<StackSafe<&u8>>>() == size_of::<&u8>());
+
+// The auto traits of `StackSafe<T>` are documented to follow `T`, so they must never regress.
+const _: () = {
+    const fn assert_auto_traits<T: Send + Sync + Unpin + std::panic::UnwindSafe>() {}
+    assert_auto_traits::<StackSafe<Box<u8>>>();
+    assert_auto_traits::<StackSafe<Arc<u8>>>();
+};
+
+impl<T> StackSafe<T> {
+    /// Creates a new [`StackSafe<T>`] wrapper around the given value.
+    ///
+    /// This is a `const fn`, so recursive tables can be defined directly in `static` items.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use stacksafe::StackSafe;
+    ///
+    /// let wrapped = StackSafe::new(vec![1, 2, 3]);
+    /// ```
+    ///
+    /// A statically defined trie:
+    ///
+    /// ```rust
+    /// use stacksafe::StackSafe;
+    /// use stacksafe::stacksafe;
+    ///
+    /// struct Trie {
+    ///     terminal: bool,
+    ///     children: &'static [(char, &'static StackSafe<Trie>)],
+    /// }
+    ///
+    /// static WORD: StackSafe<Trie> = StackSafe::new(Trie {
+    ///     terminal: true,
+    ///     children: &[],
+    /// });
+    /// static I: StackSafe<Trie> = StackSafe::new(Trie {
+    ///     terminal: false,
+    ///     children: &[('f', &WORD), ('n', &WORD)],
+    /// });
+    /// static KEYWORDS: Trie = Trie {
+    ///     terminal: false,
+    ///     children: &[('i', &I)],
+    /// };
+    ///
+    /// #[stacksafe]
+    /// fn contains(trie: &Trie, word: &str) -> bool {
+    ///     let mut chars = word.chars();
+    ///     match chars.next() {
+    ///         None => trie.terminal,
+    ///         Some(c) => trie
+    ///             .children
+    ///             .iter()
+    ///             .any(|(k, child)| *k == c && contains(child, chars.as_str())),
+    ///     }
+    /// }
+    ///
+    /// assert!(contains(&KEYWORDS, "if"));
+    /// assert!(!contains(&KEYWORDS, "i"));
+    /// ```
+    pub const fn new(value: T) -> Self {
+        StackSafe(std::mem::ManuallyDrop::new(value))
+    }
+
+    /// Consumes the [`StackSafe<T>`] wrapper and returns the inner value.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    /// This helps ensure that recursive data structure access is properly
+    /// protected against stack overflow.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[stacksafe::stacksafe]
+    /// # fn main() {
+    /// use stacksafe::StackSafe;
+    ///
+    /// let wrapped = StackSafe::new(vec![1, 2, 3]);
+    /// let inner = wrapped.into_inner();
+    /// assert_eq!(inner, vec![1, 2, 3]);
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn into_inner(mut self) -> T {
+        crate::internal::assert_protected::<T>();
+
+        let value = unsafe { std::mem::ManuallyDrop::take(&mut self.0) };
+        std::mem::forget(self);
+        value
+    }
+
+    /// Consumes the [`StackSafe<T>`] wrapper, transforms the inner value with `f` in a stack-safe
+    /// context, and wraps the result.
+    ///
+    /// This is an associated function rather than a method, so that it does not shadow a `map`
+    /// method of the wrapped value. Since the value is only unwrapped within the stack-safe
+    /// context, this can be called anywhere.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use stacksafe::StackSafe;
+    ///
+    /// let wrapped = StackSafe::new(vec![1, 2, 3]);
+    /// let len = StackSafe::map(wrapped, |v| v.len());
+    /// assert_eq!(len, StackSafe::new(3));
+    /// ```
+    #[stacksafe(crate = crate)]
+    pub fn map<U>(this: Self, f: impl FnOnce(T) -> U) -> StackSafe<U> {
+        StackSafe::new(f(this.into_inner()))
+    }
+
+    /// Consumes the [`StackSafe<T>`] wrapper without dropping the wrapped value.
+    ///
+    /// Neither the wrapped value nor anything it owns is ever dropped, and their memory is leaked.
+    /// Since no destructor runs, no stack-safe context is entered and none is required, which
+    /// makes this the cheapest way to dispose of a structure that is never going to be freed,
+    /// e.g. in arena-style programs that keep their data alive until the process exits.
+    ///
+    /// This is equivalent to [`std::mem::forget`], which is also safe to call on a
+    /// [`StackSafe<T>`]: the wrapped value is held in a [`ManuallyDrop`](std::mem::ManuallyDrop)
+    /// that is only ever released by the guarded [`Drop`] implementation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use stacksafe::StackSafe;
+    ///
+    /// let wrapped = StackSafe::new(vec![1, 2, 3]);
+    /// wrapped.forget();
+    /// ```
+    pub fn forget(self) {
+        std::mem::forget(self);
+    }
+
+    /// Returns a raw pointer to the wrapped value.
+    ///
+    /// This does not access the value, so it is allowed outside of a stack-safe context; reading
+    /// or writing through the pointer is subject to the same rules as any other raw pointer.
+    pub fn as_ptr(this: &Self) -> *const T {
+        &*this.0
+    }
+
+    /// Returns a raw mutable pointer to the wrapped value.
+    ///
+    /// See [`as_ptr`](Self::as_ptr).
+    pub fn as_mut_ptr(this: &mut Self) -> *mut T {
+        &mut *this.0
+    }
+
+    /// Projects a pinned reference to the wrapper to a pinned reference to the wrapped value.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    pub fn as_pin_ref(this: Pin<&Self>) -> Pin<&T> {
+        crate::internal::assert_protected::<T>();
+        mark_pinned();
+
+        // SAFETY: the wrapped value is structurally pinned, see the type documentation.
+        unsafe { this.map_unchecked(|this| &*this.0) }
+    }
+
+    /// Projects a pinned mutable reference to the wrapper to a pinned mutable reference to the
+    /// wrapped value.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    pub fn as_pin_mut(this: Pin<&mut Self>) -> Pin<&mut T> {
+        crate::internal::assert_protected::<T>();
+        mark_pinned();
+
+        // SAFETY: the wrapped value is structurally pinned, see the type documentation.
+        unsafe { this.map_unchecked_mut(|this| &mut *this.0) }
+    }
+
+    /// Views a mutable reference to a value as a mutable reference to a [`StackSafe<T>`], without
+    /// moving it.
+    ///
+    /// Since [`StackSafe<T>`] has the same layout as `T`, this allows migrating an existing field
+    /// in place, for example a `Box<T>` inside a structure that cannot be rebuilt.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use stacksafe::StackSafe;
+    ///
+    /// let mut field = Box::new(1);
+    /// let wrapped: &mut StackSafe<Box<i32>> = StackSafe::from_mut(&mut field);
+    /// *wrapped = StackSafe::boxed(2);
+    /// assert_eq!(*field, 2);
+    /// ```
+    pub fn from_mut(value: &mut T) -> &mut Self {
+        // SAFETY: `StackSafe<T>` is `#[repr(transparent)]` over `ManuallyDrop<T>`, which is
+        // `#[repr(transparent)]` over `T`.
+        unsafe { &mut *(value as *mut T).cast::<Self>() }
+    }
+
+    /// Converts `value` into a `T` with [`TryFrom`] in a stack-safe context, and wraps the
+    /// result.
+    ///
+    /// [`StackSafe<T>`] cannot implement [`TryFrom<U>`] itself, since the standard library already
+    /// implements [`TryFrom<T>`] for it through [`From<T>`]. This forwards the conversion instead,
+    /// so that validating conversions of recursive structures, whose implementations convert
+    /// their children the same way, never overflow the stack.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use stacksafe::StackSafe;
+    ///
+    /// enum Ast {
+    ///     Num(i64),
+    ///     Neg(Box<Ast>),
+    /// }
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Ir {
+    ///     Num(u32),
+    ///     Neg(StackSafe<Box<Ir>>),
+    /// }
+    ///
+    /// impl TryFrom<&Ast> for Ir {
+    ///     type Error = String;
+    ///
+    ///     fn try_from(ast: &Ast) -> Result<Self, String> {
+    ///         match ast {
+    ///             Ast::Num(n) => u32::try_from(*n).map(Ir::Num).map_err(|e| e.to_string()),
+    ///             Ast::Neg(inner) => StackSafe::try_boxed(&**inner).map(Ir::Neg),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let ast = Ast::Neg(Box::new(Ast::Num(1)));
+    /// let ir = StackSafe::<Ir>::try_from_value(&ast).unwrap();
+    /// assert_eq!(ir, StackSafe::new(Ir::Neg(StackSafe::boxed(Ir::Num(1)))));
+    ///
+    /// let ast = Ast::Neg(Box::new(Ast::Num(-1)));
+    /// assert!(StackSafe::<Ir>::try_from_value(&ast).is_err());
+    /// ```
+    #[stacksafe(crate = crate)]
+    pub fn try_from_value<U>(value: U) -> Result<Self, T::Error>
+    where T: TryFrom<U> {
+        T::try_from(value).map(StackSafe::new)
+    }
 
-/// Returns the current stack allocation size in bytes.
-///
-/// This is the size of new stack segments allocated when functions marked
-/// with [`#[stacksafe]`](stacksafe) require additional stack space.
-pub fn get_stack_allocation_size() -> usize {
-    STACK_ALLOC_SIZE.load(Ordering::Relaxed)
+    /// Converts the wrapped value with [`TryFrom`] in a stack-safe context.
+    ///
+    /// See [`try_from_value`](Self::try_from_value).
+    #[stacksafe(crate = crate)]
+    pub fn try_into_value<U>(self) -> Result<U, U::Error>
+    where U: TryFrom<T> {
+        U::try_from(self.into_inner())
+    }
 }
 
-/// A wrapper type for recursive data structures with automatic stack-safe operations.
-///
-/// [`StackSafe<T>`] wraps values that are part of recursive data structures, ensuring
-/// that operations like cloning, dropping, comparison, and serialization are performed
-/// safely without risking stack overflow.
-///
-/// The wrapper provides transparent access to the underlying value through [`Deref`]
-/// and [`DerefMut`], but enforces that such access occurs within a stack-safe context
-/// (i.e., within a function marked with [`#[stacksafe]`](stacksafe)).
-pub struct StackSafe<T>(std::mem::ManuallyDrop<T>);
+impl<T> StackSafe<Box<T>> {
+    /// Allocates `value` on the heap and wraps the resulting [`Box`] in a [`StackSafe`].
+    ///
+    /// This is a shorthand for `StackSafe::new(Box::new(value))`, the most common way of building
+    /// nodes in recursive data structures.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use stacksafe::StackSafe;
+    ///
+    /// enum List {
+    ///     Nil,
+    ///     Cons(i32, StackSafe<Box<List>>),
+    /// }
+    ///
+    /// let list = List::Cons(
+    ///     1,
+    ///     StackSafe::boxed(List::Cons(2, StackSafe::boxed(List::Nil))),
+    /// );
+    /// ```
+    pub fn boxed(value: T) -> Self {
+        StackSafe::new(Box::new(value))
+    }
 
-impl<T> StackSafe<T> {
-    /// Creates a new [`StackSafe<T>`] wrapper around the given value.
+    /// Converts `value` into a `T` with [`TryFrom`] in a stack-safe context, and boxes and wraps
+    /// the result.
+    ///
+    /// See [`try_from_value`](StackSafe::try_from_value).
+    #[stacksafe(crate = crate)]
+    pub fn try_boxed<U>(value: U) -> Result<Self, T::Error>
+    where T: TryFrom<U> {
+        T::try_from(value).map(StackSafe::boxed)
+    }
+
+    /// Consumes the [`StackSafe<Box<T>>`] wrapper and returns the raw pointer of the box.
+    ///
+    /// Like [`Box::into_raw`], the caller becomes responsible for the memory previously managed
+    /// by the box, and for dropping the value, typically by converting the pointer back with
+    /// [`from_raw`](Self::from_raw) or [`Box::from_raw`]. This does not access the value, so it is
+    /// allowed outside of a stack-safe context.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use stacksafe::StackSafe;
     ///
-    /// let wrapped = StackSafe::new(vec![1, 2, 3]);
+    /// let raw = StackSafe::into_raw(StackSafe::boxed(vec![1, 2, 3]));
+    /// // SAFETY: `raw` comes from `into_raw` and is converted back only once.
+    /// let wrapped = unsafe { StackSafe::from_raw(raw) };
+    /// drop(wrapped);
     /// ```
-    pub fn new(value: T) -> Self {
-        StackSafe(std::mem::ManuallyDrop::new(value))
+    pub fn into_raw(this: Self) -> *mut T {
+        let mut this = std::mem::ManuallyDrop::new(this);
+        // SAFETY: `this` is never used or dropped again.
+        let boxed = unsafe { std::mem::ManuallyDrop::take(&mut this.0) };
+        Box::into_raw(boxed)
     }
 
-    /// Consumes the [`StackSafe<T>`] wrapper and returns the inner value.
+    /// Constructs a [`StackSafe<Box<T>>`] from a raw pointer.
+    ///
+    /// This is the inverse of [`into_raw`](Self::into_raw), and takes ownership of the value just
+    /// like [`Box::from_raw`], so the value is dropped in a stack-safe context from then on.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must satisfy the requirements of [`Box::from_raw`]: it must have been allocated by
+    /// the global allocator with the layout of `T`, for example by [`into_raw`](Self::into_raw)
+    /// or [`Box::into_raw`], and it must not be used or converted back again afterwards.
+    pub unsafe fn from_raw(raw: *mut T) -> Self {
+        // SAFETY: the caller upholds the contract of `Box::from_raw`.
+        StackSafe::new(unsafe { Box::from_raw(raw) })
+    }
+
+    /// Consumes the [`StackSafe<Box<T>>`] wrapper and leaks the boxed value, returning a mutable
+    /// reference to it.
+    ///
+    /// Like [`Box::leak`], the value is never dropped and its memory is never freed (unless the
+    /// reference is converted back with [`Box::from_raw`]), so no guarded drop ever runs for it.
     ///
     /// # Panics
     ///
-    /// In debug builds, panics if called outside of a stack-safe context.
-    /// This helps ensure that recursive data structure access is properly
-    /// protected against stack overflow.
+    /// In debug builds, panics if called outside of a stack-safe context, since the returned
+    /// reference gives access to the wrapped value.
     ///
     /// # Examples
     ///
@@ -254,22 +1853,159 @@ impl<T> StackSafe<T> {
     /// # fn main() {
     /// use stacksafe::StackSafe;
     ///
-    /// let wrapped = StackSafe::new(vec![1, 2, 3]);
-    /// let inner = wrapped.into_inner();
-    /// assert_eq!(inner, vec![1, 2, 3]);
+    /// let leaked: &'static mut Vec<i32> = StackSafe::boxed(vec![1, 2, 3]).leak();
+    /// leaked.push(4);
+    /// assert_eq!(leaked, &[1, 2, 3, 4]);
     /// # }
     /// ```
     #[track_caller]
-    pub fn into_inner(mut self) -> T {
-        debug_assert!(
-            crate::internal::is_protected(),
-            "`StackSafe` should only be accessed within a stack-safe context\n\
-            help: add `#[stacksafe::stacksafe]` to the function containing this access"
-        );
+    pub fn leak<'a>(self) -> &'a mut T
+    where T: 'a {
+        Box::leak(self.into_inner())
+    }
+}
 
-        let value = unsafe { std::mem::ManuallyDrop::take(&mut self.0) };
-        std::mem::forget(self);
-        value
+impl<T> StackSafe<Arc<T>> {
+    /// Wraps `value` in an [`Arc`] and the resulting [`Arc`] in a [`StackSafe`].
+    ///
+    /// This is a shorthand for `StackSafe::new(Arc::new(value))`, the usual way of building nodes
+    /// in persistent data structures, whose versions share all unchanged subtrees.
+    ///
+    /// Such structures are updated by path copying: [`make_mut`](Self::make_mut) copies the nodes
+    /// on the path to a change while every subtree off that path stays shared. Dropping a version
+    /// only releases the nodes that no other version refers to, in a stack-safe context.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use stacksafe::StackSafe;
+    /// use stacksafe::stacksafe;
+    ///
+    /// #[derive(Clone, PartialEq)]
+    /// enum List {
+    ///     Nil,
+    ///     Cons(i32, StackSafe<Arc<List>>),
+    /// }
+    ///
+    /// // Returns a new version of `list` with the `n`-th element replaced.
+    /// #[stacksafe]
+    /// fn set(list: &mut StackSafe<Arc<List>>, n: usize, value: i32) {
+    ///     if let List::Cons(head, tail) = StackSafe::make_mut(list) {
+    ///         match n {
+    ///             0 => *head = value,
+    ///             _ => set(tail, n - 1, value),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let old = (0..100_000).fold(StackSafe::shared(List::Nil), |tail, i| {
+    ///     StackSafe::shared(List::Cons(i, tail))
+    /// });
+    /// let mut new = old.clone();
+    /// set(&mut new, 1, 42);
+    ///
+    /// assert!(!StackSafe::ptr_eq(&old, &new));
+    /// assert!(!old.eq_shared(&new));
+    /// ```
+    pub fn shared(value: T) -> Self {
+        StackSafe::new(Arc::new(value))
+    }
+
+    /// Returns `true` if both wrappers point to the same allocation, like [`Arc::ptr_eq`].
+    ///
+    /// The wrapped values are not accessed, so this can be called anywhere.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+
+    /// Compares the wrapped values, returning `true` right away if both wrappers point to the same
+    /// allocation.
+    ///
+    /// Comparing two versions of a persistent structure this way only descends into the subtrees
+    /// that are not shared, as long as the [`PartialEq`] implementation of `T` compares its
+    /// children with this method too. The comparison is performed in a stack-safe context.
+    #[stacksafe(crate = crate)]
+    pub fn eq_shared(&self, other: &Self) -> bool
+    where T: PartialEq {
+        Self::ptr_eq(self, other) || **self.0 == **other.0
+    }
+
+    /// Returns a mutable reference to the wrapped value, first replacing it with a copy if it is
+    /// shared, like [`Arc::make_mut`].
+    ///
+    /// The copy is shallow for persistent structures: the children of the copied node are shared
+    /// with the original node. Copies that are deep nonetheless are made in a stack-safe context.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    pub fn make_mut(this: &mut Self) -> &mut T
+    where T: Clone {
+        static SITE: internal::Site = internal::Site::new("stacksafe::StackSafe::make_mut");
+
+        crate::internal::assert_protected::<T>();
+
+        let arc = &mut this.0;
+        internal::guard(&SITE, move || Arc::make_mut(arc))
+    }
+
+    /// Returns a mutable reference to the wrapped value if this is its only reference, like
+    /// [`Arc::get_mut`].
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        crate::internal::assert_protected::<T>();
+
+        Arc::get_mut(&mut this.0)
+    }
+
+    /// Returns the wrapped value if this is its only reference, like [`Arc::try_unwrap`].
+    ///
+    /// Otherwise, the wrapper is returned unchanged.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        Arc::try_unwrap(this.into_inner()).map_err(StackSafe::new)
+    }
+
+    /// Returns the wrapped value if this is its only reference, or a copy of it otherwise, like
+    /// [`Arc::unwrap_or_clone`].
+    ///
+    /// The copy is made in a stack-safe context.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    pub fn unwrap_or_clone(this: Self) -> T
+    where T: Clone {
+        static SITE: internal::Site = internal::Site::new("stacksafe::StackSafe::unwrap_or_clone");
+
+        let arc = this.into_inner();
+        internal::guard(&SITE, move || Arc::unwrap_or_clone(arc))
+    }
+
+    /// Returns the wrapped value if this is its only reference, like [`Arc::into_inner`].
+    ///
+    /// Otherwise, the reference is dropped and `None` is returned. This lets a [`Dismantle`]
+    /// implementation hand over only the nodes that are not shared with other versions, so that
+    /// [`IncrementalDrop`] releases them without touching the shared ones.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    pub fn into_unique(this: Self) -> Option<T> {
+        Arc::into_inner(this.into_inner())
     }
 }
 
@@ -279,6 +2015,42 @@ impl<T> From<T> for StackSafe<T> {
     }
 }
 
+impl<T> From<StackSafe<Arc<T>>> for Arc<T> {
+    /// Unwraps the [`Arc`].
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    fn from(value: StackSafe<Arc<T>>) -> Self {
+        value.into_inner()
+    }
+}
+
+impl<T> AsRef<T> for StackSafe<T> {
+    /// Returns a reference to the wrapped value.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T> AsMut<T> for StackSafe<T> {
+    /// Returns a mutable reference to the wrapped value.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    fn as_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
 impl<T: Default> Default for StackSafe<T> {
     fn default() -> Self {
         StackSafe(std::mem::ManuallyDrop::new(T::default()))
@@ -309,11 +2081,7 @@ impl<T> Deref for StackSafe<T> {
     /// ```
     #[track_caller]
     fn deref(&self) -> &Self::Target {
-        debug_assert!(
-            crate::internal::is_protected(),
-            "`StackSafe` should only be accessed within a stack-safe context\n\
-            help: add `#[stacksafe::stacksafe]` to the function containing this access"
-        );
+        crate::internal::assert_protected::<T>();
 
         &self.0
     }
@@ -339,11 +2107,7 @@ impl<T> DerefMut for StackSafe<T> {
     /// ```
     #[track_caller]
     fn deref_mut(&mut self) -> &mut Self::Target {
-        debug_assert!(
-            crate::internal::is_protected(),
-            "`StackSafe` should only be accessed within a stack-safe context\n\
-            help: add `#[stacksafe::stacksafe]` to the function containing this access"
-        );
+        crate::internal::assert_protected::<T>();
 
         &mut self.0
     }
@@ -357,14 +2121,62 @@ impl<T: Clone> Clone for StackSafe<T> {
 }
 
 impl<T> Drop for StackSafe<T> {
-    #[stacksafe(crate = crate)]
     fn drop(&mut self) {
-        unsafe {
-            std::mem::ManuallyDrop::drop(&mut self.0);
+        // Values without drop glue, such as references into an arena, are not dropped
+        // recursively and need no stack-safe context.
+        if std::mem::needs_drop::<T>() {
+            if std::thread::panicking() {
+                drop_unwinding(&mut self.0);
+            } else {
+                drop_wrapped(&mut self.0);
+            }
         }
     }
 }
 
+// Whether any wrapped value has ever been projected to a pinned reference.
+static PINNED: AtomicBool = AtomicBool::new(false);
+
+#[inline(always)]
+fn mark_pinned() {
+    if !PINNED.load(Ordering::Relaxed) {
+        PINNED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Drops a wrapped value while the thread is unwinding from a panic.
+///
+/// A failure to allocate a new segment would otherwise raise a second panic from within a
+/// destructor and abort the process. Instead, the value is leaked, unless it may be pinned, in
+/// which case the process is aborted right away.
+#[cold]
+#[inline(never)]
+fn drop_unwinding<T>(value: &mut std::mem::ManuallyDrop<T>) {
+    static SITE: internal::Site = internal::Site::new("stacksafe::StackSafe::drop");
+    let dropped = internal::try_guard(&SITE, || unsafe {
+        std::mem::ManuallyDrop::drop(value);
+    });
+    if dropped.is_none() && PINNED.load(Ordering::Relaxed) {
+        std::process::abort();
+    }
+}
+
+#[stacksafe(crate = crate)]
+fn drop_wrapped<T>(value: &mut std::mem::ManuallyDrop<T>) {
+    unsafe {
+        std::mem::ManuallyDrop::drop(value);
+    }
+}
+
+impl<F: Future> Future for StackSafe<F> {
+    type Output = F::Output;
+
+    #[stacksafe(crate = crate)]
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<F::Output> {
+        StackSafe::as_pin_mut(self).poll(cx)
+    }
+}
+
 impl<T: std::fmt::Debug> std::fmt::Debug for StackSafe<T> {
     #[stacksafe(crate = crate)]
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -418,18 +2230,168 @@ impl<T: std::hash::Hash> std::hash::Hash for StackSafe<T> {
 }
 
 #[cfg(feature = "serde")]
-impl<T: serde::Serialize> serde::Serialize for StackSafe<T> {
+impl<T: ::serde::Serialize> ::serde::Serialize for StackSafe<T> {
     #[stacksafe(crate = crate)]
-    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         self.0.serialize(serializer)
     }
 }
 
 #[cfg(feature = "serde")]
-impl<'a, T: serde::Deserialize<'a>> serde::Deserialize<'a> for StackSafe<T> {
+impl<'a, T: ::serde::Deserialize<'a>> ::serde::Deserialize<'a> for StackSafe<T> {
     #[stacksafe(crate = crate)]
-    fn deserialize<D: serde::Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+    fn deserialize<D: ::serde::Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+        let _level = serde::Level::enter()?;
         let value = T::deserialize(deserializer)?;
         Ok(StackSafe(std::mem::ManuallyDrop::new(value)))
     }
 }
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: arbitrary::Arbitrary<'a>> arbitrary::Arbitrary<'a> for StackSafe<T> {
+    #[stacksafe(crate = crate)]
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        T::arbitrary(u).map(StackSafe::new)
+    }
+
+    #[stacksafe(crate = crate)]
+    fn arbitrary_take_rest(u: arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        T::arbitrary_take_rest(u).map(StackSafe::new)
+    }
+
+    #[stacksafe(crate = crate)]
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        T::size_hint(depth)
+    }
+
+    #[stacksafe(crate = crate)]
+    fn try_size_hint(
+        depth: usize,
+    ) -> Result<(usize, Option<usize>), arbitrary::MaxRecursionReached> {
+        T::try_size_hint(depth)
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl<T: ::proptest::arbitrary::Arbitrary> ::proptest::arbitrary::Arbitrary for StackSafe<T> {
+    type Parameters = T::Parameters;
+    type Strategy = proptest::StackSafeStrategy<T::Strategy>;
+
+    fn arbitrary_with(args: T::Parameters) -> Self::Strategy {
+        proptest::StackSafeStrategy::new(T::arbitrary_with(args))
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl<T: schemars::JsonSchema> schemars::JsonSchema for StackSafe<T> {
+    fn inline_schema() -> bool {
+        T::inline_schema()
+    }
+
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        T::schema_name()
+    }
+
+    fn schema_id() -> std::borrow::Cow<'static, str> {
+        T::schema_id()
+    }
+
+    #[stacksafe(crate = crate)]
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        T::json_schema(generator)
+    }
+}
+
+// SAFETY: `StackSafe<T>` is a `#[repr(transparent)]` wrapper around a `ManuallyDrop<T>`, which has
+// the same layout and bit validity as `T`.
+#[cfg(feature = "rkyv")]
+unsafe impl<T: rkyv::Portable> rkyv::Portable for StackSafe<T> {}
+
+#[cfg(feature = "rkyv")]
+impl<T: rkyv::Archive> rkyv::Archive for StackSafe<T> {
+    type Archived = StackSafe<T::Archived>;
+    type Resolver = T::Resolver;
+
+    fn resolve(&self, resolver: T::Resolver, out: rkyv::Place<Self::Archived>) {
+        // SAFETY: `StackSafe<T::Archived>` has the same layout as `T::Archived`.
+        let out = unsafe { out.cast_unchecked::<T::Archived>() };
+        T::resolve(&self.0, resolver, out);
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T, S> rkyv::Serialize<S> for StackSafe<T>
+where
+    T: rkyv::Serialize<S>,
+    S: rkyv::rancor::Fallible + ?Sized,
+{
+    #[stacksafe(crate = crate)]
+    fn serialize(&self, serializer: &mut S) -> Result<T::Resolver, S::Error> {
+        T::serialize(&self.0, serializer)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T, D> rkyv::Deserialize<StackSafe<T>, D> for StackSafe<T::Archived>
+where
+    T: rkyv::Archive,
+    T::Archived: rkyv::Deserialize<T, D>,
+    D: rkyv::rancor::Fallible + ?Sized,
+{
+    #[stacksafe(crate = crate)]
+    fn deserialize(&self, deserializer: &mut D) -> Result<StackSafe<T>, D::Error> {
+        T::Archived::deserialize(&self.0, deserializer).map(StackSafe::new)
+    }
+}
+
+// SAFETY: `StackSafe<T>` is a `#[repr(transparent)]` wrapper around a `ManuallyDrop<T>`, so a
+// valid `ManuallyDrop<T>` is a valid `StackSafe<T>`.
+#[cfg(feature = "rkyv")]
+unsafe impl<T, C> rkyv::bytecheck::CheckBytes<C> for StackSafe<T>
+where
+    std::mem::ManuallyDrop<T>: rkyv::bytecheck::CheckBytes<C>,
+    C: rkyv::rancor::Fallible + ?Sized,
+{
+    #[stacksafe(crate = crate)]
+    unsafe fn check_bytes(value: *const Self, context: &mut C) -> Result<(), C::Error> {
+        // SAFETY: the caller upholds the contract of `CheckBytes::check_bytes`, and the pointer
+        // keeps pointing to a value of the same layout.
+        unsafe {
+            <std::mem::ManuallyDrop<T> as rkyv::bytecheck::CheckBytes<C>>::check_bytes(
+                value.cast(),
+                context,
+            )
+        }
+    }
+}
+
+#[cfg(feature = "gc")]
+impl<T: gc::Trace> gc::Finalize for StackSafe<T> {}
+
+// SAFETY: every method forwards to the wrapped value, which is owned just like in a `Box<T>`.
+#[cfg(feature = "gc")]
+unsafe impl<T: gc::Trace> gc::Trace for StackSafe<T> {
+    #[stacksafe(crate = crate)]
+    unsafe fn trace(&self) {
+        // SAFETY: the caller upholds the contract of `Trace::trace`.
+        unsafe { self.0.trace() }
+    }
+
+    #[stacksafe(crate = crate)]
+    unsafe fn root(&self) {
+        // SAFETY: the caller upholds the contract of `Trace::root`.
+        unsafe { self.0.root() }
+    }
+
+    #[stacksafe(crate = crate)]
+    unsafe fn unroot(&self) {
+        // SAFETY: the caller upholds the contract of `Trace::unroot`.
+        unsafe { self.0.unroot() }
+    }
+
+    #[stacksafe(crate = crate)]
+    fn finalize_glue(&self) {
+        gc::Finalize::finalize(self);
+        self.0.finalize_glue();
+    }
+}
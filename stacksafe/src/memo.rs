@@ -0,0 +1,116 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Memoized recursion.
+//!
+//! Many recursive definitions, such as the Fibonacci sequence, recompute the same subproblems an
+//! exponential number of times when written as plain recursive functions. [`recurse`] evaluates
+//! such a definition with every result cached by its key, and every step guarded, so that the
+//! recursion is both fast and safe for any depth.
+//!
+//! # Examples
+//!
+//! ```rust
+//! let fibonacci = |memo: &mut stacksafe::memo::Memo<'_, u64, u64>, n: u64| match n {
+//!     0 | 1 => n,
+//!     _ => memo.get(n - 1).wrapping_add(memo.get(n - 2)),
+//! };
+//!
+//! assert_eq!(
+//!     stacksafe::memo::recurse(90, fibonacci),
+//!     2_880_067_194_370_816_120
+//! );
+//! // Each value is computed once, so deep recursion is fine too.
+//! let _ = stacksafe::memo::recurse(100_000, fibonacci);
+//! ```
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::RandomState;
+
+use crate::internal::Site;
+
+/// The table of results computed by a call to [`recurse`], through which the computation
+/// recurses.
+pub struct Memo<'f, K, V, S = RandomState> {
+    table: HashMap<K, V, S>,
+    compute: &'f Compute<'f, K, V, S>,
+}
+
+type Compute<'f, K, V, S> = dyn Fn(&mut Memo<'f, K, V, S>, K) -> V + 'f;
+
+impl<K: Eq + Hash + Clone, V: Clone, S: BuildHasher> Memo<'_, K, V, S> {
+    /// Returns the result for `key`, computing it in a stack-safe context unless it is cached.
+    ///
+    /// The result for a key must not depend on itself, or the computation recurses without end.
+    pub fn get(&mut self, key: K) -> V {
+        static SITE: Site = Site::new("stacksafe::memo::Memo::get");
+        if let Some(value) = self.table.get(&key) {
+            return value.clone();
+        }
+        let compute = self.compute;
+        let value = crate::internal::guard(&SITE, || compute(self, key.clone()));
+        self.table.insert(key, value.clone());
+        value
+    }
+
+    /// Returns the cached result for `key`, if it has been computed.
+    pub fn cached(&self, key: &K) -> Option<&V> {
+        self.table.get(key)
+    }
+
+    /// Returns the number of results computed so far.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Returns `true` if no result has been computed yet.
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+}
+
+/// Computes the result for `key` with `compute`, caching the result for every key.
+///
+/// `compute` receives the [`Memo`] table and a key, and calls [`Memo::get`] for the results it
+/// depends on rather than calling itself. Results are cloned out of the table, so cheap values
+/// such as integers or `Arc`s work best.
+pub fn recurse<K, V>(key: K, compute: impl Fn(&mut Memo<'_, K, V>, K) -> V) -> V
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    recurse_with_hasher(key, RandomState::new(), compute)
+}
+
+/// Like [`recurse`], but hashes keys with `hash_builder`.
+///
+/// A faster hasher than the default one can speed up computations with many small keys.
+pub fn recurse_with_hasher<K, V, S>(
+    key: K,
+    hash_builder: S,
+    compute: impl Fn(&mut Memo<'_, K, V, S>, K) -> V,
+) -> V
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+    S: BuildHasher,
+{
+    let mut memo = Memo {
+        table: HashMap::with_hasher(hash_builder),
+        compute: &compute,
+    };
+    memo.get(key)
+}
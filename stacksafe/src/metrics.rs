@@ -0,0 +1,151 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Counters and a hook for stack growth.
+//!
+//! With the `metrics` feature, every new stack segment is counted, along with its size, and every
+//! thread keeps track of the deepest recursion of functions marked with
+//! [`#[stacksafe]`](crate::stacksafe) it has seen. These counters are cheap to read and can be
+//! exported periodically to a metrics system such as Prometheus.
+//!
+//! [`on_grow`] installs a hook that is called whenever the stack grows into a new segment, for
+//! forwarding growth to a logging or tracing system as it happens.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use stacksafe::metrics;
+//! use stacksafe::stacksafe;
+//!
+//! #[stacksafe]
+//! fn count(n: u64) -> u64 {
+//!     if n == 0 { 0 } else { 1 + count(n - 1) }
+//! }
+//!
+//! metrics::on_grow(|segment| {
+//!     eprintln!(
+//!         "`{}` grew the stack by {} bytes",
+//!         segment.label(),
+//!         segment.size()
+//!     );
+//! });
+//!
+//! assert_eq!(count(100_000), 100_000);
+//! assert!(metrics::segments_allocated() > 0);
+//! assert!(metrics::bytes_allocated() > 0);
+//! assert!(metrics::max_depth() > 100_000);
+//! # metrics::clear_on_grow();
+//! ```
+
+use std::cell::Cell;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use crate::SegmentInfo;
+
+static SEGMENTS: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static NESTING: Cell<usize> = const { Cell::new(0) };
+    static MAX_NESTING: Cell<usize> = const { Cell::new(0) };
+}
+
+type GrowHook = Box<dyn Fn(&SegmentInfo) + Send + Sync>;
+
+static GROW_HOOK: RwLock<Option<GrowHook>> = RwLock::new(None);
+
+/// Returns how many stack segments all threads have grown into.
+///
+/// Segments that are reused, such as the ones set aside by
+/// [`realtime::init`](crate::realtime::init) or kept in the [pool](crate::set_pool_capacity), count
+/// each time they are used.
+pub fn segments_allocated() -> u64 {
+    SEGMENTS.load(Ordering::Relaxed)
+}
+
+/// Returns the total size of the stack segments counted by [`segments_allocated`], in bytes.
+pub fn bytes_allocated() -> u64 {
+    BYTES.load(Ordering::Relaxed)
+}
+
+/// Returns the deepest nesting of stack-safe contexts observed on the current thread.
+///
+/// Every call to a function marked with [`#[stacksafe]`](crate::stacksafe) adds one level, so
+/// this is the maximum recursion depth the current thread has reached since it started, or since
+/// the last call to [`reset_max_depth`].
+pub fn max_depth() -> usize {
+    MAX_NESTING.with(|m| m.get())
+}
+
+/// Resets the maximum depth of the current thread to the depth it is currently at.
+pub fn reset_max_depth() {
+    MAX_NESTING.with(|m| m.set(NESTING.with(|n| n.get())));
+}
+
+/// Installs a hook that is called whenever the stack grows into a new segment.
+///
+/// The hook is called on the growing thread, before switching to the new segment, with
+/// information about the new segment. It replaces any previously installed hook. Which growths
+/// invoke the hook can be configured with [`set_growth_sampling`](crate::set_growth_sampling);
+/// the counters of this module keep counting every growth.
+///
+/// The hook may call guarded code, but the stack is not grown while it runs, and it is not
+/// invoked again for growths in the meantime.
+pub fn on_grow(hook: impl Fn(&SegmentInfo) + Send + Sync + 'static) {
+    *GROW_HOOK.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(hook));
+}
+
+/// Removes the hook installed with [`on_grow`].
+pub fn clear_on_grow() {
+    *GROW_HOOK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Counts a new stack segment being entered, and calls the hook if the growth is sampled.
+pub(crate) fn growth(segment: SegmentInfo, sampled: bool) {
+    SEGMENTS.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(segment.size() as u64, Ordering::Relaxed);
+    if sampled {
+        crate::hook::run(|| {
+            let hook = GROW_HOOK.read().unwrap_or_else(|e| e.into_inner());
+            if let Some(hook) = hook.as_ref() {
+                hook(&segment);
+            }
+        });
+    }
+}
+
+/// Tracks one level of nesting while it is alive.
+pub(crate) struct Nesting(());
+
+impl Nesting {
+    #[inline(always)]
+    pub(crate) fn enter() -> Nesting {
+        let depth = NESTING.with(|n| {
+            let depth = n.get() + 1;
+            n.set(depth);
+            depth
+        });
+        MAX_NESTING.with(|m| m.set(m.get().max(depth)));
+        Nesting(())
+    }
+}
+
+impl Drop for Nesting {
+    #[inline(always)]
+    fn drop(&mut self) {
+        NESTING.with(|n| n.set(n.get() - 1));
+    }
+}
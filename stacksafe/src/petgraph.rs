@@ -0,0 +1,141 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion of recursive structures to and from [`petgraph`] graphs.
+//!
+//! Graph algorithms such as traversals, dominators, or isomorphism checks are readily available
+//! for [`Graph`], while recursive types are more convenient to build and pattern match on. The
+//! functions in this module move the nodes of a recursive structure into a [`Graph`] and back
+//! without recursion, so hot algorithms can work on the graph while the rest of a program keeps
+//! using the recursive form.
+//!
+//! Nodes are split apart with [`Dismantle`] and put back together with [`Assemble`]. Every node
+//! weight is a dismantled node, that is, one whose children have been moved out. Every edge leads
+//! from a parent to one of its children, and its weight is the position of the child among its
+//! siblings.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use petgraph::visit::Dfs;
+//! use stacksafe::Assemble;
+//! use stacksafe::Dismantle;
+//! use stacksafe::StackSafe;
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Tree {
+//!     value: u32,
+//!     children: Vec<StackSafe<Tree>>,
+//! }
+//!
+//! impl Dismantle for Tree {
+//!     fn dismantle(&mut self, children: &mut Vec<Self>) {
+//!         children.extend(self.children.drain(..).map(StackSafe::into_inner));
+//!     }
+//! }
+//!
+//! impl Assemble for Tree {
+//!     fn assemble(&mut self, children: Vec<Self>) {
+//!         self.children
+//!             .extend(children.into_iter().map(StackSafe::new));
+//!     }
+//! }
+//!
+//! let leaf = |value| Tree {
+//!     value,
+//!     children: vec![],
+//! };
+//! let tree = Tree {
+//!     value: 1,
+//!     children: vec![StackSafe::new(leaf(2)), StackSafe::new(leaf(3))],
+//! };
+//!
+//! let (mut graph, root) = stacksafe::petgraph::to_graph(tree);
+//! let mut dfs = Dfs::new(&graph, root);
+//! while let Some(node) = dfs.next(&graph) {
+//!     graph[node].value *= 10;
+//! }
+//!
+//! let tree = stacksafe::petgraph::from_graph(graph, root);
+//! let expected = Tree {
+//!     value: 10,
+//!     children: vec![StackSafe::new(leaf(20)), StackSafe::new(leaf(30))],
+//! };
+//! assert_eq!(tree, expected);
+//! ```
+
+use ::petgraph::graph::Graph;
+use ::petgraph::graph::NodeIndex;
+
+use crate::Assemble;
+use crate::Dismantle;
+use crate::internal::Site;
+
+/// Moves `root` and all of its descendants into a new [`Graph`], returning the graph and the
+/// index of the root node.
+pub fn to_graph<T: Dismantle>(root: T) -> (Graph<T, usize>, NodeIndex) {
+    let mut graph = Graph::new();
+    let index = insert(&mut graph, root);
+    (graph, index)
+}
+
+/// Moves `root` and all of its descendants into `graph`, returning the index of the root node.
+///
+/// No edges lead to the root; it can be connected to existing nodes with [`Graph::add_edge`].
+pub fn insert<T: Dismantle>(graph: &mut Graph<T, usize>, root: T) -> NodeIndex {
+    static SITE: Site = Site::new("stacksafe::petgraph::insert");
+    crate::internal::guard(&SITE, || {
+        crate::assemble::flatten(root, |node, parent| {
+            let index = graph.add_node(node);
+            if let Some((parent, position)) = parent {
+                graph.add_edge(parent, index, position);
+            }
+            index
+        })
+    })
+}
+
+/// Rebuilds the recursive structure rooted at `root` from `graph`.
+///
+/// The children of every node are the targets of its outgoing edges, ordered by edge weight. The
+/// graph is consumed; nodes that are not reachable from `root` are dropped.
+///
+/// # Panics
+///
+/// Panics if `root` is not a node of the graph, or if the nodes reachable from it do not form a
+/// tree.
+pub fn from_graph<T: Assemble>(graph: Graph<T, usize>, root: NodeIndex) -> T {
+    static SITE: Site = Site::new("stacksafe::petgraph::from_graph");
+
+    assert!(
+        root.index() < graph.node_count(),
+        "the root is not in the graph"
+    );
+    let (nodes, edges) = graph.into_nodes_edges();
+    let mut children = vec![Vec::new(); nodes.len()];
+    for edge in &edges {
+        children[edge.source().index()].push((edge.weight, edge.target().index()));
+    }
+    let children: Vec<Vec<usize>> = children
+        .into_iter()
+        .map(|mut targets| {
+            targets.sort_by_key(|&(position, _)| position);
+            targets.into_iter().map(|(_, target)| target).collect()
+        })
+        .collect();
+    let nodes = nodes.into_iter().map(|node| Some(node.weight)).collect();
+    crate::internal::guard(&SITE, || {
+        crate::assemble::unflatten(nodes, &children, root.index())
+    })
+}
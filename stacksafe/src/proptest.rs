@@ -0,0 +1,105 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Stack-safe generation and shrinking of [`StackSafe<T>`] values with [`proptest`].
+//!
+//! [`StackSafe<T>`] implements [`Arbitrary`](::proptest::arbitrary::Arbitrary) whenever `T` does,
+//! with the strategy of `T` wrapped in a [`StackSafeStrategy`]. Generating, shrinking, and
+//! producing the current value of the wrapped strategy all happen in a stack-safe context, so
+//! strategies for deeply recursive types, such as ones built with
+//! [`prop_recursive`](::proptest::strategy::Strategy::prop_recursive), can be nested deeply.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use proptest::prelude::*;
+//! use stacksafe::StackSafe;
+//!
+//! let mut runner = proptest::test_runner::TestRunner::default();
+//! runner
+//!     .run(&any::<StackSafe<Vec<u8>>>(), |value| {
+//!         prop_assert!(stacksafe::protect(|| value.len()) < 100);
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+
+use std::fmt;
+
+use ::proptest::strategy::NewTree;
+use ::proptest::strategy::Strategy;
+use ::proptest::strategy::ValueTree;
+use ::proptest::test_runner::TestRunner;
+
+use crate::StackSafe;
+use crate::stacksafe;
+
+/// A [`Strategy`] that generates the values of `S` in a stack-safe context, wrapped in a
+/// [`StackSafe<T>`].
+///
+/// See the [module documentation](self) for details.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StackSafeStrategy<S>(S);
+
+impl<S> StackSafeStrategy<S> {
+    /// Wraps `strategy`.
+    pub fn new(strategy: S) -> Self {
+        StackSafeStrategy(strategy)
+    }
+
+    /// Returns the wrapped strategy.
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S: Strategy> Strategy for StackSafeStrategy<S> {
+    type Tree = StackSafeValueTree<S::Tree>;
+    type Value = StackSafe<S::Value>;
+
+    #[stacksafe(crate = crate)]
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        self.0.new_tree(runner).map(StackSafeValueTree)
+    }
+}
+
+/// The [`ValueTree`] of a [`StackSafeStrategy`].
+#[derive(Clone, Copy, Default)]
+pub struct StackSafeValueTree<V>(V);
+
+impl<V: fmt::Debug> fmt::Debug for StackSafeValueTree<V> {
+    #[stacksafe(crate = crate)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("StackSafeValueTree").field(&self.0).finish()
+    }
+}
+
+impl<V: ValueTree> ValueTree for StackSafeValueTree<V> {
+    type Value = StackSafe<V::Value>;
+
+    #[stacksafe(crate = crate)]
+    fn current(&self) -> StackSafe<V::Value> {
+        StackSafe::new(self.0.current())
+    }
+
+    #[stacksafe(crate = crate)]
+    fn simplify(&mut self) -> bool {
+        self.0.simplify()
+    }
+
+    #[stacksafe(crate = crate)]
+    fn complicate(&mut self) -> bool {
+        self.0.complicate()
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Low-level stack growth primitives.
+//!
+//! [`#[stacksafe]`](crate::stacksafe) and [`protect`](crate::protect) cover most uses. This
+//! module is for libraries that manage stack growth themselves, such as interpreters or
+//! runtimes with their own recursion scheme, and would otherwise use `stacker` directly. Unlike
+//! `stacker`, the functions here grow the stack into segments managed by this crate, so that
+//! [segment allocators](crate::set_segment_allocator), [reserved
+//! segments](crate::realtime), [telemetry](crate::current_segment) and hooks apply to them, and
+//! callbacks run in a stack-safe context.
+//!
+//! # Examples
+//!
+//! ```rust
+//! fn eval(depth: u64) -> u64 {
+//!     stacksafe::raw::maybe_grow(64 * 1024, 1024 * 1024, || {
+//!         if depth == 0 { 0 } else { 1 + eval(depth - 1) }
+//!     })
+//! }
+//!
+//! assert_eq!(eval(100_000), 100_000);
+//! ```
+
+/// Runs `callback`, first switching to a new stack segment of at least `stack_size` bytes if
+/// fewer than `red_zone` bytes of stack are left.
+///
+/// This is the equivalent of `stacker::maybe_grow`. The callback runs in a stack-safe context.
+/// Like for guarded functions, the stack is not grown while growth is
+/// [disabled](crate::set_growth_enabled), on an alternate signal stack, or within a hook, and
+/// calls count towards the interval of a [yield hook](crate::with_yield_hook). Segments are
+/// labeled `stacksafe::raw::maybe_grow`.
+///
+/// To use the sizes configured for this crate, pass the ones of
+/// [`StackConfig::current`](crate::StackConfig::current), or use [`protect`](crate::protect).
+///
+/// # Panics
+///
+/// Panics if `red_zone` is zero, or if `stack_size` is not larger than `red_zone`.
+#[inline]
+pub fn maybe_grow<R>(red_zone: usize, stack_size: usize, callback: impl FnOnce() -> R) -> R {
+    assert!(red_zone > 0, "the red zone must not be empty");
+    assert!(
+        stack_size > red_zone,
+        "the stack size must be larger than the red zone"
+    );
+    crate::internal::guard_sized("stacksafe::raw::maybe_grow", red_zone, stack_size, callback)
+}
+
+/// Returns the number of bytes left on the stack segment the current thread is running on, or
+/// `None` if it cannot be determined.
+///
+/// This is the equivalent of `stacker::remaining_stack`, and also works on segments allocated by
+/// this crate.
+///
+/// # Examples
+///
+/// ```rust
+/// if let Some(remaining) = stacksafe::raw::remaining_stack() {
+///     println!("{remaining} bytes of stack left");
+/// }
+/// ```
+#[inline]
+pub fn remaining_stack() -> Option<usize> {
+    crate::segment::remaining_stack()
+}
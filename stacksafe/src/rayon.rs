@@ -0,0 +1,467 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parallel processing of large recursive structures with [`rayon`].
+//!
+//! The helpers in this module split a structure at its top levels until there are enough
+//! independent subtrees to keep every worker busy, then process those subtrees in parallel, each
+//! in its own stack-safe context. [`ParallelIteratorExt::stacksafe`] runs every item of a
+//! parallel pipeline in a stack-safe context, and [`join`] runs two closures in parallel like
+//! [`rayon::join`].
+//!
+//! The tasks started by these helpers inherit the stack-safe context and the [scoped
+//! configuration](crate::StackConfig::scope) of the thread that starts them, like the threads
+//! started with the [`thread`](crate::thread) module, even though they may run on other worker
+//! threads.
+
+use ::rayon::iter::IndexedParallelIterator;
+use ::rayon::iter::IntoParallelIterator;
+use ::rayon::iter::ParallelIterator;
+use ::rayon::iter::plumbing::Consumer;
+use ::rayon::iter::plumbing::Folder;
+use ::rayon::iter::plumbing::Producer;
+use ::rayon::iter::plumbing::ProducerCallback;
+use ::rayon::iter::plumbing::UnindexedConsumer;
+
+use crate::Dismantle;
+use crate::internal::Site;
+use crate::thread::Context;
+
+// How many levels are dismantled sequentially at most when looking for enough subtrees. This
+// bounds the sequential work for structures that do not fan out, such as linked lists.
+const MAX_SPLIT_LEVELS: usize = 16;
+
+fn split_target() -> usize {
+    ::rayon::current_num_threads() * 4
+}
+
+/// Returns a thread start handler that runs the callback installed with
+/// [`GlobalSetup::on_thread_start`](crate::GlobalSetup::on_thread_start) on every worker thread as
+/// soon as it starts, rather than when it first grows its stack.
+///
+/// # Examples
+///
+/// ```rust
+/// let pool = rayon::ThreadPoolBuilder::new()
+///     .start_handler(stacksafe::rayon::start_handler())
+///     .build()
+///     .unwrap();
+/// ```
+pub fn start_handler() -> impl Fn(usize) + Send + Sync + 'static {
+    |_| crate::global::start_thread()
+}
+
+/// Drops `value` using all threads of the current rayon thread pool.
+///
+/// The top levels of the structure are [dismantled](Dismantle) sequentially until there are
+/// enough subtrees to keep every worker busy; the subtrees are then dropped in parallel.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::Dismantle;
+/// use stacksafe::StackSafe;
+///
+/// enum Tree {
+///     Leaf(u32),
+///     Node(Vec<StackSafe<Tree>>),
+/// }
+///
+/// impl Dismantle for Tree {
+///     fn dismantle(&mut self, children: &mut Vec<Self>) {
+///         if let Tree::Node(nodes) = self {
+///             children.extend(nodes.drain(..).map(StackSafe::into_inner));
+///         }
+///     }
+/// }
+///
+/// let tree = Tree::Node((0..64).map(|i| StackSafe::new(Tree::Leaf(i))).collect());
+/// stacksafe::rayon::par_drop(tree);
+/// ```
+pub fn par_drop<T: Dismantle + Send>(value: T) {
+    static SITE: Site = Site::new("stacksafe::rayon::par_drop");
+
+    let target = split_target();
+    let mut frontier = vec![value];
+    let mut levels = 0;
+    crate::internal::guard(&SITE, || {
+        while frontier.len() < target && levels < MAX_SPLIT_LEVELS {
+            let mut next = Vec::with_capacity(frontier.len() * 2);
+            for mut node in frontier.drain(..) {
+                node.dismantle(&mut next);
+            }
+            if next.is_empty() {
+                return;
+            }
+            frontier = next;
+            levels += 1;
+        }
+    });
+
+    frontier
+        .into_par_iter()
+        .for_each(|node| crate::internal::guard(&SITE, || drop(node)));
+}
+
+/// Calls `visit` on every node of the structure rooted at `root`, using all threads of the
+/// current rayon thread pool.
+///
+/// `children` returns the direct children of a node. Nodes in the top levels of the structure
+/// are visited sequentially while looking for enough subtrees to keep every worker busy; the
+/// subtrees are then visited in parallel, each one depth-first. No particular visiting order is
+/// guaranteed.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::atomic::AtomicU64;
+/// use std::sync::atomic::Ordering;
+///
+/// use stacksafe::StackSafe;
+///
+/// struct Tree {
+///     value: u64,
+///     children: Vec<StackSafe<Tree>>,
+/// }
+///
+/// let tree = Tree {
+///     value: 1,
+///     children: (2..=10)
+///         .map(|value| {
+///             StackSafe::new(Tree {
+///                 value,
+///                 children: vec![],
+///             })
+///         })
+///         .collect(),
+/// };
+///
+/// let sum = AtomicU64::new(0);
+/// stacksafe::rayon::par_visit(
+///     &tree,
+///     |node| node.children.iter().map(|child| &**child),
+///     |node| {
+///         sum.fetch_add(node.value, Ordering::Relaxed);
+///     },
+/// );
+/// assert_eq!(sum.into_inner(), 55);
+/// ```
+pub fn par_visit<'a, T, C, I, F>(root: &'a T, children: C, visit: F)
+where
+    T: Sync,
+    C: Fn(&'a T) -> I + Sync,
+    I: IntoIterator<Item = &'a T>,
+    F: Fn(&'a T) + Sync,
+{
+    static SITE: Site = Site::new("stacksafe::rayon::par_visit");
+
+    #[crate::stacksafe(crate = crate)]
+    fn visit_all<'a, T, C, I, F>(node: &'a T, children: &C, visit: &F)
+    where
+        C: Fn(&'a T) -> I,
+        I: IntoIterator<Item = &'a T>,
+        F: Fn(&'a T),
+    {
+        visit(node);
+        for child in children(node) {
+            visit_all(child, children, visit);
+        }
+    }
+
+    let target = split_target();
+    let mut frontier = vec![root];
+    let mut levels = 0;
+    crate::internal::guard(&SITE, || {
+        while frontier.len() < target && levels < MAX_SPLIT_LEVELS {
+            let mut next = Vec::with_capacity(frontier.len() * 2);
+            for node in frontier.drain(..) {
+                visit(node);
+                next.extend(children(node));
+            }
+            frontier = next;
+            levels += 1;
+        }
+    });
+
+    frontier
+        .into_par_iter()
+        .for_each(|node| visit_all(node, &children, &visit));
+}
+
+/// Runs `oper_a` and `oper_b` potentially in parallel, like [`rayon::join`], and
+/// returns their results.
+///
+/// Both closures run in a stack-safe context if the current thread is in one, and with its
+/// [scoped configuration](crate::StackConfig::scope), on whichever worker thread picks them up.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::StackSafe;
+/// use stacksafe::stacksafe;
+///
+/// struct Tree {
+///     value: u64,
+///     children: Vec<StackSafe<Tree>>,
+/// }
+///
+/// #[stacksafe]
+/// fn sum(trees: &[StackSafe<Tree>]) -> u64 {
+///     match trees {
+///         [] => 0,
+///         [tree] => tree.value + sum(&tree.children),
+///         _ => {
+///             let (left, right) = trees.split_at(trees.len() / 2);
+///             let (left, right) = stacksafe::rayon::join(|| sum(left), || sum(right));
+///             left + right
+///         }
+///     }
+/// }
+///
+/// let leaves = (1..=100)
+///     .map(|value| {
+///         StackSafe::new(Tree {
+///             value,
+///             children: vec![],
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// assert_eq!(sum(&leaves), 5050);
+/// ```
+pub fn join<A, B, RA, RB>(oper_a: A, oper_b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    let context = Context::capture();
+    ::rayon::join(|| context.run(oper_a), || context.run(oper_b))
+}
+
+/// An extension trait for guarding the items of a [`ParallelIterator`].
+pub trait ParallelIteratorExt: ParallelIterator {
+    /// Processes every item in a stack-safe context.
+    ///
+    /// Everything that happens to an item after this adapter, such as the closures passed to
+    /// `map` or `for_each`, runs in a stack-safe context with enough stack space, so deep
+    /// recursion on an item does not need any of these closures to be marked with
+    /// [`#[stacksafe]`](crate::stacksafe).
+    ///
+    /// Items are processed with the [scoped configuration](crate::StackConfig::scope) of the
+    /// thread that drives the pipeline, on whichever worker thread processes them.
+    ///
+    /// Indexed adapters such as `enumerate` or `zip` pull items from the iterator they adapt, and
+    /// the items they pull are not guarded. Apply this adapter after them, directly before the
+    /// closures that process the items.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rayon::prelude::*;
+    /// use stacksafe::StackSafe;
+    /// use stacksafe::rayon::ParallelIteratorExt;
+    ///
+    /// let values: Vec<StackSafe<u64>> = (1..=100).map(StackSafe::new).collect();
+    /// let sum: u64 = values.par_iter().stacksafe().map(|value| **value).sum();
+    /// assert_eq!(sum, 5050);
+    /// ```
+    fn stacksafe(self) -> Guarded<Self> {
+        Guarded { base: self }
+    }
+}
+
+impl<I: ParallelIterator> ParallelIteratorExt for I {}
+
+/// A parallel iterator that processes every item in a stack-safe context.
+///
+/// This is created by [`ParallelIteratorExt::stacksafe`].
+#[derive(Debug, Clone)]
+#[must_use = "parallel iterators are lazy and do nothing unless consumed"]
+pub struct Guarded<I> {
+    base: I,
+}
+
+static GUARDED_SITE: Site = Site::new("stacksafe::rayon::Guarded");
+
+impl<I: ParallelIterator> ParallelIterator for Guarded<I> {
+    type Item = I::Item;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where C: UnindexedConsumer<Self::Item> {
+        self.base.drive_unindexed(GuardedConsumer {
+            base: consumer,
+            context: Context::capture(),
+        })
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        self.base.opt_len()
+    }
+}
+
+impl<I: IndexedParallelIterator> IndexedParallelIterator for Guarded<I> {
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        self.base.drive(GuardedConsumer {
+            base: consumer,
+            context: Context::capture(),
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+        struct Callback<CB> {
+            callback: CB,
+            context: Context,
+        }
+
+        impl<T, CB: ProducerCallback<T>> ProducerCallback<T> for Callback<CB> {
+            type Output = CB::Output;
+
+            fn callback<P: Producer<Item = T>>(self, base: P) -> CB::Output {
+                self.callback.callback(GuardedProducer {
+                    base,
+                    context: self.context,
+                })
+            }
+        }
+
+        self.base.with_producer(Callback {
+            callback,
+            context: Context::capture(),
+        })
+    }
+}
+
+struct GuardedProducer<P> {
+    base: P,
+    context: Context,
+}
+
+impl<P: Producer> Producer for GuardedProducer<P> {
+    type Item = P::Item;
+    type IntoIter = P::IntoIter;
+
+    fn into_iter(self) -> P::IntoIter {
+        self.base.into_iter()
+    }
+
+    fn min_len(&self) -> usize {
+        self.base.min_len()
+    }
+
+    fn max_len(&self) -> usize {
+        self.base.max_len()
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.base.split_at(index);
+        (
+            GuardedProducer {
+                base: left,
+                context: self.context,
+            },
+            GuardedProducer {
+                base: right,
+                context: self.context,
+            },
+        )
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, folder: F) -> F {
+        let folder = GuardedFolder {
+            base: folder,
+            context: self.context,
+        };
+        self.base.fold_with(folder).base
+    }
+}
+
+struct GuardedConsumer<C> {
+    base: C,
+    context: Context,
+}
+
+impl<T, C: Consumer<T>> Consumer<T> for GuardedConsumer<C> {
+    type Folder = GuardedFolder<C::Folder>;
+    type Reducer = C::Reducer;
+    type Result = C::Result;
+
+    fn split_at(self, index: usize) -> (Self, Self, C::Reducer) {
+        let (left, right, reducer) = self.base.split_at(index);
+        (
+            GuardedConsumer {
+                base: left,
+                context: self.context,
+            },
+            GuardedConsumer {
+                base: right,
+                context: self.context,
+            },
+            reducer,
+        )
+    }
+
+    fn into_folder(self) -> Self::Folder {
+        GuardedFolder {
+            base: self.base.into_folder(),
+            context: self.context,
+        }
+    }
+
+    fn full(&self) -> bool {
+        self.base.full()
+    }
+}
+
+impl<T, C: UnindexedConsumer<T>> UnindexedConsumer<T> for GuardedConsumer<C> {
+    fn split_off_left(&self) -> Self {
+        GuardedConsumer {
+            base: self.base.split_off_left(),
+            context: self.context,
+        }
+    }
+
+    fn to_reducer(&self) -> C::Reducer {
+        self.base.to_reducer()
+    }
+}
+
+struct GuardedFolder<F> {
+    base: F,
+    context: Context,
+}
+
+impl<T, F: Folder<T>> Folder<T> for GuardedFolder<F> {
+    type Result = F::Result;
+
+    fn consume(self, item: T) -> Self {
+        let base = self.base;
+        GuardedFolder {
+            base: self.context.guard(&GUARDED_SITE, || base.consume(item)),
+            context: self.context,
+        }
+    }
+
+    fn complete(self) -> F::Result {
+        self.base.complete()
+    }
+
+    fn full(&self) -> bool {
+        self.base.full()
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Preallocated stack segments for latency-sensitive threads.
+//!
+//! Audio callbacks, control loops and similar code must not page fault, map memory or call into
+//! the allocator once they are running. [`init`] allocates every stack segment a thread may need
+//! up front, optionally locking them in memory and touching every page, so that guarded code
+//! running on that thread only switches between segments that already exist.
+//!
+//! Reserved segments belong to the thread that called [`init`] and are used in order of nesting:
+//! the first segment the thread grows into is the first reserved segment, and so on. Once they are
+//! exhausted, or when a larger segment is requested (for example through
+//! [`on_new_stack`](crate::on_new_stack)), segments are allocated as usual.
+//!
+//! Reserving segments requires this crate to switch stacks by itself, which is not possible on
+//! every platform (such as Windows); there, [`init`] fails with
+//! [`Error::Unsupported`].
+
+use crate::Error;
+
+/// Configuration for [`init`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RealtimeConfig {
+    segments: usize,
+    lock: bool,
+    prefault: bool,
+}
+
+impl RealtimeConfig {
+    /// Creates a configuration reserving `segments` stack segments.
+    ///
+    /// Each segment is as large as the stack allocation size in effect when
+    /// [`init`] is called, as returned by [`StackConfig::current`](crate::StackConfig::current),
+    /// so `segments` bounds the depth of recursion that can run without allocating.
+    pub fn new(segments: usize) -> Self {
+        RealtimeConfig {
+            segments,
+            lock: false,
+            prefault: true,
+        }
+    }
+
+    /// Sets whether reserved segments are locked in memory so they can never be paged out.
+    ///
+    /// This is off by default. Locking memory is subject to `RLIMIT_MEMLOCK` and is only
+    /// supported on Unix.
+    pub fn lock(mut self, lock: bool) -> Self {
+        self.lock = lock;
+        self
+    }
+
+    /// Sets whether every page of the reserved segments is touched during [`init`], so that
+    /// using them later does not page fault.
+    ///
+    /// This is on by default.
+    pub fn prefault(mut self, prefault: bool) -> Self {
+        self.prefault = prefault;
+        self
+    }
+}
+
+/// Reserves stack segments for the current thread according to `config`.
+///
+/// Any segments previously reserved for the current thread are released first. This must not be
+/// called while running on a grown segment.
+///
+/// # Errors
+///
+/// Fails with [`Error::Unsupported`] if reserved segments are not supported on this platform or
+/// if called while running on a grown segment, and with [`Error::AllocationFailed`] if the
+/// segments cannot be allocated or locked in memory.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::realtime::RealtimeConfig;
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn depth(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + depth(n - 1) }
+/// }
+///
+/// if stacksafe::realtime::init(&RealtimeConfig::new(8)).is_ok() {
+///     // No stack segment is allocated here.
+///     assert_eq!(depth(10_000), 10_000);
+///     stacksafe::realtime::release();
+/// }
+/// ```
+pub fn init(config: &RealtimeConfig) -> Result<(), Error> {
+    crate::segment::reserve(
+        config.segments,
+        crate::config::effective_sizes().1,
+        config.lock,
+        config.prefault,
+    )
+}
+
+/// Releases the stack segments reserved for the current thread.
+///
+/// Segments are also released when the thread exits. This must not be called while running on a
+/// grown segment, in which case it does nothing.
+pub fn release() {
+    let _ = crate::segment::reserve(0, 0, false, false);
+}
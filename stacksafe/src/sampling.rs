@@ -0,0 +1,98 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sampling of the events emitted for stack growth.
+
+use std::cell::Cell;
+use std::hash::BuildHasher;
+use std::hash::RandomState;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+/// Which stack growth events are emitted as telemetry.
+///
+/// See [`set_growth_sampling`](crate::set_growth_sampling).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GrowthSampling {
+    /// Every event is emitted.
+    All,
+    /// Every `n`th event is emitted, counting the events of all threads, starting with the first
+    /// one. An interval of zero is treated as one.
+    EveryNth(u64),
+    /// Every event is emitted with the given probability, which is clamped to `0.0..=1.0`.
+    Probability(f64),
+}
+
+// Set in the encoded setting for a probability, whose bits are stored in the remaining ones. The
+// sign bit of a non-negative `f64` is always clear, so it cannot be confused with the bits.
+const PROBABILITY: u64 = 1 << 63;
+
+// The encoded setting: the interval, or the bits of the probability along with `PROBABILITY`.
+static SAMPLING: AtomicU64 = AtomicU64::new(1);
+
+// The number of events seen while sampling every nth event.
+static EVENTS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // The state of a xorshift generator for sampling with a probability, seeded on first use.
+    static RANDOM: Cell<u64> = const { Cell::new(0) };
+}
+
+pub(crate) fn set_growth_sampling(sampling: GrowthSampling) {
+    let encoded = match sampling {
+        GrowthSampling::All => 1,
+        GrowthSampling::EveryNth(n) => n.clamp(1, PROBABILITY - 1),
+        GrowthSampling::Probability(p) => {
+            // Also maps NaN to zero.
+            let p = if p > 0.0 { p.min(1.0) } else { 0.0 };
+            PROBABILITY | p.to_bits()
+        }
+    };
+    SAMPLING.store(encoded, Ordering::Relaxed);
+}
+
+pub(crate) fn get_growth_sampling() -> GrowthSampling {
+    match SAMPLING.load(Ordering::Relaxed) {
+        1 => GrowthSampling::All,
+        encoded if encoded & PROBABILITY != 0 => {
+            GrowthSampling::Probability(f64::from_bits(encoded & !PROBABILITY))
+        }
+        n => GrowthSampling::EveryNth(n),
+    }
+}
+
+/// Decides whether the events for a growth that is about to happen are emitted.
+pub(crate) fn sample() -> bool {
+    match get_growth_sampling() {
+        GrowthSampling::All => true,
+        GrowthSampling::EveryNth(n) => EVENTS.fetch_add(1, Ordering::Relaxed) % n == 0,
+        GrowthSampling::Probability(p) => random() < p,
+    }
+}
+
+/// Returns a uniformly distributed number in `0.0..1.0`.
+fn random() -> f64 {
+    RANDOM.with(|state| {
+        let mut x = state.get();
+        if x == 0 {
+            x = RandomState::new().hash_one(0u8) | 1;
+        }
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        // The 53 upper bits fill the mantissa.
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
@@ -0,0 +1,576 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Stack segment allocation and switching.
+//!
+//! By default, segments are allocated and switched to by `stacker`. When segments are requested
+//! from the global allocator instead, or kept in a pool for reuse, this module allocates them
+//! itself and switches to them with `psm`. Since `stacker` is unaware of such segments, the limit
+//! of the segment the current thread is running on is tracked here, and [`remaining_stack`]
+//! consults it before asking `stacker`.
+//!
+//! A [`GrowthStrategy`](crate::GrowthStrategy) installed with
+//! [`set_growth_strategy`](crate::set_growth_strategy) replaces both, and is consulted instead.
+
+use std::alloc::Layout;
+use std::any::Any;
+use std::cell::Cell;
+use std::panic::AssertUnwindSafe;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+pub(crate) use backend::Window;
+
+/// The source new stack segments are allocated from.
+///
+/// See [`set_segment_allocator`](crate::set_segment_allocator).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SegmentAllocator {
+    /// Map segments directly from the operating system.
+    ///
+    /// Each segment is surrounded by guard pages. This is the default.
+    #[default]
+    System,
+    /// Allocate segments through the [global allocator](std::alloc::GlobalAlloc).
+    ///
+    /// Segments become visible to heap profilers and allocator statistics (jemalloc, heaptrack,
+    /// bytehound, ...) at the cost of being subject to the allocator's fragmentation. On Unix, the
+    /// lowest page of every segment is protected as a guard page while it is in use.
+    ///
+    /// Such segments are managed by this crate rather than by `stacker`, so code that calls
+    /// `stacker` directly while running on one of them may misjudge the remaining stack space.
+    ///
+    /// On platforms where this crate cannot switch stacks by itself (such as Windows), this falls
+    /// back to [`SegmentAllocator::System`].
+    Global,
+}
+
+static SEGMENT_ALLOCATOR: AtomicU8 = AtomicU8::new(SegmentAllocator::System as u8);
+
+pub(crate) fn set_segment_allocator(allocator: SegmentAllocator) {
+    SEGMENT_ALLOCATOR.store(allocator as u8, Ordering::Relaxed);
+}
+
+pub(crate) fn get_segment_allocator() -> SegmentAllocator {
+    match SEGMENT_ALLOCATOR.load(Ordering::Relaxed) {
+        1 => SegmentAllocator::Global,
+        _ => SegmentAllocator::System,
+    }
+}
+
+static GROWTH_ENABLED: AtomicBool = AtomicBool::new(true);
+
+pub(crate) fn set_growth_enabled(enabled: bool) {
+    GROWTH_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub(crate) fn get_growth_enabled() -> bool {
+    GROWTH_ENABLED.load(Ordering::Relaxed)
+}
+
+static PREFAULT: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_prefault(prefault: bool) {
+    PREFAULT.store(prefault, Ordering::Relaxed);
+}
+
+pub(crate) fn get_prefault() -> bool {
+    PREFAULT.load(Ordering::Relaxed)
+}
+
+static POOL_CAPACITY: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn set_pool_capacity(segments: usize) {
+    POOL_CAPACITY.store(segments, Ordering::Relaxed);
+}
+
+pub(crate) fn get_pool_capacity() -> usize {
+    POOL_CAPACITY.load(Ordering::Relaxed)
+}
+
+/// Frees the segments pooled by the current thread.
+pub(crate) fn shrink_pool() {
+    backend::shrink_pool();
+}
+
+thread_local! {
+    // The lowest usable address of the segment allocated by this module that the current thread
+    // is running on, or zero when running on a stack managed by the OS or by `stacker`.
+    static SEGMENT_LIMIT: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Information about the stack segment the current thread is running on.
+///
+/// See [`current_segment`](crate::current_segment).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SegmentInfo {
+    depth: usize,
+    label: &'static str,
+    size: usize,
+    used: usize,
+}
+
+impl SegmentInfo {
+    /// Returns how many segments, including this one, the current thread has grown into.
+    ///
+    /// The first segment allocated on top of the thread's original stack has depth 1.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the label of the function whose call allocated this segment.
+    ///
+    /// For functions marked with [`#[stacksafe]`](crate::stacksafe), this is the function's path,
+    /// such as `my_crate::parser::parse_expr`.
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    /// Returns the size of this segment in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns how many bytes of this segment were in use when this information was captured.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Entered {
+    depth: usize,
+    label: &'static str,
+    size: usize,
+    // Whether events are emitted for this segment; see `set_growth_sampling`.
+    #[cfg_attr(not(all(windows, feature = "windows-telemetry")), allow(dead_code))]
+    sampled: bool,
+}
+
+thread_local! {
+    static ENTERED: Cell<Option<Entered>> = const { Cell::new(None) };
+}
+
+pub(crate) fn current_segment() -> Option<SegmentInfo> {
+    let entered = ENTERED.with(|e| e.get())?;
+    let remaining = remaining_stack().unwrap_or(0);
+    Some(SegmentInfo {
+        depth: entered.depth,
+        label: entered.label,
+        size: entered.size,
+        used: entered.size.saturating_sub(remaining),
+    })
+}
+
+/// Returns how many segments the current thread has grown into.
+pub(crate) fn depth() -> usize {
+    ENTERED.with(|e| e.get()).map_or(0, |e| e.depth)
+}
+
+/// Records the segment being entered, and restores the previous one when it is left.
+struct EnteredGuard(Option<Entered>);
+
+impl EnteredGuard {
+    fn enter(label: &'static str, size: usize) -> EnteredGuard {
+        EnteredGuard(ENTERED.with(|e| {
+            let previous = e.get();
+            let depth = previous.map_or(0, |p| p.depth) + 1;
+            let sampled = crate::sampling::sample();
+            if sampled {
+                #[cfg(all(windows, feature = "windows-telemetry"))]
+                crate::etw::growth(label, size, depth);
+                #[cfg(feature = "tracing")]
+                crate::tracing::growth(label, size, depth);
+            }
+            #[cfg(feature = "metrics")]
+            crate::metrics::growth(
+                SegmentInfo {
+                    depth,
+                    label,
+                    size,
+                    used: 0,
+                },
+                sampled,
+            );
+            e.replace(Some(Entered {
+                depth,
+                label,
+                size,
+                sampled,
+            }))
+        }))
+    }
+}
+
+impl Drop for EnteredGuard {
+    fn drop(&mut self) {
+        #[cfg(all(windows, feature = "windows-telemetry"))]
+        if let Some(left) = ENTERED.with(|e| e.get()).filter(|left| left.sampled) {
+            crate::etw::release(left.label, left.size, left.depth);
+        }
+        ENTERED.with(|e| e.set(self.0));
+    }
+}
+
+/// Restores the previous segment limit when the current segment is left, even by unwinding.
+struct LimitGuard(usize);
+
+impl LimitGuard {
+    fn replace(limit: usize) -> LimitGuard {
+        LimitGuard(SEGMENT_LIMIT.with(|l| l.replace(limit)))
+    }
+}
+
+impl Drop for LimitGuard {
+    fn drop(&mut self) {
+        SEGMENT_LIMIT.with(|l| l.set(self.0));
+    }
+}
+
+/// Returns the amount of stack space left on the current segment, if known.
+#[inline(always)]
+pub(crate) fn remaining_stack() -> Option<usize> {
+    match crate::strategy::installed() {
+        Some(strategy) => strategy.remaining_stack(),
+        None => backend::remaining_stack(),
+    }
+}
+
+/// Returns `true` if the current thread is running on an alternate signal stack.
+///
+/// This only calls `sigaltstack`, which is async-signal-safe.
+#[cfg(unix)]
+pub(crate) fn on_signal_stack() -> bool {
+    let mut current = std::mem::MaybeUninit::<libc::stack_t>::uninit();
+    // SAFETY: a null new stack only queries the current one into `current`.
+    if unsafe { libc::sigaltstack(std::ptr::null(), current.as_mut_ptr()) } != 0 {
+        return false;
+    }
+    // SAFETY: `sigaltstack` succeeded, so it has initialized `current`.
+    let current = unsafe { current.assume_init() };
+    current.ss_flags & libc::SS_ONSTACK != 0
+}
+
+#[cfg(not(unix))]
+pub(crate) fn on_signal_stack() -> bool {
+    false
+}
+
+/// Reserves address space for `slots` segments of `size` bytes, shared by all threads.
+pub(crate) fn reserve_region(slots: usize, size: usize) -> Result<(), crate::Error> {
+    backend::reserve_region(slots, size)
+}
+
+/// Preallocates `count` segments of `size` bytes for the current thread.
+pub(crate) fn reserve(
+    count: usize,
+    size: usize,
+    lock: bool,
+    prefault: bool,
+) -> Result<(), crate::Error> {
+    if ENTERED.with(|e| e.get()).is_some() {
+        return Err(crate::Error::Unsupported {
+            operation: "replacing reserved segments while running on a grown segment",
+        });
+    }
+    // Make sure that querying the remaining stack space is not going to allocate later.
+    let _ = stacker::remaining_stack();
+    backend::reserve(count, size, lock, prefault)
+}
+
+/// Returns `None` if fewer than `red_zone` bytes are left on the current segment.
+///
+/// Otherwise, the returned [`Window`] must be kept alive while the guarded call runs: it lets
+/// the guarded calls nested within skip looking up the limit of the stack again.
+#[inline(always)]
+pub(crate) fn check_room(red_zone: usize) -> Option<Window> {
+    match crate::strategy::installed() {
+        Some(strategy) => strategy
+            .remaining_stack()
+            .is_some_and(|remaining| remaining >= red_zone)
+            .then_some(Window::UNCHANGED),
+        None => backend::check_room(red_zone),
+    }
+}
+
+/// Returns the amount of stack space left for [`HeapTrampoline`](crate::HeapTrampoline).
+#[inline(always)]
+pub(crate) fn trampoline_remaining() -> Option<usize> {
+    backend::trampoline_remaining()
+}
+
+/// Runs `callback` on a new heap segment for [`HeapTrampoline`](crate::HeapTrampoline).
+pub(crate) fn trampoline(
+    stack_size: usize,
+    callback: &mut dyn FnMut(),
+) -> Result<(), crate::Error> {
+    backend::trampoline(stack_size, callback)
+}
+
+/// Runs `callback` on a new segment of `stack_size` bytes labeled with `label`.
+pub(crate) fn grow<R>(stack_size: usize, label: &'static str, callback: impl FnOnce() -> R) -> R {
+    match _try_grow(stack_size, label, false, callback) {
+        Ok(ret) => ret,
+        Err(failure) => failure.raise(),
+    }
+}
+
+/// Like [`grow`], but returns `None` without running `callback` if no segment could be allocated.
+pub(crate) fn try_grow<R>(
+    stack_size: usize,
+    label: &'static str,
+    callback: impl FnOnce() -> R,
+) -> Option<R> {
+    _try_grow(stack_size, label, false, callback).ok()
+}
+
+/// Like [`grow`], but the segment has exactly `stack_size` bytes: it is never a reserved or
+/// pooled segment, which may be larger, and a failed allocation is not retried with a smaller size
+/// but aborts the process.
+pub(crate) fn grow_exact<R>(
+    stack_size: usize,
+    label: &'static str,
+    callback: impl FnOnce() -> R,
+) -> R {
+    match _try_grow(stack_size, label, true, callback) {
+        Ok(ret) => ret,
+        Err(AllocFailure::Panicked(_)) => {
+            eprintln!("stacksafe: failed to allocate a stack segment of {stack_size} bytes");
+            std::process::abort()
+        }
+        Err(failure) => failure.raise(),
+    }
+}
+
+/// Like [`grow_exact`], but reports the failure as an [`Error`](crate::Error).
+pub(crate) fn try_grow_exact<R>(
+    stack_size: usize,
+    label: &'static str,
+    callback: impl FnOnce() -> R,
+) -> Result<R, crate::Error> {
+    _try_grow(stack_size, label, true, callback).map_err(|failure| match failure {
+        AllocFailure::Disabled => crate::Error::GrowthDisabled,
+        AllocFailure::Strategy(error) => error,
+        _ => crate::Error::AllocationFailed {
+            size: stack_size,
+            source: None,
+        },
+    })
+}
+
+fn _try_grow<R>(
+    stack_size: usize,
+    label: &'static str,
+    exact: bool,
+    callback: impl FnOnce() -> R,
+) -> Result<R, AllocFailure> {
+    // Erase the callback type so that the segment management code is not monomorphized.
+    let mut callback = Some(callback);
+    let mut ret = None;
+    _grow(stack_size, label, exact, &mut || {
+        // The callback may drop a `StackSafe` value. If a broken backend ever invoked it a second
+        // time, unwinding from here would run destructors of already dropped values, so abort.
+        let Some(callback) = callback.take() else {
+            std::process::abort();
+        };
+        ret = Some(callback())
+    })?;
+    Ok(ret.unwrap())
+}
+
+fn _grow(
+    stack_size: usize,
+    label: &'static str,
+    exact: bool,
+    callback: &mut dyn FnMut(),
+) -> Result<(), AllocFailure> {
+    if !get_growth_enabled() {
+        return Err(AllocFailure::Disabled);
+    }
+    crate::testing::check_growth(label, stack_size);
+    crate::global::start_thread();
+    crate::cooperate::on_growth();
+    let timer = crate::explain::GrowthTimer::start(label, stack_size);
+    let callback = &mut || timer.exclude(callback);
+    if let Some(strategy) = crate::strategy::installed() {
+        let _entered = EnteredGuard::enter(label, stack_size);
+        let _limit = LimitGuard::replace(0);
+        return strategy
+            .grow(stack_size, callback)
+            .map_err(AllocFailure::Strategy);
+    }
+    // Reserved and pooled segments may be larger than requested.
+    if !exact
+        && (backend::grow_reserved(stack_size, label, callback)
+            || backend::grow_region(stack_size, label, callback)
+            || backend::grow_pooled(stack_size, label, callback))
+    {
+        return Ok(());
+    }
+    let allocator = get_segment_allocator();
+    let mut size = stack_size;
+    loop {
+        let result = match allocator {
+            SegmentAllocator::System => grow_system(size, label, callback),
+            SegmentAllocator::Global => backend::grow_global(size, label, callback),
+        };
+        let Err(failure) = result else {
+            return Ok(());
+        };
+        // Retry with half the size, as long as that stays above the floor.
+        if exact || size / 2 < FALLBACK_FLOOR {
+            return Err(failure);
+        }
+        size /= 2;
+        report_fallback(SegmentFallback {
+            label,
+            requested: stack_size,
+            size,
+        });
+    }
+}
+
+/// Grows the stack with `stacker`, which panics if the segment cannot be allocated.
+fn grow_system(
+    stack_size: usize,
+    label: &'static str,
+    callback: &mut dyn FnMut(),
+) -> Result<(), AllocFailure> {
+    let _entered = EnteredGuard::enter(label, stack_size);
+    let _limit = LimitGuard::replace(0);
+    let mut started = false;
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        stacker::grow(stack_size, || {
+            started = true;
+            if get_prefault() {
+                backend::prefault_current();
+            }
+            segment_entry(callback);
+        })
+    }));
+    match result {
+        Ok(()) => Ok(()),
+        // The segment was allocated; the callback itself panicked.
+        Err(payload) if started => std::panic::resume_unwind(payload),
+        Err(payload) => Err(AllocFailure::Panicked(payload)),
+    }
+}
+
+/// Runs `callback` as the first frame of a newly entered segment.
+///
+/// With the `profiling` feature, this is a real frame that stays on the segment while `callback`
+/// runs, so that every stack switch shows up under the same name in profiles. The switch itself
+/// is done by `psm`, which keeps the frame pointer chain intact and describes the switch with
+/// unwind information, so stack walks continue through it into the previous segment.
+#[cfg(feature = "profiling")]
+#[inline(never)]
+pub(crate) fn segment_entry(callback: &mut dyn FnMut()) {
+    callback();
+    // Prevent the call above from becoming a tail call, which would remove this frame.
+    std::hint::black_box(());
+}
+
+#[cfg(not(feature = "profiling"))]
+#[inline(always)]
+pub(crate) fn segment_entry(callback: &mut dyn FnMut()) {
+    callback();
+}
+
+/// A stack segment that could not be allocated.
+enum AllocFailure {
+    /// `stacker` panicked with the given payload.
+    Panicked(Box<dyn Any + Send>),
+    /// The global allocator failed to allocate the given layout.
+    // Not constructed on platforms where segments are always allocated by `stacker`.
+    #[allow(dead_code)]
+    Alloc(Layout),
+    /// Growth is disabled with [`set_growth_enabled`](crate::set_growth_enabled).
+    Disabled,
+    /// The installed [`GrowthStrategy`](crate::GrowthStrategy) failed.
+    Strategy(crate::Error),
+}
+
+impl AllocFailure {
+    fn raise(self) -> ! {
+        match self {
+            AllocFailure::Panicked(payload) => std::panic::resume_unwind(payload),
+            AllocFailure::Alloc(layout) => std::alloc::handle_alloc_error(layout),
+            AllocFailure::Disabled => panic!("stack growth is disabled"),
+            AllocFailure::Strategy(error) => panic!("{error}"),
+        }
+    }
+}
+
+// Failed segment allocations are not retried with segments smaller than this.
+const FALLBACK_FLOOR: usize = 64 * 1024;
+
+/// A stack segment allocation that failed and is retried with a smaller size.
+///
+/// See [`set_fallback_hook`](crate::set_fallback_hook).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SegmentFallback {
+    label: &'static str,
+    requested: usize,
+    size: usize,
+}
+
+impl SegmentFallback {
+    /// Returns the label of the function whose call needed the segment.
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    /// Returns the size of the segment that was originally requested, in bytes.
+    pub fn requested(&self) -> usize {
+        self.requested
+    }
+
+    /// Returns the smaller size that is tried next, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+pub(crate) type FallbackHook = Box<dyn Fn(&SegmentFallback) + Send + Sync>;
+
+static FALLBACK_HOOK: RwLock<Option<FallbackHook>> = RwLock::new(None);
+
+pub(crate) fn set_fallback_hook(hook: Option<FallbackHook>) {
+    *FALLBACK_HOOK.write().unwrap_or_else(|e| e.into_inner()) = hook;
+}
+
+#[cold]
+fn report_fallback(fallback: SegmentFallback) {
+    crate::hook::run(|| {
+        let hook = FALLBACK_HOOK.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(hook) = hook.as_ref() {
+            hook(&fallback);
+        }
+    });
+}
+
+psm::psm_stack_manipulation! {
+    yes {
+        #[path = "segment/custom.rs"]
+        mod backend;
+    }
+    no {
+        #[path = "segment/fallback.rs"]
+        mod backend;
+    }
+}
@@ -0,0 +1,607 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Segments allocated by this crate and switched to with `psm`.
+
+use std::alloc::Layout;
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::panic::AssertUnwindSafe;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use super::AllocFailure;
+use super::EnteredGuard;
+use super::LimitGuard;
+use super::SEGMENT_LIMIT;
+use crate::Error;
+
+// The address space reserved for segments by `reserve_address_space`, shared by all threads.
+static REGION: OnceLock<Region> = OnceLock::new();
+
+thread_local! {
+    // Segments preallocated for the current thread; the segment at index `i` is used for the
+    // `i + 1`-th nested segment, which is possible because segments are entered and left in
+    // strict LIFO order.
+    static RESERVE: RefCell<Vec<Segment>> = const { RefCell::new(Vec::new()) };
+    // Segments that were grown into and left by the current thread, kept for reuse.
+    static POOL: RefCell<Vec<Segment>> = const { RefCell::new(Vec::new()) };
+    // A range `(limit, top)` of the stack managed by the OS or by `stacker` that the current
+    // thread is running on, marked by a guarded call that is still running at `top` and found
+    // the stack to extend down to `limit`. Until that call returns, no other stack can occupy the
+    // range, so the limit holds for any stack pointer within it.
+    static WINDOW: Cell<(usize, usize)> = const { Cell::new((usize::MAX, 0)) };
+    // The lowest usable address of the heap segment that `HeapTrampoline` runs the current thread
+    // on, or zero when running on any other stack.
+    static TRAMPOLINE_LIMIT: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Restores the previous check window when the guarded call that marked a new one returns.
+pub(crate) struct Window(Option<(usize, usize)>);
+
+impl Window {
+    pub(super) const UNCHANGED: Window = Window(None);
+
+    fn mark(limit: usize, top: usize) -> Window {
+        Window(Some(WINDOW.with(|w| w.replace((limit, top)))))
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0 {
+            WINDOW.with(|w| w.set(previous));
+        }
+    }
+}
+
+#[inline(always)]
+pub(super) fn remaining_stack() -> Option<usize> {
+    match SEGMENT_LIMIT.with(|l| l.get()) {
+        0 => stacker::remaining_stack(),
+        limit => Some((psm::stack_pointer() as usize).saturating_sub(limit)),
+    }
+}
+
+/// Returns `None` if fewer than `red_zone` bytes are left on the current segment.
+///
+/// On stacks not allocated by this module, the limit is looked up through `stacker` once and
+/// then reused by nested guarded calls for as long as the returned window is alive.
+#[inline(always)]
+pub(super) fn check_room(red_zone: usize) -> Option<Window> {
+    let sp = psm::stack_pointer() as usize;
+    if let limit @ 1.. = SEGMENT_LIMIT.with(|l| l.get()) {
+        return (sp.saturating_sub(limit) >= red_zone).then_some(Window::UNCHANGED);
+    }
+    let (limit, top) = WINDOW.with(|w| w.get());
+    if limit <= sp && sp <= top && sp - limit >= red_zone {
+        return Some(Window::UNCHANGED);
+    }
+    check_room_slow(sp, red_zone)
+}
+
+#[inline(never)]
+fn check_room_slow(sp: usize, red_zone: usize) -> Option<Window> {
+    let remaining = stacker::remaining_stack()?;
+    if remaining < red_zone {
+        return None;
+    }
+    // `remaining` was measured from a deeper frame, so this errs on the side of a higher limit.
+    Some(Window::mark(sp.saturating_sub(remaining), sp))
+}
+
+pub(super) fn grow_global(
+    stack_size: usize,
+    label: &'static str,
+    callback: &mut dyn FnMut(),
+) -> Result<(), AllocFailure> {
+    let segment = Segment::allocate(stack_size).map_err(AllocFailure::Alloc)?;
+    if super::get_prefault() {
+        segment.prefault();
+    }
+    run_on(segment.base, segment.size, label, callback);
+    Ok(())
+}
+
+pub(super) fn grow_reserved(
+    stack_size: usize,
+    label: &'static str,
+    callback: &mut dyn FnMut(),
+) -> bool {
+    let depth = super::depth();
+    let reserved = RESERVE.with(|r| r.borrow().get(depth).map(|s| (s.base, s.size)));
+    let Some((base, size)) = reserved.filter(|&(_, size)| size >= stack_size) else {
+        return false;
+    };
+    run_on(base, size, label, callback);
+    true
+}
+
+pub(super) fn grow_pooled(
+    stack_size: usize,
+    label: &'static str,
+    callback: &mut dyn FnMut(),
+) -> bool {
+    let capacity = super::get_pool_capacity();
+    if capacity == 0 {
+        return false;
+    }
+    let (size, _) = Segment::layout(stack_size);
+    let pooled = POOL
+        .try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            let index = pool.iter().rposition(|segment| segment.size == size)?;
+            Some(pool.swap_remove(index))
+        })
+        .ok()
+        .flatten();
+    let segment = match pooled {
+        Some(segment) => segment,
+        None => {
+            let segment = match super::get_segment_allocator() {
+                super::SegmentAllocator::Global => Segment::allocate(size).ok(),
+                _ => Segment::map(size),
+            };
+            let Some(segment) = segment else {
+                return false;
+            };
+            if super::get_prefault() {
+                segment.prefault();
+            }
+            segment
+        }
+    };
+    run_on(segment.base, segment.size, label, callback);
+    // Keep the segment for the next growth, unless the pool is full. If the callback panicked,
+    // the segment is dropped instead, while unwinding.
+    let _ = POOL.try_with(|pool| {
+        let mut pool = pool.borrow_mut();
+        if pool.len() < capacity {
+            pool.push(segment);
+        }
+    });
+    true
+}
+
+pub(super) fn shrink_pool() {
+    let segments = POOL.try_with(|pool| std::mem::take(&mut *pool.borrow_mut()));
+    drop(segments);
+}
+
+pub(super) fn grow_region(
+    stack_size: usize,
+    label: &'static str,
+    callback: &mut dyn FnMut(),
+) -> bool {
+    let Some(region) = REGION.get().filter(|region| region.size >= stack_size) else {
+        return false;
+    };
+    let Some(slot) = region.acquire() else {
+        return false;
+    };
+    if super::get_prefault() {
+        prefault(slot.base(), region.size);
+    }
+    run_on(slot.base(), region.size, label, callback);
+    drop(slot);
+    true
+}
+
+pub(super) fn reserve_region(slots: usize, size: usize) -> Result<(), Error> {
+    let region = Region::map(slots, size)?;
+    REGION.set(region).map_err(|_| Error::Unsupported {
+        operation: "reserving address space for stack segments more than once",
+    })
+}
+
+/// Touches every page of the current segment below the stack pointer, from the top down.
+#[inline(never)]
+pub(super) fn prefault_current() {
+    let Some(remaining) = remaining_stack() else {
+        return;
+    };
+    let page_size = page_size();
+    let sp = psm::stack_pointer() as usize;
+    // Start a page below the stack pointer to stay clear of this function's own frame.
+    let mut offset = page_size;
+    while offset <= remaining {
+        // SAFETY: the address lies within the unused part of the current segment.
+        unsafe { ((sp - offset) as *mut u8).write_volatile(0) };
+        offset += page_size;
+    }
+}
+
+pub(super) fn reserve(
+    count: usize,
+    size: usize,
+    lock: bool,
+    prefault: bool,
+) -> Result<(), Error> {
+    let mut segments = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut segment = Segment::allocate(size).map_err(|_| Error::AllocationFailed {
+            size,
+            source: None,
+        })?;
+        if lock {
+            segment.lock().map_err(|source| Error::AllocationFailed {
+                size,
+                source: Some(source),
+            })?;
+        }
+        if prefault {
+            segment.prefault();
+        }
+        segments.push(segment);
+    }
+    RESERVE.with(|r| *r.borrow_mut() = segments);
+    Ok(())
+}
+
+#[inline(always)]
+pub(super) fn trampoline_remaining() -> Option<usize> {
+    match TRAMPOLINE_LIMIT.with(|l| l.get()) {
+        0 => stacker::remaining_stack(),
+        limit => Some((psm::stack_pointer() as usize).saturating_sub(limit)),
+    }
+}
+
+pub(super) fn trampoline(stack_size: usize, callback: &mut dyn FnMut()) -> Result<(), Error> {
+    struct Restore(usize);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            TRAMPOLINE_LIMIT.with(|l| l.set(self.0));
+        }
+    }
+
+    let segment = Segment::allocate(stack_size).map_err(|_| Error::AllocationFailed {
+        size: stack_size,
+        source: None,
+    })?;
+    let restore = Restore(TRAMPOLINE_LIMIT.with(|l| l.replace(segment.base as usize)));
+    // SAFETY: the segment is suitably aligned and sized, it outlives the call, and the callback
+    // is prevented from unwinding across the stack switch.
+    let panic = unsafe {
+        psm::on_stack(segment.base, segment.size, move || {
+            std::panic::catch_unwind(AssertUnwindSafe(|| super::segment_entry(callback))).err()
+        })
+    };
+    drop(restore);
+    if let Some(payload) = panic {
+        std::panic::resume_unwind(payload);
+    }
+    Ok(())
+}
+
+fn run_on(base: *mut u8, size: usize, label: &'static str, callback: &mut dyn FnMut()) {
+    let entered = EnteredGuard::enter(label, size);
+    let limit = LimitGuard::replace(base as usize);
+    // SAFETY: the segment is suitably aligned and sized, it outlives the call, and the callback
+    // is prevented from unwinding across the stack switch.
+    let panic = unsafe {
+        psm::on_stack(base, size, move || {
+            std::panic::catch_unwind(AssertUnwindSafe(|| super::segment_entry(callback))).err()
+        })
+    };
+    drop(limit);
+    drop(entered);
+    if let Some(payload) = panic {
+        std::panic::resume_unwind(payload);
+    }
+}
+
+/// A stack segment allocated through the global allocator, or mapped from the operating system,
+/// preceded by a guard page.
+struct Segment {
+    layout: Layout,
+    ptr: *mut u8,
+    base: *mut u8,
+    size: usize,
+    locked: bool,
+    mapped: bool,
+}
+
+impl Segment {
+    /// Returns the usable size of a segment of at least `stack_size` bytes, and the layout of its
+    /// memory including the guard page.
+    fn layout(stack_size: usize) -> (usize, Layout) {
+        let page_size = page_size();
+        let size = stack_size
+            .max(1)
+            .checked_next_multiple_of(page_size)
+            .expect("unreasonably large stack requested");
+        let layout = size
+            .checked_add(page_size)
+            .and_then(|total| Layout::from_size_align(total, page_size).ok())
+            .expect("unreasonably large stack requested");
+        (size, layout)
+    }
+
+    fn allocate(stack_size: usize) -> Result<Segment, Layout> {
+        let (size, layout) = Segment::layout(stack_size);
+        // SAFETY: the layout has a non-zero size.
+        let ptr = unsafe { std::alloc::alloc(layout) };
+        if ptr.is_null() {
+            return Err(layout);
+        }
+        let segment = Segment {
+            layout,
+            ptr,
+            // SAFETY: the allocation is one page larger than `size`.
+            base: unsafe { ptr.add(page_size()) },
+            size,
+            locked: false,
+            mapped: false,
+        };
+        segment.protect_guard_page(true);
+        Ok(segment)
+    }
+
+    /// Maps a segment directly from the operating system, or returns `None` if that fails.
+    #[cfg(unix)]
+    fn map(stack_size: usize) -> Option<Segment> {
+        let (size, layout) = Segment::layout(stack_size);
+        let flags = libc::MAP_PRIVATE | libc::MAP_ANONYMOUS;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let flags = flags | libc::MAP_NORESERVE;
+        // SAFETY: an anonymous mapping at an address chosen by the kernel has no preconditions.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                layout.size(),
+                libc::PROT_READ | libc::PROT_WRITE,
+                flags,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return None;
+        }
+        let ptr = ptr.cast::<u8>();
+        let segment = Segment {
+            layout,
+            ptr,
+            // SAFETY: the mapping is one page larger than `size`.
+            base: unsafe { ptr.add(page_size()) },
+            size,
+            locked: false,
+            mapped: true,
+        };
+        segment.protect_guard_page(true);
+        Some(segment)
+    }
+
+    #[cfg(not(unix))]
+    fn map(stack_size: usize) -> Option<Segment> {
+        Segment::allocate(stack_size).ok()
+    }
+
+    /// Touches every page of the segment so that no page faults occur when it is used.
+    fn prefault(&self) {
+        prefault(self.base, self.size);
+    }
+
+    #[cfg(unix)]
+    fn lock(&mut self) -> std::io::Result<()> {
+        // SAFETY: the range is owned by this segment.
+        if unsafe { libc::mlock(self.base.cast(), self.size) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        self.locked = true;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn lock(&mut self) -> std::io::Result<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "locking stack segments in memory is not supported on this platform",
+        ))
+    }
+
+    #[cfg(unix)]
+    fn protect_guard_page(&self, protect: bool) {
+        let prot = if protect {
+            libc::PROT_NONE
+        } else {
+            libc::PROT_READ | libc::PROT_WRITE
+        };
+        // SAFETY: the first page of the allocation is page-aligned and owned by us.
+        let result = unsafe { libc::mprotect(self.ptr.cast(), page_size(), prot) };
+        assert_eq!(
+            result,
+            0,
+            "mprotect failed: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+
+    #[cfg(not(unix))]
+    fn protect_guard_page(&self, _protect: bool) {}
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if self.locked {
+            // SAFETY: the range was locked by this segment.
+            unsafe { libc::munlock(self.base.cast(), self.size) };
+        }
+        #[cfg(unix)]
+        if self.mapped {
+            // SAFETY: the mapping is owned by this segment and no longer in use.
+            unsafe { libc::munmap(self.ptr.cast(), self.layout.size()) };
+            return;
+        }
+        self.protect_guard_page(false);
+        // SAFETY: the pointer was allocated with this layout.
+        unsafe { std::alloc::dealloc(self.ptr, self.layout) };
+    }
+}
+
+/// Touches every page of the `size` bytes at `base`, which must be writable.
+fn prefault(base: *mut u8, size: usize) {
+    // Touch the pages from the top, which is where the stack starts.
+    for offset in (0..size).step_by(page_size()).rev() {
+        // SAFETY: the caller guarantees that the offset is within a writable range.
+        unsafe { base.add(offset).write_volatile(0) };
+    }
+}
+
+/// A contiguous range of address space divided into equally sized slots for segments, each
+/// preceded by a guard page.
+///
+/// The layout is set up once, so using and releasing segments never changes the mappings of the
+/// process. Released slots are reused in last-in, first-out order.
+struct Region {
+    ptr: usize,
+    len: usize,
+    size: usize,
+    free: Mutex<Vec<usize>>,
+}
+
+impl Region {
+    #[cfg(unix)]
+    fn map(slots: usize, size: usize) -> Result<Region, Error> {
+        let page_size = page_size();
+        let size = size
+            .max(1)
+            .checked_next_multiple_of(page_size)
+            .expect("unreasonably large stack requested");
+        let len = size
+            .checked_add(page_size)
+            .and_then(|stride| stride.checked_mul(slots))
+            .expect("unreasonably large address space requested");
+        if len == 0 {
+            return Err(Error::AllocationFailed { size, source: None });
+        }
+        let flags = libc::MAP_PRIVATE | libc::MAP_ANONYMOUS;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let flags = flags | libc::MAP_NORESERVE;
+        // SAFETY: an anonymous mapping at an address chosen by the kernel has no preconditions.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                flags,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(Error::AllocationFailed {
+                size: len,
+                source: Some(std::io::Error::last_os_error()),
+            });
+        }
+        let region = Region {
+            ptr: ptr as usize,
+            len,
+            size,
+            // Slots are popped from the end, so start with the lowest one.
+            free: Mutex::new((0..slots).rev().collect()),
+        };
+        for slot in 0..slots {
+            let guard = region.base(slot).wrapping_sub(page_size);
+            // SAFETY: the guard page lies within the mapping, which is owned by the region.
+            if unsafe { libc::mprotect(guard.cast(), page_size, libc::PROT_NONE) } != 0 {
+                return Err(Error::AllocationFailed {
+                    size: len,
+                    source: Some(std::io::Error::last_os_error()),
+                });
+            }
+        }
+        Ok(region)
+    }
+
+    #[cfg(not(unix))]
+    fn map(_slots: usize, _size: usize) -> Result<Region, Error> {
+        Err(Error::Unsupported {
+            operation: "reserving address space for stack segments on this platform",
+        })
+    }
+
+    /// Returns the lowest usable address of `slot`.
+    fn base(&self, slot: usize) -> *mut u8 {
+        let stride = self.size + page_size();
+        (self.ptr + slot * stride + page_size()) as *mut u8
+    }
+
+    /// Takes a free slot, which is handed back when the returned guard is dropped.
+    fn acquire(&self) -> Option<Slot<'_>> {
+        let slot = self.free.lock().unwrap_or_else(|e| e.into_inner()).pop()?;
+        Some(Slot { region: self, slot })
+    }
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        // SAFETY: the mapping is owned by the region, and none of its slots are in use.
+        #[cfg(unix)]
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len)
+        };
+    }
+}
+
+/// A slot of the [`Region`] in use by a segment.
+struct Slot<'a> {
+    region: &'a Region,
+    slot: usize,
+}
+
+impl Slot<'_> {
+    fn base(&self) -> *mut u8 {
+        self.region.base(self.slot)
+    }
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        // Give the memory back to the system while keeping the mapping.
+        #[cfg(unix)]
+        // SAFETY: the slot is no longer in use, so its contents can be discarded.
+        unsafe {
+            libc::madvise(self.base().cast(), self.region.size, libc::MADV_DONTNEED)
+        };
+        let mut free = self.region.free.lock().unwrap_or_else(|e| e.into_inner());
+        free.push(self.slot);
+    }
+}
+
+#[cfg(unix)]
+fn page_size() -> usize {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    static PAGE_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+    match PAGE_SIZE.load(Ordering::Relaxed) {
+        0 => {
+            // SAFETY: `sysconf` has no preconditions.
+            let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+            PAGE_SIZE.store(size, Ordering::Relaxed);
+            size
+        }
+        size => size,
+    }
+}
+
+#[cfg(not(unix))]
+fn page_size() -> usize {
+    4096
+}
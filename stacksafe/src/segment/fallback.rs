@@ -0,0 +1,102 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Platforms where this crate cannot switch stacks by itself, leaving every segment to `stacker`.
+
+use super::AllocFailure;
+use crate::Error;
+
+#[inline(always)]
+pub(super) fn remaining_stack() -> Option<usize> {
+    stacker::remaining_stack()
+}
+
+/// Nothing to restore on platforms where every check goes through `stacker`.
+pub(crate) struct Window;
+
+impl Window {
+    pub(super) const UNCHANGED: Window = Window;
+}
+
+/// Returns `None` if fewer than `red_zone` bytes are left on the current segment.
+#[inline(always)]
+pub(super) fn check_room(red_zone: usize) -> Option<Window> {
+    remaining_stack()
+        .is_some_and(|remaining| remaining >= red_zone)
+        .then_some(Window)
+}
+
+#[inline(always)]
+pub(super) fn trampoline_remaining() -> Option<usize> {
+    stacker::remaining_stack()
+}
+
+pub(super) fn trampoline(_stack_size: usize, _callback: &mut dyn FnMut()) -> Result<(), Error> {
+    Err(Error::Unsupported {
+        operation: "switching to a heap-allocated stack on this platform",
+    })
+}
+
+pub(super) fn grow_global(
+    stack_size: usize,
+    label: &'static str,
+    callback: &mut dyn FnMut(),
+) -> Result<(), AllocFailure> {
+    super::grow_system(stack_size, label, callback)
+}
+
+pub(super) fn grow_reserved(
+    _stack_size: usize,
+    _label: &'static str,
+    _callback: &mut dyn FnMut(),
+) -> bool {
+    false
+}
+
+pub(super) fn grow_pooled(
+    _stack_size: usize,
+    _label: &'static str,
+    _callback: &mut dyn FnMut(),
+) -> bool {
+    false
+}
+
+pub(super) fn shrink_pool() {}
+
+pub(super) fn grow_region(
+    _stack_size: usize,
+    _label: &'static str,
+    _callback: &mut dyn FnMut(),
+) -> bool {
+    false
+}
+
+pub(super) fn prefault_current() {}
+
+pub(super) fn reserve(
+    _count: usize,
+    _size: usize,
+    _lock: bool,
+    _prefault: bool,
+) -> Result<(), Error> {
+    Err(Error::Unsupported {
+        operation: "reserving stack segments on this platform",
+    })
+}
+
+pub(super) fn reserve_region(_slots: usize, _size: usize) -> Result<(), Error> {
+    Err(Error::Unsupported {
+        operation: "reserving address space for stack segments on this platform",
+    })
+}
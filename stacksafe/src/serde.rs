@@ -0,0 +1,272 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Limits on the nesting depth of deserialized input, deserialization of deep structures from
+//! flat input, and stack-safe adapters for any serializer and deserializer.
+//!
+//! Deserializing [`StackSafe<T>`](crate::StackSafe) never overflows the stack, but an attacker
+//! can still send input that is nested millions of levels deep, costing memory and time in
+//! proportion. [`DepthLimited`] and [`deserialize_with_depth_limit`] enforce an explicit policy
+//! instead: every [`StackSafe`](crate::StackSafe) deserialized is one level of nesting, and input
+//! nested more deeply than the limit is rejected with a deserialization error. The limit is
+//! counted independently of how much stack is used, so it applies the same on every platform and
+//! in every build profile.
+//!
+//! Even within such a limit, deserializing nested input recursively costs a stack segment every
+//! few thousand levels. [`deserialize_preorder`] builds a structure from a flat sequence of its
+//! nodes instead, without recursion.
+//!
+//! Types that do not wrap their children in [`StackSafe`](crate::StackSafe), such as types from
+//! other crates, can still be serialized and deserialized in a stack-safe way by wrapping the
+//! serializer in a [`StackSafeSerializer`], or the deserializer in a [`StackSafeDeserializer`].
+
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use ::serde::Deserialize;
+use ::serde::Deserializer;
+use ::serde::Serialize;
+use ::serde::Serializer;
+use ::serde::de::IgnoredAny;
+use ::serde::de::SeqAccess;
+use ::serde::de::Visitor;
+
+use crate::Assemble;
+use crate::Error;
+use crate::PreorderBuilder;
+
+mod adapter;
+
+pub use adapter::StackSafeDeserializer;
+pub use adapter::StackSafeSerializer;
+
+thread_local! {
+    // The number of `StackSafe` values currently being deserialized on this thread.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    // The depth beyond which deserialization fails, along with the limit it was configured with.
+    static LIMIT: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+/// Deserializes a `T`, failing if it contains more than `limit` nested
+/// [`StackSafe`](crate::StackSafe) values.
+///
+/// Limits nest: a limit set while another one is in effect can only make it stricter.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::StackSafe;
+///
+/// #[derive(serde::Deserialize)]
+/// enum List {
+///     Nil,
+///     Cons(u64, StackSafe<Box<List>>),
+/// }
+///
+/// let input = r#"{"Cons": [1, {"Cons": [2, {"Cons": [3, "Nil"]}]}]}"#;
+///
+/// let mut de = serde_json::Deserializer::from_str(input);
+/// assert!(stacksafe::serde::deserialize_with_depth_limit::<List, _>(&mut de, 3).is_ok());
+///
+/// let mut de = serde_json::Deserializer::from_str(input);
+/// let err = stacksafe::serde::deserialize_with_depth_limit::<List, _>(&mut de, 2)
+///     .err()
+///     .unwrap();
+/// assert!(
+///     err.to_string()
+///         .starts_with("the depth limit of 2 was exceeded")
+/// );
+/// ```
+pub fn deserialize_with_depth_limit<'de, T, D>(deserializer: D, limit: usize) -> Result<T, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    struct Restore(Option<(usize, usize)>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            LIMIT.with(|l| l.set(self.0));
+        }
+    }
+
+    let max_depth = DEPTH.with(|d| d.get()).saturating_add(limit);
+    let previous = LIMIT.with(|l| l.get());
+    let _restore = Restore(previous);
+    if previous.is_none_or(|(previous_max, _)| max_depth < previous_max) {
+        LIMIT.with(|l| l.set(Some((max_depth, limit))));
+    }
+    T::deserialize(deserializer)
+}
+
+/// Deserializes a tree from a sequence of its nodes in pre-order, each paired with its number of
+/// children.
+///
+/// Each node is deserialized as a `T` without children, and the tree is put together with a
+/// [`PreorderBuilder`] as the sequence is consumed. Nodes are never nested in the input, so the
+/// deserializer does not recurse, and a tree with millions of levels is built without growing
+/// the stack. The sequence must contain exactly one tree.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::Assemble;
+/// use stacksafe::StackSafe;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Tree {
+///     value: u32,
+///     #[serde(skip)]
+///     children: Vec<StackSafe<Tree>>,
+/// }
+///
+/// impl Assemble for Tree {
+///     fn assemble(&mut self, children: Vec<Self>) {
+///         self.children
+///             .extend(children.into_iter().map(StackSafe::new));
+///     }
+/// }
+///
+/// let input = r#"[[{"value": 1}, 2], [{"value": 2}, 0], [{"value": 3}, 0]]"#;
+/// let mut de = serde_json::Deserializer::from_str(input);
+/// let tree: Tree = stacksafe::serde::deserialize_preorder(&mut de).unwrap();
+/// assert_eq!(tree.value, 1);
+/// assert_eq!(tree.children.len(), 2);
+///
+/// let mut de = serde_json::Deserializer::from_str(r#"[[{"value": 1}, 1]]"#);
+/// assert!(stacksafe::serde::deserialize_preorder::<Tree, _>(&mut de).is_err());
+/// ```
+pub fn deserialize_preorder<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Assemble + Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    struct Preorder<T>(PhantomData<T>);
+
+    impl<'de, T: Assemble + Deserialize<'de>> Visitor<'de> for Preorder<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a sequence of nodes in pre-order with their numbers of children")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+            let mut builder = PreorderBuilder::new();
+            let mut len = 0;
+            while !builder.is_complete() {
+                let Some((node, children)) = seq.next_element::<(T, usize)>()? else {
+                    return Err(::serde::de::Error::invalid_length(len, &self));
+                };
+                builder.push(node, children);
+                len += 1;
+            }
+            if seq.next_element::<IgnoredAny>()?.is_some() {
+                return Err(::serde::de::Error::custom(
+                    "trailing nodes after a complete tree",
+                ));
+            }
+            Ok(builder.finish().unwrap())
+        }
+    }
+
+    deserializer.deserialize_seq(Preorder(PhantomData))
+}
+
+/// A `T` deserialized with at most `LIMIT` nested [`StackSafe`](crate::StackSafe) values.
+///
+/// This is [`deserialize_with_depth_limit`] as a type, for use in fields and as the target of
+/// functions like `serde_json::from_str`. Serialization is transparent.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::StackSafe;
+/// use stacksafe::serde::DepthLimited;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Tree {
+///     children: Vec<StackSafe<Tree>>,
+/// }
+///
+/// let input = r#"{"children": [{"children": [{"children": []}]}]}"#;
+/// assert!(serde_json::from_str::<DepthLimited<Tree, 2>>(input).is_ok());
+/// assert!(serde_json::from_str::<DepthLimited<Tree, 1>>(input).is_err());
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DepthLimited<T, const LIMIT: usize>(pub T);
+
+impl<T, const LIMIT: usize> DepthLimited<T, LIMIT> {
+    /// Returns the deserialized value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T, const LIMIT: usize> Deref for DepthLimited<T, LIMIT> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T, const LIMIT: usize> DerefMut for DepthLimited<T, LIMIT> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug, const LIMIT: usize> fmt::Debug for DepthLimited<T, LIMIT> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const LIMIT: usize> Deserialize<'de> for DepthLimited<T, LIMIT> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with_depth_limit(deserializer, LIMIT).map(DepthLimited)
+    }
+}
+
+impl<T: Serialize, const LIMIT: usize> Serialize for DepthLimited<T, LIMIT> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// One level of nesting, entered while a [`StackSafe`](crate::StackSafe) is deserialized.
+pub(crate) struct Level(());
+
+impl Level {
+    /// Enters a level, failing if that exceeds the depth limit in effect.
+    pub(crate) fn enter<E: ::serde::de::Error>() -> Result<Level, E> {
+        let depth = DEPTH.with(|d| d.get()) + 1;
+        if let Some((max_depth, limit)) = LIMIT.with(|l| l.get()) {
+            if depth > max_depth {
+                return Err(E::custom(Error::DepthExceeded { limit }));
+            }
+        }
+        DEPTH.with(|d| d.set(depth));
+        Ok(Level(()))
+    }
+}
+
+impl Drop for Level {
+    fn drop(&mut self) {
+        DEPTH.with(|d| d.set(d.get() - 1));
+    }
+}
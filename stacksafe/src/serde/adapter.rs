@@ -0,0 +1,580 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serializer and deserializer adapters that guard every level of nesting.
+//!
+//! The adapters wrap every nested serializer and deserializer handed out by the wrapped one, by
+//! wrapping the visitors, seeds, accessors and values passed through it, so that every level of
+//! nesting goes through a guarded call, whichever type drives it.
+
+use std::fmt;
+
+use ::serde::Deserializer;
+use ::serde::Serialize;
+use ::serde::Serializer;
+use ::serde::de;
+use ::serde::de::DeserializeSeed;
+use ::serde::de::Visitor;
+use ::serde::ser;
+
+use crate::internal::Site;
+
+static DESERIALIZE: Site = Site::new("stacksafe::serde::StackSafeDeserializer");
+static SERIALIZE: Site = Site::new("stacksafe::serde::StackSafeSerializer");
+
+/// A [`Deserializer`] that grows the stack as needed at every level of nesting of the input.
+///
+/// Deserializing a recursive type that does not wrap its children in
+/// [`StackSafe<T>`](crate::StackSafe), such as a type from another crate or a plain
+/// `Box<Self>`, recurses once per level of nesting. Wrapping the deserializer in a
+/// `StackSafeDeserializer` makes every nested value be deserialized in a stack-safe context
+/// instead, whatever type it is deserialized into, so that arbitrarily deep input can be handled
+/// without changing the data model.
+///
+/// The nesting depth itself is not limited. Combine the adapter with the limits of the
+/// deserializer, or with [`deserialize_with_depth_limit`](super::deserialize_with_depth_limit),
+/// to reject untrusted input that is nested too deeply. Dropping the deserialized value may
+/// recurse as well; see [`Dismantle`](crate::Dismantle) and
+/// [`drop_all_flattened`](crate::drop_all_flattened).
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::Deserialize;
+/// use stacksafe::serde::StackSafeDeserializer;
+///
+/// #[derive(Deserialize)]
+/// enum List {
+///     Nil,
+///     Cons(Box<List>),
+/// }
+///
+/// let mut input = String::from("\"Nil\"");
+/// for _ in 0..100_000 {
+///     input = format!("{{\"Cons\":{input}}}");
+/// }
+///
+/// let mut de = serde_json::Deserializer::from_str(&input);
+/// de.disable_recursion_limit();
+/// let list = List::deserialize(StackSafeDeserializer::new(&mut de)).unwrap();
+/// assert!(matches!(list, List::Cons(_)));
+/// # std::mem::forget(list);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StackSafeDeserializer<D>(D);
+
+impl<D> StackSafeDeserializer<D> {
+    /// Wraps `deserializer`.
+    pub fn new(deserializer: D) -> Self {
+        StackSafeDeserializer(deserializer)
+    }
+
+    /// Returns the wrapped deserializer.
+    pub fn into_inner(self) -> D {
+        self.0
+    }
+}
+
+macro_rules! forward_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(
+                self,
+                $($arg: $ty,)*
+                visitor: V,
+            ) -> Result<V::Value, D::Error> {
+                crate::internal::guard(&DESERIALIZE, move || {
+                    self.0.$method($($arg,)* Wrap(visitor))
+                })
+            }
+        )*
+    };
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for StackSafeDeserializer<D> {
+    type Error = D::Error;
+
+    forward_deserialize! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+}
+
+/// A visitor, seed, accessor or compound serializer whose nested values are wrapped in turn.
+pub struct Wrap<T>(T);
+
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method<E: de::Error>(self, value: $ty) -> Result<V::Value, E> {
+                self.0.$method(value)
+            }
+        )*
+    };
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for Wrap<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.expecting(f)
+    }
+
+    forward_visit! {
+        visit_bool(bool);
+        visit_i8(i8);
+        visit_i16(i16);
+        visit_i32(i32);
+        visit_i64(i64);
+        visit_i128(i128);
+        visit_u8(u8);
+        visit_u16(u16);
+        visit_u32(u32);
+        visit_u64(u64);
+        visit_u128(u128);
+        visit_f32(f32);
+        visit_f64(f64);
+        visit_char(char);
+        visit_str(&str);
+        visit_borrowed_str(&'de str);
+        visit_string(String);
+        visit_bytes(&[u8]);
+        visit_borrowed_bytes(&'de [u8]);
+        visit_byte_buf(Vec<u8>);
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.0.visit_none()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+        self.0.visit_some(StackSafeDeserializer(deserializer))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.0.visit_unit()
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<V::Value, D::Error> {
+        self.0
+            .visit_newtype_struct(StackSafeDeserializer(deserializer))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+        self.0.visit_seq(Wrap(seq))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        self.0.visit_map(Wrap(map))
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+        self.0.visit_enum(Wrap(data))
+    }
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for Wrap<S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<S::Value, D::Error> {
+        self.0.deserialize(StackSafeDeserializer(deserializer))
+    }
+}
+
+impl<'de, A: de::SeqAccess<'de>> de::SeqAccess<'de> for Wrap<A> {
+    type Error = A::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, A::Error> {
+        self.0.next_element_seed(Wrap(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+impl<'de, A: de::MapAccess<'de>> de::MapAccess<'de> for Wrap<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        self.0.next_key_seed(Wrap(seed))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, A::Error> {
+        self.0.next_value_seed(Wrap(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+impl<'de, A: de::EnumAccess<'de>> de::EnumAccess<'de> for Wrap<A> {
+    type Error = A::Error;
+    type Variant = Wrap<A::Variant>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Wrap<A::Variant>), A::Error> {
+        let (value, variant) = self.0.variant_seed(Wrap(seed))?;
+        Ok((value, Wrap(variant)))
+    }
+}
+
+impl<'de, A: de::VariantAccess<'de>> de::VariantAccess<'de> for Wrap<A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.0.unit_variant()
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, A::Error> {
+        self.0.newtype_variant_seed(Wrap(seed))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, A::Error> {
+        self.0.tuple_variant(len, Wrap(visitor))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        self.0.struct_variant(fields, Wrap(visitor))
+    }
+}
+
+/// A [`Serializer`] that grows the stack as needed at every level of nesting of the serialized
+/// value.
+///
+/// This is the counterpart of [`StackSafeDeserializer`]: every value nested in the one being
+/// serialized is serialized in a stack-safe context, whatever its type.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::Serialize;
+/// use stacksafe::serde::StackSafeSerializer;
+///
+/// #[derive(Serialize)]
+/// enum List {
+///     Nil,
+///     Cons(Box<List>),
+/// }
+///
+/// let mut list = List::Nil;
+/// for _ in 0..100_000 {
+///     list = List::Cons(Box::new(list));
+/// }
+///
+/// let mut json = Vec::new();
+/// let mut ser = serde_json::Serializer::new(&mut json);
+/// list.serialize(StackSafeSerializer::new(&mut ser)).unwrap();
+/// assert!(json.starts_with(br#"{"Cons":{"Cons":"#));
+/// # std::mem::forget(list);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StackSafeSerializer<S>(S);
+
+impl<S> StackSafeSerializer<S> {
+    /// Wraps `serializer`.
+    pub fn new(serializer: S) -> Self {
+        StackSafeSerializer(serializer)
+    }
+
+    /// Returns the wrapped serializer.
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+/// A value that is serialized in a stack-safe context, with a wrapped serializer.
+struct Guarded<'a, T: ?Sized>(&'a T);
+
+impl<T: ?Sized + Serialize> Serialize for Guarded<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::internal::guard(&SERIALIZE, || {
+            self.0.serialize(StackSafeSerializer(serializer))
+        })
+    }
+}
+
+macro_rules! forward_serialize {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method(self, value: $ty) -> Result<S::Ok, S::Error> {
+                self.0.$method(value)
+            }
+        )*
+    };
+}
+
+impl<S: Serializer> Serializer for StackSafeSerializer<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Wrap<S::SerializeSeq>;
+    type SerializeTuple = Wrap<S::SerializeTuple>;
+    type SerializeTupleStruct = Wrap<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Wrap<S::SerializeTupleVariant>;
+    type SerializeMap = Wrap<S::SerializeMap>;
+    type SerializeStruct = Wrap<S::SerializeStruct>;
+    type SerializeStructVariant = Wrap<S::SerializeStructVariant>;
+
+    forward_serialize! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_unit_struct(&'static str);
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.0.serialize_none()
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.0.serialize_some(&Guarded(value))
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.0.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.0.serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.0.serialize_newtype_struct(name, &Guarded(value))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.0
+            .serialize_newtype_variant(name, variant_index, variant, &Guarded(value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        self.0.serialize_seq(len).map(Wrap)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        self.0.serialize_tuple(len).map(Wrap)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        self.0.serialize_tuple_struct(name, len).map(Wrap)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        self.0
+            .serialize_tuple_variant(name, variant_index, variant, len)
+            .map(Wrap)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        self.0.serialize_map(len).map(Wrap)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        self.0.serialize_struct(name, len).map(Wrap)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        self.0
+            .serialize_struct_variant(name, variant_index, variant, len)
+            .map(Wrap)
+    }
+
+    fn collect_str<T: ?Sized + fmt::Display>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.0.collect_str(value)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+}
+
+macro_rules! forward_compound {
+    ($($trait:ident::$method:ident;)*) => {
+        $(
+            impl<S: ser::$trait> ser::$trait for Wrap<S> {
+                type Ok = S::Ok;
+                type Error = S::Error;
+
+                fn $method<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+                    self.0.$method(&Guarded(value))
+                }
+
+                fn end(self) -> Result<S::Ok, S::Error> {
+                    self.0.end()
+                }
+            }
+        )*
+    };
+}
+
+forward_compound! {
+    SerializeSeq::serialize_element;
+    SerializeTuple::serialize_element;
+    SerializeTupleStruct::serialize_field;
+    SerializeTupleVariant::serialize_field;
+}
+
+impl<S: ser::SerializeMap> ser::SerializeMap for Wrap<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), S::Error> {
+        self.0.serialize_key(&Guarded(key))
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        self.0.serialize_value(&Guarded(value))
+    }
+
+    fn serialize_entry<K: ?Sized + Serialize, V: ?Sized + Serialize>(
+        &mut self,
+        key: &K,
+        value: &V,
+    ) -> Result<(), S::Error> {
+        self.0.serialize_entry(&Guarded(key), &Guarded(value))
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.0.end()
+    }
+}
+
+macro_rules! forward_struct {
+    ($($trait:ident;)*) => {
+        $(
+            impl<S: ser::$trait> ser::$trait for Wrap<S> {
+                type Ok = S::Ok;
+                type Error = S::Error;
+
+                fn serialize_field<T: ?Sized + Serialize>(
+                    &mut self,
+                    key: &'static str,
+                    value: &T,
+                ) -> Result<(), S::Error> {
+                    self.0.serialize_field(key, &Guarded(value))
+                }
+
+                fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+                    self.0.skip_field(key)
+                }
+
+                fn end(self) -> Result<S::Ok, S::Error> {
+                    self.0.end()
+                }
+            }
+        )*
+    };
+}
+
+forward_struct! {
+    SerializeStruct;
+    SerializeStructVariant;
+}
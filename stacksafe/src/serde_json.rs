@@ -0,0 +1,354 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Stack-safe handling of [`serde_json::Value`] trees.
+//!
+//! [`Value`] is recursive, and cannot be changed to wrap its children in
+//! [`StackSafe<T>`](crate::StackSafe). Cloning, dropping, comparing, formatting, serializing and
+//! deserializing it all recurse once per level of nesting, so a deeply nested document overflows
+//! the stack in any of them. [`JsonValue`] wraps a [`Value`] and implements these operations in a
+//! stack-safe way instead, while giving access to the [`Value`] through [`Deref`] and
+//! [`DerefMut`].
+//!
+//! This module also implements [`Dismantle`] and [`IterativeEq`] for [`Value`], for use with
+//! [`drop_all_flattened`](crate::drop_all_flattened) and
+//! [`deep_eq_iterative`](crate::deep_eq_iterative).
+//!
+//! `serde_json` limits the nesting depth of the input it parses to 128 by default. Deeper input
+//! needs its `unbounded_depth` feature and [`Deserializer::disable_recursion_limit`]; combine it
+//! with [`deserialize_with_depth_limit`](crate::serde::deserialize_with_depth_limit), which
+//! counts every nested [`JsonValue`] as one level, to enforce a limit that does not depend on the
+//! stack.
+//!
+//! [`Deserializer::disable_recursion_limit`]: https://docs.rs/serde_json/1/serde_json/struct.Deserializer.html#method.disable_recursion_limit
+//!
+//! # Examples
+//!
+//! ```rust
+//! use serde_json::Value;
+//! use stacksafe::serde_json::JsonValue;
+//!
+//! let mut value = JsonValue::from(Value::Null);
+//! for _ in 0..100_000 {
+//!     value = JsonValue::from(Value::Array(vec![value.into_inner()]));
+//! }
+//!
+//! let copy = value.clone();
+//! assert_eq!(copy, value);
+//! let json = serde_json::to_string(&value).unwrap();
+//! assert!(json.starts_with("[[[["));
+//! ```
+
+use std::fmt;
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use ::serde::Deserialize;
+use ::serde::Deserializer;
+use ::serde::Serialize;
+use ::serde::Serializer;
+use ::serde::de::MapAccess;
+use ::serde::de::SeqAccess;
+use ::serde::de::Visitor;
+use ::serde::ser::SerializeMap;
+use ::serde::ser::SerializeSeq;
+use ::serde_json::Map;
+use ::serde_json::Number;
+use ::serde_json::Value;
+
+use crate::DeepDrop;
+use crate::Dismantle;
+use crate::IterativeEq;
+use crate::stacksafe;
+
+/// A [`serde_json::Value`] that is cloned, dropped, compared, formatted, serialized and
+/// deserialized in a stack-safe way.
+///
+/// See the [module documentation](self) for details.
+#[derive(Default)]
+pub struct JsonValue(Value);
+
+impl JsonValue {
+    /// Wraps `value`.
+    pub const fn new(value: Value) -> Self {
+        JsonValue(value)
+    }
+
+    /// Returns the wrapped value.
+    ///
+    /// Dropping, cloning or comparing the returned [`Value`] itself recurses again.
+    pub fn into_inner(mut self) -> Value {
+        std::mem::take(&mut self.0)
+    }
+}
+
+impl Deref for JsonValue {
+    type Target = Value;
+
+    fn deref(&self) -> &Value {
+        &self.0
+    }
+}
+
+impl DerefMut for JsonValue {
+    fn deref_mut(&mut self) -> &mut Value {
+        &mut self.0
+    }
+}
+
+impl From<Value> for JsonValue {
+    fn from(value: Value) -> Self {
+        JsonValue(value)
+    }
+}
+
+impl From<JsonValue> for Value {
+    fn from(value: JsonValue) -> Self {
+        value.into_inner()
+    }
+}
+
+impl Drop for JsonValue {
+    fn drop(&mut self) {
+        if matches!(self.0, Value::Array(_) | Value::Object(_)) {
+            crate::drop_all_flattened([std::mem::take(&mut self.0)]);
+        }
+    }
+}
+
+impl Clone for JsonValue {
+    fn clone(&self) -> Self {
+        JsonValue(clone(&self.0))
+    }
+}
+
+impl PartialEq for JsonValue {
+    fn eq(&self, other: &Self) -> bool {
+        crate::deep_eq_iterative(&self.0, &other.0)
+    }
+}
+
+impl PartialEq<Value> for JsonValue {
+    fn eq(&self, other: &Value) -> bool {
+        crate::deep_eq_iterative(&self.0, other)
+    }
+}
+
+impl Eq for JsonValue {}
+
+impl fmt::Debug for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&Guarded(&self.0), f)
+    }
+}
+
+/// Formats the value as JSON, and as pretty-printed JSON with the `{:#}` alternate flag.
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = if f.alternate() {
+            ::serde_json::to_string_pretty(&Guarded(&self.0))
+        } else {
+            ::serde_json::to_string(&Guarded(&self.0))
+        };
+        f.write_str(&json.map_err(|_| fmt::Error)?)
+    }
+}
+
+impl Serialize for JsonValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Guarded(&self.0).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonValue {
+    #[stacksafe(crate = crate)]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let _level = crate::serde::Level::enter()?;
+        deserializer.deserialize_any(ValueVisitor).map(JsonValue)
+    }
+}
+
+impl Dismantle for Value {
+    fn dismantle(&mut self, children: &mut Vec<Self>) {
+        match self {
+            Value::Array(values) => children.append(values),
+            Value::Object(map) => {
+                children.extend(std::mem::take(map).into_iter().map(|(_, value)| value));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl DeepDrop for Value {
+    fn deep_drop(self) {
+        crate::drop_all_flattened([self]);
+    }
+}
+
+impl IterativeEq for Value {
+    fn eq_shallow<'a>(&'a self, other: &'a Self, pending: &mut Vec<(&'a Self, &'a Self)>) -> bool {
+        match (self, other) {
+            (Value::Array(a), Value::Array(b)) => {
+                if a.len() != b.len() {
+                    return false;
+                }
+                pending.extend(a.iter().zip(b));
+                true
+            }
+            (Value::Object(a), Value::Object(b)) => {
+                if a.len() != b.len() {
+                    return false;
+                }
+                for (key, a) in a {
+                    match b.get(key) {
+                        Some(b) => pending.push((a, b)),
+                        None => return false,
+                    }
+                }
+                true
+            }
+            (Value::Array(_) | Value::Object(_), _) | (_, Value::Array(_) | Value::Object(_)) => {
+                false
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+#[stacksafe(crate = crate)]
+fn clone(value: &Value) -> Value {
+    match value {
+        Value::Array(values) => Value::Array(values.iter().map(clone).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), clone(value)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+/// A value that is formatted and serialized in a stack-safe context.
+struct Guarded<'a>(&'a Value);
+
+impl fmt::Debug for Guarded<'_> {
+    #[stacksafe(crate = crate)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Value::Array(values) => {
+                f.write_str("Array ")?;
+                f.debug_list().entries(values.iter().map(Guarded)).finish()
+            }
+            Value::Object(map) => {
+                f.write_str("Object ")?;
+                f.debug_map()
+                    .entries(map.iter().map(|(key, value)| (key, Guarded(value))))
+                    .finish()
+            }
+            value => fmt::Debug::fmt(value, f),
+        }
+    }
+}
+
+impl Serialize for Guarded<'_> {
+    #[stacksafe(crate = crate)]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Array(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(&Guarded(value))?;
+                }
+                seq.end()
+            }
+            Value::Object(map) => {
+                let mut ser = serializer.serialize_map(Some(map.len()))?;
+                for (key, value) in map {
+                    ser.serialize_entry(key, &Guarded(value))?;
+                }
+                ser.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// Builds a [`Value`], deserializing nested values as [`JsonValue`]s.
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(value).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_owned()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        JsonValue::deserialize(deserializer).map(JsonValue::into_inner)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        // Collect into a `JsonValue` so that the elements are dropped safely on errors.
+        let mut values = JsonValue(Value::Array(Vec::new()));
+        while let Some(value) = seq.next_element::<JsonValue>()? {
+            if let Value::Array(values) = &mut values.0 {
+                values.push(value.into_inner());
+            }
+        }
+        Ok(values.into_inner())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+        let mut map = JsonValue(Value::Object(Map::new()));
+        while let Some((key, value)) = access.next_entry::<String, JsonValue>()? {
+            if let Value::Object(map) = &mut map.0 {
+                map.insert(key, value.into_inner());
+            }
+        }
+        Ok(map.into_inner())
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use crate::stacksafe;
+
+/// A box for recursive data structures that stores small values inline.
+///
+/// [`StackSafeSmallBox<T, N>`] behaves like a [`StackSafe<Box<T>>`](crate::StackSafe): access
+/// through [`Deref`] and [`DerefMut`] is only allowed within a stack-safe context, and cloning,
+/// dropping, comparison, and hashing run with [`#[stacksafe]`](stacksafe) protection. However, if
+/// `T` is at most `N` bytes large and its alignment does not exceed that of a pointer, the value
+/// is stored inline instead of in a separate heap allocation. This keeps small nodes, such as the
+/// leaves of an abstract syntax tree, next to their parents in memory.
+///
+/// Whether values are stored inline only depends on `T` and `N`, and can be checked with
+/// [`is_inline`](StackSafeSmallBox::is_inline). A [`StackSafeSmallBox<T, N>`] is as large as `N`
+/// bytes or a pointer, whichever is larger, rounded up to the alignment of a pointer.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::StackSafe;
+/// use stacksafe::StackSafeSmallBox;
+/// use stacksafe::stacksafe;
+///
+/// enum Literal {
+///     Int(i64),
+///     Bool(bool),
+/// }
+///
+/// enum Expr {
+///     Literal(StackSafeSmallBox<Literal, 16>),
+///     Add(StackSafe<Box<Expr>>, StackSafe<Box<Expr>>),
+/// }
+///
+/// #[stacksafe]
+/// fn eval(expr: &Expr) -> i64 {
+///     match expr {
+///         Expr::Literal(literal) => match **literal {
+///             Literal::Int(value) => value,
+///             Literal::Bool(value) => value as i64,
+///         },
+///         Expr::Add(lhs, rhs) => eval(lhs) + eval(rhs),
+///     }
+/// }
+///
+/// let one = StackSafeSmallBox::new(Literal::Int(1));
+/// assert!(one.is_inline());
+///
+/// let expr = Expr::Add(
+///     StackSafe::boxed(Expr::Literal(one)),
+///     StackSafe::boxed(Expr::Literal(StackSafeSmallBox::new(Literal::Bool(true)))),
+/// );
+/// assert_eq!(eval(&expr), 2);
+/// ```
+pub struct StackSafeSmallBox<T, const N: usize> {
+    slot: Slot<N>,
+    _marker: PhantomData<T>,
+}
+
+/// Holds either the value itself or a pointer to its heap allocation.
+#[repr(C)]
+union Slot<const N: usize> {
+    inline: [MaybeUninit<u8>; N],
+    heap: *mut u8,
+}
+
+// SAFETY: the box owns its value, just like a `Box<T>`.
+unsafe impl<T: Send, const N: usize> Send for StackSafeSmallBox<T, N> {}
+
+// SAFETY: the box only hands out shared references to its value through shared references.
+unsafe impl<T: Sync, const N: usize> Sync for StackSafeSmallBox<T, N> {}
+
+impl<T, const N: usize> StackSafeSmallBox<T, N> {
+    const INLINE: bool = size_of::<T>() <= N && align_of::<T>() <= align_of::<Slot<N>>();
+
+    /// Creates a new [`StackSafeSmallBox<T, N>`] holding the given value.
+    ///
+    /// The value is moved to the heap unless it can be stored inline.
+    pub fn new(value: T) -> Self {
+        let slot = if Self::INLINE {
+            let mut slot = Slot {
+                inline: [MaybeUninit::uninit(); N],
+            };
+            // SAFETY: the slot is large and aligned enough to hold a `T`.
+            unsafe { (&raw mut slot).cast::<T>().write(value) };
+            slot
+        } else {
+            Slot {
+                heap: Box::into_raw(Box::new(value)).cast(),
+            }
+        };
+        StackSafeSmallBox {
+            slot,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the value is stored inline rather than on the heap.
+    pub fn is_inline(&self) -> bool {
+        Self::INLINE
+    }
+
+    /// Consumes the [`StackSafeSmallBox<T, N>`] and returns the inner value.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    pub fn into_inner(self) -> T {
+        crate::internal::assert_protected::<T>();
+
+        let this = ManuallyDrop::new(self);
+        // SAFETY: the value is initialized, and it is neither used nor dropped by `this` again.
+        unsafe {
+            if Self::INLINE {
+                this.as_ptr().read()
+            } else {
+                *Box::from_raw(this.slot.heap.cast::<T>())
+            }
+        }
+    }
+
+    fn as_ptr(&self) -> *const T {
+        if Self::INLINE {
+            (&raw const self.slot).cast()
+        } else {
+            // SAFETY: values that are not stored inline are stored on the heap.
+            unsafe { self.slot.heap.cast() }
+        }
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        if Self::INLINE {
+            (&raw mut self.slot).cast()
+        } else {
+            // SAFETY: values that are not stored inline are stored on the heap.
+            unsafe { self.slot.heap.cast() }
+        }
+    }
+
+    fn get(&self) -> &T {
+        // SAFETY: the pointer refers to the initialized value owned by `self`.
+        unsafe { &*self.as_ptr() }
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        // SAFETY: the pointer refers to the initialized value owned by `self`.
+        unsafe { &mut *self.as_mut_ptr() }
+    }
+}
+
+impl<T, const N: usize> From<T> for StackSafeSmallBox<T, N> {
+    fn from(value: T) -> Self {
+        StackSafeSmallBox::new(value)
+    }
+}
+
+impl<T: Default, const N: usize> Default for StackSafeSmallBox<T, N> {
+    fn default() -> Self {
+        StackSafeSmallBox::new(T::default())
+    }
+}
+
+impl<T, const N: usize> Deref for StackSafeSmallBox<T, N> {
+    type Target = T;
+
+    /// Provides transparent access to the boxed value.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        crate::internal::assert_protected::<T>();
+
+        self.get()
+    }
+}
+
+impl<T, const N: usize> DerefMut for StackSafeSmallBox<T, N> {
+    /// Provides transparent mutable access to the boxed value.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if called outside of a stack-safe context.
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        crate::internal::assert_protected::<T>();
+
+        self.get_mut()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for StackSafeSmallBox<T, N> {
+    #[stacksafe(crate = crate)]
+    fn clone(&self) -> Self {
+        StackSafeSmallBox::new(self.get().clone())
+    }
+}
+
+impl<T, const N: usize> Drop for StackSafeSmallBox<T, N> {
+    #[stacksafe(crate = crate)]
+    fn drop(&mut self) {
+        // SAFETY: the value is initialized and never used again.
+        unsafe {
+            if Self::INLINE {
+                std::ptr::drop_in_place(self.as_mut_ptr());
+            } else {
+                drop(Box::from_raw(self.slot.heap.cast::<T>()));
+            }
+        }
+    }
+}
+
+impl<T: std::fmt::Debug, const N: usize> std::fmt::Debug for StackSafeSmallBox<T, N> {
+    #[stacksafe(crate = crate)]
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#?}", self.get())
+        } else {
+            write!(f, "{:?}", self.get())
+        }
+    }
+}
+
+impl<T: std::fmt::Display, const N: usize> std::fmt::Display for StackSafeSmallBox<T, N> {
+    #[stacksafe(crate = crate)]
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#}", self.get())
+        } else {
+            write!(f, "{}", self.get())
+        }
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for StackSafeSmallBox<T, N> {
+    #[stacksafe(crate = crate)]
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for StackSafeSmallBox<T, N> {}
+
+impl<T: PartialOrd, const N: usize> PartialOrd for StackSafeSmallBox<T, N> {
+    #[stacksafe(crate = crate)]
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.get().partial_cmp(other.get())
+    }
+}
+
+impl<T: Ord, const N: usize> Ord for StackSafeSmallBox<T, N> {
+    #[stacksafe(crate = crate)]
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.get().cmp(other.get())
+    }
+}
+
+impl<T: std::hash::Hash, const N: usize> std::hash::Hash for StackSafeSmallBox<T, N> {
+    #[stacksafe(crate = crate)]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.get().hash(state);
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::OnceLock;
+
+use crate::Error;
+
+/// A way of measuring the remaining stack space and of running code on a new stack.
+///
+/// Guarded code, such as the functions marked with [`#[stacksafe]`](crate::stacksafe), asks the
+/// strategy installed with [`set_growth_strategy`](crate::set_growth_strategy) how much stack is
+/// left, and has it run the rest of the call on a new stack when that falls below the red zone.
+/// Without an installed strategy, segments are allocated by `stacker`, or by this crate as
+/// configured with [`set_segment_allocator`](crate::set_segment_allocator).
+///
+/// Everything around the switch itself is still handled by this crate: [`current_segment`],
+/// telemetry and hooks, the kill switch of [`set_growth_enabled`], and the stack-safe context.
+/// Segments reserved with [`reserve_address_space`] or [`realtime::init`], pooled segments, and
+/// the retries with smaller segments reported to [`set_fallback_hook`] are specific to the
+/// built-in growth and are bypassed.
+///
+/// [`current_segment`]: crate::current_segment
+/// [`set_growth_enabled`]: crate::set_growth_enabled
+/// [`reserve_address_space`]: crate::reserve_address_space
+/// [`realtime::init`]: crate::realtime::init
+/// [`set_fallback_hook`]: crate::set_fallback_hook
+pub trait GrowthStrategy: Send + Sync + 'static {
+    /// Returns the amount of stack space left on the current thread in bytes, or `None` if it is
+    /// unknown, in which case the stack is grown.
+    ///
+    /// This is called on entry to every guarded function, so it should be cheap.
+    fn remaining_stack(&self) -> Option<usize>;
+
+    /// Runs `callback` on a new stack of at least `stack_size` bytes, on the current thread.
+    ///
+    /// On success, `callback` must have been called exactly once. If `callback` panics, the panic
+    /// must be propagated once the previous stack is restored. An error is reported to the caller
+    /// of the guarded function, usually as a panic, and `callback` must not have been called.
+    fn grow(&self, stack_size: usize, callback: &mut dyn FnMut()) -> Result<(), Error>;
+}
+
+/// A [`GrowthStrategy`] that runs each new stack segment on a heap allocation, independently of
+/// `stacker`.
+///
+/// Segments are allocated through the [global allocator](std::alloc::GlobalAlloc) and switched to
+/// with `psm`, and the bounds of the segment the current thread is running on are tracked by the
+/// strategy itself. On a stack whose limit cannot be determined, such as the main stack on
+/// `wasm32-unknown-unknown`, the first guarded call moves onto a heap segment, so that every
+/// guarded call from then on runs on a stack of known size.
+///
+/// Where the stack cannot be switched at all, [`grow`](GrowthStrategy::grow) fails with
+/// [`Error::Unsupported`], so that guarded code reports that it cannot grow the stack instead of
+/// overflowing it.
+///
+/// # Examples
+///
+/// ```rust,standalone_crate
+/// use stacksafe::HeapTrampoline;
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn depth(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + depth(n - 1) }
+/// }
+///
+/// stacksafe::set_growth_strategy(HeapTrampoline).unwrap();
+/// assert_eq!(depth(1_000_000), 1_000_000);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct HeapTrampoline;
+
+impl GrowthStrategy for HeapTrampoline {
+    #[inline(always)]
+    fn remaining_stack(&self) -> Option<usize> {
+        crate::segment::trampoline_remaining()
+    }
+
+    fn grow(&self, stack_size: usize, callback: &mut dyn FnMut()) -> Result<(), Error> {
+        crate::segment::trampoline(stack_size, callback)
+    }
+}
+
+static STRATEGY: OnceLock<Box<dyn GrowthStrategy>> = OnceLock::new();
+
+pub(crate) fn set_growth_strategy(strategy: Box<dyn GrowthStrategy>) -> Result<(), Error> {
+    STRATEGY.set(strategy).map_err(|_| Error::Unsupported {
+        operation: "replacing the growth strategy",
+    })
+}
+
+/// Returns the installed strategy, or `None` if the built-in growth is used.
+#[inline(always)]
+pub(crate) fn installed() -> Option<&'static dyn GrowthStrategy> {
+    STRATEGY.get().map(|strategy| &**strategy)
+}
@@ -0,0 +1,183 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::StackSafe;
+use crate::internal::Site;
+use crate::stacksafe;
+
+/// Conversion between a recursive data structure built with [`StackSafe<T>`] and a plain
+/// counterpart of the same shape without the wrappers.
+///
+/// Public APIs often expose plain types, such as `Box<Expr>` rather than
+/// `StackSafe<Box<Expr>>`, while the crate behind them relies on the wrappers internally.
+/// Converting between the two by hand takes a recursive function that overflows the stack on
+/// deep inputs itself. [`Strip`] is implemented with `#[derive(Strip)]`, naming the plain type
+/// with `#[strip(plain = ...)]`: every field is converted with [`Strip`] in turn, in a
+/// stack-safe context, except for fields marked with `#[strip(keep)]`, which are moved as they
+/// are. The plain type must have the same fields, or the same variants with the same fields.
+///
+/// [`Strip`] is implemented for [`StackSafe<T>`], which is unwrapped, for [`Box<T>`], [`Option<T>`]
+/// and [`Vec<T>`], which are converted element by element, and for primitive types and
+/// [`String`], which stay as they are.
+///
+/// Note that the plain type does not benefit from any stack-safe operations: deep plain values
+/// still overflow the stack when they are, for example, dropped or cloned.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::StackSafe;
+/// use stacksafe::Strip;
+///
+/// /// The type exposed in the public API.
+/// pub enum Expr {
+///     Num(i64),
+///     Neg(Box<Expr>),
+///     Add { lhs: Box<Expr>, rhs: Box<Expr> },
+/// }
+///
+/// #[derive(Strip)]
+/// #[strip(plain = Expr)]
+/// enum SafeExpr {
+///     Num(#[strip(keep)] i64),
+///     Neg(StackSafe<Box<SafeExpr>>),
+///     Add {
+///         lhs: StackSafe<Box<SafeExpr>>,
+///         rhs: StackSafe<Box<SafeExpr>>,
+///     },
+/// }
+///
+/// let expr = Expr::Add {
+///     lhs: Box::new(Expr::Num(1)),
+///     rhs: Box::new(Expr::Neg(Box::new(Expr::Num(2)))),
+/// };
+/// let safe: SafeExpr = stacksafe::wrap(expr);
+/// assert!(matches!(stacksafe::strip(safe), Expr::Add { .. }));
+/// ```
+pub trait Strip: Sized {
+    /// The plain counterpart of this type.
+    type Plain;
+
+    /// Converts this value into its plain counterpart.
+    ///
+    /// Use [`strip`] to call this from outside of a stack-safe context.
+    fn strip(self) -> Self::Plain;
+
+    /// Converts a plain value into this type.
+    ///
+    /// Use [`wrap`] to call this from outside of a stack-safe context.
+    fn wrap(plain: Self::Plain) -> Self;
+}
+
+/// Converts a recursive data structure into its plain counterpart; see [`Strip`].
+pub fn strip<T: Strip>(value: T) -> T::Plain {
+    static SITE: Site = Site::new("stacksafe::strip");
+    crate::internal::guard(&SITE, || value.strip())
+}
+
+/// Converts a plain value into the recursive data structure it is the counterpart of; see
+/// [`Strip`].
+pub fn wrap<T: Strip>(plain: T::Plain) -> T {
+    static SITE: Site = Site::new("stacksafe::wrap");
+    crate::internal::guard(&SITE, || T::wrap(plain))
+}
+
+impl<T: Strip> Strip for StackSafe<T> {
+    type Plain = T::Plain;
+
+    #[stacksafe(crate = crate)]
+    fn strip(self) -> T::Plain {
+        self.into_inner().strip()
+    }
+
+    #[stacksafe(crate = crate)]
+    fn wrap(plain: T::Plain) -> Self {
+        StackSafe::new(T::wrap(plain))
+    }
+}
+
+impl<T: Strip> Strip for Box<T> {
+    type Plain = Box<T::Plain>;
+
+    fn strip(self) -> Box<T::Plain> {
+        Box::new((*self).strip())
+    }
+
+    fn wrap(plain: Box<T::Plain>) -> Self {
+        Box::new(T::wrap(*plain))
+    }
+}
+
+impl<T: Strip> Strip for Option<T> {
+    type Plain = Option<T::Plain>;
+
+    fn strip(self) -> Option<T::Plain> {
+        self.map(T::strip)
+    }
+
+    fn wrap(plain: Option<T::Plain>) -> Self {
+        plain.map(T::wrap)
+    }
+}
+
+impl<T: Strip> Strip for Vec<T> {
+    type Plain = Vec<T::Plain>;
+
+    fn strip(self) -> Vec<T::Plain> {
+        self.into_iter().map(T::strip).collect()
+    }
+
+    fn wrap(plain: Vec<T::Plain>) -> Self {
+        plain.into_iter().map(T::wrap).collect()
+    }
+}
+
+macro_rules! strip_as_is {
+    ($($ty:ty),*) => {
+        $(
+            impl Strip for $ty {
+                type Plain = $ty;
+
+                fn strip(self) -> $ty {
+                    self
+                }
+
+                fn wrap(plain: $ty) -> Self {
+                    plain
+                }
+            }
+        )*
+    };
+}
+
+strip_as_is!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    String
+);
@@ -0,0 +1,89 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Assertions for tests about how code uses the stack.
+
+use std::cell::Cell;
+use std::panic::Location;
+
+thread_local! {
+    // The call to `assert_no_growth` the current thread is running under, if any.
+    static FORBIDDEN: Cell<Option<&'static Location<'static>>> = const { Cell::new(None) };
+}
+
+/// Runs `f`, panicking as soon as it tries to grow the stack of the current thread.
+///
+/// This is meant for tests asserting that a hot path never needs a new stack segment for
+/// representative inputs. Any growth fails the assertion, including a switch to a segment
+/// reserved with [`realtime::init`](crate::realtime::init). Only the current thread is checked.
+///
+/// The panic is raised before the segment is allocated and unwinds through `f` as usual, so
+/// nothing is leaked. Growth is allowed again while unwinding, whether from this panic or another
+/// one, so that dropping deep structures along the way does not panic a second time and abort.
+///
+/// # Panics
+///
+/// Panics if `f` grows the stack, or if `f` panics.
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn depth(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + depth(n - 1) }
+/// }
+///
+/// assert_eq!(stacksafe::testing::assert_no_growth(|| depth(100)), 100);
+///
+/// let result =
+///     std::panic::catch_unwind(|| stacksafe::testing::assert_no_growth(|| depth(10_000_000)));
+/// assert!(result.is_err());
+/// ```
+#[track_caller]
+pub fn assert_no_growth<R>(f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<&'static Location<'static>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FORBIDDEN.with(|f| f.set(self.0));
+        }
+    }
+
+    let location = Location::caller();
+    let _restore = Restore(FORBIDDEN.with(|f| f.replace(Some(location))));
+    f()
+}
+
+/// Panics if the stack is about to grow into a segment of `size` bytes labeled with `label`
+/// under [`assert_no_growth`].
+#[inline]
+pub(crate) fn check_growth(label: &'static str, size: usize) {
+    if let Some(location) = FORBIDDEN.with(|f| f.get()) {
+        if !std::thread::panicking() {
+            growth_forbidden(location, label, size);
+        }
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn growth_forbidden(location: &'static Location<'static>, label: &'static str, size: usize) -> ! {
+    FORBIDDEN.with(|f| f.set(None));
+    panic!(
+        "`{label}` grew the stack by a segment of {size} bytes under `assert_no_growth` at \
+         {location}"
+    );
+}
@@ -0,0 +1,166 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Threads that inherit the stack-safe context of the thread that starts them.
+//!
+//! A thread started with [`std::thread`] runs its closure on a fresh stack, outside of any
+//! stack-safe context, and with the global configuration, even if it was started from a function
+//! marked with [`#[stacksafe]`](crate::stacksafe) within a [scoped
+//! configuration](crate::StackConfig::scope). Accessing a [`StackSafe<T>`](crate::StackSafe)
+//! handed over to such a thread then panics in debug builds, and guarded functions it calls
+//! ignore the configuration of the caller.
+//!
+//! [`spawn`] and [`scope`] mirror their counterparts in [`std::thread`], but run every closure in
+//! a stack-safe context if the thread starting it was in one, and with the scoped configuration
+//! of that thread.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use stacksafe::StackSafe;
+//! use stacksafe::stacksafe;
+//!
+//! #[stacksafe]
+//! fn sum(values: &[StackSafe<u64>]) -> u64 {
+//!     let (left, right) = values.split_at(values.len() / 2);
+//!     stacksafe::thread::scope(|s| {
+//!         let left = s.spawn(|| left.iter().map(|v| **v).sum::<u64>());
+//!         let right = right.iter().map(|v| **v).sum::<u64>();
+//!         left.join().unwrap() + right
+//!     })
+//! }
+//!
+//! let values: Vec<_> = (1..=100).map(StackSafe::new).collect();
+//! assert_eq!(sum(&values), 5050);
+//! ```
+
+use std::thread::JoinHandle;
+use std::thread::ScopedJoinHandle;
+
+use crate::internal::Site;
+
+static SITE: Site = Site::new("stacksafe::thread::spawn");
+
+/// The stack-safe context and the scoped configuration of a thread, to be restored on another
+/// one.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Context {
+    protected: bool,
+    sizes: Option<(usize, usize)>,
+}
+
+impl Context {
+    /// Captures the context of the current thread.
+    pub(crate) fn capture() -> Self {
+        Context {
+            protected: crate::internal::is_protected(),
+            sizes: crate::config::scoped_sizes(),
+        }
+    }
+
+    /// Runs `f` with the captured context, in a stack-safe context if it was captured in one.
+    pub(crate) fn run<R>(&self, f: impl FnOnce() -> R) -> R {
+        if self.protected {
+            self.guard(&SITE, f)
+        } else {
+            crate::config::with_scoped_sizes(self.sizes, f)
+        }
+    }
+
+    /// Runs `f` with the captured configuration, always in a stack-safe context.
+    #[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+    pub(crate) fn guard<R>(&self, site: &'static Site, f: impl FnOnce() -> R) -> R {
+        crate::config::with_scoped_sizes(self.sizes, || crate::internal::guard(site, f))
+    }
+}
+
+/// Spawns a new thread that inherits the stack-safe context of the current one, and returns a
+/// [`JoinHandle`] for it.
+///
+/// This is like [`std::thread::spawn`], except that `f` runs in a stack-safe context if the
+/// current thread is in one, and with the [scoped configuration](crate::StackConfig::scope) of
+/// the current thread, if any.
+///
+/// # Panics
+///
+/// Panics if the thread cannot be spawned, like [`std::thread::spawn`].
+///
+/// # Examples
+///
+/// ```rust
+/// use stacksafe::StackSafe;
+/// use stacksafe::stacksafe;
+///
+/// #[stacksafe]
+/// fn sum_in_background(values: Vec<StackSafe<u64>>) -> std::thread::JoinHandle<u64> {
+///     stacksafe::thread::spawn(move || values.iter().map(|v| **v).sum())
+/// }
+///
+/// let values = (1..=100).map(StackSafe::new).collect();
+/// assert_eq!(sum_in_background(values).join().unwrap(), 5050);
+/// ```
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let context = Context::capture();
+    std::thread::spawn(move || context.run(f))
+}
+
+/// Creates a scope for spawning scoped threads that inherit the stack-safe context of the current
+/// one.
+///
+/// This is like [`std::thread::scope`], except that the threads are spawned with
+/// [`Scope::spawn`], which runs them like [`spawn`]. Like [`std::thread::scope`], all threads
+/// that have not been joined manually are joined before this returns, and a panic in any of them
+/// is propagated.
+pub fn scope<'env, F, T>(f: F) -> T
+where F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T {
+    std::thread::scope(|scope| f(Scope::new(scope)))
+}
+
+/// A scope to spawn threads that inherit the stack-safe context of the thread that spawns them.
+///
+/// This is created by [`scope`].
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct Scope<'scope, 'env: 'scope> {
+    inner: std::thread::Scope<'scope, 'env>,
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    fn new(inner: &'scope std::thread::Scope<'scope, 'env>) -> &'scope Self {
+        // SAFETY: `Scope` is `#[repr(transparent)]` over `std::thread::Scope`.
+        unsafe { &*(inner as *const std::thread::Scope<'scope, 'env>).cast::<Self>() }
+    }
+
+    /// Spawns a new thread within the scope that inherits the stack-safe context of the current
+    /// one, and returns a [`ScopedJoinHandle`] for it.
+    ///
+    /// This is like [`std::thread::Scope::spawn`], except that `f` runs like the closure passed
+    /// to [`spawn`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread cannot be spawned, like [`std::thread::Scope::spawn`].
+    pub fn spawn<F, T>(&'scope self, f: F) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        let context = Context::capture();
+        self.inner.spawn(move || context.run(f))
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recursion depth in [`tracing`] spans.
+//!
+//! With the `tracing` feature, every thread keeps track of how many stack-safe contexts are
+//! nested on it, which is the recursion depth of functions marked with
+//! [`#[stacksafe]`](crate::stacksafe). [`record`] records that depth and the number of grown
+//! segments in the `stacksafe.depth` and `stacksafe.segments` fields of a span, so that traces of
+//! slow requests show how deep the recursion went. The span has to declare these fields when it
+//! is created, for example as [`Empty`](::tracing::field::Empty).
+//!
+//! Whenever a new stack segment is entered, a `DEBUG` event with the `stacksafe` target is
+//! emitted, with the `label` of the function that grew the stack, the `size` of the segment, and
+//! its `depth`. Which of these events are emitted can be configured with
+//! [`set_growth_sampling`](crate::set_growth_sampling).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use stacksafe::stacksafe;
+//! use tracing::field::Empty;
+//!
+//! #[stacksafe]
+//! fn parse(input: &[u8]) -> usize {
+//!     if input.len() % 1000 == 0 {
+//!         let span =
+//!             tracing::info_span!("parse", stacksafe.depth = Empty, stacksafe.segments = Empty);
+//!         stacksafe::tracing::record(&span);
+//!         let _entered = span.enter();
+//!     }
+//!     match input {
+//!         [] => 0,
+//!         [_, rest @ ..] => 1 + parse(rest),
+//!     }
+//! }
+//!
+//! assert_eq!(parse(&[0; 10_000]), 10_000);
+//! ```
+
+use std::cell::Cell;
+
+use ::tracing::Span;
+
+thread_local! {
+    static NESTING: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The name of the field that [`record`] records the nesting depth in.
+pub const DEPTH_FIELD: &str = "stacksafe.depth";
+
+/// The name of the field that [`record`] records the number of grown segments in.
+pub const SEGMENTS_FIELD: &str = "stacksafe.segments";
+
+/// Returns how many stack-safe contexts are nested on the current thread.
+///
+/// Every call to a function marked with [`#[stacksafe]`](crate::stacksafe) adds one level, so
+/// this is the depth of the guarded recursion the current thread is in.
+pub fn depth() -> usize {
+    NESTING.with(|n| n.get())
+}
+
+/// Returns how many stack segments the current thread has grown into.
+///
+/// This is the [depth](crate::SegmentInfo::depth) of the [current
+/// segment](crate::current_segment), or zero when running on the thread's original stack.
+pub fn segments() -> usize {
+    crate::segment::depth()
+}
+
+/// Records the current [`depth`] and number of [`segments`] in the `stacksafe.depth` and
+/// `stacksafe.segments` fields of `span`.
+///
+/// Fields that the span did not declare when it was created are left out.
+pub fn record(span: &Span) {
+    span.record(DEPTH_FIELD, depth());
+    span.record(SEGMENTS_FIELD, segments());
+}
+
+/// Counts a stack-safe context for as long as it is alive.
+pub(crate) struct Nesting(());
+
+impl Nesting {
+    #[inline(always)]
+    pub(crate) fn enter() -> Nesting {
+        NESTING.with(|n| n.set(n.get() + 1));
+        Nesting(())
+    }
+}
+
+impl Drop for Nesting {
+    #[inline(always)]
+    fn drop(&mut self) {
+        NESTING.with(|n| n.set(n.get() - 1));
+    }
+}
+
+/// Emits an event for a new stack segment being entered.
+pub(crate) fn growth(label: &'static str, size: usize, depth: usize) {
+    // Subscribers may run guarded code themselves.
+    crate::hook::run(|| {
+        ::tracing::debug!(target: "stacksafe", label, size, depth, "entered a stack segment");
+    });
+}
@@ -0,0 +1,229 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::internal::Site;
+
+/// A computation that recurses through the heap instead of the stack.
+///
+/// `#[stacksafe]` can only grow the stack at the entry of annotated functions. When recursion
+/// passes through code that is not yours, such as a callback-driven parser or visitor framework,
+/// the frames in between are never checked. Writing the recursive step as a [`Trampoline`]
+/// instead lets the callback return right away with a description of what to do next, which
+/// [`run`](Trampoline::run) then executes in a loop, keeping the pending work on the heap.
+///
+/// A trampoline is either [`done`](Trampoline::done) with a value, or a deferred
+/// [`call`](Trampoline::call) that produces the next trampoline. Results of nested calls are
+/// combined with [`and_then`](Trampoline::and_then) and [`map`](Trampoline::map), which do not
+/// nest calls on the stack either, so recursion that is not in tail position is flattened as
+/// well.
+///
+/// The computation runs in a stack-safe context, so the closures may access
+/// [`StackSafe<T>`](crate::StackSafe) values and call functions marked with
+/// [`#[stacksafe]`](crate::stacksafe), and a trampoline may be run from within such functions.
+///
+/// # Examples
+///
+/// Counting the nodes of a tree whose children are only lent to a callback:
+///
+/// ```rust
+/// use stacksafe::Trampoline;
+///
+/// struct Tree {
+///     children: Vec<Vec<usize>>,
+/// }
+///
+/// // A library function that calls back into the caller.
+/// fn with_children<'t, R>(tree: &'t Tree, node: usize, f: impl FnOnce(&'t [usize]) -> R) -> R {
+///     f(&tree.children[node])
+/// }
+///
+/// fn count(tree: &Tree, node: usize) -> Trampoline<'_, usize> {
+///     with_children(tree, node, |children| {
+///         children.iter().fold(Trampoline::done(1), |total, &child| {
+///             total.and_then(move |total| {
+///                 Trampoline::call(move || count(tree, child)).map(move |n| total + n)
+///             })
+///         })
+///     })
+/// }
+///
+/// // A path of 1,000,000 nodes.
+/// let mut children: Vec<_> = (1..1_000_000).map(|child| vec![child]).collect();
+/// children.push(Vec::new());
+/// let tree = Tree { children };
+/// assert_eq!(count(&tree, 0).run(), 1_000_000);
+/// ```
+pub struct Trampoline<'a, T> {
+    step: Step<'a, T>,
+}
+
+enum Step<'a, T> {
+    Done(T),
+    Call(Box<dyn FnOnce() -> Trampoline<'a, T> + 'a>),
+    Bind(Box<dyn Bind<'a, T> + 'a>),
+}
+
+impl<'a, T> Trampoline<'a, T> {
+    /// Returns a trampoline that is done with `value`.
+    pub fn done(value: T) -> Self {
+        Trampoline {
+            step: Step::Done(value),
+        }
+    }
+
+    /// Returns a trampoline that continues with the trampoline returned by `f`.
+    ///
+    /// `f` is only called once the trampoline is run, after the current closure has returned.
+    pub fn call(f: impl FnOnce() -> Trampoline<'a, T> + 'a) -> Self {
+        Trampoline {
+            step: Step::Call(Box::new(f)),
+        }
+    }
+
+    /// Returns a trampoline that continues with the trampoline `f` returns for the value of
+    /// this one.
+    pub fn and_then<U: 'a>(self, f: impl FnOnce(T) -> Trampoline<'a, U> + 'a) -> Trampoline<'a, U>
+    where T: 'a {
+        Trampoline {
+            step: Step::Bind(Box::new(Bound {
+                first: self,
+                then: f,
+            })),
+        }
+    }
+
+    /// Returns a trampoline that is done with the result of `f` for the value of this one.
+    pub fn map<U: 'a>(self, f: impl FnOnce(T) -> U + 'a) -> Trampoline<'a, U>
+    where T: 'a {
+        self.and_then(|value| Trampoline::done(f(value)))
+    }
+
+    /// Returns `true` if the trampoline is done, without running it.
+    pub fn is_done(&self) -> bool {
+        matches!(self.step, Step::Done(_))
+    }
+
+    /// Runs the computation to completion and returns its value.
+    pub fn run(self) -> T
+    where T: 'a {
+        static SITE: Site = Site::new("stacksafe::Trampoline::run");
+        crate::internal::guard(&SITE, || {
+            let slot = Rc::new(Cell::new(None));
+            let mut pending = Vec::new();
+            let mut task: Box<dyn Task<'a> + 'a> = Box::new(Running {
+                trampoline: self,
+                slot: slot.clone(),
+            });
+            while let Some(next) = task.step(&mut pending) {
+                task = next;
+            }
+            slot.take()
+                .expect("a finished trampoline always stores its value")
+        })
+    }
+}
+
+impl<T> From<T> for Trampoline<'_, T> {
+    fn from(value: T) -> Self {
+        Trampoline::done(value)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Trampoline<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.step {
+            Step::Done(value) => f.debug_tuple("Done").field(value).finish(),
+            Step::Call(_) | Step::Bind(_) => f.write_str("Pending"),
+        }
+    }
+}
+
+// The continuations waiting for the value of the task being run, innermost last.
+type Pending<'a> = Vec<Box<dyn FnOnce() -> Box<dyn Task<'a> + 'a> + 'a>>;
+
+// A trampoline whose type has been erased, together with where to store its value.
+trait Task<'a> {
+    // Advances the task by one step, and returns the task to run next, or `None` once nothing
+    // is pending anymore.
+    fn step(self: Box<Self>, pending: &mut Pending<'a>) -> Option<Box<dyn Task<'a> + 'a>>;
+}
+
+struct Running<'a, T> {
+    trampoline: Trampoline<'a, T>,
+    slot: Rc<Cell<Option<T>>>,
+}
+
+impl<'a, T: 'a> Task<'a> for Running<'a, T> {
+    fn step(self: Box<Self>, pending: &mut Pending<'a>) -> Option<Box<dyn Task<'a> + 'a>> {
+        let Running { trampoline, slot } = *self;
+        match trampoline.step {
+            Step::Done(value) => {
+                slot.set(Some(value));
+                pending.pop().map(|resume| resume())
+            }
+            Step::Call(f) => Some(Box::new(Running {
+                trampoline: f(),
+                slot,
+            })),
+            Step::Bind(bind) => Some(bind.start(slot, pending)),
+        }
+    }
+}
+
+// The erased form of `Bound`, which hides the type of the first value.
+trait Bind<'a, T> {
+    // Defers the continuation until the first trampoline is done, and returns that as the task
+    // to run next.
+    fn start(
+        self: Box<Self>,
+        slot: Rc<Cell<Option<T>>>,
+        pending: &mut Pending<'a>,
+    ) -> Box<dyn Task<'a> + 'a>;
+}
+
+struct Bound<'a, T, F> {
+    first: Trampoline<'a, T>,
+    then: F,
+}
+
+impl<'a, T: 'a, U: 'a, F> Bind<'a, U> for Bound<'a, T, F>
+where F: FnOnce(T) -> Trampoline<'a, U> + 'a
+{
+    fn start(
+        self: Box<Self>,
+        slot: Rc<Cell<Option<U>>>,
+        pending: &mut Pending<'a>,
+    ) -> Box<dyn Task<'a> + 'a> {
+        let Bound { first, then } = *self;
+        let first_slot = Rc::new(Cell::new(None));
+        let value = first_slot.clone();
+        pending.push(Box::new(move || {
+            let value = value
+                .take()
+                .expect("the first trampoline stores its value before it is resumed");
+            Box::new(Running {
+                trampoline: then(value),
+                slot,
+            })
+        }));
+        Box::new(Running {
+            trampoline: first,
+            slot: first_slot,
+        })
+    }
+}
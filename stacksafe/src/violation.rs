@@ -0,0 +1,109 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::panic::Location;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// An access to a [`StackSafe`](crate::StackSafe) value outside of a stack-safe context.
+///
+/// See [`set_violation_handler`](crate::set_violation_handler).
+#[derive(Clone, Copy, Debug)]
+pub struct Violation {
+    type_name: &'static str,
+    location: &'static Location<'static>,
+    remaining_stack: Option<usize>,
+}
+
+impl Violation {
+    /// Returns the name of the wrapped type that was accessed.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns where the access happened.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// Returns how many bytes of stack were left at the time of the access, if the violation
+    /// was detected by checking the remaining stack space.
+    ///
+    /// This is only the case with the `verify-stack` feature.
+    pub fn remaining_stack(&self) -> Option<usize> {
+        self.remaining_stack
+    }
+}
+
+/// What to do about a [`Violation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ViolationAction {
+    /// Panic at the location of the access. This is what happens without a handler.
+    Panic,
+    /// Let the access proceed.
+    Ignore,
+}
+
+pub(crate) type Handler = Box<dyn Fn(&Violation) -> ViolationAction + Send + Sync>;
+
+static HANDLER: RwLock<Option<Handler>> = RwLock::new(None);
+
+// The number of violations reported since the process started or the count was last reset.
+static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn set_violation_handler(handler: Option<Handler>) {
+    *HANDLER.write().unwrap_or_else(|e| e.into_inner()) = handler;
+}
+
+pub(crate) fn count() -> usize {
+    COUNT.load(Ordering::Relaxed)
+}
+
+pub(crate) fn reset_count() {
+    COUNT.store(0, Ordering::Relaxed);
+}
+
+#[cold]
+#[track_caller]
+pub(crate) fn report(type_name: &'static str, remaining_stack: Option<usize>) {
+    COUNT.fetch_add(1, Ordering::Relaxed);
+    let violation = Violation {
+        type_name,
+        location: Location::caller(),
+        remaining_stack,
+    };
+    // Violations raised by hooks themselves are ignored rather than reentering the handler.
+    let action =
+        crate::hook::run(
+            || match HANDLER.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                Some(handler) => handler(&violation),
+                None => ViolationAction::Panic,
+            },
+        )
+        .unwrap_or(ViolationAction::Ignore);
+    if action != ViolationAction::Panic {
+        return;
+    }
+    match remaining_stack {
+        Some(remaining) => panic!(
+            "`StackSafe` accessed with only {remaining} bytes of stack left\n\
+            help: add `#[stacksafe::stacksafe]` to the recursive functions leading to this access"
+        ),
+        None => panic!(
+            "`StackSafe` should only be accessed within a stack-safe context\n\
+            help: add `#[stacksafe::stacksafe]` to the function containing this access"
+        ),
+    }
+}
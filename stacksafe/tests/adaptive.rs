@@ -0,0 +1,55 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::stacksafe;
+
+const FRAME: usize = 256 * 1024;
+
+#[stacksafe]
+fn fat(n: u64) -> u64 {
+    let mut buf = [0u8; FRAME];
+    std::hint::black_box(&mut buf);
+    if n == 0 {
+        buf[FRAME - 1] as u64
+    } else {
+        1 + std::hint::black_box(fat(n - 1)) + buf[0] as u64
+    }
+}
+
+#[stacksafe]
+fn thin(n: u64) -> usize {
+    if n == 0 {
+        stacksafe::current_segment().map_or(0, |segment| segment.depth())
+    } else {
+        std::hint::black_box(thin(n - 1))
+    }
+}
+
+#[test]
+fn adaptive_red_zone() {
+    assert!(!stacksafe::get_adaptive_red_zone());
+    stacksafe::set_adaptive_red_zone(true);
+    assert!(stacksafe::get_adaptive_red_zone());
+
+    // Frames larger than the configured minimum would overflow without adaptation.
+    std::thread::spawn(|| assert_eq!(fat(100), 100))
+        .join()
+        .unwrap();
+
+    // Small frames still grow the stack when needed.
+    assert!(std::thread::spawn(|| thin(200_000)).join().unwrap() > 0);
+
+    stacksafe::set_adaptive_red_zone(false);
+    assert!(!stacksafe::get_adaptive_red_zone());
+}
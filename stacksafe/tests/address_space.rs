@@ -0,0 +1,97 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(unix)]
+
+use stacksafe::Error;
+use stacksafe::stacksafe;
+
+const SEGMENTS: usize = 16;
+const SEGMENT_SIZE: usize = 1 << 20;
+
+/// Returns the address of a local variable at the bottom of `n` nested calls.
+#[stacksafe]
+fn bottom(n: u64) -> usize {
+    if n == 0 {
+        let local = 0u8;
+        std::hint::black_box(&local) as *const u8 as usize
+    } else {
+        bottom(n - 1)
+    }
+}
+
+#[stacksafe]
+fn depth(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+
+#[cfg(target_os = "linux")]
+#[stacksafe]
+fn mappings_at_bottom(n: u64) -> usize {
+    if n == 0 {
+        mappings()
+    } else {
+        mappings_at_bottom(n - 1)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn mappings() -> usize {
+    std::fs::read_to_string("/proc/self/maps")
+        .unwrap()
+        .lines()
+        .count()
+}
+
+// The reserved range is shared by the whole process, so everything is checked in a single test
+// that no other test can interfere with.
+#[test]
+fn test_reserved_address_space() {
+    stacksafe::set_stack_allocation_size(SEGMENT_SIZE);
+    stacksafe::reserve_address_space(SEGMENTS, SEGMENT_SIZE).unwrap();
+    assert!(matches!(
+        stacksafe::reserve_address_space(SEGMENTS, SEGMENT_SIZE),
+        Err(Error::Unsupported { .. })
+    ));
+
+    // Growing into a slot does not map any memory.
+    #[cfg(target_os = "linux")]
+    {
+        // Warm up first, so that lazily initialized state is in place.
+        mappings_at_bottom(20_000);
+        let before = mappings();
+        assert_eq!(mappings_at_bottom(20_000), before);
+    }
+
+    // Released slots are reused right away.
+    let first = bottom(20_000);
+    for _ in 0..10 {
+        assert_eq!(bottom(20_000), first);
+    }
+
+    // Once every slot is in use, segments are mapped as usual.
+    let n = 1_000_000;
+    let threads: Vec<_> = (0..4)
+        .map(|_| std::thread::spawn(move || depth(n)))
+        .collect();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), n);
+    }
+
+    // So are segments larger than a slot.
+    assert_eq!(
+        stacksafe::on_new_stack(4 * SEGMENT_SIZE, || depth(1000)),
+        1000
+    );
+}
@@ -0,0 +1,62 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::Arbitrary;
+use arbitrary::Unstructured;
+use stacksafe::StackSafe;
+use stacksafe::stacksafe;
+
+enum List {
+    Nil,
+    Cons(StackSafe<Box<List>>),
+}
+
+impl<'a> Arbitrary<'a> for List {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        if u.arbitrary()? {
+            Ok(List::Cons(u.arbitrary()?))
+        } else {
+            Ok(List::Nil)
+        }
+    }
+}
+
+#[stacksafe]
+fn len(list: &List) -> usize {
+    match list {
+        List::Nil => 0,
+        List::Cons(next) => 1 + len(next),
+    }
+}
+
+#[test]
+fn test_arbitrary_deep() {
+    let data = vec![1; 100_000];
+    let list = List::arbitrary(&mut Unstructured::new(&data)).unwrap();
+    assert_eq!(len(&list), 100_000);
+
+    let list = List::arbitrary_take_rest(Unstructured::new(&data)).unwrap();
+    assert_eq!(len(&list), 100_000);
+}
+
+#[test]
+fn test_arbitrary_transparent() {
+    let data = [1, 2, 3, 4];
+    let value = StackSafe::<u32>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+    let expected = u32::arbitrary(&mut Unstructured::new(&data)).unwrap();
+    assert!(value == StackSafe::new(expected));
+    assert_eq!(StackSafe::<u32>::size_hint(0), u32::size_hint(0));
+}
@@ -0,0 +1,84 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![deny(missing_docs)]
+
+//! Checks that `#[stacksafe]` leaves the attributes of a function in place.
+
+use stacksafe::stacksafe;
+
+/// Returns the label of the segment that `n` nested calls grow into, if any.
+#[stacksafe]
+#[must_use]
+pub fn labeled(n: u64) -> Option<&'static str> {
+    if n == 0 {
+        stacksafe::current_segment().map(|segment| segment.label())
+    } else {
+        labeled(n - 1)
+    }
+}
+
+/// Like [`labeled`], but with a label of its own.
+#[stacksafe(name = "renamed")]
+pub fn named(n: u64) -> Option<&'static str> {
+    if n == 0 {
+        stacksafe::current_segment().map(|segment| segment.label())
+    } else {
+        named(n - 1)
+    }
+}
+
+#[deprecated(note = "use `labeled`")]
+#[stacksafe]
+fn deprecated(n: u64) -> u64 {
+    n
+}
+
+#[stacksafe]
+#[inline(never)]
+#[cfg_attr(all(), doc(alias = "triple"))]
+#[allow(clippy::identity_op)]
+fn custom(n: u64) -> u64 {
+    n * 3 + 0
+}
+
+#[cfg(any())]
+#[stacksafe]
+fn removed() -> u64 {
+    compile_error!("`#[cfg]` applies before the expansion")
+}
+
+#[test]
+fn test_label_from_identifier() {
+    assert_eq!(labeled(1_000_000), Some("attributes::labeled"));
+}
+
+#[test]
+fn test_label_from_name() {
+    assert_eq!(named(1_000_000), Some("attributes::renamed"));
+}
+
+#[test]
+fn test_attributes_preserved() {
+    // Both expectations go unfulfilled, and fail the build under `-D warnings`, if the attributes
+    // are lost in the expansion.
+    #[expect(unused_must_use)]
+    {
+        labeled(0);
+    }
+    #[expect(deprecated)]
+    let n = deprecated(1);
+    assert_eq!(n, 1);
+    assert_eq!(custom(2), 6);
+}
@@ -0,0 +1,109 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::StackSafe;
+use stacksafe::backtrack;
+
+#[test]
+fn test_exploration_order() {
+    // Explores a binary tree of depth 3, recording the visited paths.
+    let mut visited = Vec::new();
+    let ret: Option<()> = backtrack(String::new(), |path, choices| {
+        if path.len() < 3 {
+            choices.push(format!("{path}0"));
+            choices.push(format!("{path}1"));
+        }
+        visited.push(path);
+        None
+    });
+    assert!(ret.is_none());
+
+    let mut expected = vec![String::new()];
+    for depth in 1..=3 {
+        expected.extend((0..1 << depth).map(|i| format!("{i:0depth$b}")));
+    }
+    // Depth first, in the order the choices were pushed, like a recursive search.
+    expected.sort();
+    assert_eq!(visited, expected);
+}
+
+#[test]
+fn test_deep_search() {
+    // Finds a subset of the first 200_000 numbers that sums up to 3, trying to take each number
+    // before leaving it out.
+    const N: u64 = 200_000;
+    let mut steps = 0;
+    let ret = backtrack(
+        (0, 0, Vec::new()),
+        |(i, sum, taken): (u64, u64, Vec<u64>), choices| {
+            steps += 1;
+            if sum == 3 && i == N {
+                return Some(taken);
+            }
+            if i < N {
+                if sum + i <= 3 {
+                    let mut with = taken.clone();
+                    with.push(i);
+                    choices.push((i + 1, sum + i, with));
+                }
+                choices.push((i + 1, sum, taken));
+            }
+            None
+        },
+    );
+    assert_eq!(ret.unwrap(), [0, 1, 2]);
+    assert!(steps > N);
+}
+
+#[test]
+fn test_cut() {
+    let mut visited = Vec::new();
+    let ret = backtrack(0, |n, choices| {
+        visited.push(n);
+        match n {
+            0 => {
+                choices.push(1);
+                choices.push(2);
+            }
+            1 => {
+                choices.push(3);
+                choices.cut();
+                choices.push(4);
+                choices.push(5);
+            }
+            5 => return Some(n),
+            _ => {}
+        }
+        None
+    });
+    assert_eq!(ret, Some(5));
+    // The cut discarded 2 and 3.
+    assert_eq!(visited, [0, 1, 4, 5]);
+}
+
+#[test]
+fn test_step_is_stack_safe() {
+    let value = StackSafe::new(vec![1, 2, 3]);
+    let ret = backtrack(0, |i, choices| {
+        // Accessing the value panics in debug builds outside of a stack-safe context.
+        if value[i] == 3 {
+            return Some(i);
+        }
+        choices.extend([i + 1]);
+        assert_eq!(choices.len(), 1);
+        assert!(!choices.is_empty());
+        None
+    });
+    assert_eq!(ret, Some(2));
+}
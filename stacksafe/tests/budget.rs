@@ -0,0 +1,61 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::CallBudget;
+use stacksafe::Error;
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn recurse(n: u64, budget: &CallBudget) -> Result<u64, Error> {
+    let _frame = budget.enter()?;
+    if n == 0 {
+        Ok(budget.depth() as u64)
+    } else {
+        recurse(n - 1, budget)
+    }
+}
+
+#[test]
+fn test_budget_limits_depth() {
+    let budget = CallBudget::new(100_000);
+    assert_eq!(recurse(99_999, &budget).unwrap(), 100_000);
+    assert_eq!(budget.depth(), 0);
+    assert_eq!(budget.remaining(), 100_000);
+
+    let err = recurse(100_000, &budget).unwrap_err();
+    assert!(matches!(err, Error::BudgetExceeded { budget: 100_000 }));
+    assert_eq!(err.to_string(), "the call budget of 100000 was exceeded");
+    // All frames are released on the way out.
+    assert_eq!(budget.depth(), 0);
+    assert_eq!(budget.max_frames(), 100_000);
+}
+
+#[test]
+fn test_frames_are_released_on_drop() {
+    let budget = CallBudget::new(2);
+    let first = budget.enter().unwrap();
+    let second = budget.enter().unwrap();
+    assert_eq!(budget.remaining(), 0);
+    assert!(budget.enter().is_err());
+
+    drop(first);
+    assert_eq!(budget.depth(), 1);
+    let third = budget.enter().unwrap();
+    assert_eq!(budget.depth(), 2);
+    drop((second, third));
+    assert_eq!(budget.depth(), 0);
+
+    let budget = CallBudget::new(0);
+    assert!(budget.enter().is_err());
+}
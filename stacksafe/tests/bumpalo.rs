@@ -0,0 +1,144 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "bumpalo")]
+
+use bumpalo::Bump;
+use stacksafe::StackSafe;
+use stacksafe::bumpalo::CloneIn;
+use stacksafe::bumpalo::CloneIntoArena;
+use stacksafe::stacksafe;
+
+#[derive(Debug, PartialEq)]
+enum List<'bump> {
+    Nil,
+    Cons(u64, Option<StackSafe<&'bump mut List<'bump>>>),
+}
+
+impl<'bump> CloneIn<'bump> for List<'bump> {
+    fn clone_in(&self, bump: &'bump Bump) -> Self {
+        match self {
+            List::Nil => List::Nil,
+            List::Cons(value, tail) => List::Cons(*value, tail.clone_in(bump)),
+        }
+    }
+}
+
+fn build(bump: &Bump, n: u64) -> List<'_> {
+    (0..n).fold(List::Nil, |tail, i| {
+        List::Cons(i, Some(StackSafe::new_in(tail, bump)))
+    })
+}
+
+#[stacksafe]
+fn sum(list: &List) -> u64 {
+    match list {
+        List::Nil => 0,
+        List::Cons(value, tail) => value + tail.as_ref().map_or(0, |tail| sum(tail)),
+    }
+}
+
+#[stacksafe]
+fn increment(list: &mut List) {
+    if let List::Cons(value, tail) = list {
+        *value += 1;
+        if let Some(tail) = tail {
+            increment(tail);
+        }
+    }
+}
+
+#[test]
+fn test_deep_arena_list() {
+    let bump = Bump::new();
+    let other = Bump::new();
+    let list = build(&bump, 1_000_000);
+    assert_eq!(sum(&list), 499_999_500_000);
+
+    let mut copy = list.clone_in(&other);
+    assert_eq!(copy, list);
+
+    increment(&mut copy);
+    assert_eq!(sum(&copy), 500_000_500_000);
+    assert_eq!(sum(&list), 499_999_500_000);
+}
+
+#[test]
+fn test_drop_outside_context() {
+    // Arena nodes have nothing to drop, so no stack-safe context is needed.
+    let bump = Bump::new();
+    let node = StackSafe::new_in(List::Nil, &bump);
+    drop(node);
+    drop(build(&bump, 1_000_000));
+}
+
+struct Tree {
+    value: u64,
+    children: Vec<StackSafe<Box<Tree>>>,
+    next: Option<StackSafe<Box<Tree>>>,
+}
+
+struct ArenaTree<'bump> {
+    value: u64,
+    children: &'bump mut [StackSafe<&'bump mut ArenaTree<'bump>>],
+    next: Option<StackSafe<&'bump mut ArenaTree<'bump>>>,
+}
+
+impl<'bump> CloneIntoArena<'bump> for Tree {
+    type Output = ArenaTree<'bump>;
+
+    fn clone_into_arena(&self, bump: &'bump Bump) -> ArenaTree<'bump> {
+        ArenaTree {
+            value: self.value,
+            children: self.children.clone_into_arena(bump),
+            next: self.next.clone_into_arena(bump),
+        }
+    }
+}
+
+#[stacksafe]
+fn arena_sum(tree: &ArenaTree) -> u64 {
+    tree.value
+        + tree.children.iter().map(|c| arena_sum(c)).sum::<u64>()
+        + tree.next.as_ref().map_or(0, |next| arena_sum(next))
+}
+
+#[test]
+fn test_clone_into_arena() {
+    let leaf = |value| {
+        StackSafe::new(Box::new(Tree {
+            value,
+            children: Vec::new(),
+            next: None,
+        }))
+    };
+    let chain = (0..1_000_000).fold(None, |next, value| {
+        Some(StackSafe::new(Box::new(Tree {
+            value,
+            children: vec![leaf(1), leaf(2)],
+            next,
+        })))
+    });
+
+    let bump = Bump::new();
+    let tree = Tree {
+        value: 0,
+        children: vec![leaf(7)],
+        next: chain,
+    };
+    let copy = stacksafe::bumpalo::clone_into_arena(&tree, &bump);
+    assert_eq!(copy.value, 0);
+    assert_eq!(copy.children.len(), 1);
+    assert_eq!(arena_sum(&copy), 499_999_500_000 + 3_000_000 + 7);
+}
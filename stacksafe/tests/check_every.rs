@@ -0,0 +1,106 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::stacksafe;
+
+#[stacksafe(check_every = 64)]
+fn count(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + count(n - 1) }
+}
+
+#[stacksafe(check_every = 16)]
+fn padded(n: u64) -> u64 {
+    let buffer = [0u8; 2048];
+    if n == 0 {
+        0
+    } else {
+        1 + padded(n - 1) + u64::from(std::hint::black_box(buffer)[0])
+    }
+}
+
+#[stacksafe(check_every = 8)]
+fn is_even(n: u64) -> bool {
+    if n == 0 { true } else { is_odd(n - 1) }
+}
+
+#[stacksafe(check_every = 8)]
+fn is_odd(n: u64) -> bool {
+    if n == 0 { false } else { is_even(n - 1) }
+}
+
+#[stacksafe(check_every = 64)]
+fn small(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + large(n - 1) }
+}
+
+#[stacksafe(check_every = 64)]
+fn large(n: u64) -> u64 {
+    let buffer = [0u8; 16 * 1024];
+    if n == 0 {
+        0
+    } else {
+        1 + small(n - 1) + u64::from(std::hint::black_box(buffer)[0])
+    }
+}
+
+#[stacksafe(check_every = 1_000_000)]
+fn huge_interval(n: u64) -> u64 {
+    let buffer = [0u8; 1024];
+    if n == 0 {
+        0
+    } else {
+        1 + huge_interval(n - 1) + u64::from(std::hint::black_box(buffer)[0])
+    }
+}
+
+#[stacksafe(check_every = 1)]
+fn every(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + every(n - 1) }
+}
+
+#[stacksafe(check_every = 32)]
+fn protected() -> bool {
+    stacksafe::internal::is_protected()
+}
+
+#[test]
+fn test_check_every() {
+    assert_eq!(count(1_000_000), 1_000_000);
+    assert_eq!(padded(10_000), 10_000);
+    assert!(is_even(1_000_000));
+    assert_eq!(every(1_000_000), 1_000_000);
+
+    // Calls that skip the check still run in a stack-safe context.
+    for _ in 0..100 {
+        assert!(protected());
+    }
+}
+
+#[test]
+fn test_check_every_small_stack() {
+    std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(|| {
+            assert_eq!(count(100_000), 100_000);
+            assert_eq!(padded(1_000), 1_000);
+            // The functions share no countdown, so the small frames of one do not hide the large
+            // frames of the other.
+            assert_eq!(small(1_000), 1_000);
+            // Calls whose interval does not fit into the stack check every time.
+            assert_eq!(huge_interval(10_000), 10_000);
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
@@ -0,0 +1,64 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::internal::stacker;
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn outer(n: u64) -> u64 {
+    if n == 0 { 0 } else { middle(n) }
+}
+
+#[stacksafe]
+fn middle(n: u64) -> u64 {
+    inner(n)
+}
+
+#[stacksafe]
+fn inner(n: u64) -> u64 {
+    1 + outer(n - 1)
+}
+
+#[test]
+fn test_nested_guarded_calls() {
+    let n = 300_000;
+    assert_eq!(outer(n), n);
+}
+
+#[test]
+fn test_small_thread_stack() {
+    let n = 100_000;
+    let depth = std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(move || outer(n))
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(depth, n);
+}
+
+#[stacksafe]
+fn on_foreign_segments(rounds: usize, n: u64) -> u64 {
+    // Each round runs on a segment switched to behind this crate's back, which may reuse the
+    // memory of the previous one while the window marked on the original stack is still alive.
+    (0..rounds)
+        .map(|_| stacker::grow(64 * 1024, || outer(n)))
+        .sum()
+}
+
+#[test]
+fn test_foreign_segments() {
+    let n = 20_000;
+    assert_eq!(on_foreign_segments(8, n), 8 * n);
+}
@@ -0,0 +1,178 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use stacksafe::stacksafe;
+
+struct Tree {
+    value: u32,
+    children: Vec<Tree>,
+}
+
+impl Tree {
+    fn chain(depth: u32) -> Tree {
+        let mut tree = Tree {
+            value: 0,
+            children: Vec::new(),
+        };
+        for value in 1..=depth {
+            tree = Tree {
+                value,
+                children: vec![tree],
+            };
+        }
+        tree
+    }
+
+    #[stacksafe]
+    fn sum(&self) -> u64 {
+        let value = || u64::from(self.value);
+        value() + self.children.iter().map(|c| c.sum()).sum::<u64>()
+    }
+
+    #[stacksafe]
+    fn visit(&self, f: &mut dyn FnMut(u32)) {
+        f(self.value);
+        self.children.iter().for_each(|c| c.visit(&mut *f));
+    }
+
+    #[stacksafe]
+    fn scale(&mut self, k: u32) {
+        let apply = |t: &mut Tree| t.value *= k;
+        apply(self);
+        self.children.iter_mut().for_each(|c| c.scale(k));
+    }
+
+    #[stacksafe]
+    fn find(&self, value: u32) -> Option<&Tree> {
+        let here = || (self.value == value).then_some(self);
+        here().or_else(|| self.children.iter().find_map(|c| c.find(value)))
+    }
+
+    #[stacksafe]
+    fn values_mut(&mut self) -> Vec<&mut u32> {
+        let mut values = vec![&mut self.value];
+        values.extend(self.children.iter_mut().flat_map(|c| c.values_mut()));
+        values
+    }
+
+    #[stacksafe]
+    fn into_values(self) -> Vec<u32> {
+        let Tree { value, children } = self;
+        let rest = move || children.into_iter().flat_map(|c| c.into_values());
+        std::iter::once(value).chain(rest()).collect()
+    }
+
+    #[stacksafe]
+    fn adder(&self) -> impl Fn(&u32) -> u32 + '_ {
+        move |x| x + self.value
+    }
+
+    #[stacksafe]
+    fn counter(&self) -> impl FnMut() -> u32 + '_ {
+        let mut n = 0;
+        move || {
+            n += self.value;
+            n
+        }
+    }
+}
+
+#[stacksafe]
+fn extend<'a>(values: &'a mut Vec<u32>, extra: &[u32]) -> &'a u32 {
+    let mut push = |x| values.push(x);
+    extra.iter().copied().for_each(&mut push);
+    let last = || values.last().unwrap();
+    last()
+}
+
+#[test]
+fn test_methods_with_closures() {
+    let mut tree = Tree::chain(10_000);
+    assert_eq!(tree.sum(), 50_005_000);
+
+    let mut count = 0;
+    tree.visit(&mut |_| count += 1);
+    assert_eq!(count, 10_001);
+
+    tree.scale(2);
+    assert_eq!(tree.sum(), 100_010_000);
+    assert_eq!(tree.find(2).map(|t| t.children.len()), Some(1));
+    assert!(tree.find(3).is_none());
+
+    for value in tree.values_mut() {
+        *value += 1;
+    }
+    assert_eq!(tree.adder()(&1), 20_002);
+    let mut counter = tree.counter();
+    counter();
+    assert_eq!(counter(), 40_002);
+    drop(counter);
+
+    let values = tree.into_values();
+    assert_eq!(values.len(), 10_001);
+    assert_eq!(values[..3], [20_001, 19_999, 19_997]);
+}
+
+#[test]
+fn test_borrowed_arguments() {
+    let mut values = vec![1];
+    assert_eq!(*extend(&mut values, &[2, 3]), 3);
+    assert_eq!(values, [1, 2, 3]);
+}
+
+struct Probe(Rc<Cell<Option<bool>>>);
+
+impl Drop for Probe {
+    fn drop(&mut self) {
+        self.0.set(Some(stacksafe::internal::is_protected()));
+    }
+}
+
+struct Pair {
+    value: u32,
+    _probe: Probe,
+}
+
+impl Pair {
+    #[stacksafe]
+    fn value(self) -> u32 {
+        self.value
+    }
+}
+
+#[stacksafe]
+fn value((pair, _): (Pair, u32)) -> u32 {
+    pair.value
+}
+
+#[test]
+fn test_arguments_are_captured_whole() {
+    let dropped = Rc::new(Cell::new(None));
+    let pair = Pair {
+        value: 1,
+        _probe: Probe(dropped.clone()),
+    };
+    assert_eq!(pair.value(), 1);
+    assert_eq!(dropped.take(), Some(true));
+
+    let pair = Pair {
+        value: 2,
+        _probe: Probe(dropped.clone()),
+    };
+    assert_eq!(value((pair, 0)), 2);
+    assert_eq!(dropped.take(), Some(true));
+}
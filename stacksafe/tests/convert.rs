@@ -0,0 +1,81 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::StackSafe;
+use stacksafe::stacksafe;
+
+enum Ast {
+    Num(i64),
+    Add(Box<Ast>, Box<Ast>),
+}
+
+#[derive(Debug)]
+enum Ir {
+    Num(u32),
+    Add(StackSafe<Box<Ir>>, StackSafe<Box<Ir>>),
+}
+
+impl TryFrom<&Ast> for Ir {
+    type Error = String;
+
+    fn try_from(ast: &Ast) -> Result<Self, String> {
+        match ast {
+            Ast::Num(n) => u32::try_from(*n)
+                .map(Ir::Num)
+                .map_err(|_| format!("{n} is out of range")),
+            Ast::Add(lhs, rhs) => Ok(Ir::Add(
+                StackSafe::try_boxed(&**lhs)?,
+                StackSafe::try_boxed(&**rhs)?,
+            )),
+        }
+    }
+}
+
+impl TryFrom<Box<Ir>> for u64 {
+    type Error = String;
+
+    fn try_from(ir: Box<Ir>) -> Result<Self, String> {
+        match *ir {
+            Ir::Num(n) => Ok(u64::from(n)),
+            Ir::Add(lhs, rhs) => Ok(lhs.try_into_value::<u64>()? + rhs.try_into_value::<u64>()?),
+        }
+    }
+}
+
+fn chain(depth: u64, last: i64) -> Ast {
+    (0..depth).fold(Ast::Num(last), |ast, _| {
+        Ast::Add(Box::new(Ast::Num(1)), Box::new(ast))
+    })
+}
+
+#[stacksafe]
+fn drop_ast(ast: Ast) {
+    if let Ast::Add(_, rhs) = ast {
+        drop_ast(*rhs);
+    }
+}
+
+#[test]
+fn test_deep_conversion() {
+    let ast = chain(100_000, 1);
+    let ir = StackSafe::<Box<Ir>>::try_boxed(&ast).unwrap();
+    assert_eq!(ir.try_into_value::<u64>().unwrap(), 100_001);
+    drop_ast(ast);
+
+    let ast = chain(100_000, -1);
+    let err = StackSafe::<Ir>::try_from_value(&ast).unwrap_err();
+    assert!(StackSafe::<Ir>::try_from_value(&Ast::Num(2)).is_ok());
+    assert_eq!(err, "-1 is out of range");
+    drop_ast(ast);
+}
@@ -0,0 +1,64 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::StackSafe;
+use stacksafe::StackSafeCow;
+use stacksafe::stacksafe;
+
+#[derive(Debug, Clone, PartialEq)]
+enum List {
+    Nil,
+    Cons(u64, StackSafe<Box<List>>),
+}
+
+fn build(n: u64) -> List {
+    (0..n).fold(List::Nil, |tail, i| List::Cons(i, StackSafe::boxed(tail)))
+}
+
+#[stacksafe]
+fn increment_head(list: &mut StackSafeCow<List>) {
+    if let List::Cons(head, _) = list.to_mut() {
+        *head += 1;
+    }
+}
+
+#[stacksafe]
+fn head(list: &StackSafeCow<List>) -> Option<u64> {
+    match &**list {
+        List::Nil => None,
+        List::Cons(head, _) => Some(*head),
+    }
+}
+
+#[test]
+fn test_borrowed_until_mutated() {
+    let list = build(10);
+    let mut cow = StackSafeCow::borrowed(&list);
+    assert!(cow.is_borrowed());
+    assert_eq!(cow, StackSafeCow::owned(build(10)));
+
+    increment_head(&mut cow);
+    assert!(cow.is_owned());
+    assert_eq!(head(&cow), Some(10));
+    assert_eq!(head(&StackSafeCow::borrowed(&list)), Some(9));
+}
+
+#[test]
+fn test_deep_clone_on_write() {
+    let list = build(1_000_000);
+    let mut cow = StackSafeCow::from(&list);
+    increment_head(&mut cow);
+    let owned = cow.clone().into_owned();
+    assert_eq!(head(&StackSafeCow::owned(owned)), Some(1_000_000));
+}
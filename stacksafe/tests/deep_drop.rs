@@ -0,0 +1,116 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::DeepDrop;
+use stacksafe::StackSafe;
+
+// Stands in for a type from another crate.
+mod foreign {
+    pub struct Node {
+        pub value: u64,
+        pub next: Option<Box<Node>>,
+    }
+}
+
+impl DeepDrop for foreign::Node {
+    fn deep_drop(self) {
+        self.next.deep_drop();
+    }
+}
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+struct Counted;
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// The derive requires type parameters to implement the trait, like the standard derives.
+impl DeepDrop for Counted {
+    fn deep_drop(self) {}
+}
+
+#[derive(DeepDrop)]
+enum Tree<T> {
+    Leaf(#[deep_drop(skip)] T),
+    Node {
+        children: Vec<Tree<T>>,
+        label: Option<String>,
+    },
+    Pair(Box<Tree<T>>, StackSafe<Box<Tree<T>>>),
+}
+
+#[derive(DeepDrop)]
+struct Root {
+    tree: Tree<Counted>,
+    #[deep_drop(skip)]
+    extra: Counted,
+}
+
+fn chain(n: u64) -> Option<Box<foreign::Node>> {
+    (0..n).fold(None, |next, value| {
+        Some(Box::new(foreign::Node { value, next }))
+    })
+}
+
+#[test]
+fn test_foreign_list() {
+    let list = chain(1_000_000);
+    assert_eq!(list.as_ref().unwrap().value, 999_999);
+    stacksafe::drop_deep(list);
+}
+
+#[test]
+fn test_derive() {
+    let before = DROPPED.load(Ordering::Relaxed);
+    let tree = (0..100_000).fold(Tree::Leaf(Counted), |tree, i| match i % 3 {
+        0 => Tree::Node {
+            children: vec![tree, Tree::Leaf(Counted)],
+            label: Some(i.to_string()),
+        },
+        1 => Tree::Pair(Box::new(tree), StackSafe::boxed(Tree::Leaf(Counted))),
+        _ => Tree::Pair(Box::new(Tree::Leaf(Counted)), StackSafe::boxed(tree)),
+    });
+    stacksafe::drop_deep(Root {
+        tree,
+        extra: Counted,
+    });
+    assert_eq!(DROPPED.load(Ordering::Relaxed) - before, 100_002);
+}
+
+#[test]
+fn test_tuples() {
+    let value = (
+        chain(1_000_000),
+        1u8,
+        (String::new(), vec![chain(1_000_000)]),
+    );
+    stacksafe::drop_deep(value);
+}
+
+#[test]
+#[cfg(feature = "serde_json")]
+fn test_json_value() {
+    let mut value = serde_json::Value::Null;
+    for _ in 0..1_000_000 {
+        value = serde_json::Value::Array(vec![value]);
+    }
+    stacksafe::drop_deep(value);
+}
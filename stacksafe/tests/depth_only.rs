@@ -0,0 +1,75 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::panic::catch_unwind;
+
+use stacksafe::stacksafe;
+
+#[stacksafe(depth_only, max_depth = 1000)]
+fn nest(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + nest(n - 1) }
+}
+
+#[stacksafe(depth_only)]
+fn unlimited(n: u64) -> u64 {
+    if n == 0 {
+        nest(0)
+    } else {
+        1 + unlimited(n - 1)
+    }
+}
+
+#[stacksafe(depth_only, max_depth = 10)]
+fn is_even(n: u64) -> bool {
+    if n == 0 { true } else { is_odd(n - 1) }
+}
+
+#[stacksafe(depth_only, max_depth = 10)]
+fn is_odd(n: u64) -> bool {
+    if n == 0 { false } else { is_even(n - 1) }
+}
+
+#[stacksafe(depth_only)]
+fn segment() -> Option<stacksafe::SegmentInfo> {
+    stacksafe::current_segment()
+}
+
+#[stacksafe]
+fn segment_at(n: u64) -> Option<stacksafe::SegmentInfo> {
+    if n == 0 { segment() } else { segment_at(n - 1) }
+}
+
+#[test]
+fn test_depth_only() {
+    assert_eq!(nest(999), 999);
+    let err = catch_unwind(|| nest(1000)).unwrap_err();
+    let message = err.downcast_ref::<String>().unwrap();
+    assert!(message.contains("the depth limit of 1000 was exceeded"));
+    assert!(message.contains("::nest"));
+
+    // The depth is restored after unwinding.
+    assert_eq!(nest(999), 999);
+
+    // Calls to other `depth_only` functions count towards the limit.
+    assert_eq!(unlimited(998), 998);
+    assert!(catch_unwind(|| unlimited(999)).is_err());
+
+    assert!(is_odd(9));
+    assert!(catch_unwind(|| is_even(10)).is_err());
+
+    // Depth-only functions never grow the stack, but guarded functions around them still do.
+    assert!(segment().is_none());
+    let outer = segment_at(1_000_000);
+    assert!(outer.is_none_or(|segment| segment.label().ends_with("::segment_at")));
+}
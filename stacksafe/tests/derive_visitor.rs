@@ -0,0 +1,118 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "derive-visitor")]
+
+use derive_visitor::Drive;
+use derive_visitor::DriveMut;
+use derive_visitor::Event;
+use derive_visitor::visitor_enter_fn_mut;
+use derive_visitor::visitor_fn;
+use stacksafe::StackSafe;
+
+#[derive(Drive, DriveMut)]
+struct Node {
+    #[drive(skip)]
+    value: u64,
+    children: Vec<StackSafe<Box<Node>>>,
+}
+
+#[derive(Drive, DriveMut)]
+struct Shallow {
+    #[drive(with = "stacksafe::derive_visitor::drive_shallow")]
+    root: StackSafe<Box<Node>>,
+}
+
+#[derive(Drive, DriveMut)]
+struct Deep {
+    #[drive(with = "stacksafe::derive_visitor::drive_deep")]
+    root: StackSafe<Box<Node>>,
+}
+
+fn chain(n: u64) -> StackSafe<Box<Node>> {
+    (0..n).fold(
+        StackSafe::new(Box::new(Node {
+            value: n,
+            children: Vec::new(),
+        })),
+        |child, value| {
+            StackSafe::new(Box::new(Node {
+                value,
+                children: vec![child],
+            }))
+        },
+    )
+}
+
+fn count_nodes<T: Drive>(value: &T) -> usize {
+    let mut count = 0;
+    value.drive(&mut visitor_fn(|_: &Node, event| {
+        if matches!(event, Event::Enter) {
+            count += 1;
+        }
+    }));
+    count
+}
+
+fn count_wrappers<T: Drive>(value: &T) -> usize {
+    let mut count = 0;
+    value.drive(&mut visitor_fn(|_: &StackSafe<Box<Node>>, event| {
+        if matches!(event, Event::Enter) {
+            count += 1;
+        }
+    }));
+    count
+}
+
+#[test]
+fn test_transparent() {
+    let root = chain(100_000);
+    assert_eq!(count_nodes(&root), 100_001);
+    assert_eq!(count_wrappers(&root), 0);
+
+    let mut root = root;
+    let mut sum = 0;
+    root.drive_mut(&mut visitor_enter_fn_mut(|node: &mut Node| {
+        node.value += 1;
+        sum += node.value;
+    }));
+    assert_eq!(sum, (1..=100_000).sum::<u64>() + 100_001);
+}
+
+#[test]
+fn test_shallow() {
+    let mut shallow = Shallow { root: chain(100) };
+    assert_eq!(count_nodes(&shallow), 0);
+    assert_eq!(count_wrappers(&shallow), 1);
+
+    // A rewriter can replace the whole subtree without descending into it.
+    shallow.drive_mut(&mut visitor_enter_fn_mut(
+        |root: &mut StackSafe<Box<Node>>| *root = chain(0),
+    ));
+    let deep = Deep { root: shallow.root };
+    assert_eq!(count_nodes(&deep), 1);
+}
+
+#[test]
+fn test_deep() {
+    let mut deep = Deep {
+        root: chain(100_000),
+    };
+    assert_eq!(count_nodes(&deep), 100_001);
+    assert_eq!(count_wrappers(&deep), 1);
+
+    let mut entered = 0;
+    deep.drive_mut(&mut visitor_enter_fn_mut(|_: &mut Node| entered += 1));
+    assert_eq!(entered, 100_001);
+}
@@ -0,0 +1,76 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::Dismantle;
+use stacksafe::IncrementalDrop;
+use stacksafe::StackSafe;
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+enum List {
+    Nil,
+    Cons(Counted, StackSafe<Box<List>>),
+}
+
+struct Counted;
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Dismantle for List {
+    fn dismantle(&mut self, children: &mut Vec<Self>) {
+        if let List::Cons(_, tail) = self {
+            children.push(std::mem::replace(&mut **tail, List::Nil));
+        }
+    }
+}
+
+fn build(n: usize) -> List {
+    (0..n).fold(List::Nil, |tail, _| {
+        List::Cons(Counted, StackSafe::boxed(tail))
+    })
+}
+
+#[test]
+fn test_incremental_drop() {
+    let mut garbage = IncrementalDrop::new(build(1_000_000));
+    let before = DROPPED.load(Ordering::SeqCst);
+
+    assert!(!garbage.step(1000));
+    assert_eq!(DROPPED.load(Ordering::SeqCst) - before, 1000);
+    assert_eq!(garbage.pending(), 1);
+
+    // 999_001 nodes (including the final `Nil`) are left, plus 11 deferred ones.
+    garbage.defer(build(10));
+    let mut steps = 0;
+    loop {
+        steps += 1;
+        if garbage.step(1000) {
+            break;
+        }
+    }
+    assert!(garbage.is_finished());
+    assert_eq!(steps, 1000);
+    assert_eq!(DROPPED.load(Ordering::SeqCst) - before, 1_000_010);
+
+    let garbage = IncrementalDrop::new(build(1_000_000));
+    drop(garbage);
+    assert_eq!(DROPPED.load(Ordering::SeqCst) - before, 2_000_010);
+}
@@ -0,0 +1,63 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::Dismantle;
+use stacksafe::StackSafe;
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+enum List {
+    Nil,
+    Cons(Counted, StackSafe<Box<List>>),
+}
+
+struct Counted;
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Dismantle for List {
+    fn dismantle(&mut self, children: &mut Vec<Self>) {
+        if let List::Cons(_, tail) = self {
+            children.push(std::mem::replace(&mut **tail, List::Nil));
+        }
+    }
+}
+
+fn build(n: usize) -> List {
+    (0..n).fold(List::Nil, |tail, _| {
+        List::Cons(Counted, StackSafe::boxed(tail))
+    })
+}
+
+#[test]
+fn test_drop_all() {
+    let before = DROPPED.load(Ordering::SeqCst);
+    let lists: Vec<_> = (0..100_000).map(|_| StackSafe::boxed(build(3))).collect();
+    stacksafe::drop_all(lists);
+    let deep: Vec<_> = (0..3).map(|_| StackSafe::new(build(300_000))).collect();
+    stacksafe::drop_all(deep);
+    assert_eq!(DROPPED.load(Ordering::SeqCst) - before, 1_200_000);
+
+    let before = DROPPED.load(Ordering::SeqCst);
+    let lists: Vec<_> = (0..10).map(|_| build(100_000)).collect();
+    stacksafe::drop_all_flattened(lists);
+    assert_eq!(DROPPED.load(Ordering::SeqCst) - before, 1_000_000);
+}
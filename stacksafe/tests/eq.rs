@@ -0,0 +1,193 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use stacksafe::IterativeEq;
+use stacksafe::StackSafe;
+
+#[derive(Debug, PartialEq)]
+enum Tree {
+    Leaf(u64),
+    Node(Vec<StackSafe<Box<Tree>>>),
+}
+
+impl IterativeEq for Tree {
+    fn eq_shallow<'a>(&'a self, other: &'a Self, pending: &mut Vec<(&'a Self, &'a Self)>) -> bool {
+        match (self, other) {
+            (Tree::Leaf(a), Tree::Leaf(b)) => a == b,
+            (Tree::Node(a), Tree::Node(b)) if a.len() == b.len() => {
+                pending.extend(a.iter().zip(b).map(|(a, b)| (&***a, &***b)));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn chain(depth: u64, leaf: u64) -> Tree {
+    (0..depth).fold(Tree::Leaf(leaf), |tree, i| {
+        Tree::Node(vec![
+            StackSafe::boxed(Tree::Leaf(i)),
+            StackSafe::boxed(tree),
+        ])
+    })
+}
+
+fn count_growth<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let grown = Rc::new(Cell::new(0));
+    let hook = {
+        let grown = grown.clone();
+        move || grown.set(grown.get() + 1)
+    };
+    let result = stacksafe::with_yield_hook(0, hook, f);
+    (result, grown.get())
+}
+
+fn count_calls<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let calls = Rc::new(Cell::new(0));
+    let hook = {
+        let calls = calls.clone();
+        move || calls.set(calls.get() + 1)
+    };
+    let result = stacksafe::with_yield_hook(1, hook, f);
+    (result, calls.get())
+}
+
+#[test]
+fn test_deep_eq_iterative() {
+    let (a, b, c) = (
+        chain(1_000_000, 0),
+        chain(1_000_000, 0),
+        chain(1_000_000, 1),
+    );
+
+    let (equal, grown) = count_growth(|| stacksafe::deep_eq_iterative(&a, &b));
+    assert!(equal);
+    assert_eq!(grown, 0);
+
+    let (equal, grown) = count_growth(|| stacksafe::deep_eq_iterative(&a, &c));
+    assert!(!equal);
+    assert_eq!(grown, 0);
+
+    // The recursive comparison agrees, but needs many segments.
+    let (equal, grown) = count_growth(|| a == b);
+    assert!(equal);
+    assert!(grown > 0);
+}
+
+#[test]
+fn test_shape_mismatch() {
+    let a = Tree::Node(vec![StackSafe::boxed(Tree::Leaf(1))]);
+    let b = Tree::Node(vec![]);
+    assert!(!stacksafe::deep_eq_iterative(&a, &b));
+    assert!(!stacksafe::deep_eq_iterative(&a, &Tree::Leaf(1)));
+    assert!(stacksafe::deep_eq_iterative(&a, &a));
+}
+
+#[test]
+fn test_map_eq_deep() {
+    let a: HashMap<u64, _> = (0..1000).map(|i| (i, StackSafe::new(i))).collect();
+    let mut b = a.clone();
+
+    // A single stack-safe context is established for the whole comparison.
+    let (equal, calls) = count_calls(|| stacksafe::map_eq_deep(&a, &b));
+    assert!(equal);
+    assert_eq!(calls, 1);
+    let (equal, calls) = count_calls(|| a == b);
+    assert!(equal);
+    assert_eq!(calls, 1000);
+
+    b.insert(999, StackSafe::new(0));
+    assert!(!stacksafe::map_eq_deep(&a, &b));
+    b.remove(&999);
+    assert!(!stacksafe::map_eq_deep(&a, &b));
+    b.insert(1000, StackSafe::new(999));
+    assert!(!stacksafe::map_eq_deep(&a, &b));
+
+    let deep = |leaf| HashMap::from([("tree", StackSafe::new(chain(100_000, leaf)))]);
+    assert!(stacksafe::map_eq_deep(&deep(0), &deep(0)));
+    assert!(!stacksafe::map_eq_deep(&deep(0), &deep(1)));
+}
+
+#[test]
+fn test_btree_map_eq_deep() {
+    let a: BTreeMap<u64, _> = (0..1000).map(|i| (i, StackSafe::new(i))).collect();
+    let mut b = a.clone();
+    let (equal, calls) = count_calls(|| stacksafe::btree_map_eq_deep(&a, &b));
+    assert!(equal);
+    assert_eq!(calls, 1);
+
+    b.insert(999, StackSafe::new(0));
+    assert!(!stacksafe::btree_map_eq_deep(&a, &b));
+    b.remove(&999);
+    b.insert(1000, StackSafe::new(999));
+    assert!(!stacksafe::btree_map_eq_deep(&a, &b));
+}
+
+#[test]
+fn test_slice_deep() {
+    let a: Vec<_> = (0..1000).map(StackSafe::new).collect();
+    let mut b = a.clone();
+    let (ordering, calls) = count_calls(|| stacksafe::slice_cmp_deep(&a, &b));
+    assert_eq!(ordering, Ordering::Equal);
+    assert_eq!(calls, 1);
+    assert!(stacksafe::slice_eq_deep(&a, &b));
+
+    b[500] = StackSafe::new(0);
+    assert!(!stacksafe::slice_eq_deep(&a, &b));
+    assert_eq!(stacksafe::slice_cmp_deep(&a, &b), Ordering::Greater);
+    assert_eq!(stacksafe::slice_cmp_deep(&a[..500], &b), Ordering::Less);
+    assert!(!stacksafe::slice_eq_deep(&a[..500], &b));
+
+    let a = [StackSafe::new(1.0), StackSafe::new(f64::NAN)];
+    let b = [StackSafe::new(2.0), StackSafe::new(0.0)];
+    assert_eq!(
+        stacksafe::slice_partial_cmp_deep(&a, &b),
+        Some(Ordering::Less)
+    );
+    assert_eq!(stacksafe::slice_partial_cmp_deep(&a, &a), None);
+
+    let deep = |leaf| vec![StackSafe::new(chain(100_000, leaf))];
+    assert!(stacksafe::slice_eq_deep(&deep(0), &deep(0)));
+    assert!(!stacksafe::slice_eq_deep(&deep(0), &deep(1)));
+}
+
+#[test]
+fn test_sort_deep() {
+    let keys: Vec<_> = (0..1000u64).map(|i| (i * 7919 % 1000, i)).collect();
+    let mut values: Vec<_> = keys.iter().copied().map(StackSafe::new).collect();
+    let mut sorted = keys.clone();
+    sorted.sort();
+    let expected: Vec<_> = sorted.iter().copied().map(StackSafe::new).collect();
+
+    let (_, calls) = count_calls(|| stacksafe::sort_deep(&mut values));
+    assert_eq!(calls, 1);
+    assert_eq!(values, expected);
+
+    values.reverse();
+    stacksafe::sort_unstable_deep(&mut values);
+    assert_eq!(values, expected);
+
+    for (i, key) in sorted.iter().enumerate() {
+        assert_eq!(stacksafe::binary_search_deep(&values, key), Ok(i));
+    }
+    let (found, calls) = count_calls(|| stacksafe::binary_search_deep(&values, &(1000, 0)));
+    assert_eq!(found, Err(1000));
+    assert_eq!(calls, 1);
+}
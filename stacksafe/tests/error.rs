@@ -0,0 +1,96 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::error::Error as _;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use stacksafe::Error;
+use stacksafe::SegmentAllocator;
+
+/// Fails allocations large enough to be stack segments while `FAIL` is set.
+struct FailingAllocator;
+
+static FAIL: AtomicBool = AtomicBool::new(false);
+
+unsafe impl GlobalAlloc for FailingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() >= 64 * 1024 && FAIL.load(Ordering::SeqCst) {
+            return std::ptr::null_mut();
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: FailingAllocator = FailingAllocator;
+
+#[test]
+fn test_try_on_new_stack() {
+    stacksafe::set_segment_allocator(SegmentAllocator::Global);
+
+    let label = stacksafe::try_on_new_stack(1024 * 1024, || {
+        stacksafe::current_segment().map(|segment| segment.label())
+    })
+    .unwrap();
+    assert_eq!(label, Some("stacksafe::try_on_new_stack"));
+
+    if cfg!(windows) {
+        // Segments always come from `stacker` there.
+        return;
+    }
+    FAIL.store(true, Ordering::SeqCst);
+    let result = stacksafe::try_on_new_stack(1024 * 1024, || unreachable!());
+    FAIL.store(false, Ordering::SeqCst);
+    assert!(matches!(
+        result,
+        Err(Error::AllocationFailed {
+            size: 1048576,
+            source: None
+        })
+    ));
+}
+
+#[test]
+fn test_display() {
+    let err = Error::DepthExceeded { limit: 128 };
+    assert_eq!(err.to_string(), "the depth limit of 128 was exceeded");
+    assert!(err.source().is_none());
+
+    let err = Error::AllocationFailed {
+        size: 4096,
+        source: Some(std::io::Error::from(std::io::ErrorKind::OutOfMemory)),
+    };
+    assert_eq!(
+        err.to_string(),
+        "failed to allocate a stack segment of 4096 bytes"
+    );
+    assert!(err.source().is_some());
+
+    let err = std::io::Error::from(Error::Unsupported {
+        operation: "reserving stack segments on this platform",
+    });
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(
+        err.to_string(),
+        "reserving stack segments on this platform is not supported"
+    );
+}
@@ -0,0 +1,30 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(all(windows, feature = "windows-telemetry"))]
+
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn depth(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+
+#[test]
+fn test_growth_with_telemetry() {
+    // Events are only written while a session enables the provider; growth works either way.
+    assert_eq!(depth(1_000_000), 1_000_000);
+    let handle = std::thread::spawn(|| depth(1_000_000));
+    assert_eq!(handle.join().unwrap(), 1_000_000);
+}
@@ -0,0 +1,67 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn depth(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+
+#[test]
+fn test_explain() {
+    let (n, explanation) = stacksafe::explain(|| depth(10));
+    assert_eq!(n, 10);
+    assert!(explanation.growths().is_empty());
+    assert_eq!(explanation.max_depth(), 0);
+
+    let (n, explanation) = stacksafe::explain(|| depth(1_000_000));
+    assert_eq!(n, 1_000_000);
+    let growths = explanation.growths();
+    assert!(growths.len() > 1);
+    assert_eq!(explanation.max_depth(), growths.len());
+    for (i, growth) in growths.iter().enumerate() {
+        assert_eq!(growth.label(), "explain::depth");
+        assert_eq!(growth.size(), stacksafe::get_stack_allocation_size());
+        assert_eq!(growth.depth(), i + 1);
+    }
+    assert_eq!(growths[0].used(), 0);
+    assert!(explanation.peak_segment_usage() > 0);
+    assert!(explanation.peak_segment_usage() <= stacksafe::get_stack_allocation_size());
+    assert!(explanation.growth_time() <= explanation.elapsed());
+}
+
+#[test]
+fn test_nested_explain() {
+    let (inner, outer) = stacksafe::explain(|| {
+        stacksafe::on_new_stack(1024 * 1024, || {
+            let ((), inner) = stacksafe::explain(|| {
+                stacksafe::on_new_stack(1024 * 1024, || ());
+            });
+            inner
+        })
+    });
+    assert_eq!(outer.growths().len(), 1);
+    assert_eq!(outer.growths()[0].label(), "stacksafe::on_new_stack");
+    assert_eq!(outer.max_depth(), 1);
+    assert_eq!(inner.growths().len(), 1);
+    assert_eq!(inner.growths()[0].depth(), 2);
+    assert!(inner.growths()[0].used() > 0);
+
+    // Recording stops when the closure panics.
+    let result = std::panic::catch_unwind(|| stacksafe::explain(|| -> u64 { panic!("explained") }));
+    assert!(result.is_err());
+    let ((), explanation) = stacksafe::explain(|| ());
+    assert!(explanation.growths().is_empty());
+}
@@ -0,0 +1,125 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::fmt::Write;
+
+use stacksafe::fmt::Indented;
+use stacksafe::fmt::debug_limited;
+
+enum Expr {
+    Num(i64),
+    Neg(Box<Expr>),
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Num(n) => write!(f, "{n}"),
+            Expr::Neg(inner) => write!(f, "(-{})", stacksafe::fmt::guarded(inner)),
+        }
+    }
+}
+
+impl fmt::Debug for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Num(n) => f.debug_tuple("Num").field(n).finish(),
+            Expr::Neg(inner) => f
+                .debug_tuple("Neg")
+                .field(&stacksafe::fmt::guarded(inner))
+                .finish(),
+        }
+    }
+}
+
+fn nested(depth: usize) -> Expr {
+    (0..depth).fold(Expr::Num(1), |expr, _| Expr::Neg(Box::new(expr)))
+}
+
+struct Tree(Vec<Tree>);
+
+fn print(tree: &Tree, out: &mut Indented<'_>) -> fmt::Result {
+    write!(out, "node")?;
+    writeln!(out, " {}", out.level())?;
+    out.nested(|out| tree.0.iter().try_for_each(|child| print(child, out)))
+}
+
+#[test]
+fn test_guarded() {
+    let expr = nested(3);
+    assert_eq!(expr.to_string(), "(-(-(-1)))");
+    assert_eq!(format!("{expr:?}"), "Neg(Neg(Neg(Num(1))))");
+    assert_eq!(format!("{:>4}", stacksafe::fmt::guarded(&7)), "   7");
+    assert_eq!(format!("{:x}", stacksafe::fmt::guarded(&255)), "ff");
+
+    let expr = nested(1_000_000);
+    assert_eq!(expr.to_string().len(), 3_000_001);
+    assert_eq!(format!("{expr:?}").len(), 5_000_006);
+    std::mem::forget(expr);
+}
+
+#[test]
+fn test_indented() {
+    let tree = Tree(vec![Tree(vec![Tree(vec![])]), Tree(vec![])]);
+    let mut out = String::new();
+    print(&tree, &mut Indented::new(&mut out, "  ")).unwrap();
+    assert_eq!(out, "node 0\n  node 1\n    node 2\n  node 1\n");
+
+    // Blank lines are not indented, and partial lines are indented once.
+    let mut out = String::new();
+    let mut indented = Indented::new(&mut out, "\t");
+    indented
+        .nested(|indented| write!(indented, "a\n\nb").and_then(|()| writeln!(indented, "c")))
+        .unwrap();
+    assert_eq!(out, "\ta\n\n\tbc\n");
+
+    let deep = (0..1_000_000).fold(Tree(vec![]), |tree, _| Tree(vec![tree]));
+    let mut out = String::new();
+    print(&deep, &mut Indented::new(&mut out, "")).unwrap();
+    assert_eq!(out.lines().count(), 1_000_001);
+    std::mem::forget(deep);
+}
+
+#[test]
+fn test_debug_limited() {
+    let expr = nested(3);
+    assert_eq!(debug_limited(&expr, 0).to_string(), "Neg(..)");
+    assert_eq!(debug_limited(&expr, 2).to_string(), "Neg(Neg(Neg(..)))");
+    assert_eq!(debug_limited(&expr, 4).to_string(), "Neg(Neg(Neg(Num(1))))");
+    assert_eq!(
+        debug_limited(&expr, 4).max_bytes(10).to_string(),
+        "Neg(Neg(Ne..."
+    );
+    assert_eq!(format!("{:?}", debug_limited(&expr, 1)), "Neg(Neg(..))");
+
+    // Delimiters in string literals do not count.
+    let value = vec![("a(\"[", vec![1]), ("}", vec![2])];
+    assert_eq!(
+        debug_limited(&value, 2).to_string(),
+        r#"[("a(\"[", [..]), ("}", [..])]"#
+    );
+    assert_eq!(
+        format!("{:#}", debug_limited(&value, 1)),
+        "[\n    (..),\n    (..),\n]"
+    );
+
+    let expr = nested(1_000_000);
+    assert_eq!(
+        debug_limited(&expr, 1_000_000).max_bytes(16).to_string(),
+        "Neg(Neg(Neg(Neg(..."
+    );
+    assert_eq!(debug_limited(&expr, 2).to_string(), "Neg(Neg(Neg(..)))");
+    std::mem::forget(expr);
+}
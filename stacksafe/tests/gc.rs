@@ -0,0 +1,59 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "gc")]
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use gc::Finalize;
+use gc::Gc;
+use gc::Trace;
+use gc::custom_trace;
+use stacksafe::StackSafe;
+
+static FINALIZED: AtomicUsize = AtomicUsize::new(0);
+
+struct Node {
+    next: Option<StackSafe<Gc<Node>>>,
+}
+
+impl Finalize for Node {
+    fn finalize(&self) {
+        FINALIZED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: all fields that may contain `Gc` pointers are marked.
+unsafe impl Trace for Node {
+    custom_trace!(this, {
+        // SAFETY: `mark` is only called from within the `Trace` methods it is defined in.
+        unsafe { mark(&this.next) };
+    });
+}
+
+#[test]
+fn test_gc_deep_chain() {
+    let mut head = None;
+    for _ in 0..100_000 {
+        head = Some(StackSafe::new(Gc::new(Node { next: head })));
+    }
+    // Marking reachable objects traces the whole chain recursively.
+    gc::force_collect();
+    assert_eq!(FINALIZED.load(Ordering::Relaxed), 0);
+
+    drop(head);
+    gc::force_collect();
+    assert_eq!(FINALIZED.load(Ordering::Relaxed), 100_000);
+}
@@ -0,0 +1,76 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::ConfigError;
+use stacksafe::GlobalSetup;
+use stacksafe::StackConfig;
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn count(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + count(n - 1) }
+}
+
+#[test]
+fn test_install_global() {
+    static STARTS: AtomicUsize = AtomicUsize::new(0);
+
+    // Nothing is installed if the configuration is inconsistent.
+    let err = stacksafe::install_global(
+        GlobalSetup::new()
+            .config(StackConfig::default().stack_allocation_size(64 * 1024))
+            .prefault_segments(true),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ConfigError::SegmentTooSmall { .. }));
+    assert!(!stacksafe::get_prefault_segments());
+
+    stacksafe::install_global(
+        GlobalSetup::new()
+            .config(StackConfig::default().stack_allocation_size(4 * 1024 * 1024))
+            .prefault_segments(true)
+            .on_thread_start(|| {
+                STARTS.fetch_add(1, Ordering::Relaxed);
+                // The callback runs as a hook, so guarded code does not grow the stack here.
+                assert_eq!(count(10), 10);
+            }),
+    )
+    .unwrap();
+    assert_eq!(stacksafe::get_stack_allocation_size(), 4 * 1024 * 1024);
+    assert!(stacksafe::get_prefault_segments());
+
+    // Threads run the callback once, the first time they grow the stack.
+    std::thread::spawn(|| count(10)).join().unwrap();
+    assert_eq!(STARTS.load(Ordering::Relaxed), 0);
+    let threads: Vec<_> = (0..4)
+        .map(|_| std::thread::spawn(|| count(1_000_000) + count(1_000_000)))
+        .collect();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), 2_000_000);
+    }
+    assert_eq!(STARTS.load(Ordering::Relaxed), 4);
+
+    // The current thread runs a newly installed callback again.
+    count(1_000_000);
+    assert_eq!(STARTS.load(Ordering::Relaxed), 5);
+    stacksafe::install_global(GlobalSetup::new().on_thread_start(|| {
+        STARTS.fetch_add(10, Ordering::Relaxed);
+    }))
+    .unwrap();
+    count(1_000_000);
+    assert_eq!(STARTS.load(Ordering::Relaxed), 15);
+}
@@ -0,0 +1,61 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::Error;
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn depth(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+
+// Growth is disabled for the whole process, so everything is checked in a single test that no
+// other test can interfere with.
+#[test]
+fn test_kill_switch() {
+    assert!(stacksafe::get_growth_enabled());
+    let n = 100_000;
+    let (_, explanation) = stacksafe::explain(|| depth(n));
+    assert!(!explanation.growths().is_empty());
+
+    stacksafe::set_growth_enabled(false);
+    assert!(!stacksafe::get_growth_enabled());
+
+    // Guarded functions run in place, on a stack that is large enough here.
+    let (ret, explanation) = std::thread::Builder::new()
+        .stack_size(512 * 1024 * 1024)
+        .spawn(move || stacksafe::explain(|| depth(n)))
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(ret, n);
+    assert!(explanation.growths().is_empty());
+
+    // Explicit growth fails fast.
+    assert!(matches!(
+        stacksafe::try_on_new_stack(1024 * 1024, || ()),
+        Err(Error::GrowthDisabled)
+    ));
+    let payload =
+        std::panic::catch_unwind(|| stacksafe::on_new_stack(1024 * 1024, || ())).unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<&str>(),
+        Some(&"stack growth is disabled")
+    );
+
+    stacksafe::set_growth_enabled(true);
+    let (ret, explanation) = stacksafe::explain(|| depth(n));
+    assert_eq!(ret, n);
+    assert!(!explanation.growths().is_empty());
+}
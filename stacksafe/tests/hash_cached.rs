@@ -0,0 +1,91 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::HashCached;
+use stacksafe::StackSafe;
+use stacksafe::stacksafe;
+
+static HASHED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum List {
+    Nil,
+    Cons(Counted, StackSafe<Box<List>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Counted(u64);
+
+impl Hash for Counted {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        HASHED.fetch_add(1, Ordering::Relaxed);
+        self.0.hash(state);
+    }
+}
+
+fn build(n: u64) -> List {
+    (0..n).fold(List::Nil, |tail, i| {
+        List::Cons(Counted(i), StackSafe::boxed(tail))
+    })
+}
+
+#[stacksafe]
+fn set_head(list: &mut List, value: u64) {
+    if let List::Cons(head, _) = list {
+        head.0 = value;
+    }
+}
+
+#[test]
+fn test_hash_cached() {
+    let mut key = HashCached::new(build(1_000_000));
+    assert!(!key.is_cached());
+
+    HASHED.store(0, Ordering::Relaxed);
+    let hash = key.hash_value();
+    assert!(key.is_cached());
+
+    // Clones share the cached hash.
+    #[allow(clippy::mutable_key_type)]
+    let mut set = HashSet::new();
+    set.insert(key.clone());
+    assert!(set.contains(&key));
+    assert_eq!(HASHED.load(Ordering::Relaxed), 1_000_000);
+
+    set_head(&mut key, 0);
+    assert!(!key.is_cached());
+    assert_ne!(key.hash_value(), hash);
+    assert!(!set.contains(&key));
+    assert_eq!(HASHED.load(Ordering::Relaxed), 2_000_000);
+}
+
+#[test]
+fn test_eq_uses_cached_hash() {
+    let a = HashCached::new(Counted(1));
+    let b = HashCached::new(Counted(1));
+    let c = HashCached::new(Counted(2));
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+
+    a.hash_value();
+    c.hash_value();
+    assert_ne!(a, c);
+    assert_eq!(a.into_inner(), Counted(1));
+}
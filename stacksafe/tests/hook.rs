@@ -0,0 +1,83 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::StackSafe;
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn outer(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + outer(n - 1) }
+}
+
+#[stacksafe]
+fn inner(value: &StackSafe<Vec<u64>>, n: u64) -> String {
+    if n == 0 {
+        format!("{value:?}")
+    } else {
+        inner(value, n - 1)
+    }
+}
+
+#[test]
+fn test_yield_hook_does_not_grow() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let value = StackSafe::new(vec![1, 2, 3]);
+    let hook = move || {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+        // The hook runs right before a segment is allocated, so the call below would grow the
+        // stack as well, and invoke the hook again, if it were not suppressed.
+        assert_eq!(inner(&value, 10), "[1, 2, 3]");
+    };
+    assert_eq!(
+        stacksafe::with_yield_hook(0, hook, || outer(1_000_000)),
+        1_000_000
+    );
+
+    assert!(CALLS.load(Ordering::Relaxed) > 0);
+    let hotspots = stacksafe::growth_hotspots();
+    let labels: Vec<&str> = hotspots.iter().map(|h| h.label()).collect();
+    assert_eq!(labels, vec!["hook::outer"]);
+    assert_eq!(hotspots[0].growths(), CALLS.load(Ordering::Relaxed));
+}
+
+#[test]
+#[cfg(not(feature = "verify-stack"))]
+fn test_violation_handler_reentrancy() {
+    use stacksafe::ViolationAction;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let logged = StackSafe::new(vec![4u64, 5]);
+    stacksafe::set_violation_handler(move |_| {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+        // Accessing a `StackSafe` here is a violation as well, which must not reenter the handler.
+        assert_eq!(format!("{:?}", *logged), "[4, 5]");
+        ViolationAction::Ignore
+    });
+
+    let value = StackSafe::new(vec![1u64, 2, 3]);
+    assert_eq!(value.len(), 3);
+    let expected = if cfg!(any(debug_assertions, feature = "strict")) {
+        1
+    } else {
+        0
+    };
+    assert_eq!(CALLS.load(Ordering::Relaxed), expected);
+
+    stacksafe::clear_violation_handler();
+}
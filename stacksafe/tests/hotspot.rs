@@ -0,0 +1,49 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn shallow(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + shallow(n - 1) }
+}
+
+#[stacksafe]
+fn deep(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + deep(n - 1) }
+}
+
+#[test]
+fn test_growth_hotspots() {
+    shallow(10);
+    assert!(stacksafe::growth_hotspots().is_empty());
+
+    shallow(100_000);
+    deep(1_000_000);
+    std::thread::spawn(|| deep(1_000_000)).join().unwrap();
+
+    let hotspots = stacksafe::growth_hotspots();
+    let labels: Vec<&str> = hotspots.iter().map(|h| h.label()).collect();
+    assert_eq!(labels, vec!["hotspot::deep", "hotspot::shallow"]);
+    assert!(hotspots[0].growths() > hotspots[1].growths());
+    assert!(hotspots[1].growths() > 0);
+
+    stacksafe::reset_growth_hotspots();
+    assert!(stacksafe::growth_hotspots().is_empty());
+
+    shallow(100_000);
+    let hotspots = stacksafe::growth_hotspots();
+    assert_eq!(hotspots.len(), 1);
+    assert_eq!(hotspots[0].label(), "hotspot::shallow");
+}
@@ -0,0 +1,124 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::stacksafe;
+
+struct Parity;
+
+#[stacksafe]
+impl Parity {
+    fn is_even(&self, n: u64) -> bool {
+        if n == 0 { true } else { self.is_odd(n - 1) }
+    }
+
+    fn is_odd(&self, n: u64) -> bool {
+        if n == 0 { false } else { self.is_even(n - 1) }
+    }
+
+    fn label(&self, n: u64) -> Option<&'static str> {
+        if n == 0 {
+            stacksafe::current_segment().map(|segment| segment.label())
+        } else {
+            self.label(n - 1)
+        }
+    }
+
+    #[stacksafe(name = "custom")]
+    fn renamed(&self, n: u64) -> Option<&'static str> {
+        if n == 0 {
+            stacksafe::current_segment().map(|segment| segment.label())
+        } else {
+            self.renamed(n - 1)
+        }
+    }
+
+    // Neither method could be wrapped in a guarded call.
+    #[stacksafe(skip)]
+    const fn zero() -> u64 {
+        0
+    }
+
+    #[stacksafe(skip)]
+    async fn ready(&self) -> u64 {
+        Self::zero()
+    }
+}
+
+trait Tree {
+    fn children(&self) -> &[Self]
+    where Self: Sized;
+}
+
+#[stacksafe]
+trait Walk: Tree + Sized {
+    fn count(&self) -> usize {
+        1 + self.children().iter().map(Walk::count).sum::<usize>()
+    }
+
+    fn label(&self) -> Option<&'static str> {
+        match self.children().first() {
+            Some(child) => Walk::label(child),
+            None => stacksafe::current_segment().map(|segment| segment.label()),
+        }
+    }
+}
+
+struct Node(Vec<Node>);
+
+impl Tree for Node {
+    fn children(&self) -> &[Self] {
+        &self.0
+    }
+}
+
+impl Walk for Node {}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.0);
+        while let Some(mut node) = stack.pop() {
+            stack.append(&mut node.0);
+        }
+    }
+}
+
+fn chain(len: usize) -> Node {
+    (0..len).fold(Node(Vec::new()), |node, _| Node(vec![node]))
+}
+
+#[test]
+fn test_impl_block() {
+    assert!(Parity.is_even(1_000_000));
+    assert!(Parity.is_odd(1_000_001));
+    assert_eq!(Parity::zero(), 0);
+    drop(Parity.ready());
+}
+
+#[test]
+fn test_trait_default_methods() {
+    assert_eq!(chain(200_000).count(), 200_001);
+}
+
+#[test]
+fn test_method_labels() {
+    if let Some(label) = Parity.label(1_000_000) {
+        assert_eq!(label, concat!(module_path!(), "::Parity::label"));
+    }
+    if let Some(label) = Parity.renamed(1_000_000) {
+        assert_eq!(label, concat!(module_path!(), "::custom"));
+    }
+    if let Some(label) = Walk::label(&chain(200_000)) {
+        assert_eq!(label, concat!(module_path!(), "::Walk::label"));
+    }
+}
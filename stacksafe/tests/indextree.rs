@@ -0,0 +1,92 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "indextree")]
+
+use stacksafe::Assemble;
+use stacksafe::Dismantle;
+use stacksafe::StackSafe;
+
+#[derive(Debug, PartialEq)]
+struct Tree {
+    value: u64,
+    children: Vec<StackSafe<Tree>>,
+}
+
+impl Dismantle for Tree {
+    fn dismantle(&mut self, children: &mut Vec<Self>) {
+        children.extend(self.children.drain(..).map(StackSafe::into_inner));
+    }
+}
+
+impl Assemble for Tree {
+    fn assemble(&mut self, children: Vec<Self>) {
+        self.children
+            .extend(children.into_iter().map(StackSafe::new));
+    }
+}
+
+fn tree(value: u64, children: Vec<Tree>) -> Tree {
+    Tree {
+        value,
+        children: children.into_iter().map(StackSafe::new).collect(),
+    }
+}
+
+fn chain(depth: u64) -> Tree {
+    (1..depth).fold(tree(0, vec![]), |child, value| tree(value, vec![child]))
+}
+
+#[test]
+fn test_round_trip() {
+    let original = || {
+        tree(1, vec![
+            tree(2, vec![tree(4, vec![]), tree(5, vec![])]),
+            tree(3, vec![tree(6, vec![])]),
+        ])
+    };
+
+    let (arena, root) = stacksafe::indextree::to_arena(original());
+    let preorder: Vec<u64> = root
+        .descendants(&arena)
+        .map(|id| arena[id].get().value)
+        .collect();
+    assert_eq!(preorder, vec![1, 2, 4, 5, 3, 6]);
+    assert!(arena.iter().all(|node| node.get().children.is_empty()));
+
+    assert_eq!(stacksafe::indextree::from_arena(arena, root), original());
+}
+
+#[test]
+fn test_deep_round_trip() {
+    let (arena, root) = stacksafe::indextree::to_arena(chain(100_000));
+    assert_eq!(arena.len(), 100_000);
+    assert_eq!(root.descendants(&arena).count(), 100_000);
+
+    assert_eq!(
+        stacksafe::indextree::from_arena(arena, root),
+        chain(100_000)
+    );
+}
+
+#[test]
+fn test_subtree() {
+    let mut arena = indextree::Arena::new();
+    let first = stacksafe::indextree::insert(&mut arena, tree(1, vec![tree(2, vec![])]));
+    let second = stacksafe::indextree::insert(&mut arena, tree(3, vec![tree(4, vec![])]));
+    first.append(second, &mut arena);
+
+    let subtree = stacksafe::indextree::from_arena(arena, second);
+    assert_eq!(subtree, tree(3, vec![tree(4, vec![])]));
+}
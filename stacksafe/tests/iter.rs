@@ -0,0 +1,73 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::StackSafe;
+use stacksafe::iter::Yielder;
+use stacksafe::stacksafe;
+
+enum List {
+    Nil,
+    Cons(u64, StackSafe<Box<List>>),
+}
+
+fn build(n: u64) -> List {
+    (0..n).fold(List::Nil, |tail, i| List::Cons(i, StackSafe::boxed(tail)))
+}
+
+#[stacksafe]
+fn postorder(list: &List, out: &mut Yielder<u64>) {
+    if let List::Cons(value, tail) = list {
+        postorder(tail, out);
+        out.emit(*value);
+    }
+}
+
+#[test]
+fn test_deep_producer() {
+    let list = Arc::new(build(1_000_000));
+    let mut iter = stacksafe::iter::from_recursive(move |out| postorder(&list, out));
+    assert_eq!(iter.next(), Some(0));
+    assert_eq!(iter.next(), Some(1));
+    assert_eq!(iter.count(), 999_998);
+}
+
+#[test]
+fn test_lazy_and_cancellable() {
+    static EMITTED: AtomicUsize = AtomicUsize::new(0);
+
+    let mut iter = stacksafe::iter::from_recursive(|out| {
+        for i in 0.. {
+            out.emit(i);
+            EMITTED.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+    assert_eq!(iter.next(), Some(0));
+    assert_eq!(iter.next(), Some(1));
+    assert!(EMITTED.load(Ordering::SeqCst) <= 2);
+    drop(iter);
+}
+
+#[test]
+#[should_panic(expected = "producer failed")]
+fn test_producer_panic() {
+    let iter = stacksafe::iter::from_recursive(|out| {
+        out.emit(1);
+        panic!("producer failed");
+    });
+    iter.for_each(drop);
+}
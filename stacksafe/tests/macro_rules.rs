@@ -0,0 +1,108 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Functions annotated with `#[stacksafe]` that are generated by declarative macros.
+
+mod reexport {
+    pub use stacksafe;
+}
+
+macro_rules! triangular {
+    ($vis:vis fn $name:ident($arg:ident: $ty:ty) -> $ret:ty $body:block) => {
+        #[stacksafe::stacksafe]
+        $vis fn $name($arg: $ty) -> $ret $body
+    };
+}
+
+triangular!(
+    pub(crate) fn by_signature(n: u64) -> u64 {
+        if n == 0 { 0 } else { n + by_signature(n - 1) }
+    }
+);
+
+macro_rules! triangular_in {
+    ($name:ident, $krate:path, $profile:expr) => {
+        #[stacksafe::stacksafe(crate = $krate, profile = $profile)]
+        fn $name(n: u64) -> u64 {
+            if n == 0 { 0 } else { n + $name(n - 1) }
+        }
+    };
+}
+
+triangular_in!(by_path, ::stacksafe, "macro_rules");
+
+// Expands the same way as a macro exported by another crate that re-exports `stacksafe`.
+macro_rules! triangular_via_reexport {
+    ($name:ident) => {
+        #[$crate::reexport::stacksafe::stacksafe(crate = $crate::reexport::stacksafe)]
+        fn $name(n: u64) -> u64 {
+            if n == 0 { 0 } else { n + $name(n - 1) }
+        }
+    };
+}
+
+triangular_via_reexport!(by_reexport);
+
+macro_rules! shadowing {
+    () => {
+        #[stacksafe::stacksafe]
+        fn shadowing(n: u64) -> impl Fn(&u64) -> u64 {
+            let __stacksafe_ret = n;
+            move |m| __stacksafe_ret + m
+        }
+    };
+}
+
+shadowing!();
+
+#[stacksafe::stacksafe]
+fn r#loop(n: u64) -> Option<&'static str> {
+    if n == 0 {
+        stacksafe::current_segment().map(|segment| segment.label())
+    } else {
+        std::hint::black_box(r#loop(n - 1))
+    }
+}
+
+#[no_implicit_prelude]
+mod no_prelude {
+    #[::stacksafe::stacksafe]
+    pub fn triangular(n: u64) -> u64 {
+        if n == 0 { 0 } else { n + triangular(n - 1) }
+    }
+
+    #[::stacksafe::stacksafe]
+    pub fn adder(x: u32) -> impl ::core::ops::Fn(&u32) -> u32 {
+        move |y| x + y
+    }
+}
+
+#[test]
+fn test_macro_generated() {
+    assert_eq!(by_signature(100_000), 5_000_050_000);
+    assert_eq!(by_path(100_000), 5_000_050_000);
+    assert_eq!(by_reexport(100_000), 5_000_050_000);
+    assert_eq!(shadowing(1)(&2), 3);
+}
+
+#[test]
+fn test_raw_identifier_label() {
+    assert_eq!(r#loop(1_000_000), Some("macro_rules::loop"));
+}
+
+#[test]
+fn test_no_implicit_prelude() {
+    assert_eq!(no_prelude::triangular(100_000), 5_000_050_000);
+    assert_eq!(no_prelude::adder(1)(&2), 3);
+}
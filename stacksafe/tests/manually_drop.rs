@@ -0,0 +1,187 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::panic::AssertUnwindSafe;
+
+use stacksafe::StackSafe;
+use stacksafe::stacksafe;
+
+thread_local! {
+    static LOG: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+    static PANIC_ON: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+struct Tracked(u64);
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        LOG.with(|log| log.borrow_mut().push(self.0));
+        if PANIC_ON.with(|p| p.get()) == Some(self.0) {
+            panic!("failed to drop {}", self.0);
+        }
+    }
+}
+
+fn take_log() -> Vec<u64> {
+    LOG.with(|log| std::mem::take(&mut *log.borrow_mut()))
+}
+
+fn assert_dropped_once(mut log: Vec<u64>, n: u64) {
+    log.sort_unstable();
+    assert_eq!(log, (0..n).collect::<Vec<_>>());
+}
+
+struct Node {
+    // Only ever dropped.
+    #[allow(dead_code)]
+    tracked: Tracked,
+    // Only ever dropped.
+    #[allow(dead_code)]
+    next: Option<StackSafe<Box<Node>>>,
+}
+
+fn build(n: u64) -> Option<StackSafe<Box<Node>>> {
+    (0..n).fold(None, |next, id| {
+        Some(StackSafe::boxed(Node {
+            tracked: Tracked(id),
+            next,
+        }))
+    })
+}
+
+struct Pair {
+    first: StackSafe<Tracked>,
+    // Only ever dropped.
+    #[allow(dead_code)]
+    second: StackSafe<Tracked>,
+}
+
+#[test]
+fn test_deep_chain_dropped_once() {
+    drop(build(100_000));
+    assert_dropped_once(take_log(), 100_000);
+}
+
+#[test]
+fn test_into_inner_transfers_ownership() {
+    #[stacksafe]
+    fn unwrap(wrapped: StackSafe<Tracked>) -> Tracked {
+        wrapped.into_inner()
+    }
+
+    let inner = unwrap(StackSafe::new(Tracked(0)));
+    assert!(take_log().is_empty());
+    drop(inner);
+    assert_eq!(take_log(), [0]);
+}
+
+#[test]
+fn test_forget_drops_nothing() {
+    StackSafe::new(Tracked(0)).forget();
+    std::mem::forget(StackSafe::new(Tracked(1)));
+    assert!(take_log().is_empty());
+}
+
+#[test]
+fn test_replace_through_deref_mut() {
+    #[stacksafe]
+    fn take(wrapped: &mut StackSafe<Option<Tracked>>) -> Option<Tracked> {
+        std::mem::take(&mut **wrapped)
+    }
+
+    #[stacksafe]
+    fn assign(wrapped: &mut StackSafe<Option<Tracked>>, value: Tracked) {
+        **wrapped = Some(value);
+    }
+
+    let mut wrapped = StackSafe::new(Some(Tracked(0)));
+    let taken = take(&mut wrapped);
+    assert!(take_log().is_empty());
+
+    assign(&mut wrapped, Tracked(1));
+    assert!(take_log().is_empty());
+    assign(&mut wrapped, Tracked(2));
+    assert_eq!(take_log(), [1]);
+
+    drop(wrapped);
+    assert_eq!(take_log(), [2]);
+    drop(taken);
+    assert_eq!(take_log(), [0]);
+}
+
+#[test]
+fn test_partially_moved_struct() {
+    {
+        let pair = Pair {
+            first: StackSafe::new(Tracked(0)),
+            second: StackSafe::new(Tracked(1)),
+        };
+        let first = pair.first;
+        assert!(take_log().is_empty());
+        drop(first);
+        assert_eq!(take_log(), [0]);
+    }
+    assert_eq!(take_log(), [1]);
+
+    drop(Pair {
+        first: StackSafe::new(Tracked(0)),
+        second: StackSafe::new(Tracked(1)),
+    });
+    assert_eq!(take_log(), [0, 1]);
+}
+
+#[test]
+fn test_panic_in_wrapped_drop() {
+    struct Inner {
+        // Only ever dropped.
+        #[allow(dead_code)]
+        panics: Tracked,
+        // Only ever dropped.
+        #[allow(dead_code)]
+        after: Tracked,
+    }
+
+    let outer = (
+        StackSafe::new(Inner {
+            panics: Tracked(0),
+            after: Tracked(1),
+        }),
+        StackSafe::new(Tracked(2)),
+    );
+
+    PANIC_ON.with(|p| p.set(Some(0)));
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| drop(outer)));
+    PANIC_ON.with(|p| p.set(None));
+
+    let payload = result.unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<String>().unwrap(),
+        "failed to drop 0"
+    );
+    assert_eq!(take_log(), [0, 1, 2]);
+}
+
+#[test]
+fn test_panic_deep_in_chain() {
+    let list = build(100_000);
+
+    PANIC_ON.with(|p| p.set(Some(50_000)));
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| drop(list)));
+    PANIC_ON.with(|p| p.set(None));
+
+    assert!(result.is_err());
+    assert_dropped_once(take_log(), 100_000);
+}
@@ -0,0 +1,106 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::Assemble;
+use stacksafe::Dismantle;
+use stacksafe::StackSafe;
+use stacksafe::stacksafe;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Tree {
+    value: u64,
+    children: Vec<StackSafe<Tree>>,
+}
+
+impl Dismantle for Tree {
+    fn dismantle(&mut self, children: &mut Vec<Self>) {
+        children.extend(self.children.drain(..).map(StackSafe::into_inner));
+    }
+}
+
+impl Assemble for Tree {
+    fn assemble(&mut self, children: Vec<Self>) {
+        self.children
+            .extend(children.into_iter().map(StackSafe::new));
+    }
+}
+
+fn tree(value: u64, children: Vec<Tree>) -> Tree {
+    Tree {
+        value,
+        children: children.into_iter().map(StackSafe::new).collect(),
+    }
+}
+
+fn chain(depth: u64) -> Tree {
+    (1..depth).fold(tree(0, vec![]), |child, value| tree(value, vec![child]))
+}
+
+#[stacksafe]
+fn sum(tree: &Tree) -> u64 {
+    tree.value + tree.children.iter().map(|child| sum(child)).sum::<u64>()
+}
+
+#[test]
+fn test_bottom_up_order() {
+    let original = tree(1, vec![
+        tree(2, vec![tree(4, vec![]), tree(5, vec![])]),
+        tree(3, vec![]),
+    ]);
+
+    let mut visited = Vec::new();
+    let mapped = stacksafe::map_tree(original.clone(), |mut node| {
+        visited.push(node.value);
+        // Children have already been mapped when their parent is.
+        node.value = node.value * 10 + node.children.iter().map(|c| c.value).sum::<u64>();
+        node
+    });
+    assert_eq!(visited, vec![4, 5, 2, 3, 1]);
+    assert_eq!(mapped.value, 10 + 110 + 30);
+
+    let mut in_place = original;
+    let mut visited = Vec::new();
+    stacksafe::map_tree_in_place(&mut in_place, |node| {
+        visited.push(node.value);
+        node.value = node.value * 10 + node.children.iter().map(|c| c.value).sum::<u64>();
+    });
+    assert_eq!(visited, vec![4, 5, 2, 3, 1]);
+    assert_eq!(in_place, mapped);
+}
+
+#[test]
+fn test_deep_map() {
+    let mapped = stacksafe::map_tree(chain(1_000_000), |mut node| {
+        node.value += 1;
+        node
+    });
+    assert_eq!(sum(&mapped), sum(&chain(1_000_000)) + 1_000_000);
+
+    let mut tree = chain(1_000_000);
+    stacksafe::map_tree_in_place(&mut tree, |node| node.value *= 2);
+    assert_eq!(sum(&tree), sum(&chain(1_000_000)) * 2);
+}
+
+#[test]
+fn test_replace_nodes() {
+    // Leaves are replaced by new subtrees, which are not visited again.
+    let mapped = stacksafe::map_tree(tree(1, vec![tree(2, vec![])]), |node| {
+        if node.children.is_empty() {
+            tree(node.value, vec![tree(0, vec![])])
+        } else {
+            node
+        }
+    });
+    assert_eq!(mapped, tree(1, vec![tree(2, vec![tree(0, vec![])])]));
+}
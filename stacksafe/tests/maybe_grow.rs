@@ -0,0 +1,64 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::panic::catch_unwind;
+
+use stacksafe::StackSafe;
+
+struct Node {
+    next: Option<StackSafe<Box<Node>>>,
+}
+
+fn len(node: &Node) -> usize {
+    stacksafe::raw::maybe_grow(64 * 1024, 1024 * 1024, || match &node.next {
+        Some(next) => 1 + len(next),
+        None => 1,
+    })
+}
+
+fn segment(depth: u64) -> Option<stacksafe::SegmentInfo> {
+    stacksafe::raw::maybe_grow(64 * 1024, 1024 * 1024, || {
+        if depth == 0 {
+            stacksafe::current_segment()
+        } else {
+            segment(depth - 1)
+        }
+    })
+}
+
+#[test]
+fn test_maybe_grow() {
+    let list = (0..100_000).fold(Node { next: None }, |node, _| Node {
+        next: Some(StackSafe::boxed(node)),
+    });
+    assert_eq!(len(&list), 100_001);
+
+    if let Some(segment) = segment(100_000) {
+        assert_eq!(segment.label(), "stacksafe::raw::maybe_grow");
+        assert!(segment.size() >= 1024 * 1024);
+    }
+
+    assert!(catch_unwind(|| stacksafe::raw::maybe_grow(0, 1024, || ())).is_err());
+    assert!(catch_unwind(|| stacksafe::raw::maybe_grow(1024, 1024, || ())).is_err());
+}
+
+#[test]
+fn test_remaining_stack() {
+    let outer = stacksafe::raw::remaining_stack();
+    let inner = stacksafe::on_new_stack(1024 * 1024, stacksafe::raw::remaining_stack);
+    if let (Some(_), Some(inner)) = (outer, inner) {
+        assert!(inner <= 1024 * 1024);
+        assert!(inner > 512 * 1024);
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::hash::BuildHasherDefault;
+use std::hash::DefaultHasher;
+
+use stacksafe::memo::Memo;
+
+#[test]
+fn test_each_key_computed_once() {
+    let calls = Cell::new(0);
+    let fibonacci = |memo: &mut Memo<'_, u64, u64>, n: u64| {
+        calls.set(calls.get() + 1);
+        match n {
+            0 | 1 => n,
+            _ => memo.get(n - 1) + memo.get(n - 2),
+        }
+    };
+    assert_eq!(stacksafe::memo::recurse(50, fibonacci), 12_586_269_025);
+    assert_eq!(calls.get(), 51);
+}
+
+#[test]
+fn test_deep_chain() {
+    let n = 1_000_000;
+    let depth = |memo: &mut Memo<'_, u64, u64>, n: u64| {
+        assert!(stacksafe::internal::is_protected());
+        if n == 0 { 0 } else { 1 + memo.get(n - 1) }
+    };
+    assert_eq!(stacksafe::memo::recurse(n, depth), n);
+}
+
+#[test]
+fn test_cached_results() {
+    let hasher = BuildHasherDefault::<DefaultHasher>::default();
+    // The number of ways to climb `n` stairs taking one or two at a time, checking that the
+    // smaller results are already cached when a larger one is computed.
+    let stairs = |memo: &mut Memo<'_, u32, u64, _>, n: u32| {
+        if n <= 1 {
+            return 1;
+        }
+        let ways = memo.get(n - 1) + memo.get(n - 2);
+        assert_eq!(memo.cached(&(n - 1)).copied(), Some(ways - memo.get(n - 2)));
+        assert!(memo.cached(&n).is_none());
+        assert_eq!(memo.len(), n as usize);
+        ways
+    };
+    assert_eq!(
+        stacksafe::memo::recurse_with_hasher(30, hasher, stairs),
+        1_346_269
+    );
+}
@@ -0,0 +1,77 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "metrics")]
+
+use std::sync::Mutex;
+
+use stacksafe::metrics;
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn count(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + count(n - 1) }
+}
+
+#[test]
+fn test_metrics() {
+    static GROWN: Mutex<Vec<(&str, usize, usize)>> = Mutex::new(Vec::new());
+
+    metrics::on_grow(|segment| {
+        assert_eq!(segment.used(), 0);
+        // Growth is suppressed while the hook runs.
+        assert_eq!(count(10), 10);
+        GROWN
+            .lock()
+            .unwrap()
+            .push((segment.label(), segment.size(), segment.depth()));
+    });
+
+    assert_eq!(metrics::max_depth(), 0);
+    assert_eq!(count(100), 100);
+    assert_eq!(metrics::max_depth(), 101);
+    assert_eq!(metrics::segments_allocated(), 0);
+
+    assert_eq!(count(200_000), 200_000);
+    assert_eq!(metrics::max_depth(), 200_001);
+    let grown = std::mem::take(&mut *GROWN.lock().unwrap());
+    assert!(!grown.is_empty());
+    assert_eq!(metrics::segments_allocated(), grown.len() as u64);
+    let bytes: usize = grown.iter().map(|(_, size, _)| size).sum();
+    assert_eq!(metrics::bytes_allocated(), bytes as u64);
+    for (i, (label, _, depth)) in grown.iter().enumerate() {
+        assert_eq!(*label, "metrics::count");
+        assert_eq!(*depth, i + 1);
+    }
+
+    // The maximum depth is tracked per thread.
+    std::thread::spawn(|| {
+        assert_eq!(metrics::max_depth(), 0);
+        assert_eq!(count(10), 10);
+        assert_eq!(metrics::max_depth(), 11);
+    })
+    .join()
+    .unwrap();
+
+    metrics::reset_max_depth();
+    assert_eq!(metrics::max_depth(), 0);
+    count(10);
+    assert_eq!(metrics::max_depth(), 11);
+
+    metrics::clear_on_grow();
+    let segments = metrics::segments_allocated();
+    assert_eq!(count(200_000), 200_000);
+    assert!(metrics::segments_allocated() > segments);
+    assert!(GROWN.lock().unwrap().is_empty());
+}
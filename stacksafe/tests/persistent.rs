@@ -0,0 +1,182 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::Dismantle;
+use stacksafe::IncrementalDrop;
+use stacksafe::StackSafe;
+use stacksafe::stacksafe;
+
+static COMPARED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone)]
+struct Tree {
+    value: u64,
+    children: Vec<StackSafe<Arc<Tree>>>,
+}
+
+impl PartialEq for Tree {
+    fn eq(&self, other: &Self) -> bool {
+        COMPARED.fetch_add(1, Ordering::Relaxed);
+        self.value == other.value
+            && self.children.len() == other.children.len()
+            && (self.children.iter())
+                .zip(&other.children)
+                .all(|(a, b)| a.eq_shared(b))
+    }
+}
+
+impl Dismantle for Tree {
+    fn dismantle(&mut self, children: &mut Vec<Self>) {
+        children.extend(self.children.drain(..).filter_map(StackSafe::into_unique));
+    }
+}
+
+fn leaf(value: u64) -> StackSafe<Arc<Tree>> {
+    StackSafe::shared(Tree {
+        value,
+        children: vec![],
+    })
+}
+
+fn spine(depth: u64) -> StackSafe<Arc<Tree>> {
+    (1..depth).fold(leaf(0), |child, value| {
+        StackSafe::shared(Tree {
+            value,
+            children: vec![child, leaf(value)],
+        })
+    })
+}
+
+// Replaces the value of the node at the end of `path`, copying only the nodes along it.
+#[stacksafe]
+fn set(tree: &mut StackSafe<Arc<Tree>>, path: &[usize], value: u64) {
+    let node = StackSafe::make_mut(tree);
+    match path.split_first() {
+        None => node.value = value,
+        Some((&index, rest)) => set(&mut node.children[index], rest, value),
+    }
+}
+
+#[stacksafe]
+fn child(tree: &StackSafe<Arc<Tree>>, index: usize) -> StackSafe<Arc<Tree>> {
+    tree.children[index].clone()
+}
+
+#[stacksafe]
+fn unique(tree: StackSafe<Arc<Tree>>) -> Option<Tree> {
+    StackSafe::into_unique(tree)
+}
+
+#[test]
+fn test_path_copying() {
+    let old = spine(4);
+    let mut new = old.clone();
+    assert!(StackSafe::ptr_eq(&old, &new));
+
+    set(&mut new, &[0, 1], 42);
+    assert!(!StackSafe::ptr_eq(&old, &new));
+
+    // The changed path is copied, everything else is shared.
+    let (old_left, new_left) = (child(&old, 0), child(&new, 0));
+    assert!(!StackSafe::ptr_eq(&old_left, &new_left));
+    assert!(StackSafe::ptr_eq(&child(&old, 1), &child(&new, 1)));
+    assert!(StackSafe::ptr_eq(
+        &child(&old_left, 0),
+        &child(&new_left, 0)
+    ));
+    assert!(!StackSafe::ptr_eq(
+        &child(&old_left, 1),
+        &child(&new_left, 1)
+    ));
+
+    assert!(!old.eq_shared(&new));
+    set(&mut new, &[0, 1], 2);
+    assert!(old.eq_shared(&new));
+}
+
+#[test]
+fn test_eq_shared_short_circuits() {
+    let old = spine(100_000);
+    let mut new = old.clone();
+    set(&mut new, &[1], 7);
+
+    COMPARED.store(0, Ordering::Relaxed);
+    assert!(!old.eq_shared(&new));
+    // Only the copied root and its unshared child are compared.
+    assert_eq!(COMPARED.load(Ordering::Relaxed), 2);
+
+    COMPARED.store(0, Ordering::Relaxed);
+    assert!(old.eq_shared(&spine(100_000)));
+    assert_eq!(COMPARED.load(Ordering::Relaxed), 199_999);
+}
+
+#[test]
+fn test_drop_shared_spines() {
+    let old = spine(1_000_000);
+    let mut new = old.clone();
+    set(&mut new, &[0, 0, 0], 7);
+
+    let handle = std::thread::spawn(move || drop(old));
+    handle.join().unwrap();
+    assert!(!new.eq_shared(&spine(1_000_000)));
+
+    let mut garbage = IncrementalDrop::new(unique(new).unwrap());
+    while !garbage.step(10_000) {}
+}
+
+#[derive(Clone)]
+struct Chain(u64, Option<StackSafe<Box<Chain>>>);
+
+fn chain(depth: u64) -> Chain {
+    (1..depth).fold(Chain(0, None), |next, value| {
+        Chain(value, Some(StackSafe::new(Box::new(next))))
+    })
+}
+
+#[stacksafe]
+fn sum(chain: &Chain) -> u64 {
+    chain.0 + chain.1.as_ref().map_or(0, |next| sum(next))
+}
+
+#[stacksafe]
+fn copy_on_write(shared: &StackSafe<Arc<Chain>>) {
+    // A unique reference is changed in place.
+    let mut unique = StackSafe::shared(chain(10));
+    StackSafe::get_mut(&mut unique).unwrap().0 += 1;
+    let unique = StackSafe::try_unwrap(unique).ok().unwrap();
+    assert_eq!(sum(&unique), 46);
+
+    // A shared reference cannot be changed in place, and is deeply copied on write.
+    let mut copy = shared.clone();
+    assert!(StackSafe::get_mut(&mut copy).is_none());
+    let mut copy = StackSafe::try_unwrap(copy).err().unwrap();
+    StackSafe::make_mut(&mut copy).0 = 0;
+    assert!(!StackSafe::ptr_eq(shared, &copy));
+    assert_eq!(sum(&copy), sum(shared) - 999_999);
+
+    let owned = StackSafe::unwrap_or_clone(shared.clone());
+    assert_eq!(sum(&owned), sum(shared));
+    let owned = StackSafe::unwrap_or_clone(copy);
+    assert_eq!(sum(&owned), sum(shared) - 999_999);
+}
+
+#[test]
+fn test_copy_on_write() {
+    let shared = StackSafe::shared(chain(1_000_000));
+    copy_on_write(&shared);
+}
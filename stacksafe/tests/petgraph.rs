@@ -0,0 +1,105 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "petgraph")]
+
+use petgraph::Direction;
+use stacksafe::Assemble;
+use stacksafe::Dismantle;
+use stacksafe::StackSafe;
+
+#[derive(Debug, PartialEq)]
+struct Tree {
+    value: u64,
+    children: Vec<StackSafe<Tree>>,
+}
+
+impl Dismantle for Tree {
+    fn dismantle(&mut self, children: &mut Vec<Self>) {
+        children.extend(self.children.drain(..).map(StackSafe::into_inner));
+    }
+}
+
+impl Assemble for Tree {
+    fn assemble(&mut self, children: Vec<Self>) {
+        self.children
+            .extend(children.into_iter().map(StackSafe::new));
+    }
+}
+
+fn tree(value: u64, children: Vec<Tree>) -> Tree {
+    Tree {
+        value,
+        children: children.into_iter().map(StackSafe::new).collect(),
+    }
+}
+
+fn chain(depth: u64) -> Tree {
+    (1..depth).fold(tree(0, vec![]), |child, value| tree(value, vec![child]))
+}
+
+#[test]
+fn test_round_trip() {
+    let original = || {
+        tree(1, vec![
+            tree(2, vec![tree(4, vec![]), tree(5, vec![])]),
+            tree(3, vec![tree(6, vec![])]),
+        ])
+    };
+
+    let (graph, root) = stacksafe::petgraph::to_graph(original());
+    assert_eq!(graph.node_count(), 6);
+    assert_eq!(graph.edge_count(), 5);
+    assert_eq!(graph[root].value, 1);
+    assert_eq!(
+        graph.neighbors_directed(root, Direction::Incoming).count(),
+        0
+    );
+
+    assert_eq!(stacksafe::petgraph::from_graph(graph, root), original());
+}
+
+#[test]
+fn test_deep_round_trip() {
+    let (graph, root) = stacksafe::petgraph::to_graph(chain(100_000));
+    assert_eq!(graph.node_count(), 100_000);
+
+    assert_eq!(stacksafe::petgraph::from_graph(graph, root), chain(100_000));
+}
+
+#[test]
+fn test_edge_order() {
+    // Children are ordered by edge weight rather than by insertion order.
+    let mut graph = petgraph::graph::Graph::new();
+    let root = graph.add_node(tree(1, vec![]));
+    let second = graph.add_node(tree(3, vec![]));
+    let first = graph.add_node(tree(2, vec![]));
+    graph.add_edge(root, second, 1);
+    graph.add_edge(root, first, 0);
+
+    let rebuilt = stacksafe::petgraph::from_graph(graph, root);
+    assert_eq!(rebuilt, tree(1, vec![tree(2, vec![]), tree(3, vec![])]));
+}
+
+#[test]
+#[should_panic(expected = "the nodes do not form a tree")]
+fn test_cycle() {
+    let mut graph = petgraph::graph::Graph::new();
+    let root = graph.add_node(tree(1, vec![]));
+    let child = graph.add_node(tree(2, vec![]));
+    graph.add_edge(root, child, 0);
+    graph.add_edge(child, root, 0);
+
+    stacksafe::petgraph::from_graph(graph, root);
+}
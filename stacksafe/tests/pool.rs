@@ -0,0 +1,123 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Changes global settings, so it runs as a test binary of its own.
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::SegmentAllocator;
+use stacksafe::stacksafe;
+
+const SEGMENT: usize = 1024 * 1024;
+
+/// Counts allocations of at least a segment's size.
+struct Counting;
+
+static LARGE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+// SAFETY: every method forwards to the system allocator.
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() >= SEGMENT {
+            LARGE_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        // SAFETY: forwarded from the caller.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: forwarded from the caller.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[stacksafe]
+fn depth(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+
+fn large_allocations(f: impl FnOnce()) -> usize {
+    let before = LARGE_ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    LARGE_ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn repeat() {
+    for _ in 0..10 {
+        assert_eq!(depth(100_000), 100_000);
+    }
+}
+
+#[test]
+fn test_pool() {
+    assert_eq!(stacksafe::get_pool_capacity(), 0);
+    stacksafe::set_stack_allocation_size(SEGMENT);
+    stacksafe::set_segment_allocator(SegmentAllocator::Global);
+
+    // Without a pool, every growth allocates a new segment.
+    let allocations = large_allocations(repeat);
+    if allocations == 0 {
+        // This platform leaves every segment to `stacker`.
+        return;
+    }
+    assert!(allocations >= 10);
+
+    // With a pool, the segments are allocated once, and then reused.
+    stacksafe::set_pool_capacity(1000);
+    assert!(large_allocations(repeat) < allocations);
+    assert_eq!(large_allocations(repeat), 0);
+
+    // Freed segments are allocated again.
+    stacksafe::shrink_pool();
+    assert!(large_allocations(repeat) > 0);
+    assert_eq!(large_allocations(repeat), 0);
+
+    // Segments of a different size are not reused.
+    stacksafe::set_stack_allocation_size(2 * SEGMENT);
+    assert!(large_allocations(repeat) > 0);
+    assert_eq!(large_allocations(repeat), 0);
+
+    // Segments of an exact size are always allocated anew, even if a pooled one would fit.
+    assert_eq!(
+        large_allocations(|| stacksafe::on_new_stack(2 * SEGMENT, || ())),
+        1
+    );
+    assert_eq!(
+        large_allocations(|| stacksafe::try_on_new_stack(SEGMENT, || ()).unwrap()),
+        1
+    );
+
+    // A pool that is too small for all segments keeps allocating some.
+    stacksafe::shrink_pool();
+    stacksafe::set_pool_capacity(1);
+    repeat();
+    assert!(large_allocations(repeat) > 0);
+
+    // Segments mapped from the operating system are pooled as well.
+    stacksafe::shrink_pool();
+    stacksafe::set_segment_allocator(SegmentAllocator::System);
+    stacksafe::set_pool_capacity(1000);
+    assert_eq!(large_allocations(repeat), 0);
+
+    // Other threads keep pools of their own.
+    std::thread::spawn(repeat).join().unwrap();
+    stacksafe::shrink_pool();
+}
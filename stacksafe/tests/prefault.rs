@@ -0,0 +1,37 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::SegmentAllocator;
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn depth(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+
+#[test]
+fn test_prefault_segments() {
+    assert!(!stacksafe::get_prefault_segments());
+    stacksafe::set_prefault_segments(true);
+
+    assert_eq!(depth(1_000_000), 1_000_000);
+    assert_eq!(stacksafe::on_new_stack(1024 * 1024, || depth(1000)), 1000);
+
+    stacksafe::set_segment_allocator(SegmentAllocator::Global);
+    assert_eq!(depth(1_000_000), 1_000_000);
+    stacksafe::set_segment_allocator(SegmentAllocator::System);
+
+    stacksafe::set_prefault_segments(false);
+    assert_eq!(depth(1_000_000), 1_000_000);
+}
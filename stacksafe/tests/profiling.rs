@@ -0,0 +1,42 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "profiling")]
+
+use std::backtrace::Backtrace;
+
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn capture(n: u64) -> String {
+    if n == 0 {
+        Backtrace::force_capture().to_string()
+    } else {
+        let trace = capture(n - 1);
+        std::hint::black_box(trace)
+    }
+}
+
+#[inline(never)]
+fn before_switch() -> String {
+    capture(100_000)
+}
+
+#[test]
+fn test_backtrace_crosses_segments() {
+    let trace = before_switch();
+    assert!(trace.contains("segment_entry"));
+    assert!(trace.contains("before_switch"));
+    assert!(trace.contains("test_backtrace_crosses_segments"));
+}
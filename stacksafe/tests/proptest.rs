@@ -0,0 +1,71 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "proptest")]
+
+use proptest::prelude::*;
+use proptest::strategy::LazyJust;
+use proptest::strategy::ValueTree;
+use proptest::test_runner::TestRunner;
+use stacksafe::StackSafe;
+use stacksafe::proptest::StackSafeStrategy;
+use stacksafe::stacksafe;
+
+#[derive(Debug)]
+enum List {
+    Nil,
+    Cons(StackSafe<Box<List>>),
+}
+
+#[stacksafe]
+fn len(list: &List) -> usize {
+    match list {
+        List::Nil => 0,
+        List::Cons(next) => 1 + len(next),
+    }
+}
+
+#[test]
+fn test_proptest_deep() {
+    let mut strategy = LazyJust::new(|| List::Nil).boxed();
+    for _ in 0..100_000 {
+        strategy = StackSafeStrategy::new(strategy.prop_map(Box::new))
+            .prop_map(List::Cons)
+            .boxed();
+    }
+
+    let mut runner = TestRunner::deterministic();
+    let tree = strategy.new_tree(&mut runner).unwrap();
+    assert_eq!(len(&tree.current()), 100_000);
+
+    // The strategy and the tree are nested as deeply as the list, and drop recursively.
+    std::mem::forget(tree);
+    std::mem::forget(strategy);
+}
+
+#[test]
+fn test_proptest_arbitrary() {
+    let mut runner = TestRunner::deterministic();
+    runner
+        .run(&any::<StackSafe<Vec<u8>>>(), |value| {
+            prop_assert!(stacksafe::protect(|| value.len()) < 100);
+            Ok(())
+        })
+        .unwrap();
+
+    // Shrinking is forwarded to the wrapped tree.
+    let mut tree = any::<StackSafe<u32>>().new_tree(&mut runner).unwrap();
+    while tree.simplify() {}
+    assert!(tree.current() == StackSafe::new(0));
+}
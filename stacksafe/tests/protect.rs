@@ -0,0 +1,73 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::panic::catch_unwind;
+
+use stacksafe::StackSafe;
+
+struct Tree {
+    value: u64,
+    children: Vec<StackSafe<Tree>>,
+}
+
+impl Tree {
+    fn chain(depth: u64) -> Tree {
+        (1..=depth).fold(
+            Tree {
+                value: 0,
+                children: Vec::new(),
+            },
+            |tree, value| Tree {
+                value,
+                children: vec![StackSafe::new(tree)],
+            },
+        )
+    }
+}
+
+fn sum(tree: &Tree) -> u64 {
+    tree.children.iter().fold(tree.value, |total, child| {
+        total + stacksafe::protect(|| sum(child))
+    })
+}
+
+fn visit(tree: &Tree, f: &mut dyn FnMut(&Tree)) {
+    f(tree);
+    for child in &tree.children {
+        stacksafe::protect(|| visit(child, &mut *f));
+    }
+}
+
+#[test]
+fn test_protect() {
+    let tree = Tree::chain(100_000);
+    assert_eq!(sum(&tree), 100_000 * 100_001 / 2);
+
+    let mut count = 0;
+    let mut labels = Vec::new();
+    visit(&tree, &mut |tree| {
+        count += 1;
+        if tree.value == 0 {
+            labels.push(stacksafe::current_segment().map(|segment| segment.label()));
+        }
+    });
+    assert_eq!(count, 100_001);
+    assert!(labels[0].is_none_or(|label| label == "stacksafe::protect"));
+
+    drop(tree);
+
+    let value = stacksafe::protect(|| String::from("value"));
+    assert_eq!(value, "value");
+    assert!(catch_unwind(|| stacksafe::protect(|| panic!("boom"))).is_err());
+}
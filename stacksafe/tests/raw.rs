@@ -0,0 +1,86 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::StackSafe;
+use stacksafe::stacksafe;
+
+struct Node {
+    value: u64,
+    next: Option<StackSafe<Box<Node>>>,
+}
+
+fn build(n: u64) -> Option<StackSafe<Box<Node>>> {
+    (0..n).fold(None, |next, value| {
+        Some(StackSafe::boxed(Node { value, next }))
+    })
+}
+
+#[stacksafe]
+fn sum(node: &Option<StackSafe<Box<Node>>>) -> u64 {
+    match node {
+        Some(node) => node.value + sum(&node.next),
+        None => 0,
+    }
+}
+
+#[test]
+fn test_raw_round_trip() {
+    let raw = StackSafe::into_raw(build(100_000).unwrap());
+    // SAFETY: the pointer points to a live node.
+    assert_eq!(unsafe { (*raw).value }, 99_999);
+    // SAFETY: `raw` comes from `into_raw` and is converted back only once.
+    let list = Some(unsafe { StackSafe::from_raw(raw) });
+    assert_eq!(sum(&list), 99_999 * 100_000 / 2);
+
+    // Boxes allocated elsewhere can be adopted as well.
+    let raw = Box::into_raw(Box::new(Node {
+        value: 7,
+        next: None,
+    }));
+    // SAFETY: `raw` comes from `Box::into_raw` and is converted back only once.
+    let node = Some(unsafe { StackSafe::from_raw(raw) });
+    assert_eq!(sum(&node), 7);
+}
+
+#[test]
+fn test_pointers() {
+    let mut wrapped = StackSafe::new(5u64);
+    // SAFETY: the pointer is valid and not aliased.
+    unsafe { *StackSafe::as_mut_ptr(&mut wrapped) += 1 };
+    // SAFETY: the pointer is valid.
+    assert_eq!(unsafe { *StackSafe::as_ptr(&wrapped) }, 6);
+}
+
+#[test]
+fn test_from_mut() {
+    struct Legacy {
+        value: u64,
+        next: Option<Box<Legacy>>,
+    }
+
+    let mut legacy = Legacy {
+        value: 0,
+        next: Some(Box::new(Legacy {
+            value: 1,
+            next: None,
+        })),
+    };
+    let wrapped: &mut StackSafe<Box<Legacy>> = StackSafe::from_mut(legacy.next.as_mut().unwrap());
+    // The previous value is dropped in a stack-safe context.
+    *wrapped = StackSafe::boxed(Legacy {
+        value: 2,
+        next: None,
+    });
+    assert_eq!(legacy.next.unwrap().value, 2);
+}
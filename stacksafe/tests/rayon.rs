@@ -0,0 +1,198 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "rayon")]
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::Dismantle;
+use stacksafe::StackSafe;
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+struct Tree {
+    value: u64,
+    children: Vec<StackSafe<Tree>>,
+}
+
+impl Drop for Tree {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Dismantle for Tree {
+    fn dismantle(&mut self, children: &mut Vec<Self>) {
+        children.extend(self.children.drain(..).map(StackSafe::into_inner));
+    }
+}
+
+// A wide top with a long chain hanging from every leaf.
+fn build(width: u64, depth: u64) -> Tree {
+    let chain = |start: u64| {
+        (start..start + depth).fold(None, |tail: Option<Tree>, value| {
+            Some(Tree {
+                value,
+                children: tail.map(StackSafe::new).into_iter().collect(),
+            })
+        })
+    };
+    Tree {
+        value: 0,
+        children: (0..width)
+            .map(|i| StackSafe::new(chain(1 + i * depth).unwrap()))
+            .collect(),
+    }
+}
+
+#[test]
+fn test_par_visit() {
+    let tree = build(64, 10_000);
+    let sum = AtomicU64::new(0);
+    let count = AtomicU64::new(0);
+    stacksafe::rayon::par_visit(
+        &tree,
+        |node| node.children.iter().map(|child| &**child),
+        |node| {
+            sum.fetch_add(node.value, Ordering::Relaxed);
+            count.fetch_add(1, Ordering::Relaxed);
+        },
+    );
+    let n = 64 * 10_000;
+    assert_eq!(count.into_inner(), n + 1);
+    assert_eq!(sum.into_inner(), n * (n + 1) / 2);
+    stacksafe::rayon::par_drop(tree);
+}
+
+#[test]
+fn test_par_drop() {
+    let tree = build(64, 10_000);
+    let before = DROPPED.load(Ordering::SeqCst);
+    stacksafe::rayon::par_drop(tree);
+    assert!(DROPPED.load(Ordering::SeqCst) - before > 64 * 10_000);
+
+    let chain = build(1, 1_000_000);
+    stacksafe::rayon::par_drop(chain);
+}
+
+#[test]
+fn test_start_handler() {
+    static STARTS: AtomicUsize = AtomicUsize::new(0);
+
+    stacksafe::install_global(stacksafe::GlobalSetup::new().on_thread_start(|| {
+        STARTS.fetch_add(1, Ordering::Relaxed);
+    }))
+    .unwrap();
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(3)
+        .start_handler(stacksafe::rayon::start_handler())
+        .build()
+        .unwrap();
+    pool.broadcast(|_| ());
+    assert_eq!(STARTS.load(Ordering::Relaxed), 3);
+}
+
+#[stacksafe::stacksafe]
+fn chain_sum(node: &Tree) -> u64 {
+    node.value
+        + node
+            .children
+            .iter()
+            .map(|child| chain_sum(child))
+            .sum::<u64>()
+}
+
+#[test]
+fn test_guarded_iterator() {
+    use rayon::prelude::*;
+    use stacksafe::rayon::ParallelIteratorExt;
+
+    let tree = build(64, 10_000);
+    let sum: u64 = tree
+        .children
+        .par_iter()
+        .stacksafe()
+        .map(|child| {
+            assert!(stacksafe::internal::is_protected());
+            chain_sum(child)
+        })
+        .sum();
+    let n = 64 * 10_000;
+    assert_eq!(sum, n * (n + 1) / 2);
+    stacksafe::rayon::par_drop(tree);
+
+    let values: Vec<StackSafe<u64>> = (0..1000).map(StackSafe::new).collect();
+    let mut doubled = Vec::new();
+    values
+        .par_iter()
+        .enumerate()
+        .stacksafe()
+        .map(|(i, value)| (i, **value * 2))
+        .collect_into_vec(&mut doubled);
+    assert!(doubled.iter().all(|&(i, value)| value == i as u64 * 2));
+
+    let zipped: Vec<u64> = values
+        .par_iter()
+        .zip(values.par_iter())
+        .stacksafe()
+        .map(|(a, b)| **a + **b)
+        .collect();
+    assert_eq!(zipped[999], 1998);
+}
+
+#[stacksafe::stacksafe]
+fn segment_size(n: u64) -> Option<usize> {
+    if n == 0 {
+        stacksafe::current_segment().map(|segment| segment.size())
+    } else {
+        segment_size(n - 1)
+    }
+}
+
+#[test]
+fn test_join_inherits_context() {
+    let value = StackSafe::new(1);
+    let config = stacksafe::StackConfig::default().stack_allocation_size(16 * 1024 * 1024);
+    let (a, b) = config
+        .scope(|| {
+            stacksafe::protect(|| stacksafe::rayon::join(|| *value + 1, || segment_size(1_000_000)))
+        })
+        .unwrap();
+    assert_eq!(a, 2);
+    if let Some(size) = b {
+        assert!(size >= 16 * 1024 * 1024);
+    }
+}
+
+#[test]
+fn test_guarded_iterator_inherits_config() {
+    use rayon::prelude::*;
+    use stacksafe::rayon::ParallelIteratorExt;
+
+    let config = stacksafe::StackConfig::default().stack_allocation_size(16 * 1024 * 1024);
+    let sizes: Vec<Option<usize>> = config
+        .scope(|| {
+            (0..8)
+                .into_par_iter()
+                .stacksafe()
+                .map(|_| segment_size(1_000_000))
+                .collect()
+        })
+        .unwrap();
+    for size in sizes.into_iter().flatten() {
+        assert!(size >= 16 * 1024 * 1024);
+    }
+}
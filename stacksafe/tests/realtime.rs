@@ -0,0 +1,90 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::SegmentAllocator;
+use stacksafe::realtime::RealtimeConfig;
+use stacksafe::stacksafe;
+
+/// Counts allocations that are at least as large as a stack segment.
+struct CountingAllocator;
+
+static SEGMENT_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() >= stacksafe::get_stack_allocation_size() {
+            SEGMENT_ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[stacksafe]
+fn depth(n: u64) -> u64 {
+    if n == 0 {
+        0
+    } else {
+        1 + std::hint::black_box(depth(n - 1))
+    }
+}
+
+#[test]
+fn reserved_segments() {
+    stacksafe::set_segment_allocator(SegmentAllocator::Global);
+
+    let result = std::thread::spawn(|| {
+        if let Err(err) = stacksafe::realtime::init(&RealtimeConfig::new(64)) {
+            assert!(matches!(err, stacksafe::Error::Unsupported { .. }));
+            return;
+        }
+        let before = SEGMENT_ALLOCATIONS.load(Ordering::SeqCst);
+        assert_eq!(depth(100_000), 100_000);
+        assert_eq!(SEGMENT_ALLOCATIONS.load(Ordering::SeqCst), before);
+
+        // Segments are only reserved for the thread that called `init`.
+        let before = SEGMENT_ALLOCATIONS.load(Ordering::SeqCst);
+        std::thread::scope(|s| {
+            s.spawn(|| assert_eq!(depth(100_000), 100_000));
+        });
+        assert!(SEGMENT_ALLOCATIONS.load(Ordering::SeqCst) > before);
+
+        // Nothing can be reserved while running on a grown segment.
+        stacksafe::on_new_stack(1024 * 1024, || {
+            let err = stacksafe::realtime::init(&RealtimeConfig::new(1)).unwrap_err();
+            assert!(matches!(err, stacksafe::Error::Unsupported { .. }));
+        });
+
+        stacksafe::realtime::release();
+        let before = SEGMENT_ALLOCATIONS.load(Ordering::SeqCst);
+        assert_eq!(depth(100_000), 100_000);
+        assert!(SEGMENT_ALLOCATIONS.load(Ordering::SeqCst) > before);
+    })
+    .join();
+
+    stacksafe::set_segment_allocator(SegmentAllocator::System);
+    result.unwrap();
+}
@@ -0,0 +1,70 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "rkyv")]
+
+use rkyv::Archive;
+use rkyv::Deserialize;
+use rkyv::Serialize;
+use rkyv::rancor::Error;
+use stacksafe::StackSafe;
+use stacksafe::stacksafe;
+
+#[derive(Archive, Serialize, Deserialize)]
+#[rkyv(
+    serialize_bounds(
+        __S: rkyv::ser::Writer + rkyv::ser::Allocator,
+        __S::Error: rkyv::rancor::Source,
+    ),
+    deserialize_bounds(__D::Error: rkyv::rancor::Source),
+    bytecheck(bounds(
+        __C: rkyv::validation::ArchiveContext,
+        __C::Error: rkyv::rancor::Source,
+    )),
+)]
+enum List {
+    Nil,
+    Cons(#[rkyv(omit_bounds)] StackSafe<Box<List>>),
+}
+
+#[stacksafe]
+fn len(list: &List) -> usize {
+    match list {
+        List::Nil => 0,
+        List::Cons(next) => 1 + len(next),
+    }
+}
+
+#[stacksafe]
+fn archived_len(list: &ArchivedList) -> usize {
+    match list {
+        ArchivedList::Nil => 0,
+        ArchivedList::Cons(next) => 1 + archived_len(next),
+    }
+}
+
+#[test]
+fn test_rkyv_deep() {
+    let mut list = List::Nil;
+    for _ in 0..100_000 {
+        list = List::Cons(StackSafe::new(Box::new(list)));
+    }
+
+    let bytes = rkyv::to_bytes::<Error>(&list).unwrap();
+    let archived = rkyv::access::<ArchivedList, Error>(&bytes).unwrap();
+    assert_eq!(archived_len(archived), 100_000);
+
+    let copy = rkyv::deserialize::<List, Error>(archived).unwrap();
+    assert_eq!(len(&copy), 100_000);
+}
@@ -0,0 +1,47 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::StackSafe;
+
+fn depth(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+
+#[test]
+fn test_plain_recursion() {
+    let n = 1_000_000;
+    assert_eq!(stacksafe::run_with_stack(1 << 30, || depth(n)), n);
+}
+
+#[test]
+fn test_borrow_and_protect() {
+    let values: Vec<StackSafe<u64>> = (1..=10).map(StackSafe::new).collect();
+    let sum = stacksafe::run_with_stack(1 << 20, || {
+        assert!(stacksafe::internal::is_protected());
+        values.iter().map(|value| **value).sum::<u64>()
+    });
+    assert_eq!(sum, 55);
+}
+
+#[test]
+fn test_panic_propagates() {
+    let result = std::panic::catch_unwind(|| {
+        stacksafe::run_with_stack(1 << 20, || panic!("from the large stack"))
+    });
+    let payload = result.unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<&str>(),
+        Some(&"from the large stack")
+    );
+}
@@ -0,0 +1,147 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "tracing")]
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use stacksafe::GrowthSampling;
+use tracing::Metadata;
+use tracing::Subscriber;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span;
+
+/// A subscriber that keeps the fields of the growth events.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<(String, u64, u64)>>>);
+
+#[derive(Default)]
+struct Growth(String, u64, u64);
+
+impl Visit for Growth {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "label" {
+            self.0 = value.to_owned();
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "size" => self.1 = value,
+            "depth" => self.2 = value,
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target() == "stacksafe"
+    }
+
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        let mut growth = Growth::default();
+        event.record(&mut growth);
+        self.0.lock().unwrap().push((growth.0, growth.1, growth.2));
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+const SIZE: usize = 256 * 1024;
+
+/// Grows the stack `n` times and returns how many growth events were emitted.
+fn grow(n: usize) -> usize {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        for _ in 0..n {
+            stacksafe::on_new_stack(SIZE, || ());
+        }
+    });
+    recorder.0.lock().unwrap().len()
+}
+
+// The sampling is global, so it is only changed by this test.
+#[test]
+fn test_growth_sampling() {
+    assert_eq!(stacksafe::get_growth_sampling(), GrowthSampling::All);
+
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        stacksafe::on_new_stack(SIZE, || stacksafe::on_new_stack(SIZE, || ()));
+    });
+    assert_eq!(*recorder.0.lock().unwrap(), [
+        ("stacksafe::on_new_stack".to_owned(), SIZE as u64, 1),
+        ("stacksafe::on_new_stack".to_owned(), SIZE as u64, 2),
+    ]);
+    assert_eq!(grow(10), 10);
+
+    // The first event and every fourth after it.
+    stacksafe::set_growth_sampling(GrowthSampling::EveryNth(4));
+    assert_eq!(
+        stacksafe::get_growth_sampling(),
+        GrowthSampling::EveryNth(4)
+    );
+    assert_eq!(grow(10), 3);
+
+    stacksafe::set_growth_sampling(GrowthSampling::Probability(0.0));
+    assert_eq!(grow(100), 0);
+    stacksafe::set_growth_sampling(GrowthSampling::Probability(1.0));
+    assert_eq!(grow(100), 100);
+    stacksafe::set_growth_sampling(GrowthSampling::Probability(0.5));
+    assert_eq!(
+        stacksafe::get_growth_sampling(),
+        GrowthSampling::Probability(0.5)
+    );
+    let sampled = grow(1000);
+    assert!(
+        (350..650).contains(&sampled),
+        "{sampled} of 1000 events sampled"
+    );
+
+    stacksafe::set_growth_sampling(GrowthSampling::Probability(f64::NAN));
+    assert_eq!(
+        stacksafe::get_growth_sampling(),
+        GrowthSampling::Probability(0.0)
+    );
+    stacksafe::set_growth_sampling(GrowthSampling::Probability(2.0));
+    assert_eq!(
+        stacksafe::get_growth_sampling(),
+        GrowthSampling::Probability(1.0)
+    );
+    stacksafe::set_growth_sampling(GrowthSampling::EveryNth(0));
+    assert_eq!(grow(10), 10);
+
+    // Counters are not sampled.
+    stacksafe::set_growth_sampling(GrowthSampling::Probability(0.0));
+    let ((), explanation) = stacksafe::explain(|| {
+        stacksafe::on_new_stack(SIZE, || ());
+    });
+    assert_eq!(explanation.growths().len(), 1);
+    stacksafe::set_growth_sampling(GrowthSampling::All);
+}
@@ -0,0 +1,36 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "schemars")]
+
+use schemars::JsonSchema;
+use schemars::schema_for;
+use stacksafe::StackSafe;
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Tree {
+    value: u32,
+    children: Vec<StackSafe<Tree>>,
+}
+
+#[test]
+fn test_schemars_transparent() {
+    assert_eq!(schema_for!(StackSafe<u32>), schema_for!(u32));
+    assert_eq!(StackSafe::<Tree>::schema_name(), Tree::schema_name());
+
+    // The recursive field refers back to the root schema.
+    let schema = schema_for!(Tree).to_value();
+    assert_eq!(schema["properties"]["children"]["items"]["$ref"], "#");
+}
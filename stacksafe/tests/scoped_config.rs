@@ -0,0 +1,96 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::panic::catch_unwind;
+
+use stacksafe::ConfigError;
+use stacksafe::StackConfig;
+use stacksafe::stacksafe;
+
+const SIZE: usize = 16 * 1024 * 1024;
+
+#[stacksafe]
+fn segment_size(n: u64) -> Option<usize> {
+    if n == 0 {
+        stacksafe::current_segment().map(|segment| segment.size())
+    } else {
+        segment_size(n - 1)
+    }
+}
+
+#[stacksafe(profile = "scoped_config")]
+fn profiled(n: u64) -> Option<usize> {
+    if n == 0 {
+        stacksafe::current_segment().map(|segment| segment.size())
+    } else {
+        profiled(n - 1)
+    }
+}
+
+fn config(size: usize) -> StackConfig {
+    StackConfig::default().stack_allocation_size(size)
+}
+
+#[test]
+fn test_scope() {
+    let size = config(SIZE).scope(|| {
+        assert_eq!(StackConfig::current(), config(SIZE));
+        segment_size(1_000_000)
+    });
+    assert!(size.unwrap().is_none_or(|size| size >= SIZE));
+    assert_eq!(StackConfig::current(), StackConfig::default());
+    assert!(segment_size(1_000_000).is_none_or(|size| size < SIZE));
+}
+
+#[test]
+fn test_nested_scopes() {
+    config(SIZE)
+        .scope(|| {
+            config(2 * SIZE).scope(|| {
+                assert!(segment_size(1_000_000).is_none_or(|size| size >= 2 * SIZE));
+            })?;
+            assert_eq!(StackConfig::current(), config(SIZE));
+            assert!(catch_unwind(|| config(2 * SIZE).scope(|| panic!("boom"))).is_err());
+            assert_eq!(StackConfig::current(), config(SIZE));
+            Ok::<_, ConfigError>(())
+        })
+        .unwrap()
+        .unwrap();
+}
+
+#[test]
+fn test_scope_is_thread_local() {
+    config(SIZE)
+        .scope(|| {
+            let other = std::thread::spawn(StackConfig::current).join().unwrap();
+            assert_eq!(other, StackConfig::default());
+        })
+        .unwrap();
+}
+
+#[test]
+fn test_profiles_take_precedence() {
+    stacksafe::register_profile("scoped_config", config(4 * 1024 * 1024)).unwrap();
+    let size = config(SIZE).scope(|| profiled(1_000_000)).unwrap();
+    assert!(size.is_none_or(|size| size < SIZE));
+}
+
+#[test]
+fn test_invalid_scope() {
+    let err = StackConfig::default()
+        .minimum_stack_size(0)
+        .scope(|| unreachable!())
+        .unwrap_err();
+    assert_eq!(err, ConfigError::ZeroMinimumStackSize);
+}
@@ -0,0 +1,130 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::SegmentAllocator;
+use stacksafe::stacksafe;
+
+/// Counts allocations that are at least as large as a stack segment.
+struct CountingAllocator;
+
+static SEGMENT_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+// Allocations larger than this fail.
+const ALLOCATION_LIMIT: usize = 6 * 1024 * 1024;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() > ALLOCATION_LIMIT {
+            return std::ptr::null_mut();
+        }
+        if layout.size() >= stacksafe::get_stack_allocation_size() {
+            SEGMENT_ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[stacksafe]
+fn depth(n: u64) -> u64 {
+    if n == 0 {
+        0
+    } else {
+        std::hint::black_box(depth(n - 1)) + 1
+    }
+}
+
+#[stacksafe]
+fn panic_at(n: u64) -> u64 {
+    if n == 0 {
+        panic!("bottom reached");
+    }
+    1 + panic_at(n - 1)
+}
+
+#[test]
+fn test_global_segments() {
+    stacksafe::set_segment_allocator(SegmentAllocator::Global);
+    assert_eq!(stacksafe::get_segment_allocator(), SegmentAllocator::Global);
+
+    let before = SEGMENT_ALLOCATIONS.load(Ordering::SeqCst);
+    assert_eq!(depth(1_000_000), 1_000_000);
+    assert!(SEGMENT_ALLOCATIONS.load(Ordering::SeqCst) > before);
+
+    let before = SEGMENT_ALLOCATIONS.load(Ordering::SeqCst);
+    let n = stacksafe::on_new_stack(4 * 1024 * 1024, || depth(100_000));
+    assert_eq!(n, 100_000);
+    assert!(SEGMENT_ALLOCATIONS.load(Ordering::SeqCst) > before);
+}
+
+#[test]
+fn test_panic_in_global_segment() {
+    stacksafe::set_segment_allocator(SegmentAllocator::Global);
+
+    let result = std::panic::catch_unwind(|| panic_at(1_000_000));
+    assert!(result.is_err());
+    assert_eq!(depth(1_000_000), 1_000_000);
+}
+
+#[test]
+fn test_fallback_size() {
+    use std::sync::Mutex;
+
+    static FALLBACKS: Mutex<Vec<stacksafe::SegmentFallback>> = Mutex::new(Vec::new());
+
+    stacksafe::set_segment_allocator(SegmentAllocator::Global);
+    stacksafe::set_fallback_hook(|fallback| FALLBACKS.lock().unwrap().push(*fallback));
+
+    // 16 MiB and 8 MiB segments cannot be allocated, 4 MiB ones can.
+    const SIZE: usize = 16 * 1024 * 1024;
+    let n = stacksafe::raw::maybe_grow(SIZE - 1, SIZE, || depth(100_000));
+    assert_eq!(n, 100_000);
+    // Segments of an exact size are not retried.
+    if !cfg!(windows) {
+        assert!(stacksafe::try_on_new_stack(SIZE, || ()).is_err());
+    }
+    stacksafe::clear_fallback_hook();
+
+    let fallbacks: Vec<_> = FALLBACKS.lock().unwrap().clone();
+    assert!(
+        fallbacks
+            .iter()
+            .all(|f| f.label() != "stacksafe::try_on_new_stack")
+    );
+    let fallbacks: Vec<_> = fallbacks
+        .iter()
+        .filter(|f| f.label() == "stacksafe::raw::maybe_grow")
+        .map(|f| (f.requested(), f.size()))
+        .collect();
+    if cfg!(windows) {
+        // Segments always come from `stacker` there.
+        return;
+    }
+    assert_eq!(fallbacks, [
+        (16 * 1024 * 1024, 8 * 1024 * 1024),
+        (16 * 1024 * 1024, 4 * 1024 * 1024)
+    ]);
+}
@@ -0,0 +1,241 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "serde")]
+
+use serde::Deserialize;
+use serde::Serialize;
+use stacksafe::Assemble;
+use stacksafe::Dismantle;
+use stacksafe::StackSafe;
+use stacksafe::serde::DepthLimited;
+use stacksafe::serde::StackSafeDeserializer;
+use stacksafe::serde::StackSafeSerializer;
+use stacksafe::serde::deserialize_preorder;
+use stacksafe::serde::deserialize_with_depth_limit;
+use stacksafe::stacksafe;
+
+#[derive(Debug, Deserialize, PartialEq)]
+enum List {
+    Nil,
+    Cons(StackSafe<Box<List>>),
+}
+
+fn nested(depth: usize) -> String {
+    let mut json = String::from("\"Nil\"");
+    for _ in 0..depth {
+        json = format!("{{\"Cons\":{json}}}");
+    }
+    json
+}
+
+fn deserialize_limited(json: &str, limit: usize) -> Result<List, serde_json::Error> {
+    let mut de = serde_json::Deserializer::from_str(json);
+    deserialize_with_depth_limit(&mut de, limit)
+}
+
+#[test]
+fn test_depth_limit() {
+    let json = nested(100);
+    assert!(deserialize_limited(&json, 100).is_ok());
+
+    let err = deserialize_limited(&json, 99).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("the depth limit of 99 was exceeded")
+    );
+
+    // The depth is tracked correctly after a failure.
+    assert!(deserialize_limited(&json, 100).is_ok());
+    assert!(serde_json::from_str::<List>(&json).is_ok());
+}
+
+#[test]
+fn test_depth_limited_field() {
+    #[derive(Deserialize)]
+    struct Request {
+        lists: Vec<DepthLimited<List, 10>>,
+    }
+
+    let json = format!("{{\"lists\":[{},{}]}}", nested(10), nested(5));
+    let request: Request = serde_json::from_str(&json).unwrap();
+    assert_eq!(request.lists.len(), 2);
+
+    let json = format!("{{\"lists\":[{},{}]}}", nested(5), nested(11));
+    assert!(serde_json::from_str::<Request>(&json).is_err());
+}
+
+#[test]
+fn test_nested_limits() {
+    #[derive(Deserialize)]
+    struct Outer {
+        #[allow(dead_code)]
+        inner: StackSafe<Inner>,
+    }
+
+    #[derive(Deserialize)]
+    struct Inner {
+        #[allow(dead_code)]
+        list: DepthLimited<List, 100>,
+    }
+
+    // The outer limit counts the levels above the inner one, and stays in effect.
+    let json = format!("{{\"inner\":{{\"list\":{}}}}}", nested(10));
+    assert!(serde_json::from_str::<DepthLimited<Outer, 11>>(&json).is_ok());
+    assert!(serde_json::from_str::<DepthLimited<Outer, 10>>(&json).is_err());
+
+    // The inner limit applies on its own.
+    let json = format!("{{\"inner\":{{\"list\":{}}}}}", nested(101));
+    assert!(serde_json::from_str::<Outer>(&json).is_err());
+}
+
+#[derive(Debug, Deserialize)]
+struct Tree {
+    value: u64,
+    #[serde(skip)]
+    children: Vec<StackSafe<Tree>>,
+}
+
+impl Assemble for Tree {
+    fn assemble(&mut self, children: Vec<Self>) {
+        self.children
+            .extend(children.into_iter().map(StackSafe::new));
+    }
+}
+
+/// Returns the values along the first children of `tree`.
+#[stacksafe]
+fn spine(mut tree: &Tree) -> Vec<u64> {
+    let mut values = vec![tree.value];
+    while let Some(child) = tree.children.first() {
+        tree = child;
+        values.push(tree.value);
+    }
+    values
+}
+
+fn from_preorder(json: &str) -> Result<Tree, serde_json::Error> {
+    let mut de = serde_json::Deserializer::from_str(json);
+    deserialize_preorder(&mut de)
+}
+
+#[test]
+fn test_preorder_deep() {
+    let depth = 1_000_000;
+    let nodes: Vec<String> = (0..depth)
+        .map(|i| format!("[{{\"value\":{i}}},{}]", usize::from(i + 1 < depth)))
+        .collect();
+    let json = format!("[{}]", nodes.join(","));
+
+    let (tree, explanation) = stacksafe::explain(|| from_preorder(&json).unwrap());
+    assert!(explanation.growths().is_empty());
+    assert!(spine(&tree).into_iter().eq(0..depth as u64));
+}
+
+#[test]
+fn test_preorder_shape() {
+    let json =
+        r#"[[{"value":1},3],[{"value":2},1],[{"value":3},0],[{"value":4},0],[{"value":5},0]]"#;
+    let tree = from_preorder(json).unwrap();
+    assert_eq!(spine(&tree), [1, 2, 3]);
+    assert_eq!(tree.children.len(), 3);
+}
+
+#[test]
+fn test_preorder_invalid() {
+    let missing = from_preorder(r#"[[{"value":1},2],[{"value":2},0]]"#).unwrap_err();
+    assert!(
+        missing.to_string().starts_with("invalid length 2"),
+        "{missing}"
+    );
+
+    let trailing = from_preorder(r#"[[{"value":1},0],[{"value":2},0]]"#).unwrap_err();
+    assert!(
+        trailing.to_string().starts_with("trailing nodes"),
+        "{trailing}"
+    );
+
+    assert!(from_preorder("[]").is_err());
+    assert!(from_preorder(r#"{"value":1}"#).is_err());
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+enum Plain {
+    Nil,
+    Cons(Box<Plain>),
+    Pair(Option<Box<Plain>>, Vec<Plain>),
+}
+
+impl Dismantle for Plain {
+    fn dismantle(&mut self, stack: &mut Vec<Self>) {
+        match self {
+            Plain::Nil => {}
+            Plain::Cons(next) => stack.push(std::mem::replace(next, Plain::Nil)),
+            Plain::Pair(first, rest) => {
+                stack.extend(first.take().map(|first| *first));
+                stack.append(rest);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_adapters_deep() {
+    let depth = 100_000;
+    let mut json = String::from("\"Nil\"");
+    for i in 0..depth {
+        json = match i % 3 {
+            0 => format!("{{\"Cons\":{json}}}"),
+            1 => format!("{{\"Pair\":[{json},[]]}}"),
+            _ => format!("{{\"Pair\":[null,[\"Nil\",{json}]]}}"),
+        };
+    }
+
+    let mut de = serde_json::Deserializer::from_str(&json);
+    de.disable_recursion_limit();
+    let (value, explanation) =
+        stacksafe::explain(|| Plain::deserialize(StackSafeDeserializer::new(&mut de)).unwrap());
+    de.end().unwrap();
+    assert!(!explanation.growths().is_empty());
+
+    let mut out = Vec::new();
+    let mut ser = serde_json::Serializer::new(&mut out);
+    value.serialize(StackSafeSerializer::new(&mut ser)).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), json);
+
+    stacksafe::drop_all_flattened([value]);
+}
+
+#[test]
+fn test_adapters_transparent() {
+    let value = Plain::Pair(Some(Box::new(Plain::Cons(Box::new(Plain::Nil)))), vec![
+        Plain::Nil,
+    ]);
+    let json = serde_json::to_string(&value).unwrap();
+
+    let mut de = serde_json::Deserializer::from_str(&json);
+    assert_eq!(
+        Plain::deserialize(StackSafeDeserializer::new(&mut de)).unwrap(),
+        value
+    );
+
+    let mut out = Vec::new();
+    let mut ser = serde_json::Serializer::new(&mut out);
+    value.serialize(StackSafeSerializer::new(&mut ser)).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), json);
+
+    // Errors of the wrapped deserializer are passed through.
+    let mut de = serde_json::Deserializer::from_str(r#"{"Cons":{"Pair":[null]}}"#);
+    assert!(Plain::deserialize(StackSafeDeserializer::new(&mut de)).is_err());
+}
@@ -0,0 +1,100 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "serde_json")]
+
+use serde::Deserialize;
+use serde_json::Map;
+use serde_json::Value;
+use serde_json::json;
+use stacksafe::serde_json::JsonValue;
+
+const DEPTH: usize = 100_000;
+
+fn nested(depth: usize) -> JsonValue {
+    let mut value = JsonValue::new(json!({"leaf": [1, 2.5, "three", null, true]}));
+    for i in 0..depth {
+        value = if i % 2 == 0 {
+            JsonValue::new(Value::Array(vec![Value::from(i), value.into_inner()]))
+        } else {
+            let mut map = Map::new();
+            map.insert("next".to_owned(), value.into_inner());
+            JsonValue::new(Value::Object(map))
+        };
+    }
+    value
+}
+
+fn parse(json: &str) -> serde_json::Result<JsonValue> {
+    let mut de = serde_json::Deserializer::from_str(json);
+    de.disable_recursion_limit();
+    let value = JsonValue::deserialize(&mut de)?;
+    de.end()?;
+    Ok(value)
+}
+
+#[test]
+fn test_shallow_values_match_serde_json() {
+    let value = json!({"a": [1, -2, 3.5, "x", null, false, {"b": {}}], "c": []});
+    let wrapped = JsonValue::new(value.clone());
+    assert_eq!(format!("{wrapped:?}"), format!("{value:?}"));
+    assert_eq!(wrapped.to_string(), value.to_string());
+    assert_eq!(format!("{wrapped:#}"), format!("{value:#}"));
+    assert_eq!(serde_json::to_string(&wrapped).unwrap(), value.to_string());
+    assert_eq!(parse(&value.to_string()).unwrap(), value);
+    assert_eq!(wrapped.clone().into_inner(), value);
+
+    assert_ne!(JsonValue::new(json!([1, [2]])), json!([1, [3]]));
+    assert_ne!(JsonValue::new(json!({"a": 1})), json!({"b": 1}));
+    assert_ne!(JsonValue::new(json!([1])), json!({"0": 1}));
+    assert_ne!(JsonValue::new(json!([])), json!(null));
+}
+
+#[test]
+fn test_deep_values() {
+    let value = nested(DEPTH);
+    let copy = value.clone();
+    assert!(copy == value);
+    assert!(format!("{value:?}").starts_with("Object {\"next\": Array [Number(99998), Object {"));
+
+    let json = serde_json::to_string(&value).unwrap();
+    assert_eq!(json, value.to_string());
+    let parsed = parse(&json).unwrap();
+    assert!(parsed == value);
+
+    let mut other = nested(DEPTH);
+    *other.pointer_mut("/next/0").unwrap() = json!(-1);
+    assert!(other != value);
+}
+
+#[test]
+fn test_depth_limit() {
+    let json = nested(1000).to_string();
+    let mut de = serde_json::Deserializer::from_str(&json);
+    de.disable_recursion_limit();
+    assert!(stacksafe::serde::deserialize_with_depth_limit::<JsonValue, _>(&mut de, 100).is_err());
+
+    let mut de = serde_json::Deserializer::from_str(&json);
+    de.disable_recursion_limit();
+    assert!(stacksafe::serde::deserialize_with_depth_limit::<JsonValue, _>(&mut de, 2000).is_ok());
+
+    // Truncated input drops what has been parsed so far.
+    assert!(parse(&json[..json.len() - 1]).is_err());
+}
+
+#[test]
+fn test_flattened_drop() {
+    let values: Vec<Value> = (0..10).map(|_| nested(DEPTH).into_inner()).collect();
+    stacksafe::drop_all_flattened(values);
+}
@@ -0,0 +1,71 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(unix)]
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use stacksafe::stacksafe;
+
+static RESULT: AtomicU64 = AtomicU64::new(0);
+
+#[stacksafe]
+fn depth(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+
+extern "C" fn handler(_: libc::c_int) {
+    RESULT.store(depth(10), Ordering::Relaxed);
+}
+
+#[test]
+fn test_no_growth_on_signal_stack() {
+    assert!(stacksafe::sigaltstack_compatible());
+
+    std::thread::spawn(|| {
+        let mut stack = vec![0u8; 1024 * 1024];
+        // SAFETY: the alternate stack is disabled again before its memory is freed, and the
+        // handler only touches atomics and the stack.
+        unsafe {
+            let altstack = libc::stack_t {
+                ss_sp: stack.as_mut_ptr().cast(),
+                ss_flags: 0,
+                ss_size: stack.len(),
+            };
+            assert_eq!(libc::sigaltstack(&altstack, std::ptr::null_mut()), 0);
+
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_ONSTACK;
+            assert_eq!(
+                libc::sigaction(libc::SIGUSR1, &action, std::ptr::null_mut()),
+                0
+            );
+            assert_eq!(libc::raise(libc::SIGUSR1), 0);
+
+            let disable = libc::stack_t {
+                ss_sp: std::ptr::null_mut(),
+                ss_flags: libc::SS_DISABLE,
+                ss_size: 0,
+            };
+            assert_eq!(libc::sigaltstack(&disable, std::ptr::null_mut()), 0);
+        }
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(RESULT.load(Ordering::Relaxed), 10);
+    assert!(stacksafe::growth_hotspots().is_empty());
+}
@@ -0,0 +1,92 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::rc::Rc;
+
+use stacksafe::StackSafeSmallBox;
+use stacksafe::stacksafe;
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+enum Expr {
+    Num(i64),
+    Neg(StackSafeSmallBox<Expr, 16>),
+    Add(StackSafeSmallBox<Expr, 16>, StackSafeSmallBox<Expr, 16>),
+}
+
+fn negate(depth: u64) -> Expr {
+    (0..depth).fold(Expr::Num(1), |expr, _| {
+        Expr::Neg(StackSafeSmallBox::new(expr))
+    })
+}
+
+#[stacksafe]
+fn eval(expr: &Expr) -> i64 {
+    match expr {
+        Expr::Num(value) => *value,
+        Expr::Neg(expr) => -eval(expr),
+        Expr::Add(lhs, rhs) => eval(lhs) + eval(rhs),
+    }
+}
+
+#[stacksafe]
+fn unwrap(small: StackSafeSmallBox<Rc<u64>, 8>) -> Rc<u64> {
+    small.into_inner()
+}
+
+#[test]
+fn test_layout() {
+    assert!(StackSafeSmallBox::<u64, 8>::new(1).is_inline());
+    assert!(StackSafeSmallBox::<[u8; 16], 16>::new([0; 16]).is_inline());
+    assert!(!StackSafeSmallBox::<[u8; 17], 16>::new([0; 17]).is_inline());
+    assert!(!StackSafeSmallBox::<u128, 16>::new(0).is_inline());
+    assert!(!StackSafeSmallBox::<Expr, 16>::new(Expr::Num(0)).is_inline());
+
+    assert_eq!(size_of::<StackSafeSmallBox<u8, 0>>(), size_of::<usize>());
+    assert_eq!(size_of::<StackSafeSmallBox<u8, 16>>(), 16);
+    assert_eq!(size_of::<StackSafeSmallBox<u8, 17>>(), 24);
+}
+
+#[test]
+fn test_drop_and_into_inner() {
+    let value = Rc::new(7);
+    let inline = StackSafeSmallBox::<_, 8>::new(value.clone());
+    let heap = StackSafeSmallBox::<_, 0>::new(value.clone());
+    assert!(inline.is_inline());
+    assert!(!heap.is_inline());
+    assert_eq!(Rc::strong_count(&value), 3);
+
+    drop(heap);
+    assert_eq!(Rc::strong_count(&value), 2);
+
+    let unwrapped = unwrap(inline);
+    assert_eq!(Rc::strong_count(&value), 2);
+    drop(unwrapped);
+    assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_deep_structure() {
+    let expr = negate(1_000_000);
+    assert_eq!(eval(&expr), 1);
+
+    let cloned = expr.clone();
+    assert_eq!(cloned, expr);
+    assert!(
+        Expr::Add(
+            StackSafeSmallBox::new(Expr::Num(1)),
+            StackSafeSmallBox::new(Expr::Num(2))
+        ) > expr
+    );
+    drop(cloned);
+}
@@ -0,0 +1,150 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::hash::BuildHasher;
+use std::hash::RandomState;
+
+use stacksafe::stacksafe_type;
+
+#[stacksafe_type(placeholder = Expr::Num(0))]
+enum Expr {
+    Num(i64),
+    Neg(Box<Expr>),
+    Add { lhs: Box<Expr>, rhs: Box<Expr> },
+}
+
+#[stacksafe_type(placeholder = Node::new(None))]
+struct Node<T> {
+    value: Option<T>,
+    children: Vec<Node<T>>,
+    next: Option<Box<Node<T>>>,
+}
+
+impl<T> Node<T> {
+    fn new(value: Option<T>) -> Self {
+        Node {
+            value,
+            children: Vec::new(),
+            next: None,
+        }
+    }
+}
+
+#[stacksafe_type(placeholder = Float::Leaf, skip(Hash, Debug))]
+enum Float {
+    Leaf,
+    Node(f64, Box<Float>),
+}
+
+fn chain(depth: usize) -> Expr {
+    (0..depth).fold(Expr::Num(1), |expr, _| Expr::Neg(Box::new(expr)))
+}
+
+#[test]
+fn test_shallow() {
+    let expr = Expr::Add {
+        lhs: Box::new(Expr::Num(1)),
+        rhs: Box::new(Expr::Neg(Box::new(Expr::Num(2)))),
+    };
+    assert_eq!(format!("{expr:?}"), "Add { lhs: Num(1), rhs: Neg(Num(2)) }");
+    assert_eq!(format!("{expr:#?}"), format!("{:#?}", expr.clone()));
+    assert!(expr == expr.clone());
+    assert!(expr != Expr::Num(1));
+    assert!(Expr::Num(1) != Expr::Num(2));
+
+    let hasher = RandomState::new();
+    assert_eq!(hasher.hash_one(&expr), hasher.hash_one(expr.clone()));
+    assert_ne!(
+        hasher.hash_one(Expr::Num(1)),
+        hasher.hash_one(Expr::Neg(Box::new(Expr::Num(1))))
+    );
+
+    let node = Node {
+        value: Some("root"),
+        children: vec![Node::new(None)],
+        next: None,
+    };
+    assert_eq!(
+        format!("{node:?}"),
+        r#"Node { value: Some("root"), children: [Node { value: None, children: [], next: None }], next: None }"#
+    );
+
+    let float = Float::Node(0.5, Box::new(Float::Leaf));
+    assert!(float == float.clone());
+}
+
+#[test]
+fn test_deep() {
+    let expr = chain(200_000);
+    let copy = expr.clone();
+    assert!(copy == expr);
+    assert!(copy != chain(199_999));
+
+    let hasher = RandomState::new();
+    assert_eq!(hasher.hash_one(&expr), hasher.hash_one(&copy));
+
+    let debug = format!("{expr:?}");
+    assert_eq!(debug.len(), "Neg()".len() * 200_000 + "Num(1)".len());
+    drop((expr, copy));
+
+    // Nested through collections and options.
+    let node = (0..200_000).fold(Node::new(None), |node, value| Node {
+        value: Some(value),
+        children: vec![node],
+        next: None,
+    });
+    let node = (0..200_000).fold(node, |node, value| Node {
+        value: Some(value),
+        children: Vec::new(),
+        next: Some(Box::new(node)),
+    });
+    assert!(node.clone() == node);
+    drop(node);
+
+    let float = (0..200_000).fold(Float::Leaf, |float, i| {
+        Float::Node(i as f64, Box::new(float))
+    });
+    drop(float);
+}
+
+#[test]
+fn test_drop_after_panic() {
+    let expr = chain(200_000);
+    let result = std::panic::catch_unwind(move || {
+        let _expr = expr;
+        panic!("unwinding");
+    });
+    assert!(result.is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    #[stacksafe_type(placeholder = List::Nil, serde)]
+    #[serde(rename_all = "lowercase")]
+    enum List {
+        Nil,
+        Cons(u64, Box<List>),
+    }
+
+    let list = List::Cons(1, Box::new(List::Cons(2, Box::new(List::Nil))));
+    let json = serde_json::to_string(&list).unwrap();
+    assert_eq!(json, r#"{"cons":[1,{"cons":[2,"nil"]}]}"#);
+    assert!(serde_json::from_str::<List>(&json).unwrap() == list);
+
+    let list = (0..100_000).fold(List::Nil, |list, i| List::Cons(i, Box::new(list)));
+    let json = serde_json::to_string(&list).unwrap();
+    assert!(json.starts_with(r#"{"cons":[99999,{"cons":[99998,"#));
+    assert_eq!(json.matches("cons").count(), 100_000);
+}
@@ -0,0 +1,70 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::Error;
+use stacksafe::GrowthStrategy;
+use stacksafe::HeapTrampoline;
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn depth(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+
+static GROWTHS: AtomicUsize = AtomicUsize::new(0);
+
+struct Counting;
+
+impl GrowthStrategy for Counting {
+    fn remaining_stack(&self) -> Option<usize> {
+        HeapTrampoline.remaining_stack()
+    }
+
+    fn grow(&self, stack_size: usize, callback: &mut dyn FnMut()) -> Result<(), Error> {
+        GROWTHS.fetch_add(1, Ordering::Relaxed);
+        HeapTrampoline.grow(stack_size, callback)
+    }
+}
+
+// The strategy is installed for the whole process, so everything is checked in a single test.
+#[test]
+fn test_strategy() {
+    stacksafe::set_growth_strategy(Counting).unwrap();
+    assert!(matches!(
+        stacksafe::set_growth_strategy(HeapTrampoline),
+        Err(Error::Unsupported { .. })
+    ));
+
+    assert_eq!(depth(10), 10);
+    assert_eq!(GROWTHS.load(Ordering::Relaxed), 0);
+
+    assert_eq!(depth(1_000_000), 1_000_000);
+    let growths = GROWTHS.load(Ordering::Relaxed);
+    assert!(growths > 0);
+
+    let segment = stacksafe::on_new_stack(1024 * 1024, || stacksafe::current_segment().unwrap());
+    assert_eq!(segment.depth(), 1);
+    assert_eq!(segment.size(), 1024 * 1024);
+    assert_eq!(GROWTHS.load(Ordering::Relaxed), growths + 1);
+
+    // Panics are propagated through the heap segments.
+    let payload = std::panic::catch_unwind(|| {
+        stacksafe::on_new_stack(1024 * 1024, || panic!("boom"));
+    })
+    .unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+}
@@ -0,0 +1,59 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(all(feature = "strict", not(feature = "verify-stack")))]
+
+use std::sync::Mutex;
+
+use stacksafe::StackSafe;
+use stacksafe::ViolationAction;
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn len(value: &StackSafe<Vec<u8>>) -> usize {
+    value.len()
+}
+
+// The handler and the count are global, so everything is checked in a single test.
+#[test]
+fn test_strict() {
+    static LOCATIONS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+    let value = StackSafe::new(vec![1u8, 2, 3]);
+    stacksafe::reset_violation_count();
+
+    // Violations are detected in every build profile.
+    assert!(!stacksafe::internal::is_protected());
+    let result = std::panic::catch_unwind(|| value.len());
+    assert!(result.is_err());
+    assert_eq!(stacksafe::violation_count(), 1);
+
+    // Guarded accesses are not violations.
+    assert_eq!(len(&value), 3);
+    assert_eq!(stacksafe::violation_count(), 1);
+
+    // A handler may log violations and let them proceed; they are still counted.
+    stacksafe::set_violation_handler(|violation| {
+        LOCATIONS.lock().unwrap().push(violation.location().line());
+        ViolationAction::Ignore
+    });
+    let line = line!() + 1;
+    assert_eq!(value.len(), 3);
+    assert_eq!(*LOCATIONS.lock().unwrap(), [line]);
+    assert_eq!(stacksafe::violation_count(), 2);
+
+    stacksafe::reset_violation_count();
+    assert_eq!(stacksafe::violation_count(), 0);
+    stacksafe::clear_violation_handler();
+}
@@ -0,0 +1,123 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::StackSafe;
+use stacksafe::Strip;
+
+#[derive(Debug, PartialEq)]
+pub enum Tree {
+    Leaf,
+    Node(Box<Tree>, u64, Box<Tree>),
+}
+
+#[derive(Debug, PartialEq, Strip)]
+#[strip(plain = Tree)]
+enum SafeTree {
+    Leaf,
+    Node(StackSafe<Box<SafeTree>>, u64, StackSafe<Box<SafeTree>>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Document<T> {
+    title: String,
+    root: Option<Box<Tree>>,
+    extra: Vec<T>,
+}
+
+#[derive(Debug, PartialEq, Strip)]
+#[strip(plain = Document<T>)]
+struct SafeDocument<T> {
+    title: String,
+    root: Option<StackSafe<Box<SafeTree>>>,
+    #[strip(keep)]
+    extra: Vec<T>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Unit;
+
+#[derive(Debug, PartialEq, Strip)]
+#[strip(plain = Unit)]
+struct SafeUnit;
+
+fn leaf() -> Box<Tree> {
+    Box::new(Tree::Leaf)
+}
+
+#[test]
+fn test_round_trip() {
+    let tree = Tree::Node(Box::new(Tree::Node(leaf(), 1, leaf())), 2, leaf());
+    let safe: SafeTree = stacksafe::wrap(tree);
+    assert_eq!(
+        safe,
+        SafeTree::Node(
+            StackSafe::boxed(SafeTree::Node(
+                StackSafe::boxed(SafeTree::Leaf),
+                1,
+                StackSafe::boxed(SafeTree::Leaf)
+            )),
+            2,
+            StackSafe::boxed(SafeTree::Leaf)
+        )
+    );
+    assert_eq!(
+        stacksafe::strip(safe),
+        Tree::Node(Box::new(Tree::Node(leaf(), 1, leaf())), 2, leaf())
+    );
+
+    let document = Document {
+        title: "tree".to_owned(),
+        root: Some(leaf()),
+        extra: vec![[1u8]],
+    };
+    let safe: SafeDocument<[u8; 1]> = stacksafe::wrap(document);
+    assert_eq!(safe.extra, [[1]]);
+    let document = stacksafe::strip(safe);
+    assert_eq!(document.title, "tree");
+    assert_eq!(document.root, Some(leaf()));
+
+    assert_eq!(stacksafe::strip(SafeUnit), Unit);
+}
+
+#[test]
+fn test_deep() {
+    let depth = 100_000;
+    let safe = (0..depth).fold(SafeTree::Leaf, |tree, i| {
+        SafeTree::Node(StackSafe::boxed(tree), i, StackSafe::boxed(SafeTree::Leaf))
+    });
+
+    let mut tree = stacksafe::strip(safe);
+    // Take the plain tree apart iteratively, since dropping it would overflow the stack.
+    let mut left = 0;
+    loop {
+        match std::mem::replace(&mut tree, Tree::Leaf) {
+            Tree::Leaf => break,
+            Tree::Node(lhs, _, _) => {
+                tree = *lhs;
+                left += 1;
+            }
+        }
+    }
+    assert_eq!(left, depth);
+
+    // Converting back and forth keeps deep trees intact.
+    let safe = (0..depth).fold(SafeTree::Leaf, |tree, i| {
+        SafeTree::Node(StackSafe::boxed(SafeTree::Leaf), i, StackSafe::boxed(tree))
+    });
+    let rebuilt: SafeTree = stacksafe::wrap(stacksafe::strip(safe));
+    let expected = (0..depth).fold(SafeTree::Leaf, |tree, i| {
+        SafeTree::Node(StackSafe::boxed(SafeTree::Leaf), i, StackSafe::boxed(tree))
+    });
+    assert_eq!(rebuilt, expected);
+}
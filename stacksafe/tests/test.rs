@@ -79,3 +79,541 @@ fn test_no_ret() {
     no_ret(&mut x);
     assert_eq!(x, 420);
 }
+
+#[test]
+fn test_shared_core_state() {
+    #[stacksafe::stacksafe]
+    fn protected() -> bool {
+        stacksafe_core::is_protected()
+    }
+
+    assert_eq!(protected(), cfg!(any(debug_assertions, feature = "strict")));
+    assert!(!stacksafe_core::is_protected());
+    assert_eq!(
+        stacksafe::get_minimum_stack_size(),
+        stacksafe_core::get_minimum_stack_size()
+    );
+    assert_eq!(
+        stacksafe::get_stack_allocation_size(),
+        stacksafe_core::get_stack_allocation_size()
+    );
+}
+
+// The expansion emitted by `stacksafe-macro` 1.0, which must keep compiling.
+fn legacy_expansion(n: u64) -> u64 {
+    ::stacksafe::internal::stacker::maybe_grow(
+        ::stacksafe::get_minimum_stack_size(),
+        ::stacksafe::get_stack_allocation_size(),
+        ::stacksafe::internal::with_protected(move || -> u64 {
+            if n == 0 {
+                0
+            } else {
+                1 + legacy_expansion(n - 1)
+            }
+        }),
+    )
+}
+
+#[test]
+fn test_legacy_expansion() {
+    assert_eq!(legacy_expansion(100_000), 100_000);
+}
+
+// The expansions emitted by the current `stacksafe-macro`, which must keep compiling.
+fn current_expansion(n: u64) -> u64 {
+    static __STACKSAFE_SITE: ::stacksafe::internal::Site =
+        ::stacksafe::internal::Site::new(::core::concat!(::core::module_path!(), "::", "current"))
+            .profile("test_current_expansion")
+            .red_zone(64 * 1024)
+            .stack_size(1024 * 1024)
+            .check_every(4);
+    ::stacksafe::internal::guard(&__STACKSAFE_SITE, move || -> u64 {
+        if n == 0 {
+            0
+        } else {
+            1 + current_expansion(n - 1)
+        }
+    })
+}
+
+fn current_depth_expansion(n: u64) -> u64 {
+    static __STACKSAFE_SITE: ::stacksafe::internal::Site =
+        ::stacksafe::internal::Site::new("depth").max_depth(1000);
+    ::stacksafe::internal::depth_guard(&__STACKSAFE_SITE, move || -> u64 {
+        if n == 0 {
+            0
+        } else {
+            1 + current_depth_expansion(n - 1)
+        }
+    })
+}
+
+#[allow(dead_code)]
+enum ExpandedList {
+    Nil,
+    Cons(Box<ExpandedList>),
+}
+
+impl Drop for ExpandedList {
+    fn drop(&mut self) {
+        static __STACKSAFE_SITE: ::stacksafe::internal::Site =
+            ::stacksafe::internal::Site::new("ExpandedList::drop");
+        ::stacksafe::internal::drop_guard(&__STACKSAFE_SITE, self, || ExpandedList::Nil);
+    }
+}
+
+#[test]
+fn test_current_expansion() {
+    stacksafe::register_profile("test_current_expansion", stacksafe::StackConfig::default())
+        .unwrap();
+    assert_eq!(current_expansion(100_000), 100_000);
+    assert_eq!(current_depth_expansion(999), 999);
+    assert!(std::panic::catch_unwind(|| current_depth_expansion(1000)).is_err());
+
+    let mut list = ExpandedList::Nil;
+    for _ in 0..100_000 {
+        list = ExpandedList::Cons(Box::new(list));
+    }
+    drop(list);
+}
+
+#[test]
+fn test_niche_layout() {
+    use stacksafe::StackSafe;
+
+    #[allow(dead_code)]
+    struct Node {
+        value: u64,
+        children: Vec<StackSafe<Node>>,
+        next: Option<StackSafe<Box<Node>>>,
+    }
+
+    assert_eq!(
+        size_of::<Option<StackSafe<Box<Node>>>>(),
+        size_of::<usize>()
+    );
+    assert_eq!(size_of::<StackSafe<Node>>(), size_of::<Node>());
+    assert_eq!(align_of::<StackSafe<Node>>(), align_of::<Node>());
+}
+
+#[test]
+fn test_boxed_constructors() {
+    use stacksafe::StackSafe;
+
+    #[derive(Debug, PartialEq)]
+    enum List {
+        Nil,
+        Cons(u32, StackSafe<Box<List>>),
+    }
+
+    let list = List::Cons(
+        1,
+        StackSafe::boxed(List::Cons(2, StackSafe::boxed(List::Nil))),
+    );
+    let expected = List::Cons(
+        1,
+        StackSafe::new(Box::new(List::Cons(2, StackSafe::new(Box::new(List::Nil))))),
+    );
+    assert_eq!(list, expected);
+
+    // Conversions only ever wrap, so that the target type is inferred from the source.
+    let unboxed: StackSafe<u32> = 42.into();
+    let boxed = StackSafe::from(Box::new(42));
+    assert_eq!(unboxed, StackSafe::new(42));
+    assert_eq!(boxed, StackSafe::boxed(42));
+}
+
+#[test]
+fn test_leak_and_forget() {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use stacksafe::StackSafe;
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    StackSafe::new(Counted).forget();
+    assert_eq!(DROPS.load(Ordering::Relaxed), 0);
+
+    #[stacksafe::stacksafe]
+    fn leak(value: StackSafe<Box<Counted>>) -> &'static mut Counted {
+        value.leak()
+    }
+
+    let leaked = leak(StackSafe::boxed(Counted));
+    assert_eq!(DROPS.load(Ordering::Relaxed), 0);
+
+    drop(unsafe { Box::from_raw(leaked) });
+    assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_on_new_stack() {
+    let size = 1024 * 1024;
+    let remaining = stacksafe::on_new_stack(size, || {
+        assert_eq!(
+            stacksafe_core::is_protected(),
+            cfg!(any(debug_assertions, feature = "strict"))
+        );
+        stacksafe::internal::stacker::remaining_stack().unwrap()
+    });
+    assert!(remaining <= size);
+    assert!(remaining > size / 2);
+}
+
+#[stacksafe::stacksafe]
+fn segment_at(n: u64) -> Option<stacksafe::SegmentInfo> {
+    if n == 0 {
+        stacksafe::current_segment()
+    } else {
+        std::hint::black_box(segment_at(n - 1))
+    }
+}
+
+#[stacksafe::stacksafe]
+fn panic_at(n: u64) -> u64 {
+    if n == 0 {
+        panic!("bottom reached");
+    }
+    std::hint::black_box(panic_at(n - 1)) + 1
+}
+
+#[test]
+fn test_current_segment() {
+    assert!(stacksafe::current_segment().is_none());
+
+    let segment = segment_at(1_000_000).unwrap();
+    assert!(segment.depth() > 1);
+    assert_eq!(segment.label(), "test::segment_at");
+    assert_eq!(segment.size(), stacksafe::get_stack_allocation_size());
+    assert!(segment.used() <= segment.size());
+
+    let segment = stacksafe::on_new_stack(1024 * 1024, stacksafe::current_segment).unwrap();
+    assert_eq!(segment.depth(), 1);
+    assert_eq!(segment.label(), "stacksafe::on_new_stack");
+
+    assert!(stacksafe::current_segment().is_none());
+}
+
+#[test]
+fn test_panic_hook() {
+    stacksafe::install_panic_hook();
+    stacksafe::install_panic_hook();
+
+    assert!(std::panic::catch_unwind(|| panic_at(1_000_000)).is_err());
+    assert!(stacksafe::current_segment().is_none());
+}
+
+#[test]
+fn test_yield_hook() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let calls = Rc::new(Cell::new(0));
+    let hook = {
+        let calls = calls.clone();
+        move || {
+            calls.set(calls.get() + 1);
+            // Guarded calls made by the hook must not reenter it.
+            assert_eq!(mut_arg(1), 10);
+        }
+    };
+    assert_eq!(stacksafe::with_yield_hook(1, hook, || mut_arg(1)), 10);
+    assert_eq!(calls.get(), 1);
+
+    calls.set(0);
+    let hook = {
+        let calls = calls.clone();
+        move || calls.set(calls.get() + 1)
+    };
+    let v: Vec<u64> = (0..1000).collect();
+    assert_eq!(stacksafe::with_yield_hook(100, hook, || sum(&v)), 499500);
+    assert_eq!(calls.get(), 10);
+
+    // Growth-only hooks fire once per allocated segment.
+    let hook = {
+        let calls = calls.clone();
+        move || calls.set(calls.get() + 1)
+    };
+    calls.set(0);
+    let n = stacksafe::with_yield_hook(0, hook, || segment_at(1_000_000).unwrap().depth());
+    assert_eq!(calls.get(), n);
+
+    // The hook is uninstalled afterwards.
+    sum(&v);
+    assert_eq!(calls.get(), n);
+}
+
+#[test]
+fn test_config_validation() {
+    use stacksafe::ConfigError;
+    use stacksafe::StackConfig;
+
+    assert_eq!(StackConfig::default().validate(), Ok(()));
+    assert_eq!(
+        StackConfig::current().get_minimum_stack_size(),
+        stacksafe::get_minimum_stack_size()
+    );
+
+    let config = StackConfig::default()
+        .minimum_stack_size(8 * 1024 * 1024)
+        .stack_allocation_size(2 * 1024 * 1024);
+    assert_eq!(
+        config.validate(),
+        Err(ConfigError::SegmentTooSmall {
+            minimum_stack_size: 8 * 1024 * 1024,
+            stack_allocation_size: 2 * 1024 * 1024,
+        })
+    );
+    assert!(
+        config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("8388608")
+    );
+
+    // Nothing is changed when validation fails.
+    let before = StackConfig::current();
+    assert_eq!(
+        StackConfig::current().minimum_stack_size(0).apply(),
+        Err(ConfigError::ZeroMinimumStackSize)
+    );
+    assert_eq!(StackConfig::current(), before);
+    assert_eq!(before.clone().apply(), Ok(()));
+}
+
+#[stacksafe::stacksafe(profile = "test_profiles")]
+fn profiled(n: u64) -> usize {
+    if n == 0 {
+        stacksafe::current_segment().map_or(0, |segment| segment.size())
+    } else {
+        std::hint::black_box(profiled(n - 1))
+    }
+}
+
+#[test]
+fn test_profiles() {
+    use stacksafe::ConfigError;
+    use stacksafe::StackConfig;
+
+    // The global configuration applies until the profile is registered.
+    assert_eq!(stacksafe::get_profile("test_profiles"), None);
+    assert_eq!(profiled(10), 0);
+
+    let config = StackConfig::default()
+        .minimum_stack_size(64 * 1024)
+        .stack_allocation_size(4 * 1024 * 1024);
+    stacksafe::register_profile("test_profiles", config.clone()).unwrap();
+    assert_eq!(stacksafe::get_profile("test_profiles"), Some(config));
+    assert_eq!(profiled(1_000_000), 4 * 1024 * 1024);
+
+    // Invalid configurations are rejected and leave the profile untouched.
+    assert_eq!(
+        stacksafe::register_profile(
+            "test_profiles",
+            StackConfig::default().minimum_stack_size(0)
+        ),
+        Err(ConfigError::ZeroMinimumStackSize)
+    );
+
+    // Re-registering a profile updates functions that already use it.
+    let config = StackConfig::default()
+        .minimum_stack_size(64 * 1024)
+        .stack_allocation_size(3 * 1024 * 1024);
+    stacksafe::register_profile("test_profiles", config).unwrap();
+    assert_eq!(profiled(1_000_000), 3 * 1024 * 1024);
+}
+
+#[stacksafe::stacksafe(red_zone = 64 * 1024 * 1024, stack_size = 128 * 1024 * 1024)]
+fn large_red_zone() -> Option<(usize, usize)> {
+    stacksafe::current_segment().map(|segment| (segment.depth(), segment.size()))
+}
+
+#[stacksafe::stacksafe(profile = "test_sizes", stack_size = 5 * 1024 * 1024)]
+fn sized(n: u64) -> usize {
+    if n == 0 {
+        stacksafe::current_segment().map_or(0, |segment| segment.size())
+    } else {
+        std::hint::black_box(sized(n - 1))
+    }
+}
+
+#[test]
+fn test_attribute_sizes() {
+    use stacksafe::StackConfig;
+
+    // The red zone exceeds what is left of the thread's stack, so the first call already grows.
+    assert_eq!(large_red_zone(), Some((1, 128 * 1024 * 1024)));
+
+    // The attribute takes precedence over the profile.
+    assert_eq!(sized(1_000_000), 5 * 1024 * 1024);
+    let config = StackConfig::default()
+        .minimum_stack_size(64 * 1024)
+        .stack_allocation_size(3 * 1024 * 1024);
+    stacksafe::register_profile("test_sizes", config).unwrap();
+    assert_eq!(sized(1_000_000), 5 * 1024 * 1024);
+}
+
+#[test]
+fn test_const_constructors() {
+    use stacksafe::StackSafe;
+    use stacksafe::StackSafeCow;
+
+    enum Expr {
+        Num(u64),
+        Add(&'static StackSafe<Expr>, &'static StackSafe<Expr>),
+    }
+
+    static ONE: StackSafe<Expr> = StackSafe::new(Expr::Num(1));
+    static TWO: StackSafe<Expr> = StackSafe::new(Expr::Add(&ONE, &ONE));
+    static FOUR: StackSafe<Expr> = StackSafe::new(Expr::Add(&TWO, &TWO));
+    const NAME: StackSafeCow<'static, u64> = StackSafeCow::borrowed(&42);
+
+    #[stacksafe::stacksafe]
+    fn eval(expr: &StackSafe<Expr>) -> u64 {
+        match &**expr {
+            Expr::Num(n) => *n,
+            Expr::Add(lhs, rhs) => eval(lhs) + eval(rhs),
+        }
+    }
+
+    assert_eq!(eval(&FOUR), 4);
+    assert!(NAME.is_borrowed());
+}
+
+#[stacksafe::stacksafe]
+fn diverge(n: u64) -> ! {
+    if n == 0 {
+        std::panic::panic_any(stacksafe::current_segment().map(|segment| segment.depth()));
+    }
+    diverge(std::hint::black_box(n - 1))
+}
+
+#[stacksafe::stacksafe]
+fn event_loop(mut events: Vec<u32>) -> ! {
+    loop {
+        match events.pop() {
+            Some(0) => event_loop(events),
+            Some(_) => continue,
+            None => std::panic::panic_any(events.capacity()),
+        }
+    }
+}
+
+#[test]
+fn test_never_return() {
+    let payload = std::panic::catch_unwind(|| diverge(1_000_000)).unwrap_err();
+    let depth = payload.downcast::<Option<usize>>().unwrap();
+    assert!(depth.unwrap() > 0);
+
+    let payload = std::panic::catch_unwind(|| event_loop(vec![1, 0, 2, 0])).unwrap_err();
+    assert_eq!(*payload.downcast::<usize>().unwrap(), 4);
+}
+
+struct Chain {
+    value: u32,
+    next: Option<Box<stacksafe::StackSafe<Chain>>>,
+}
+
+impl Chain {
+    fn new(len: u32) -> Chain {
+        (1..len).fold(
+            Chain {
+                value: 0,
+                next: None,
+            },
+            |next, value| Chain {
+                value,
+                next: Some(Box::new(stacksafe::StackSafe::new(next))),
+            },
+        )
+    }
+
+    #[stacksafe::stacksafe]
+    fn last<'a>(&'a self) -> &'a Chain {
+        match &self.next {
+            Some(next) => next.last(),
+            None => self,
+        }
+    }
+
+    #[stacksafe::stacksafe]
+    fn last_mut(&mut self) -> &mut Chain {
+        match self.next {
+            Some(ref mut next) => next.last_mut(),
+            None => self,
+        }
+    }
+
+    #[stacksafe::stacksafe]
+    fn values(&self) -> impl Iterator<Item = &u32> + '_ {
+        std::iter::once(&self.value)
+    }
+}
+
+#[stacksafe::stacksafe]
+fn apply_n<F>(s: &str, n: u32, f: &F) -> String
+where F: for<'a> Fn(&'a str) -> &'a str {
+    if n == 0 {
+        s.to_string()
+    } else {
+        apply_n(f(s), n - 1, f)
+    }
+}
+
+#[stacksafe::stacksafe]
+fn pick<'a, 'b>(a: &'a str, _b: &'b str) -> &'a str {
+    a
+}
+
+#[stacksafe::stacksafe]
+fn call_fn_ptr(f: for<'a> fn(&'a str) -> &'a str, s: &str) -> usize {
+    f(s).len()
+}
+
+#[stacksafe::stacksafe]
+fn adder(x: &u32) -> impl Fn(&u32) -> u32 + '_ {
+    move |y| x + y
+}
+
+#[stacksafe::stacksafe]
+fn skipper(n: usize) -> impl for<'a> Fn(&'a str) -> &'a str {
+    move |s| &s[n..]
+}
+
+#[stacksafe::stacksafe]
+fn repeater<T: Clone, const N: usize>(x: T) -> impl Fn(&u32) -> [T; N] {
+    move |_| std::array::from_fn(|_| x.clone())
+}
+
+#[stacksafe::stacksafe]
+fn constant<'a, T: 'a>(x: &'a T) -> impl Fn(&u32) -> &'a T {
+    move |_| x
+}
+
+#[test]
+fn test_lifetime_signatures() {
+    let mut chain = Chain::new(100_000);
+    assert_eq!(chain.last().value, 0);
+    chain.last_mut().value = 7;
+    assert_eq!(chain.last().value, 7);
+    assert_eq!(chain.values().copied().collect::<Vec<_>>(), [99_999]);
+
+    assert_eq!(apply_n("abcdef", 3, &|s| &s[1..]), "def");
+    assert_eq!(pick("a", &String::from("b")), "a");
+    assert_eq!(call_fn_ptr(|s| s.trim(), " ab "), 2);
+
+    assert_eq!(adder(&1)(&2), 3);
+    assert_eq!(skipper(1)("abc"), "bc");
+    assert_eq!(repeater::<_, 2>('x')(&0), ['x', 'x']);
+    assert_eq!(*constant(&5)(&0), 5);
+}
@@ -0,0 +1,85 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::panic::AssertUnwindSafe;
+
+use stacksafe::StackSafe;
+use stacksafe::stacksafe;
+use stacksafe::testing::assert_no_growth;
+
+#[stacksafe]
+fn depth(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+
+struct List {
+    #[allow(dead_code)]
+    next: Option<StackSafe<Box<List>>>,
+}
+
+fn list(len: usize) -> List {
+    let mut list = List { next: None };
+    for _ in 0..len {
+        list = List {
+            next: Some(StackSafe::new(Box::new(list))),
+        };
+    }
+    list
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_shallow_passes() {
+    assert_eq!(assert_no_growth(|| depth(1000)), 1000);
+}
+
+#[test]
+fn test_growth_fails() {
+    let result = std::panic::catch_unwind(|| assert_no_growth(|| depth(1_000_000)));
+    let payload = result.unwrap_err();
+    let message = panic_message(&*payload);
+    assert!(message.contains("testing::depth"), "{message}");
+    assert!(message.contains("assert_no_growth"), "{message}");
+    assert!(message.contains("tests/testing.rs"), "{message}");
+}
+
+#[test]
+fn test_unwinding_drops_deep_structures() {
+    // The list is dropped while unwinding from the failed assertion, which needs to grow the
+    // stack itself.
+    let result = std::panic::catch_unwind(|| {
+        assert_no_growth(|| {
+            let list = list(1_000_000);
+            depth(1_000_000);
+            drop(list);
+        })
+    });
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_growth_allowed_afterwards() {
+    let result =
+        std::panic::catch_unwind(AssertUnwindSafe(|| assert_no_growth(|| depth(1_000_000))));
+    assert!(result.is_err());
+    assert_eq!(depth(1_000_000), 1_000_000);
+    assert_eq!(assert_no_growth(|| depth(10)), 10);
+}
@@ -0,0 +1,78 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use stacksafe::StackConfig;
+use stacksafe::StackSafe;
+use stacksafe::stacksafe;
+
+#[stacksafe]
+fn segment_size(n: u64) -> Option<usize> {
+    if n == 0 {
+        stacksafe::current_segment().map(|segment| segment.size())
+    } else {
+        segment_size(n - 1)
+    }
+}
+
+fn large_segments() -> StackConfig {
+    StackConfig::default().stack_allocation_size(16 * 1024 * 1024)
+}
+
+#[test]
+fn test_spawn_inherits_protection() {
+    let value = StackSafe::new(vec![1, 2, 3]);
+    let handle = stacksafe::protect(|| {
+        stacksafe::thread::spawn(move || {
+            assert!(stacksafe::internal::is_protected());
+            value.len()
+        })
+    });
+    assert_eq!(handle.join().unwrap(), 3);
+
+    let handle = stacksafe::thread::spawn(stacksafe::internal::is_protected);
+    assert_eq!(
+        handle.join().unwrap(),
+        cfg!(not(any(debug_assertions, feature = "strict")))
+    );
+}
+
+#[test]
+fn test_spawn_inherits_config() {
+    let handle = large_segments()
+        .scope(|| stacksafe::thread::spawn(|| segment_size(1_000_000)))
+        .unwrap();
+    if let Some(size) = handle.join().unwrap() {
+        assert!(size >= 16 * 1024 * 1024);
+    }
+}
+
+#[test]
+fn test_scope() {
+    let values: Vec<_> = (1..=100).map(StackSafe::new).collect();
+    let (sum, size) = large_segments()
+        .scope(|| {
+            stacksafe::protect(|| {
+                stacksafe::thread::scope(|s| {
+                    let sum = s.spawn(|| values.iter().map(|v| **v).sum::<u64>());
+                    let size = s.spawn(|| segment_size(1_000_000));
+                    (sum.join().unwrap(), size.join().unwrap())
+                })
+            })
+        })
+        .unwrap();
+    assert_eq!(sum, 5050);
+    if let Some(size) = size {
+        assert!(size >= 16 * 1024 * 1024);
+    }
+}
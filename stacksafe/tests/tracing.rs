@@ -0,0 +1,107 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "tracing")]
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use stacksafe::stacksafe;
+use tracing::Metadata;
+use tracing::Subscriber;
+use tracing::field::Empty;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span;
+
+/// A subscriber that keeps the unsigned integer fields recorded on spans.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<(&'static str, u64)>>>);
+
+impl Visit for Recorder {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.lock().unwrap().push((field.name(), value));
+    }
+
+    fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        span.record(&mut self.clone());
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &span::Id, values: &span::Record<'_>) {
+        values.record(&mut self.clone());
+    }
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, _: &tracing::Event<'_>) {}
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+#[stacksafe]
+fn descend(n: u64) {
+    if n == 0 {
+        let span = tracing::info_span!("leaf", stacksafe.depth = Empty, stacksafe.segments = Empty);
+        stacksafe::tracing::record(&span);
+    } else {
+        descend(n - 1);
+    }
+}
+
+#[test]
+fn test_record() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || descend(100_000));
+    let fields = recorder.0.lock().unwrap().clone();
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields[0], ("stacksafe.depth", 100_001));
+    assert_eq!(fields[1].0, "stacksafe.segments");
+    assert!(fields[1].1 > 0);
+
+    assert_eq!(stacksafe::tracing::depth(), 0);
+    assert_eq!(stacksafe::tracing::segments(), 0);
+}
+
+#[test]
+fn test_undeclared_fields() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let span = tracing::info_span!("partial", stacksafe.segments = Empty);
+        stacksafe::tracing::record(&span);
+    });
+    assert_eq!(*recorder.0.lock().unwrap(), [("stacksafe.segments", 0)]);
+}
+
+#[test]
+fn test_depth_after_panic() {
+    #[stacksafe]
+    fn fail() {
+        assert_eq!(stacksafe::tracing::depth(), 1);
+        panic!("unwinding");
+    }
+
+    assert!(std::panic::catch_unwind(fail).is_err());
+    assert_eq!(stacksafe::tracing::depth(), 0);
+}
@@ -0,0 +1,130 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::panic::AssertUnwindSafe;
+use std::panic::catch_unwind;
+
+use stacksafe::StackSafe;
+use stacksafe::Trampoline;
+use stacksafe::stacksafe;
+
+fn countdown(n: u64) -> Trampoline<'static, u64> {
+    if n == 0 {
+        Trampoline::done(0)
+    } else {
+        Trampoline::call(move || countdown(n - 1))
+    }
+}
+
+fn sum(n: u64) -> Trampoline<'static, u64> {
+    if n == 0 {
+        Trampoline::done(0)
+    } else {
+        Trampoline::call(move || sum(n - 1)).map(move |total| total + n)
+    }
+}
+
+fn is_even(n: u64) -> Trampoline<'static, bool> {
+    if n == 0 {
+        Trampoline::done(true)
+    } else {
+        Trampoline::call(move || is_odd(n - 1))
+    }
+}
+
+fn is_odd(n: u64) -> Trampoline<'static, bool> {
+    if n == 0 {
+        Trampoline::done(false)
+    } else {
+        Trampoline::call(move || is_even(n - 1))
+    }
+}
+
+// Stands in for a library that calls back into the caller for every element.
+fn visit<'i, R>(items: &'i [u64], f: impl FnOnce(&'i u64, &'i [u64]) -> R) -> R {
+    f(&items[0], &items[1..])
+}
+
+fn render(items: &[u64]) -> Trampoline<'_, String> {
+    if items.is_empty() {
+        return Trampoline::done(String::new());
+    }
+    visit(items, |item, rest| {
+        let item = *item;
+        Trampoline::call(move || render(rest)).map(move |mut s| {
+            if !s.is_empty() {
+                s.insert(0, ',');
+            }
+            s.insert_str(0, &item.to_string());
+            s
+        })
+    })
+}
+
+#[stacksafe]
+fn depth(node: &Option<Box<StackSafe<Node>>>) -> usize {
+    match node {
+        None => 0,
+        Some(node) => 1 + depth(&node.next),
+    }
+}
+
+struct Node {
+    next: Option<Box<StackSafe<Node>>>,
+}
+
+#[test]
+fn test_trampoline() {
+    assert_eq!(countdown(1_000_000).run(), 0);
+    assert_eq!(sum(1_000_000).run(), 500_000_500_000);
+    assert!(is_even(1_000_000).run());
+    assert!(!is_even(999_999).run());
+
+    let items: Vec<u64> = (0..100_000).collect();
+    let rendered = render(&items).run();
+    assert!(rendered.starts_with("0,1,2,"));
+    assert!(rendered.ends_with(",99998,99999"));
+
+    assert!(Trampoline::done(1).is_done());
+    assert!(!countdown(1).is_done());
+    let trampoline: Trampoline<'_, u8> = 7.into();
+    assert_eq!(format!("{trampoline:?}"), "Done(7)");
+    assert_eq!(format!("{:?}", countdown(1)), "Pending");
+
+    // Values of different types are combined.
+    let length = Trampoline::done("abc")
+        .and_then(|s| Trampoline::call(move || Trampoline::done(s.len())))
+        .map(|n| n as u64 * 2);
+    assert_eq!(length.run(), 6);
+}
+
+#[test]
+fn test_trampoline_protected() {
+    let mut list = None;
+    for _ in 0..1_000 {
+        list = Some(Box::new(StackSafe::new(Node { next: list })));
+    }
+    // The closures run in a stack-safe context, so guarded functions can be called from them.
+    let total = Trampoline::call(|| Trampoline::done(depth(&list))).run();
+    assert_eq!(total, 1_000);
+}
+
+#[test]
+fn test_trampoline_panic() {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        Trampoline::call(|| sum(1_000).map(|_| -> u64 { panic!("boom") })).run()
+    }));
+    let payload = result.unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+}
@@ -0,0 +1,57 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::StackSafe;
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+struct Node {
+    value: u64,
+    // Only ever dropped.
+    #[allow(dead_code)]
+    next: Option<StackSafe<Box<Node>>>,
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+        if self.value == 500_000 {
+            panic!("failed to drop node {}", self.value);
+        }
+    }
+}
+
+fn build(n: u64) -> Option<StackSafe<Box<Node>>> {
+    (0..n).fold(None, |next, value| {
+        Some(StackSafe::boxed(Node { value, next }))
+    })
+}
+
+#[test]
+fn test_panic_during_deep_drop() {
+    let list = build(1_000_000);
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| drop(list)));
+
+    let payload = result.unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<String>().unwrap(),
+        "failed to drop node 500000"
+    );
+    // The nodes behind the panicking one are dropped while unwinding, on grown segments.
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 1_000_000);
+}
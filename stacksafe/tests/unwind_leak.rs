@@ -0,0 +1,90 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use stacksafe::Error;
+use stacksafe::GrowthStrategy;
+use stacksafe::StackSafe;
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+struct Tracked;
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// Always needs to grow the stack, and never can.
+struct Exhausted;
+
+impl GrowthStrategy for Exhausted {
+    fn remaining_stack(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn grow(&self, _stack_size: usize, _callback: &mut dyn FnMut()) -> Result<(), Error> {
+        Err(Error::Unsupported {
+            operation: "growing the stack",
+        })
+    }
+}
+
+// Set for a copy of this test that checks that pinned values are never leaked.
+const PINNED: &str = "STACKSAFE_TEST_PINNED";
+
+// The strategy is installed for the whole process, so everything is checked in a single test.
+#[test]
+fn test_leak_during_unwinding() {
+    if std::env::var_os(PINNED).is_some() {
+        // Once a value has been pinned, a value that cannot be dropped aborts the process.
+        let value = std::pin::pin!(StackSafe::new(Tracked));
+        stacksafe::protect(|| StackSafe::as_pin_ref(value.as_ref()));
+        stacksafe::set_growth_strategy(Exhausted).unwrap();
+        let _ = std::panic::catch_unwind(|| {
+            let _value = StackSafe::new(Tracked);
+            panic!("boom");
+        });
+        unreachable!("the process was not aborted");
+    }
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "test_leak_during_unwinding", "--nocapture"])
+        .env(PINNED, "1")
+        .output()
+        .unwrap();
+    // Neither a success nor a failed test, which exits with 101.
+    assert!(!matches!(output.status.code(), Some(0 | 101)), "{output:?}");
+
+    stacksafe::set_growth_strategy(Exhausted).unwrap();
+
+    // Values dropped while unwinding are leaked instead of dropped on the exhausted stack.
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        let _value = StackSafe::new(Tracked);
+        panic!("boom");
+    }));
+    assert_eq!(result.unwrap_err().downcast_ref::<&str>(), Some(&"boom"));
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 0);
+
+    // Values without drop glue need no stack-safe context, so they are dropped as usual.
+    let result = std::panic::catch_unwind(|| {
+        let _value = StackSafe::new(1u8);
+        panic!("boom");
+    });
+    assert!(result.is_err());
+}
@@ -0,0 +1,133 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Mutex;
+
+use stacksafe::StackSafe;
+use stacksafe::ViolationAction;
+
+#[test]
+#[cfg(not(feature = "verify-stack"))]
+fn test_violation_handler() {
+    static VIOLATIONS: Mutex<Vec<(&str, u32)>> = Mutex::new(Vec::new());
+
+    let value = StackSafe::new(vec![1u8, 2, 3]);
+
+    stacksafe::set_violation_handler(|violation| {
+        VIOLATIONS
+            .lock()
+            .unwrap()
+            .push((violation.type_name(), violation.location().line()));
+        ViolationAction::Ignore
+    });
+    let line = line!() + 1;
+    assert_eq!(value.len(), 3);
+    if cfg!(any(debug_assertions, feature = "strict")) {
+        assert_eq!(*VIOLATIONS.lock().unwrap(), [("alloc::vec::Vec<u8>", line)]);
+    } else {
+        assert!(VIOLATIONS.lock().unwrap().is_empty());
+    }
+
+    stacksafe::set_violation_handler(|_| ViolationAction::Panic);
+    let result = std::panic::catch_unwind(|| value.len());
+    assert_eq!(
+        result.is_err(),
+        cfg!(any(debug_assertions, feature = "strict"))
+    );
+
+    // Without a handler, violations panic.
+    stacksafe::clear_violation_handler();
+    let result = std::panic::catch_unwind(|| value.len());
+    assert_eq!(
+        result.is_err(),
+        cfg!(any(debug_assertions, feature = "strict"))
+    );
+}
+
+#[test]
+#[cfg(feature = "verify-stack")]
+fn test_verify_stack() {
+    use stacksafe::stacksafe;
+
+    fn unguarded(value: &StackSafe<u64>, n: u64) -> u64 {
+        let value_here = **value;
+        if n == 0 {
+            value_here
+        } else {
+            value_here + std::hint::black_box(unguarded(value, n - 1))
+        }
+    }
+
+    #[stacksafe]
+    fn guarded(value: &StackSafe<u64>, n: u64) -> u64 {
+        let value_here = **value;
+        if n == 0 {
+            value_here
+        } else {
+            value_here + std::hint::black_box(guarded(value, n - 1))
+        }
+    }
+
+    let value = StackSafe::new(1);
+
+    // Accesses outside of a stack-safe context are still caught where it is tracked.
+    let result = std::panic::catch_unwind(|| *value);
+    assert_eq!(
+        result.is_err(),
+        cfg!(any(debug_assertions, feature = "strict"))
+    );
+    assert_eq!(guarded(&value, 1_000_000), 1_000_001);
+
+    // Otherwise, a missing annotation is caught before the stack overflows.
+    stacksafe::set_violation_handler(|violation| match violation.remaining_stack() {
+        Some(_) => ViolationAction::Panic,
+        None => ViolationAction::Ignore,
+    });
+    let result = std::thread::Builder::new()
+        .stack_size(1024 * 1024)
+        .spawn(move || unguarded(&value, u64::MAX))
+        .unwrap()
+        .join();
+    let payload = result.unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(message.contains("bytes of stack left"), "{message}");
+
+    let violations = std::sync::Arc::new(Mutex::new(Vec::new()));
+    stacksafe::set_violation_handler({
+        let violations = violations.clone();
+        move |violation| {
+            let Some(remaining) = violation.remaining_stack() else {
+                return ViolationAction::Ignore;
+            };
+            violations.lock().unwrap().push(remaining);
+            ViolationAction::Panic
+        }
+    });
+    let value = StackSafe::new(1);
+    let result = std::thread::Builder::new()
+        .stack_size(1024 * 1024)
+        .spawn(move || unguarded(&value, u64::MAX))
+        .unwrap()
+        .join();
+    assert!(result.is_err());
+    let violations = violations.lock().unwrap();
+    assert_eq!(violations.len(), 1);
+    // The caller is held to the whole red zone where it is known to be unguarded.
+    assert!(violations[0] < 128 * 1024);
+    assert_eq!(
+        violations[0] > 128 * 1024 / 4,
+        cfg!(any(debug_assertions, feature = "strict"))
+    );
+    stacksafe::clear_violation_handler();
+}
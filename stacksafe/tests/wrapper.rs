@@ -0,0 +1,126 @@
+// Copyright 2025 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+
+use stacksafe::StackSafe;
+
+enum List {
+    Nil,
+    Cons(StackSafe<Box<List>>),
+}
+
+fn build(n: usize) -> List {
+    (0..n).fold(List::Nil, |tail, _| List::Cons(StackSafe::boxed(tail)))
+}
+
+#[test]
+fn test_map() {
+    let wrapped = StackSafe::new(vec![1, 2, 3]);
+    let doubled = StackSafe::map(wrapped, |v| {
+        v.into_iter().map(|x| x * 2).collect::<Vec<_>>()
+    });
+    assert_eq!(doubled, StackSafe::new(vec![2, 4, 6]));
+
+    // The old value is dropped in a stack-safe context.
+    let deep = StackSafe::new(build(1_000_000));
+    let tail = StackSafe::map(deep, |list| match list {
+        List::Nil => None,
+        List::Cons(tail) => Some(tail),
+    });
+    drop(tail);
+}
+
+#[test]
+fn test_as_ref_and_as_mut() {
+    fn len(value: &impl AsRef<Vec<i32>>) -> usize {
+        value.as_ref().len()
+    }
+
+    let mut wrapped = StackSafe::new(vec![1, 2, 3]);
+    stacksafe::protect(|| {
+        wrapped.as_mut().push(4);
+        assert_eq!(len(&wrapped), 4);
+    });
+}
+
+#[test]
+fn test_arc_conversion() {
+    let wrapped = StackSafe::shared(1);
+    let other = wrapped.clone();
+    let arc: Arc<i32> = stacksafe::protect(|| wrapped.into());
+    assert_eq!(Arc::strong_count(&arc), 2);
+    drop(other);
+}
+
+#[test]
+fn test_send_sync() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let wrapped = StackSafe::boxed(build(10));
+    assert_send_sync(&wrapped);
+    std::thread::spawn(move || drop(wrapped)).join().unwrap();
+}
+
+struct Pinned {
+    value: u32,
+    _pinned: PhantomPinned,
+}
+
+#[test]
+fn test_pin_projection() {
+    let wrapped = pin!(StackSafe::new(Pinned {
+        value: 1,
+        _pinned: PhantomPinned,
+    }));
+    stacksafe::protect(|| {
+        let mut wrapped = wrapped;
+        assert_eq!(StackSafe::as_pin_ref(wrapped.as_ref()).value, 1);
+        // SAFETY: `value` is not structurally pinned.
+        unsafe {
+            StackSafe::as_pin_mut(wrapped.as_mut())
+                .get_unchecked_mut()
+                .value = 2
+        };
+        assert_eq!(StackSafe::as_pin_ref(wrapped.as_ref()).value, 2);
+    });
+}
+
+fn sum(n: u64) -> Pin<Box<dyn Future<Output = u64>>> {
+    Box::pin(StackSafe::new(async move {
+        if n == 0 { 0 } else { n + sum(n - 1).await }
+    }))
+}
+
+#[test]
+fn test_deep_future() {
+    let mut cx = Context::from_waker(Waker::noop());
+
+    let mut future = sum(100_000);
+    assert_eq!(future.as_mut().poll(&mut cx), Poll::Ready(5_000_050_000));
+
+    // An unfinished chain of futures is dropped in a stack-safe context too.
+    let mut pending = pin!(StackSafe::new(async {
+        let inner = sum(100_000);
+        std::future::pending::<()>().await;
+        inner.await
+    }));
+    assert_eq!(pending.as_mut().poll(&mut cx), Poll::Pending);
+}